@@ -8,4 +8,27 @@ fn main() {
             println!("cargo:rustc-link-lib=framework=AppKit");
         }
     }
+    #[cfg(feature = "node")]
+    napi_build::setup();
+    #[cfg(feature = "cbindgen")]
+    {
+        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=src/lib.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        let crate_dir = std::path::Path::new(&crate_dir);
+        // The header is generated into `OUT_DIR` so builds never write to the source tree. The copy in `include/` is
+        // checked against it by the tests.
+        let out_dir = std::env::var("OUT_DIR").unwrap();
+        // Parse the sources directly rather than through `cargo metadata`, so generating the header never touches the
+        // network.
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml"))
+            .expect("Unable to read cbindgen.toml");
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(crate_dir.join("src/lib.rs"))
+            .generate()
+            .expect("Unable to generate C bindings")
+            .write_to_file(std::path::Path::new(&out_dir).join("tts.h"));
+    }
 }
@@ -1,13 +1,6 @@
 use std::io;
 use std::{thread, time};
 
-#[cfg(target_os = "macos")]
-use cocoa_foundation::base::id;
-#[cfg(target_os = "macos")]
-use cocoa_foundation::foundation::NSRunLoop;
-#[cfg(target_os = "macos")]
-use objc::{msg_send, sel, sel_impl};
-
 use tts::*;
 
 fn main() -> Result<(), Error> {
@@ -28,11 +21,8 @@ fn main() -> Result<(), Error> {
     // The below is only needed to make the example run on MacOS because there is no NSRunLoop in this context.
     // It shouldn't be needed in an app or game that almost certainly has one already.
     #[cfg(target_os = "macos")]
-    {
-        let run_loop: id = unsafe { NSRunLoop::currentRunLoop() };
-        unsafe {
-            let _: () = msg_send![run_loop, run];
-        }
+    while tts.is_speaking()? {
+        Tts::pump_run_loop(time::Duration::from_millis(100));
     }
     io::stdin().read_line(&mut _input)?;
     Ok(())
@@ -0,0 +1,44 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use tts::*;
+
+/// Counts every allocation, so the cost of speaking can be measured without the backend's own.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const CALLS: usize = 10_000;
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    // The no-op backend discards speech, so only what the crate allocates is counted.
+    let mut tts = Tts::new(Backends::Noop)?;
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..CALLS {
+        tts.speak("Focus moved to the next field.", false)?;
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!(
+        "{} allocations over {CALLS} calls to speak(), {:.2} per call",
+        allocations,
+        allocations as f64 / CALLS as f64
+    );
+    Ok(())
+}
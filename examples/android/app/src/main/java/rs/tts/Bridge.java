@@ -21,4 +21,6 @@ public class Bridge extends UtteranceProgressListener implements TextToSpeech.On
 
     public native void onError(String utteranceId) ;
 
+    public native void onRangeStart(String utteranceId, int start, int end, int frame);
+
 }
\ No newline at end of file
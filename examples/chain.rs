@@ -0,0 +1,44 @@
+use std::io;
+#[cfg(target_os = "macos")]
+use std::time::Duration;
+
+use tts::*;
+
+const LINES: [&str; 4] = [
+    "Each line is spoken",
+    "from the callback",
+    "that fires when the one before it",
+    "finishes.",
+];
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    let mut tts = Tts::default()?;
+    let Features {
+        utterance_callbacks,
+        ..
+    } = tts.supported_features();
+    if !utterance_callbacks {
+        println!("Utterance callbacks aren't supported.");
+        return Ok(());
+    }
+    let mut next = 1;
+    let mut chained = tts.clone();
+    // Callbacks run without the crate's locks held, so they can call back into `Tts`.
+    tts.on_utterance_end(Some(Box::new(move |_| {
+        if let Some(line) = LINES.get(next) {
+            next += 1;
+            chained.speak(*line, false).unwrap();
+        }
+    })))?;
+    tts.speak(LINES[0], false)?;
+    // The below is only needed to make the example run on MacOS because there is no NSRunLoop in this context.
+    // It shouldn't be needed in an app or game that almost certainly has one already.
+    #[cfg(target_os = "macos")]
+    for _ in 0..100 {
+        Tts::pump_run_loop(Duration::from_millis(100));
+    }
+    let mut _input = String::new();
+    io::stdin().read_line(&mut _input)?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::io;
+#[cfg(target_os = "macos")]
+use std::time::Duration;
+
+use tts::*;
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    let tts = Tts::default()?;
+    let mut dialogue = match tts.dialogue() {
+        Ok(dialogue) => dialogue,
+        Err(Error::UnsupportedFeature) => {
+            println!("Dialogues aren't supported.");
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let voices = tts.voices()?;
+    let language = tts.voice().ok().flatten().map(|voice| voice.language());
+    let mut voices = voices.iter().filter(|voice| {
+        language
+            .as_ref()
+            .is_none_or(|l| voice.matches(l) >= MatchQuality::Script)
+    });
+    let (Some(first), Some(second)) = (voices.next(), voices.next()) else {
+        println!("At least two voices are needed.");
+        return Ok(());
+    };
+    println!("{} and {} are talking.", first.name(), second.name());
+    dialogue
+        .line(first, "Did you hear that?")
+        .line(second, "Hear what?")
+        .line(first, "Exactly.");
+    dialogue.speak(false)?;
+    // The below is only needed to make the example run on MacOS because there is no NSRunLoop in this context.
+    // It shouldn't be needed in an app or game that almost certainly has one already.
+    #[cfg(target_os = "macos")]
+    for _ in 0..100 {
+        Tts::pump_run_loop(Duration::from_millis(100));
+    }
+    let mut _input = String::new();
+    io::stdin().read_line(&mut _input)?;
+    Ok(())
+}
@@ -1,11 +1,7 @@
 use std::io;
 
 #[cfg(target_os = "macos")]
-use cocoa_foundation::base::id;
-#[cfg(target_os = "macos")]
-use cocoa_foundation::foundation::NSRunLoop;
-#[cfg(target_os = "macos")]
-use objc::{msg_send, sel, sel_impl};
+use std::time::Duration;
 
 use tts::*;
 
@@ -32,6 +28,12 @@ fn main() -> Result<(), Error> {
             println!("Stopped speaking {:?}", utterance)
         })))?;
     }
+    let Features { word_callbacks, .. } = tts.supported_features();
+    if word_callbacks {
+        tts.on_word_boundary(Some(Box::new(|utterance, range| {
+            println!("Speaking {:?} of {:?}", range, utterance)
+        })))?;
+    }
     let Features { is_speaking, .. } = tts.supported_features();
     if is_speaking {
         println!("Are we speaking? {}", tts.is_speaking()?);
@@ -93,11 +95,8 @@ fn main() -> Result<(), Error> {
     // The below is only needed to make the example run on MacOS because there is no NSRunLoop in this context.
     // It shouldn't be needed in an app or game that almost certainly has one already.
     #[cfg(target_os = "macos")]
-    {
-        let run_loop: id = unsafe { NSRunLoop::currentRunLoop() };
-        unsafe {
-            let _: () = msg_send![run_loop, run];
-        }
+    while tts.is_speaking()? {
+        Tts::pump_run_loop(Duration::from_millis(100));
     }
     io::stdin().read_line(&mut _input)?;
     Ok(())
@@ -1,4 +1,7 @@
-use std::io;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
 
 use tts::*;
 
@@ -9,6 +12,15 @@ fn main() -> Result<(), Error> {
     loop {
         let mut _input = String::new();
         io::stdin().read_line(&mut _input)?;
+        let start = Instant::now();
         tts.speak("Hello, world.", true)?;
+        let queued = start.elapsed();
+        println!("speak() returned after {queued:?}");
+        if queued > Duration::from_millis(10) {
+            println!("That's long enough to stall an input handler.");
+        }
+        if let Some(latency) = tts.last_latency() {
+            println!("The last utterance began {latency:?} after it was spoken");
+        }
     }
 }
@@ -1,14 +1,6 @@
-#[cfg(target_os = "macos")]
-use cocoa_foundation::base::id;
-#[cfg(target_os = "macos")]
-use cocoa_foundation::foundation::NSDefaultRunLoopMode;
-#[cfg(target_os = "macos")]
-use cocoa_foundation::foundation::NSRunLoop;
-#[cfg(target_os = "macos")]
-use objc::class;
-#[cfg(target_os = "macos")]
-use objc::{msg_send, sel, sel_impl};
-use std::{thread, time};
+#[cfg(not(target_os = "macos"))]
+use std::thread;
+use std::time;
 use tts::*;
 
 fn main() -> Result<(), Error> {
@@ -17,15 +9,10 @@ fn main() -> Result<(), Error> {
     let mut phrase = 1;
     loop {
         tts.speak(format!("Phrase {}", phrase), false)?;
-        #[cfg(target_os = "macos")]
-        {
-            let run_loop: id = unsafe { NSRunLoop::currentRunLoop() };
-            unsafe {
-                let date: id = msg_send![class!(NSDate), distantFuture];
-                let _: () = msg_send![run_loop, runMode:NSDefaultRunLoopMode beforeDate:date];
-            }
-        }
         let time = time::Duration::from_secs(5);
+        #[cfg(target_os = "macos")]
+        Tts::pump_run_loop(time);
+        #[cfg(not(target_os = "macos"))]
         thread::sleep(time);
         phrase += 1;
     }
@@ -0,0 +1,26 @@
+use std::{thread, time::Duration};
+
+use tts::*;
+
+const TEXT: &str =
+    "Sentence callbacks work wherever utterance callbacks do. Each sentence is reported as it \
+     begins! Reading apps can highlight it. Does that help?";
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    let mut tts = Tts::default()?;
+    tts.on_sentence_begin(Some(Box::new(|_, range| println!("{}", &TEXT[range]))))?;
+    tts.speak(TEXT, false)?;
+    let Features { is_speaking, .. } = tts.supported_features();
+    if is_speaking {
+        while tts.is_speaking()? {
+            #[cfg(target_os = "macos")]
+            Tts::pump_run_loop(Duration::from_millis(100));
+            #[cfg(not(target_os = "macos"))]
+            thread::sleep(Duration::from_millis(100));
+        }
+    } else {
+        thread::sleep(Duration::from_secs(10));
+    }
+    Ok(())
+}
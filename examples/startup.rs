@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+use tts::*;
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    // Run this in a fresh process, since some of the work happens once per process.
+    let start = Instant::now();
+    let tts = Tts::default()?;
+    let created = start.elapsed();
+    println!(
+        "Created the {:?} backend in {created:?}",
+        tts.backend_info().backend
+    );
+    let Features {
+        utterance_callbacks,
+        ..
+    } = tts.supported_features();
+    if utterance_callbacks {
+        // Delegates and listeners are registered with the platform the first time callbacks are used.
+        let start = Instant::now();
+        tts.on_utterance_end(Some(Box::new(|_| {})))?;
+        println!(
+            "Setting the first callback took {:?}, which apps without callbacks no longer spend at startup",
+            start.elapsed()
+        );
+    }
+    Ok(())
+}
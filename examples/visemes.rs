@@ -0,0 +1,27 @@
+use std::{thread, time::Duration};
+
+use tts::*;
+
+fn main() -> Result<(), Error> {
+    env_logger::init();
+    let mut tts = Tts::default()?;
+    tts.on_viseme(Some(Box::new(|_, viseme, at| {
+        println!("{:>6}ms {:?}", at.as_millis(), viseme)
+    })))?;
+    tts.speak(
+        "Visemes let characters move their lips as they speak.",
+        false,
+    )?;
+    let Features { is_speaking, .. } = tts.supported_features();
+    if is_speaking {
+        while tts.is_speaking()? {
+            #[cfg(target_os = "macos")]
+            Tts::pump_run_loop(Duration::from_millis(100));
+            #[cfg(not(target_os = "macos"))]
+            thread::sleep(Duration::from_millis(100));
+        }
+    } else {
+        thread::sleep(Duration::from_secs(10));
+    }
+    Ok(())
+}
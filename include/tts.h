@@ -0,0 +1,414 @@
+#ifndef TTS_H
+#define TTS_H
+
+/* This file is generated by cbindgen with the `cbindgen` feature. Don't edit it by hand; copy it from the build's `OUT_DIR`. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+typedef enum Backends {
+#if defined(TTS_ANDROID)
+  Backends_Android = 0,
+#endif
+#if defined(TTS_MACOS)
+  // `NSSpeechSynthesizer` is not thread-safe, so this backend must be created and used on the main thread.
+  Backends_AppKit = 1,
+#endif
+#if (defined(TTS_MACOS) || defined(TTS_IOS))
+  Backends_AvFoundation = 2,
+#endif
+#if defined(TTS_LINUX)
+  Backends_SpeechDispatcher = 3,
+#endif
+#if (defined(TTS_WINDOWS) && defined(TTS_TOLK))
+  Backends_Tolk = 4,
+#endif
+#if defined(TTS_WASM)
+  Backends_Web = 5,
+#endif
+#if defined(TTS_WINDOWS)
+  Backends_WinRt = 6,
+#endif
+  // Silently discards speech, for when no real backend is available.
+  Backends_Noop = 7,
+} Backends;
+
+// The gender of a voice, as its backend reports it.
+//
+// The discriminants and serialized names are stable, so they can be stored and passed across the FFI.
+typedef enum Gender {
+  Gender_Male = 0,
+  Gender_Female = 1,
+  // Neither male nor female, as AppKit reports some novelty voices.
+  Gender_Neutral = 2,
+  // The backend doesn't say, as with the Web, Speech Dispatcher and Android, and Apple's unspecified voices.
+  Gender_Unknown = 3,
+} Gender;
+
+// A capability a backend may have, as queried with `tts_supports()`. Values are fixed, and new capabilities are
+// only ever added at the end.
+typedef enum TtsCapability {
+  TtsCapability_IsSpeaking = 0,
+  TtsCapability_Pitch = 1,
+  TtsCapability_Rate = 2,
+  TtsCapability_Stop = 3,
+  TtsCapability_UtteranceCallbacks = 4,
+  TtsCapability_Voice = 5,
+  TtsCapability_GetVoice = 6,
+  TtsCapability_Volume = 7,
+  TtsCapability_WordCallbacks = 8,
+  TtsCapability_Synthesize = 9,
+  TtsCapability_Pause = 10,
+  TtsCapability_Queue = 11,
+  TtsCapability_OutputDevice = 12,
+  TtsCapability_Earcons = 13,
+  TtsCapability_Ssml = 14,
+  TtsCapability_Braille = 15,
+  TtsCapability_Priorities = 16,
+} TtsCapability;
+
+// The outcome of a fallible FFI call.
+typedef enum TtsErrorCode {
+  TtsErrorCode_Ok = 0,
+  // A required pointer argument was null.
+  TtsErrorCode_NullPointer,
+  // A string argument wasn't valid UTF-8.
+  TtsErrorCode_InvalidString,
+  // The backend doesn't support this operation.
+  TtsErrorCode_UnsupportedFeature,
+  // A value was outside the supported range.
+  TtsErrorCode_OutOfRange,
+  // The requested backend isn't available on this system.
+  TtsErrorCode_BackendUnavailable,
+  // The call must be made from the main thread.
+  TtsErrorCode_NotMainThread,
+  // A value expected from the backend wasn't received.
+  TtsErrorCode_NoValue,
+  // An I/O error occurred.
+  TtsErrorCode_Io,
+  // The operation failed in the backend.
+  TtsErrorCode_OperationFailed,
+  // The platform reported an error, such as a Speech Dispatcher, WinRT, JNI or JavaScript error.
+  TtsErrorCode_Platform,
+} TtsErrorCode;
+
+// The format to synthesize audio in, as with `Tts::synthesize_as()`.
+typedef struct AudioFormat AudioFormat;
+
+typedef struct Tts Tts;
+
+// Identifies an utterance. Like `BackendId`, its string form, such as `speechd:42`, is the same on every platform,
+// and is how it's serialized.
+typedef struct UtteranceId UtteranceId;
+
+// A callback receiving an utterance ID, valid only for the duration of the call, and the `user_data` it was registered with.
+// May be null.
+typedef void (*TtsUtteranceCallback)(const struct UtteranceId *utterance, void *user_data);
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns the code of the most recent error, or `Ok` if there is none.
+enum TtsErrorCode tts_get_error_code(void);
+
+// Copies a NUL-terminated description of the most recent error into `buf`, truncating it to fit `len` bytes.
+// Returns the length of the full message, excluding the terminator, or 0 if there is no error.
+//
+// # Safety
+//
+// `buf` must be null or writable for `len` bytes.
+size_t tts_get_error_message(char *buf,
+                             size_t len);
+
+// Clears the most recent error.
+void tts_clear_error(void);
+
+// Create a new `Tts` instance with the specified backend, one of the values of `Backends`, storing it in `out`.
+// Fails with `BackendUnavailable` for backends this build of the library doesn't have.
+//
+// # Safety
+//
+// `out` must be writable.
+enum TtsErrorCode tts_new(uint32_t backend,
+                          struct Tts **out);
+
+// Create a new `Tts` instance with the default backend for this platform, storing it in `out`.
+//
+// # Safety
+//
+// `out` must be writable.
+enum TtsErrorCode tts_default(struct Tts **out);
+
+// Free the memory associated with a `Tts` object.
+// Does nothing if `tts` is null.
+//
+// # Safety
+//
+// `tts` must be null or a pointer returned by `tts_new` or `tts_default`, and must not be used afterward.
+void tts_free(struct Tts *tts);
+
+// Returns whether this `Tts` object supports `capability`, one of the values of `TtsCapability`, or false if `tts` is null or `capability` isn't one
+// this library knows of.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer.
+bool tts_supports(const struct Tts *tts,
+                  uint32_t capability);
+
+// Speaks the specified text, optionally interrupting current speech.
+// If `utterance` is not null, it is set to a pointer to the utterance ID, which must be freed with
+// `tts_free_utterance`, or to null if the backend doesn't report IDs.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer, `text` a valid NUL-terminated string, and `utterance` null or writable.
+enum TtsErrorCode tts_speak(struct Tts *tts,
+                            const char *text,
+                            bool interrupt,
+                            struct UtteranceId **utterance);
+
+// Free the memory associated with an `UtteranceId`.
+// Does nothing if `utterance` is null.
+//
+// # Safety
+//
+// `utterance` must be null or a pointer returned by `tts_speak`, and must not be used afterward.
+void tts_free_utterance(struct UtteranceId *utterance);
+
+// Synthesizes the specified text to 16-bit PCM audio without speaking it, blocking until synthesis completes.
+// On success, `buf` is set to the interleaved samples, which must be freed with `tts_free_buffer`, and `len` to
+// their number. `sample_rate` and `channels` may be null if they aren't needed.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer, `text` a valid NUL-terminated string, `buf` and `len` writable, and
+// `sample_rate` and `channels` null or writable.
+enum TtsErrorCode tts_synthesize(struct Tts *tts,
+                                 const char *text,
+                                 int16_t **buf,
+                                 size_t *len,
+                                 uint32_t *sample_rate,
+                                 uint16_t *channels);
+
+// Free the samples returned by `tts_synthesize`.
+// Does nothing if `buf` is null.
+//
+// # Safety
+//
+// `buf` and `len` must be null or exactly as returned by `tts_synthesize`, and `buf` must not be used afterward.
+void tts_free_buffer(int16_t *buf,
+                     size_t len);
+
+// Stops current speech.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_stop(struct Tts *tts);
+
+// Stores the minimum rate for this speech synthesizer in `rate`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+enum TtsErrorCode tts_min_rate(const struct Tts *tts, float *rate);
+
+// Stores the maximum rate for this speech synthesizer in `rate`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+enum TtsErrorCode tts_max_rate(const struct Tts *tts, float *rate);
+
+// Stores the normal rate for this speech synthesizer in `rate`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+enum TtsErrorCode tts_normal_rate(const struct Tts *tts, float *rate);
+
+// Gets the current speech rate, storing it in `rate`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+enum TtsErrorCode tts_get_rate(struct Tts *tts, float *rate);
+
+// Sets the desired speech rate.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_set_rate(struct Tts *tts, float rate);
+
+// Stores the minimum pitch for this speech synthesizer in `pitch`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+enum TtsErrorCode tts_min_pitch(const struct Tts *tts, float *pitch);
+
+// Stores the maximum pitch for this speech synthesizer in `pitch`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+enum TtsErrorCode tts_max_pitch(const struct Tts *tts, float *pitch);
+
+// Stores the normal pitch for this speech synthesizer in `pitch`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+enum TtsErrorCode tts_normal_pitch(const struct Tts *tts, float *pitch);
+
+// Gets the current speech pitch, storing it in `pitch`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+enum TtsErrorCode tts_get_pitch(struct Tts *tts, float *pitch);
+
+// Sets the desired speech pitch.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_set_pitch(struct Tts *tts, float pitch);
+
+// Stores the minimum volume for this speech synthesizer in `volume`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+enum TtsErrorCode tts_min_volume(const struct Tts *tts, float *volume);
+
+// Stores the maximum volume for this speech synthesizer in `volume`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+enum TtsErrorCode tts_max_volume(const struct Tts *tts, float *volume);
+
+// Stores the normal volume for this speech synthesizer in `volume`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+enum TtsErrorCode tts_normal_volume(const struct Tts *tts, float *volume);
+
+// Gets the current speech volume, storing it in `volume`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+enum TtsErrorCode tts_get_volume(struct Tts *tts, float *volume);
+
+// Sets the desired speech volume.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_set_volume(struct Tts *tts, float volume);
+
+// Returns whether this speech synthesizer is speaking, storing it in `speaking`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `speaking` null or writable.
+enum TtsErrorCode tts_is_speaking(struct Tts *tts, bool *speaking);
+
+// Pauses speech, which can be continued with `tts_resume`.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_pause(struct Tts *tts);
+
+// Resumes speech paused with `tts_pause`.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_resume(struct Tts *tts);
+
+// Stops the utterance being spoken and moves on to the next one queued.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_skip(struct Tts *tts);
+
+#if !defined(TTS_WASM)
+// Blocks until speech has finished, including while it's paused.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer.
+enum TtsErrorCode tts_wait(struct Tts *tts);
+#endif
+
+// Returns whether speech is paused, storing it in `paused`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `paused` null or writable.
+enum TtsErrorCode tts_is_paused(struct Tts *tts, bool *paused);
+
+// Returns the number of utterances from this instance that are speaking or queued, storing it in `len`.
+//
+// # Safety
+//
+// `tts` must be null or a valid `Tts` pointer, and `len` null or writable.
+enum TtsErrorCode tts_queue_len(struct Tts *tts,
+                                size_t *len);
+
+// Calls `callback` when this speech synthesizer begins speaking an utterance.
+// Pass a null `callback` to remove it.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+enum TtsErrorCode tts_on_utterance_begin(struct Tts *tts,
+                                         TtsUtteranceCallback callback,
+                                         void *user_data);
+
+// Calls `callback` when this speech synthesizer finishes speaking an utterance.
+// Pass a null `callback` to remove it.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+enum TtsErrorCode tts_on_utterance_end(struct Tts *tts,
+                                       TtsUtteranceCallback callback,
+                                       void *user_data);
+
+// Calls `callback` when this speech synthesizer is stopped with utterances still in its queue.
+// Pass a null `callback` to remove it.
+//
+// # Safety
+//
+// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+enum TtsErrorCode tts_on_utterance_stop(struct Tts *tts,
+                                        TtsUtteranceCallback callback,
+                                        void *user_data);
+
+// Returns whether two utterance IDs refer to the same utterance, such as one from `tts_speak` and one passed to a callback.
+// Returns `false` if either is null.
+//
+// # Safety
+//
+// `a` and `b` must each be null or a valid `UtteranceId` pointer.
+bool tts_utterance_id_eq(const struct UtteranceId *a,
+                         const struct UtteranceId *b);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TTS_H */
@@ -0,0 +1,216 @@
+// A small C++17 wrapper over tts.h.
+//
+// Errors are thrown as `tts::Error`. Define the `TTS_*` platform macros used by tts.h
+// (`TTS_LINUX`, `TTS_WINDOWS`, ...) before including this header to name a specific backend. Backends
+// the library wasn't built with are rejected with `TtsErrorCode_BackendUnavailable`.
+
+#ifndef TTS_HPP
+#define TTS_HPP
+
+#include <cstddef>
+#include <cstdint>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <vector>
+
+#include "tts.h"
+
+namespace tts {
+
+// An error reported by the library, carrying its `TtsErrorCode`.
+class Error : public std::runtime_error {
+ public:
+  Error(TtsErrorCode code, const std::string &message)
+      : std::runtime_error(message), code_(code) {}
+
+  TtsErrorCode code() const noexcept { return code_; }
+
+ private:
+  TtsErrorCode code_;
+};
+
+// Audio returned by `Client::synthesize()`.
+struct Audio {
+  std::uint32_t sample_rate = 0;
+  std::uint16_t channels = 0;
+  // Interleaved 16-bit PCM samples.
+  std::vector<std::int16_t> samples;
+};
+
+namespace detail {
+
+inline void check(TtsErrorCode code) {
+  if (code == TtsErrorCode_Ok) {
+    return;
+  }
+  std::vector<char> buf(tts_get_error_message(nullptr, 0) + 1);
+  tts_get_error_message(buf.data(), buf.size());
+  tts_clear_error();
+  throw Error(code, std::string(buf.data()));
+}
+
+}  // namespace detail
+
+// Owns a `Tts` instance, freeing it on destruction.
+class Client {
+ public:
+  // Creates a client with the default backend for this platform.
+  Client() { detail::check(tts_default(&tts_)); }
+
+  // Creates a client with the specified backend.
+  explicit Client(Backends backend) { detail::check(tts_new(backend, &tts_)); }
+
+  ~Client() { tts_free(tts_); }
+
+  Client(const Client &) = delete;
+  Client &operator=(const Client &) = delete;
+
+  Client(Client &&other) noexcept : tts_(std::exchange(other.tts_, nullptr)) {}
+
+  Client &operator=(Client &&other) noexcept {
+    if (this != &other) {
+      tts_free(tts_);
+      tts_ = std::exchange(other.tts_, nullptr);
+    }
+    return *this;
+  }
+
+  Features features() const { return tts_supported_features(tts_); }
+
+  // Speaks `text`, optionally interrupting current speech.
+  void speak(const std::string &text, bool interrupt = false) {
+    detail::check(tts_speak(tts_, text.c_str(), interrupt, nullptr));
+  }
+
+  // Synthesizes `text` to audio without speaking it, blocking until synthesis completes.
+  Audio synthesize(const std::string &text) {
+    Audio audio;
+    std::int16_t *buf = nullptr;
+    std::size_t len = 0;
+    detail::check(tts_synthesize(tts_, text.c_str(), &buf, &len, &audio.sample_rate, &audio.channels));
+    audio.samples.assign(buf, buf + len);
+    tts_free_buffer(buf, len);
+    return audio;
+  }
+
+  void stop() { detail::check(tts_stop(tts_)); }
+
+  void pause() { detail::check(tts_pause(tts_)); }
+
+  void resume() { detail::check(tts_resume(tts_)); }
+
+  // Stops the utterance being spoken and moves on to the next one queued.
+  void skip() { detail::check(tts_skip(tts_)); }
+
+#if !defined(TTS_WASM)
+  // Blocks until speech has finished, including while it's paused.
+  void wait() { detail::check(tts_wait(tts_)); }
+#endif
+
+  bool is_speaking() {
+    bool speaking = false;
+    detail::check(tts_is_speaking(tts_, &speaking));
+    return speaking;
+  }
+
+  bool is_paused() {
+    bool paused = false;
+    detail::check(tts_is_paused(tts_, &paused));
+    return paused;
+  }
+
+  std::size_t queue_len() {
+    std::size_t len = 0;
+    detail::check(tts_queue_len(tts_, &len));
+    return len;
+  }
+
+  float min_rate() const {
+    float rate = 0;
+    detail::check(tts_min_rate(tts_, &rate));
+    return rate;
+  }
+
+  float max_rate() const {
+    float rate = 0;
+    detail::check(tts_max_rate(tts_, &rate));
+    return rate;
+  }
+
+  float normal_rate() const {
+    float rate = 0;
+    detail::check(tts_normal_rate(tts_, &rate));
+    return rate;
+  }
+
+  float rate() {
+    float rate = 0;
+    detail::check(tts_get_rate(tts_, &rate));
+    return rate;
+  }
+
+  void set_rate(float rate) { detail::check(tts_set_rate(tts_, rate)); }
+
+  float min_pitch() const {
+    float pitch = 0;
+    detail::check(tts_min_pitch(tts_, &pitch));
+    return pitch;
+  }
+
+  float max_pitch() const {
+    float pitch = 0;
+    detail::check(tts_max_pitch(tts_, &pitch));
+    return pitch;
+  }
+
+  float normal_pitch() const {
+    float pitch = 0;
+    detail::check(tts_normal_pitch(tts_, &pitch));
+    return pitch;
+  }
+
+  float pitch() {
+    float pitch = 0;
+    detail::check(tts_get_pitch(tts_, &pitch));
+    return pitch;
+  }
+
+  void set_pitch(float pitch) { detail::check(tts_set_pitch(tts_, pitch)); }
+
+  float min_volume() const {
+    float volume = 0;
+    detail::check(tts_min_volume(tts_, &volume));
+    return volume;
+  }
+
+  float max_volume() const {
+    float volume = 0;
+    detail::check(tts_max_volume(tts_, &volume));
+    return volume;
+  }
+
+  float normal_volume() const {
+    float volume = 0;
+    detail::check(tts_normal_volume(tts_, &volume));
+    return volume;
+  }
+
+  float volume() {
+    float volume = 0;
+    detail::check(tts_get_volume(tts_, &volume));
+    return volume;
+  }
+
+  void set_volume(float volume) { detail::check(tts_set_volume(tts_, volume)); }
+
+  // The underlying handle, for calling tts.h functions not wrapped here.
+  Tts *get() const noexcept { return tts_; }
+
+ private:
+  Tts *tts_ = nullptr;
+};
+
+}  // namespace tts
+
+#endif  // TTS_HPP
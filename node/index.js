@@ -0,0 +1,78 @@
+// An EventEmitter over the native bindings, built into this directory with `npm run build`.
+//
+// Emits "begin", "end" and "stop" with the utterance ID, and "word" with the utterance ID and the UTF-16 offsets of
+// the word about to be spoken.
+
+"use strict";
+
+const { EventEmitter } = require("events");
+const native = require("./tts.node");
+
+class Tts extends EventEmitter {
+  constructor() {
+    super();
+    this.native = new native.Tts((event, ...args) => this.emit(event, ...args));
+  }
+
+  supportedFeatures() {
+    return this.native.supportedFeatures();
+  }
+
+  speak(text, interrupt = false) {
+    return this.native.speak(text, interrupt);
+  }
+
+  stop() {
+    this.native.stop();
+  }
+
+  pause() {
+    this.native.pause();
+  }
+
+  resume() {
+    this.native.resume();
+  }
+
+  isSpeaking() {
+    return this.native.isSpeaking();
+  }
+
+  get rate() {
+    return this.native.rate;
+  }
+
+  set rate(rate) {
+    this.native.rate = rate;
+  }
+
+  get pitch() {
+    return this.native.pitch;
+  }
+
+  set pitch(pitch) {
+    this.native.pitch = pitch;
+  }
+
+  get volume() {
+    return this.native.volume;
+  }
+
+  set volume(volume) {
+    this.native.volume = volume;
+  }
+
+  voices() {
+    return this.native.voices();
+  }
+
+  voice() {
+    return this.native.voice();
+  }
+
+  setVoice(id) {
+    this.native.setVoice(id);
+  }
+}
+
+module.exports = { Tts };
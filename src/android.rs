@@ -0,0 +1,155 @@
+//! Android-specific initialization.
+//!
+//! By default the Android backend finds the `JavaVM` and `Context` through
+//! [`ndk-context`](https://crates.io/crates/ndk-context), which is set up by
+//! `ndk-glue` and friends. Hosts that don't use those, such as GameActivity,
+//! Flutter or plain JNI libraries, should call [`init`] before creating a
+//! [`Tts`](crate::Tts) instead.
+
+use jni::{objects::GlobalRef, JNIEnv, JavaVM};
+use parking_lot::RwLock;
+
+use crate::{backends::set_bridge, Error};
+
+/// An installed TTS engine, as returned by `Tts::engines()`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Engine {
+    /// The engine's package name, for passing to `Tts::new_with_engine()`.
+    pub name: String,
+    /// A human-readable label, suitable for presenting to users.
+    pub label: String,
+    /// Whether this is the user's default engine.
+    pub default: bool,
+}
+
+/// What speech is being used for, which determines its volume stream and how it interacts with other audio.
+///
+/// Speech is always tagged with the speech content type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioUsage {
+    /// Media playback, following the media volume.
+    #[default]
+    Media,
+    /// Accessibility, such as a screen reader, following the accessibility volume.
+    AssistanceAccessibility,
+    /// Navigation directions.
+    AssistanceNavigationGuidance,
+    /// Responses from a virtual assistant.
+    Assistant,
+    /// Notifications.
+    Notification,
+    /// Game audio.
+    Game,
+}
+
+impl AudioUsage {
+    /// The corresponding `AudioAttributes.USAGE_*` constant.
+    pub(crate) fn value(self) -> i32 {
+        match self {
+            AudioUsage::Media => 1,
+            AudioUsage::Notification => 5,
+            AudioUsage::AssistanceAccessibility => 11,
+            AudioUsage::AssistanceNavigationGuidance => 12,
+            AudioUsage::Game => 14,
+            AudioUsage::Assistant => 16,
+        }
+    }
+}
+
+/// The intent action that asks the engine to install missing voice data (`TextToSpeech.Engine.ACTION_INSTALL_TTS_DATA`).
+pub const ACTION_INSTALL_TTS_DATA: &str = "android.speech.tts.engine.INSTALL_TTS_DATA";
+
+/// Whether the engine can speak a language, as reported by `TextToSpeech.isLanguageAvailable()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LanguageAvailability {
+    /// The language isn't supported.
+    NotSupported,
+    /// The language is supported, but its voice data isn't installed.
+    MissingData,
+    /// The language is supported, but not the requested region.
+    Available,
+    /// The language and region are supported, but not the requested variant.
+    CountryAvailable,
+    /// The language, region and variant are all supported.
+    CountryVariantAvailable,
+}
+
+impl LanguageAvailability {
+    pub(crate) fn from_value(value: i32) -> Self {
+        match value {
+            0 => LanguageAvailability::Available,
+            1 => LanguageAvailability::CountryAvailable,
+            2 => LanguageAvailability::CountryVariantAvailable,
+            -1 => LanguageAvailability::MissingData,
+            _ => LanguageAvailability::NotSupported,
+        }
+    }
+
+    /// Whether the language can be spoken now.
+    pub fn is_available(self) -> bool {
+        self >= LanguageAvailability::Available
+    }
+}
+
+static CONTEXT: RwLock<Option<(JavaVM, GlobalRef)>> = RwLock::new(None);
+
+/// Provides the `JavaVM` and `android.content.Context` the Android backend should use.
+///
+/// Call this from a thread with access to the application's class loader
+/// (typically a Java thread calling into native code) so the `rs.tts.Bridge`
+/// class can be found. Calling it again replaces the previous context.
+pub fn init(vm: JavaVM, context: GlobalRef) -> Result<(), Error> {
+    let mut env = vm.attach_current_thread_permanently()?;
+    set_bridge(&mut env)?;
+    let mut ctx = CONTEXT.write();
+    *ctx = Some((vm, context));
+    Ok(())
+}
+
+#[cfg(not(feature = "ndk-context"))]
+fn uninitialized() -> Error {
+    Error::BackendUnavailable {
+        backend: crate::Backends::Android,
+        reason:
+            "no Java VM or context; call `tts::android::init()` or enable the `ndk-context` feature"
+                .into(),
+    }
+}
+
+pub(crate) fn vm() -> Result<JavaVM, Error> {
+    if let Some((vm, _)) = &*CONTEXT.read() {
+        let vm = unsafe { JavaVM::from_raw(vm.get_java_vm_pointer()) }?;
+        Ok(vm)
+    } else {
+        #[cfg(feature = "ndk-context")]
+        {
+            let ctx = ndk_context::android_context();
+            let vm = unsafe { JavaVM::from_raw(ctx.vm().cast()) }?;
+            Ok(vm)
+        }
+        #[cfg(not(feature = "ndk-context"))]
+        Err(uninitialized())
+    }
+}
+
+pub(crate) fn context(env: &mut JNIEnv) -> Result<GlobalRef, Error> {
+    if let Some((_, context)) = &*CONTEXT.read() {
+        Ok(context.clone())
+    } else {
+        #[cfg(feature = "ndk-context")]
+        {
+            let ctx = ndk_context::android_context();
+            let context = unsafe { jni::objects::JObject::from_raw(ctx.context().cast()) };
+            let context = env.new_global_ref(context)?;
+            Ok(context)
+        }
+        #[cfg(not(feature = "ndk-context"))]
+        {
+            let _ = env;
+            Err(uninitialized())
+        }
+    }
+}
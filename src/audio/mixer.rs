@@ -0,0 +1,139 @@
+//! Decides how outputs playing at once share the speakers, according to each one's [`MixPolicy`].
+
+use crate::MixPolicy;
+
+/// What the mixer knows of an output.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Track {
+    pub(crate) policy: MixPolicy,
+    /// Whether the output was paused, as opposed to held by the mixer.
+    pub(crate) paused: bool,
+    /// When the output began playing, ordering outputs that wait for each other, or `None` while it's idle.
+    pub(crate) since: Option<u64>,
+}
+
+/// How an output plays in the mix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Mix {
+    /// Whether it plays, rather than being paused or held.
+    pub(crate) play: bool,
+    /// The volume it plays at, lowered by those ducking it.
+    pub(crate) gain: f32,
+}
+
+/// Returns how each of `tracks` plays alongside the others, in order.
+///
+/// An exclusive track waits for those that began before it, and those that begin after it wait for it. A track
+/// that ducks lowers the volume of every other while it plays. Paused tracks neither hold nor duck the others.
+pub(crate) fn mix(tracks: &[Track]) -> Vec<Mix> {
+    let mut playing: Vec<(usize, u64)> = tracks
+        .iter()
+        .enumerate()
+        .filter(|(_, track)| !track.paused)
+        .filter_map(|(i, track)| Some((i, track.since?)))
+        .collect();
+    playing.sort_by_key(|&(_, since)| since);
+    let exclusive = |i: usize| tracks[i].policy == MixPolicy::Exclusive;
+    let mut held = vec![false; tracks.len()];
+    for (n, &(i, _)) in playing.iter().enumerate() {
+        let earlier = &playing[..n];
+        held[i] =
+            (!earlier.is_empty() && exclusive(i)) || earlier.iter().any(|&(j, _)| exclusive(j));
+    }
+    tracks
+        .iter()
+        .enumerate()
+        .map(|(i, track)| Mix {
+            play: !track.paused && !held[i],
+            gain: playing
+                .iter()
+                .filter(|&&(j, _)| j != i && !held[j])
+                .filter_map(|&(j, _)| match tracks[j].policy {
+                    MixPolicy::Duck(level) => Some(level.clamp(0., 1.)),
+                    _ => None,
+                })
+                .product(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(policy: MixPolicy, since: Option<u64>) -> Track {
+        Track {
+            policy,
+            paused: false,
+            since,
+        }
+    }
+
+    fn mixed(play: bool, gain: f32) -> Mix {
+        Mix { play, gain }
+    }
+
+    #[test]
+    fn exclusive_tracks_wait_and_hold_later_ones() {
+        let tracks = [
+            track(MixPolicy::Mix, Some(0)),
+            track(MixPolicy::Exclusive, Some(1)),
+            track(MixPolicy::Mix, Some(2)),
+            track(MixPolicy::Exclusive, None),
+        ];
+        assert_eq!(
+            mix(&tracks),
+            [
+                mixed(true, 1.),
+                mixed(false, 1.),
+                mixed(false, 1.),
+                mixed(true, 1.)
+            ]
+        );
+        let first = [
+            track(MixPolicy::Mix, Some(3)),
+            track(MixPolicy::Exclusive, Some(2)),
+        ];
+        assert_eq!(mix(&first), [mixed(false, 1.), mixed(true, 1.)]);
+    }
+
+    #[test]
+    fn ducking_tracks_lower_the_others_while_playing() {
+        let tracks = [
+            track(MixPolicy::Mix, Some(0)),
+            track(MixPolicy::Duck(0.5), Some(1)),
+            track(MixPolicy::Duck(0.5), Some(2)),
+            track(MixPolicy::Duck(0.1), None),
+        ];
+        assert_eq!(
+            mix(&tracks),
+            [
+                mixed(true, 0.25),
+                mixed(true, 0.5),
+                mixed(true, 0.5),
+                mixed(true, 0.25)
+            ]
+        );
+        // One held by an exclusive track is silent, so it doesn't duck.
+        let held = [
+            track(MixPolicy::Exclusive, Some(0)),
+            track(MixPolicy::Duck(0.5), Some(1)),
+        ];
+        assert_eq!(mix(&held), [mixed(true, 1.), mixed(false, 1.)]);
+    }
+
+    #[test]
+    fn paused_tracks_neither_hold_nor_duck() {
+        let mut tracks = [
+            track(MixPolicy::Exclusive, Some(0)),
+            track(MixPolicy::Duck(0.5), Some(1)),
+            track(MixPolicy::Mix, Some(2)),
+        ];
+        tracks[0].paused = true;
+        tracks[1].paused = true;
+        assert_eq!(
+            mix(&tracks),
+            [mixed(false, 1.), mixed(false, 1.), mixed(true, 1.)]
+        );
+    }
+}
@@ -0,0 +1,16 @@
+//! Playback for backends that produce audio buffers rather than speaking themselves.
+//!
+//! Enabled with the `audio-output` feature, with which WinRT plays its speech through it. A backend owns an
+//! [`AudioOutput`], adds each utterance to its queue, hands over the audio once it's synthesized, and delegates
+//! stopping, pausing and queue queries to it. Utterance and word callbacks fire as playback reaches them.
+//!
+//! Outputs playing at once are coordinated by a mixer, which holds or ducks them according to each one's
+//! [`MixPolicy`](crate::MixPolicy).
+
+mod mixer;
+#[cfg(feature = "audio-output")]
+mod output;
+mod queue;
+
+#[cfg(feature = "audio-output")]
+pub(crate) use output::AudioOutput;
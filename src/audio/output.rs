@@ -0,0 +1,452 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    ops::Range,
+    rc::Rc,
+    sync::{mpsc, Arc, LazyLock},
+    thread,
+    time::Duration,
+};
+
+use log::{info, warn};
+use parking_lot::Mutex;
+use rodio::{
+    cpal::{
+        self,
+        traits::{DeviceTrait, HostTrait},
+    },
+    OutputStream, OutputStreamHandle, Sink, Source, StreamError,
+};
+
+use super::{
+    mixer::{self, Track},
+    queue::{Control, Queue},
+};
+use crate::{AudioBuffer, BackendId, Error, MixPolicy, OutputDevice, UtteranceId, WordTiming};
+
+/// An output's place in the mix.
+struct Mixed {
+    sink: Arc<Sink>,
+    track: Track,
+}
+
+#[derive(Default)]
+struct Mixer {
+    outputs: HashMap<u64, Mixed>,
+    next_output: u64,
+    next_start: u64,
+}
+
+impl Mixer {
+    /// Holds or ducks each output according to the policies of those playing alongside it.
+    fn apply(&self) {
+        let tracks: Vec<Track> = self.outputs.values().map(|mixed| mixed.track).collect();
+        for (mixed, mix) in self.outputs.values().zip(mixer::mix(&tracks)) {
+            mixed.sink.set_volume(mix.gain);
+            if mix.play {
+                mixed.sink.play();
+            } else {
+                mixed.sink.pause();
+            }
+        }
+    }
+}
+
+static MIXER: LazyLock<Mutex<Mixer>> = LazyLock::new(Default::default);
+
+/// Runs `f` with the mixer's entry for `output`, then remixes.
+fn remix<F: FnOnce(&mut Mixed)>(output: u64, f: F) {
+    let mut mixer = MIXER.lock();
+    if let Some(mixed) = mixer.outputs.get_mut(&output) {
+        f(mixed);
+    }
+    mixer.apply();
+}
+
+/// Marks `output` as playing, unless it already was, or as idle.
+fn set_playing(output: u64, playing: bool) {
+    let mut mixer = MIXER.lock();
+    let start = mixer.next_start;
+    let Some(mixed) = mixer.outputs.get_mut(&output) else {
+        return;
+    };
+    match (mixed.track.since, playing) {
+        (None, true) => {
+            mixed.track.since = Some(start);
+            mixer.next_start += 1;
+        }
+        (Some(_), false) => mixed.track.since = None,
+        _ => return,
+    }
+    mixer.apply();
+}
+
+/// An output's entry in the mixer, removed once the output is dropped.
+struct MixerEntry(u64);
+
+impl MixerEntry {
+    fn new(sink: Arc<Sink>) -> Self {
+        let mut mixer = MIXER.lock();
+        let id = mixer.next_output;
+        mixer.next_output += 1;
+        mixer.outputs.insert(
+            id,
+            Mixed {
+                sink,
+                track: Default::default(),
+            },
+        );
+        Self(id)
+    }
+}
+
+impl Drop for MixerEntry {
+    fn drop(&mut self) {
+        let mut mixer = MIXER.lock();
+        mixer.outputs.remove(&self.0);
+        mixer.apply();
+    }
+}
+
+struct Output {
+    // Dropping the stream ends playback, so it lives as long as the sink.
+    _stream: OutputStream,
+    sink: Arc<Sink>,
+}
+
+impl Output {
+    fn open(
+        stream: Result<(OutputStream, OutputStreamHandle), StreamError>,
+    ) -> Result<Self, Error> {
+        let (stream, handle) = stream.map_err(|e| Error::AudioOutput(e.to_string()))?;
+        let sink = Sink::try_new(&handle).map_err(|e| Error::AudioOutput(e.to_string()))?;
+        Ok(Self {
+            _stream: stream,
+            sink: Arc::new(sink),
+        })
+    }
+}
+
+/// A point in an utterance's audio that playback reached.
+enum Reached {
+    Begin(u64),
+    Word(u64, Range<usize>),
+    End(u64),
+}
+
+/// Audio waiting to play, with the sample each word begins at.
+pub(crate) struct Audio {
+    buffer: AudioBuffer,
+    words: VecDeque<(usize, Range<usize>)>,
+}
+
+/// An utterance's audio, which reports the points playback reaches and ends early once stopped.
+struct Speech {
+    number: u64,
+    control: Arc<Control>,
+    reached: mpsc::Sender<Reached>,
+    channels: u16,
+    sample_rate: u32,
+    samples: std::vec::IntoIter<i16>,
+    words: VecDeque<(usize, Range<usize>)>,
+    position: usize,
+    /// Once stopped, the samples left to fade out over, and how many it began with.
+    fade: Option<(usize, usize)>,
+    ended: bool,
+}
+
+impl Speech {
+    /// Returns the next sample of the stopped audio as it fades out, ending it once silent, or at once if it hadn't
+    /// begun.
+    fn fade_out(&mut self) -> Option<i16> {
+        let (left, total) = match self.fade {
+            Some(fade) => fade,
+            None if self.position == 0 => (0, 0),
+            None => {
+                let frames = self.control.fade().as_secs_f64() * self.sample_rate as f64;
+                let samples = frames as usize * self.channels as usize;
+                (samples, samples)
+            }
+        };
+        let sample = match left {
+            0 => None,
+            _ => self.samples.next(),
+        };
+        let Some(sample) = sample else {
+            self.ended = true;
+            return None;
+        };
+        self.fade = Some((left - 1, total));
+        Some((sample as f32 * left as f32 / total as f32) as i16)
+    }
+}
+
+impl Iterator for Speech {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if self.ended {
+            return None;
+        }
+        if self.control.is_stopped() {
+            return self.fade_out();
+        }
+        // Callbacks run on a thread of their own, so they can't hold up the audio.
+        if self.position == 0 {
+            let _ = self.reached.send(Reached::Begin(self.number));
+        }
+        while self
+            .words
+            .front()
+            .is_some_and(|&(at, _)| at <= self.position)
+        {
+            if let Some((_, range)) = self.words.pop_front() {
+                let _ = self.reached.send(Reached::Word(self.number, range));
+            }
+        }
+        let sample = self.samples.next();
+        match sample {
+            Some(_) => self.position += 1,
+            None => {
+                self.ended = true;
+                let _ = self.reached.send(Reached::End(self.number));
+            }
+        }
+        sample
+    }
+}
+
+impl Source for Speech {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Appends the audio of utterance `number` to `output`'s sink.
+fn append(
+    output: u64,
+    reached: &mpsc::Sender<Reached>,
+    number: u64,
+    control: Arc<Control>,
+    audio: Audio,
+) {
+    let Some(sink) = MIXER
+        .lock()
+        .outputs
+        .get(&output)
+        .map(|mixed| mixed.sink.clone())
+    else {
+        return;
+    };
+    sink.append(Speech {
+        number,
+        control,
+        reached: reached.clone(),
+        channels: audio.buffer.channels,
+        sample_rate: audio.buffer.sample_rate,
+        samples: audio.buffer.samples.into_iter(),
+        words: audio.words,
+        position: 0,
+        fade: None,
+        ended: false,
+    });
+}
+
+/// An utterance whose audio is still to come, which can be provided from any thread.
+pub(crate) struct Pending {
+    number: u64,
+    output: u64,
+    queue: Arc<Queue<Audio>>,
+    reached: mpsc::Sender<Reached>,
+}
+
+impl Pending {
+    /// Plays `buffer` once the utterances queued before it have, firing word boundary callbacks at `words`.
+    pub(crate) fn play(self, buffer: AudioBuffer, words: &[WordTiming]) {
+        if buffer.channels == 0 || buffer.sample_rate == 0 {
+            warn!("Dropping audio with no channels or sample rate");
+            return self.fail();
+        }
+        let words = words
+            .iter()
+            .map(|word| {
+                // Words begin on a frame, so every channel reaches them together.
+                let frame = (word.start.as_secs_f64() * buffer.sample_rate as f64) as usize;
+                (frame * buffer.channels as usize, word.range.clone())
+            })
+            .collect();
+        let audio = Audio { buffer, words };
+        let (output, reached) = (self.output, &self.reached);
+        self.queue
+            .ready(self.number, audio, |number, control, audio| {
+                append(output, reached, number, control, audio)
+            });
+    }
+
+    /// Gives up on the utterance, firing its stop callback.
+    pub(crate) fn fail(self) {
+        let (output, reached) = (self.output, &self.reached);
+        self.queue.fail(self.number, |number, control, audio| {
+            append(output, reached, number, control, audio)
+        });
+        if self.queue.len() == 0 {
+            set_playing(output, false);
+        }
+    }
+}
+
+/// A queue of utterances playing through an output device.
+#[derive(Clone)]
+pub(crate) struct AudioOutput {
+    output: Rc<RefCell<Output>>,
+    mixer: Rc<MixerEntry>,
+    queue: Arc<Queue<Audio>>,
+    reached: mpsc::Sender<Reached>,
+}
+
+impl AudioOutput {
+    /// Opens the default output device for `backend`, whose utterance IDs are made with `utterance_id`.
+    pub(crate) fn new(
+        backend: BackendId,
+        utterance_id: fn(u64) -> UtteranceId,
+    ) -> Result<Self, Error> {
+        info!("Opening audio output for {:?}", backend);
+        let output = Output::open(OutputStream::try_default())?;
+        let mixer = MixerEntry::new(output.sink.clone());
+        let queue = Arc::new(Queue::new(backend, utterance_id));
+        let (reached, receiver) = mpsc::channel();
+        // Ends once the output and the audio it played are gone.
+        let (events, id) = (queue.clone(), mixer.0);
+        thread::Builder::new()
+            .name("tts-audio-output".into())
+            .spawn(move || {
+                for reached in receiver {
+                    match reached {
+                        Reached::Begin(number) => events.begin(number),
+                        Reached::Word(number, range) => events.word(number, range),
+                        Reached::End(number) => {
+                            if events.end(number) {
+                                set_playing(id, false);
+                            }
+                        }
+                    }
+                }
+            })
+            .map_err(|e| Error::AudioOutput(e.to_string()))?;
+        Ok(Self {
+            output: Rc::new(RefCell::new(output)),
+            mixer: Rc::new(mixer),
+            queue,
+            reached,
+        })
+    }
+
+    /// Adds an utterance whose audio is still to come, returning its ID and the handle to provide its audio with.
+    pub(crate) fn push(&self) -> (UtteranceId, Pending) {
+        let number = self.queue.push();
+        // Anything this output must wait for holds it before its audio begins.
+        set_playing(self.mixer.0, true);
+        let pending = Pending {
+            number,
+            output: self.mixer.0,
+            queue: self.queue.clone(),
+            reached: self.reached.clone(),
+        };
+        (self.queue.utterance_id(number), pending)
+    }
+
+    /// Stops playback and drops the queue, firing the stop callback for each utterance. The audio playing fades out
+    /// over `fade` without holding up the caller, unless the output is paused, in which case it ends at once.
+    pub(crate) fn stop(&self, fade: Duration) {
+        let fade = if self.is_paused() {
+            Duration::ZERO
+        } else {
+            fade
+        };
+        self.queue.stop(fade);
+        set_playing(self.mixer.0, false);
+    }
+
+    /// Stops the utterance playing and moves on to the next, firing the stop callback for the one skipped.
+    pub(crate) fn skip(&self) {
+        let (output, reached) = (self.mixer.0, &self.reached);
+        self.queue
+            .skip(|number, control, audio| append(output, reached, number, control, audio));
+        if self.queue.len() == 0 {
+            set_playing(output, false);
+        }
+    }
+
+    pub(crate) fn pause(&self) {
+        remix(self.mixer.0, |mixed| mixed.track.paused = true);
+    }
+
+    pub(crate) fn resume(&self) {
+        remix(self.mixer.0, |mixed| mixed.track.paused = false);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        MIXER
+            .lock()
+            .outputs
+            .get(&self.mixer.0)
+            .is_some_and(|mixed| mixed.track.paused)
+    }
+
+    pub(crate) fn set_mix_policy(&self, policy: MixPolicy) {
+        remix(self.mixer.0, |mixed| mixed.track.policy = policy);
+    }
+
+    pub(crate) fn is_speaking(&self) -> bool {
+        self.queue.len() > 0
+    }
+
+    pub(crate) fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub(crate) fn output_devices(&self) -> Result<Vec<OutputDevice>, Error> {
+        let devices = cpal::default_host()
+            .output_devices()
+            .map_err(|e| Error::AudioOutput(e.to_string()))?;
+        Ok(devices
+            .filter_map(|device| device.name().ok())
+            .map(|name| OutputDevice {
+                id: name.clone(),
+                name,
+            })
+            .collect())
+    }
+
+    /// Switches to `device`, or the default device if `None`, stopping anything playing.
+    pub(crate) fn set_output_device(&self, device: Option<&OutputDevice>) -> Result<(), Error> {
+        let output = match device {
+            Some(device) => {
+                let device = cpal::default_host()
+                    .output_devices()
+                    .map_err(|e| Error::AudioOutput(e.to_string()))?
+                    .find(|d| d.name().ok().as_ref() == Some(&device.id))
+                    .ok_or(Error::OperationFailed)?;
+                Output::open(OutputStream::try_from_device(&device))?
+            }
+            None => Output::open(OutputStream::try_default())?,
+        };
+        self.stop(Duration::ZERO);
+        let sink = output.sink.clone();
+        *self.output.borrow_mut() = output;
+        remix(self.mixer.0, |mixed| mixed.sink = sink);
+        Ok(())
+    }
+}
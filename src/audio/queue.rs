@@ -0,0 +1,340 @@
+//! The utterances an output is playing or has queued, which fire its backend's callbacks as playback reaches them.
+//!
+//! Audio can be ready out of order, as when a short utterance is synthesized before a long one queued ahead of it,
+//! so each is handed over to play only once those before it have been.
+
+use std::{
+    collections::VecDeque,
+    ops::Range,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use log::trace;
+use parking_lot::Mutex;
+
+use crate::{callbacks, BackendId, UtteranceEvent, UtteranceId};
+
+static NEXT_UTTERANCE: Mutex<u64> = Mutex::new(0);
+
+/// Tells the audio thread that an utterance was stopped, so its audio ends early.
+#[derive(Debug, Default)]
+pub(crate) struct Control {
+    stopped: AtomicBool,
+    /// How long the audio fades out for once stopped, in nanoseconds.
+    fade: AtomicU64,
+}
+
+impl Control {
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Acquire)
+    }
+
+    /// How long the audio fades out for, if it's stopped.
+    pub(crate) fn fade(&self) -> Duration {
+        Duration::from_nanos(self.fade.load(Ordering::Acquire))
+    }
+
+    fn stop(&self, fade: Duration) {
+        self.fade.store(fade.as_nanos() as u64, Ordering::Release);
+        self.stopped.store(true, Ordering::Release);
+    }
+}
+
+struct Utterance<T> {
+    number: u64,
+    control: Arc<Control>,
+    /// The audio, from when it's ready until it's handed over to play.
+    audio: Option<T>,
+    /// Whether the audio was handed over.
+    playing: bool,
+}
+
+/// An output's utterances, oldest first, holding the audio of each until it can play.
+pub(crate) struct Queue<T> {
+    backend: BackendId,
+    utterance_id: fn(u64) -> UtteranceId,
+    utterances: Mutex<VecDeque<Utterance<T>>>,
+}
+
+impl<T> Queue<T> {
+    /// Creates a queue for `backend`, whose utterance IDs are made with `utterance_id`.
+    pub(crate) fn new(backend: BackendId, utterance_id: fn(u64) -> UtteranceId) -> Self {
+        Self {
+            backend,
+            utterance_id,
+            utterances: Default::default(),
+        }
+    }
+
+    pub(crate) fn utterance_id(&self, number: u64) -> UtteranceId {
+        (self.utterance_id)(number)
+    }
+
+    fn fire(&self, event: UtteranceEvent, number: u64) {
+        let utterance = self.utterance_id(number);
+        trace!("Firing {:?} for {:?}", event, utterance);
+        callbacks::fire_utterance(self.backend, event, utterance);
+    }
+
+    /// Adds an utterance whose audio is still to come, returning its number.
+    pub(crate) fn push(&self) -> u64 {
+        let number = {
+            let mut next = NEXT_UTTERANCE.lock();
+            let number = *next;
+            *next += 1;
+            number
+        };
+        self.utterances.lock().push_back(Utterance {
+            number,
+            control: Default::default(),
+            audio: None,
+            playing: false,
+        });
+        number
+    }
+
+    /// Stores the audio of utterance `number`, then calls `play` with that of each utterance no longer waiting on
+    /// one before it, in order. Audio for an utterance that was stopped is dropped.
+    pub(crate) fn ready<F: FnMut(u64, Arc<Control>, T)>(&self, number: u64, audio: T, play: F) {
+        let mut utterances = self.utterances.lock();
+        if let Some(utterance) = utterances.iter_mut().find(|u| u.number == number) {
+            utterance.audio = Some(audio);
+        }
+        Self::play_ready(&mut utterances, play);
+    }
+
+    /// Drops utterance `number`, whose audio couldn't be made, firing its stop callback, then plays what was waiting
+    /// on it as `ready()` does.
+    pub(crate) fn fail<F: FnMut(u64, Arc<Control>, T)>(&self, number: u64, play: F) {
+        let failed = {
+            let mut utterances = self.utterances.lock();
+            let position = utterances.iter().position(|u| u.number == number);
+            let failed = position.and_then(|position| utterances.remove(position));
+            Self::play_ready(&mut utterances, play);
+            failed
+        };
+        if failed.is_some() {
+            self.fire(UtteranceEvent::Stop, number);
+        }
+    }
+
+    // Handing audio over while the queue is locked keeps it in order when several threads provide it.
+    fn play_ready<F: FnMut(u64, Arc<Control>, T)>(
+        utterances: &mut VecDeque<Utterance<T>>,
+        mut play: F,
+    ) {
+        for utterance in utterances.iter_mut().skip_while(|u| u.playing) {
+            let Some(audio) = utterance.audio.take() else {
+                break;
+            };
+            utterance.playing = true;
+            play(utterance.number, utterance.control.clone(), audio);
+        }
+    }
+
+    /// Fires the begin callback of utterance `number`, whose audio is starting, unless it was stopped.
+    pub(crate) fn begin(&self, number: u64) {
+        if self.is_current(number) {
+            self.fire(UtteranceEvent::Begin, number);
+        }
+    }
+
+    /// Fires the word boundary callback of utterance `number` for the word at `range`, unless it was stopped.
+    pub(crate) fn word(&self, number: u64, range: Range<usize>) {
+        if self.is_current(number) {
+            callbacks::fire_word(self.backend, self.utterance_id(number), range);
+        }
+    }
+
+    /// Removes utterance `number`, whose audio finished, firing its end callback unless it was stopped. Returns
+    /// whether the queue is now empty.
+    pub(crate) fn end(&self, number: u64) -> bool {
+        let (ended, empty) = {
+            let mut utterances = self.utterances.lock();
+            let ended = utterances.front().is_some_and(|u| u.number == number);
+            if ended {
+                utterances.pop_front();
+            }
+            (ended, utterances.is_empty())
+        };
+        if ended {
+            self.fire(UtteranceEvent::End, number);
+        }
+        empty
+    }
+
+    /// Stops every utterance, fading out the audio playing over `fade`, and fires their stop callbacks.
+    pub(crate) fn stop(&self, fade: Duration) {
+        let stopped: Vec<u64> = self
+            .utterances
+            .lock()
+            .drain(..)
+            .map(|utterance| {
+                utterance.control.stop(fade);
+                utterance.number
+            })
+            .collect();
+        for number in stopped {
+            self.fire(UtteranceEvent::Stop, number);
+        }
+    }
+
+    /// Stops the utterance at the front, firing its stop callback, then plays what was waiting on it as `ready()`
+    /// does.
+    pub(crate) fn skip<F: FnMut(u64, Arc<Control>, T)>(&self, play: F) {
+        let skipped = {
+            let mut utterances = self.utterances.lock();
+            let skipped = utterances.pop_front();
+            if let Some(skipped) = &skipped {
+                skipped.control.stop(Duration::ZERO);
+            }
+            Self::play_ready(&mut utterances, play);
+            skipped
+        };
+        if let Some(skipped) = skipped {
+            self.fire(UtteranceEvent::Stop, skipped.number);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.utterances.lock().len()
+    }
+
+    fn is_current(&self, number: u64) -> bool {
+        self.utterances
+            .lock()
+            .front()
+            .is_some_and(|u| u.number == number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{backends::fake, Tts};
+
+    type Log = Rc<RefCell<Vec<(&'static str, UtteranceId)>>>;
+
+    /// Returns a queue firing the callbacks of a new fake `Tts`, and a log of the utterance events they receive.
+    fn queue() -> (Tts, Queue<&'static str>, Log) {
+        let tts = Tts::fake();
+        let backend = tts.0.read().id().unwrap();
+        let log: Log = Default::default();
+        for (name, event) in [
+            ("begin", UtteranceEvent::Begin),
+            ("end", UtteranceEvent::End),
+            ("stop", UtteranceEvent::Stop),
+        ] {
+            let log = log.clone();
+            let callback: Box<dyn FnMut(UtteranceId)> =
+                Box::new(move |id| log.borrow_mut().push((name, id)));
+            match event {
+                UtteranceEvent::Begin => tts.on_utterance_begin(Some(callback)),
+                UtteranceEvent::End => tts.on_utterance_end(Some(callback)),
+                UtteranceEvent::Stop => tts.on_utterance_stop(Some(callback)),
+            }
+            .unwrap();
+        }
+        let words = log.clone();
+        tts.on_word_boundary(Some(Box::new(move |id, _| {
+            words.borrow_mut().push(("word", id))
+        })))
+        .unwrap();
+        (tts, Queue::new(backend, fake::utterance_id), log)
+    }
+
+    /// Collects the audio `ready()` and its kin hand over, none of which should already be stopped.
+    fn played<'a>(
+        played: &'a mut Vec<(u64, &'static str)>,
+    ) -> impl FnMut(u64, Arc<Control>, &'static str) + 'a {
+        move |number, control, audio| {
+            assert!(!control.is_stopped());
+            played.push((number, audio));
+        }
+    }
+
+    #[test]
+    fn audio_plays_in_order_once_ready() {
+        let (_tts, queue, log) = queue();
+        let (a, b, c) = (queue.push(), queue.push(), queue.push());
+        let mut handed_over = vec![];
+        queue.ready(b, "b", played(&mut handed_over));
+        assert!(handed_over.is_empty());
+        queue.ready(a, "a", played(&mut handed_over));
+        queue.ready(c, "c", played(&mut handed_over));
+        assert_eq!(handed_over, [(a, "a"), (b, "b"), (c, "c")]);
+        queue.begin(a);
+        queue.word(a, 0..3);
+        assert!(!queue.end(a));
+        queue.begin(b);
+        assert!(!queue.end(b));
+        queue.begin(c);
+        assert!(queue.end(c));
+        let id = |n| queue.utterance_id(n);
+        assert_eq!(
+            *log.borrow(),
+            [
+                ("begin", id(a)),
+                ("word", id(a)),
+                ("end", id(a)),
+                ("begin", id(b)),
+                ("end", id(b)),
+                ("begin", id(c)),
+                ("end", id(c)),
+            ]
+        );
+    }
+
+    #[test]
+    fn stopping_fires_stop_once_and_drops_late_audio() {
+        let (_tts, queue, log) = queue();
+        let (a, b) = (queue.push(), queue.push());
+        let mut controls = vec![];
+        queue.ready(a, "a", |_, control, _| controls.push(control));
+        queue.begin(a);
+        queue.stop(Duration::from_millis(20));
+        assert!(controls
+            .iter()
+            .all(|control| control.is_stopped() && control.fade() == Duration::from_millis(20)));
+        assert_eq!(queue.len(), 0);
+        // The audio thread may still report what it was playing, and synthesis may still finish.
+        queue.ready(b, "b", |_, _, _| panic!("stopped audio was played"));
+        queue.begin(a);
+        queue.word(a, 0..3);
+        assert!(queue.end(a));
+        let id = |n| queue.utterance_id(n);
+        assert_eq!(
+            *log.borrow(),
+            [("begin", id(a)), ("stop", id(a)), ("stop", id(b))]
+        );
+    }
+
+    #[test]
+    fn skipping_plays_what_waited_on_the_skipped_utterance() {
+        let (_tts, queue, log) = queue();
+        let (a, b, c) = (queue.push(), queue.push(), queue.push());
+        let mut handed_over = vec![];
+        queue.ready(b, "b", played(&mut handed_over));
+        queue.skip(played(&mut handed_over));
+        assert_eq!(handed_over, [(b, "b")]);
+        queue.fail(c, played(&mut handed_over));
+        queue.begin(b);
+        assert!(queue.end(b));
+        let id = |n| queue.utterance_id(n);
+        assert_eq!(
+            *log.borrow(),
+            [
+                ("stop", id(a)),
+                ("stop", id(c)),
+                ("begin", id(b)),
+                ("end", id(b)),
+            ]
+        );
+    }
+}
@@ -1,45 +1,141 @@
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", feature = "android"))]
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ffi::{CStr, CString},
+    fs,
     os::raw::c_void,
-    sync::{Mutex, RwLock},
-    thread,
-    time::{Duration, Instant},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, SyncSender},
+        LazyLock,
+    },
+    time::Duration,
 };
 
 use jni::{
-    objects::{GlobalRef, JObject, JString},
+    objects::{GlobalRef, JObject, JObjectArray, JString},
     sys::{jfloat, jint, JNI_VERSION_1_6},
     JNIEnv, JavaVM,
 };
-use lazy_static::lazy_static;
-use log::{error, info};
+use log::{error, info, trace, warn};
+use oxilangtag::LanguageTag;
+use parking_lot::{Mutex, RwLock};
 
-use crate::{Backend, BackendId, Error, Features, UtteranceId, Voice, CALLBACKS};
+use crate::{
+    android::{AudioUsage, Engine, LanguageAvailability},
+    callbacks, utf16_range_to_byte_range, AudioBuffer, Backend, BackendId, BackendInfo, Backends,
+    Capability, DuckingMode, Earcon, Error, Features, Gender, UtteranceEvent, UtteranceId,
+    UtteranceOptions, Voice, VoiceQuality, WordTiming,
+};
+
+static BRIDGE: Mutex<Option<GlobalRef>> = Mutex::new(None);
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+static PENDING_INITIALIZATIONS: LazyLock<Mutex<HashMap<u64, PendingInitialization>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static FAILED_INITIALIZATIONS: LazyLock<RwLock<HashSet<u64>>> =
+    LazyLock::new(|| RwLock::new(HashSet::new()));
+static NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+static SYNTHESES: LazyLock<Mutex<HashMap<u64, SyncSender<bool>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// A word reported while synthesizing to a file, as its UTF-16 start and end offsets and the audio frame it begins at.
+type SynthesizedWord = (i32, i32, i32);
+
+/// The words reported while synthesizing each utterance to a file.
+static SYNTHESIS_RANGES: LazyLock<Mutex<HashMap<u64, Vec<SynthesizedWord>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static UTTERANCE_TEXTS: LazyLock<Mutex<HashMap<u64, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+/// Utterances speaking or queued on each backend with ducking enabled.
+static DUCKING: LazyLock<Mutex<HashMap<BackendId, HashSet<u64>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Requests or abandons transient audio focus, which asks other apps to lower their volume while speech plays.
+fn audio_focus(env: &mut JNIEnv, request: bool) -> Result<(), Error> {
+    let context = crate::android::context(env)?;
+    let service = env.new_string("audio")?;
+    let manager = env
+        .call_method(
+            context.as_obj(),
+            "getSystemService",
+            "(Ljava/lang/String;)Ljava/lang/Object;",
+            &[(&service).into()],
+        )?
+        .l()?;
+    let listener = JObject::null();
+    if request {
+        // AudioManager.STREAM_MUSIC, AudioManager.AUDIOFOCUS_GAIN_TRANSIENT_MAY_DUCK
+        env.call_method(
+            &manager,
+            "requestAudioFocus",
+            "(Landroid/media/AudioManager$OnAudioFocusChangeListener;II)I",
+            &[(&listener).into(), 3.into(), 3.into()],
+        )?;
+    } else {
+        env.call_method(
+            &manager,
+            "abandonAudioFocus",
+            "(Landroid/media/AudioManager$OnAudioFocusChangeListener;)I",
+            &[(&listener).into()],
+        )?;
+    }
+    Ok(())
+}
+
+/// Takes audio focus before the first of a backend's utterances plays, if it ducks other audio.
+fn duck(env: &mut JNIEnv, backend_id: BackendId, utterance_id: u64) {
+    let mut ducking = DUCKING.lock();
+    if let Some(active) = ducking.get_mut(&backend_id) {
+        if active.is_empty() {
+            if let Err(e) = audio_focus(env, true) {
+                error!("Failed to request audio focus: {:?}", e);
+            }
+        }
+        active.insert(utterance_id);
+    }
+}
+
+/// Releases audio focus once a backend's last utterance finishes.
+fn unduck(env: &mut JNIEnv, backend_id: BackendId, utterance_id: u64) {
+    let mut ducking = DUCKING.lock();
+    if let Some(active) = ducking.get_mut(&backend_id) {
+        if active.remove(&utterance_id) && active.is_empty() {
+            if let Err(e) = audio_focus(env, false) {
+                error!("Failed to abandon audio focus: {:?}", e);
+            }
+        }
+    }
+}
 
-lazy_static! {
-    static ref BRIDGE: Mutex<Option<GlobalRef>> = Mutex::new(None);
-    static ref NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
-    static ref PENDING_INITIALIZATIONS: RwLock<HashSet<u64>> = RwLock::new(HashSet::new());
-    static ref NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+/// Signals a thread waiting on `synthesizeToFile()`, returning whether the utterance was a synthesis.
+fn finish_synthesis(utterance_id: u64, success: bool) -> bool {
+    let mut syntheses = SYNTHESES.lock();
+    if let Some(tx) = syntheses.remove(&utterance_id) {
+        let _ = tx.send(success);
+        true
+    } else {
+        false
+    }
 }
 
 #[allow(non_snake_case)]
 #[no_mangle]
 pub extern "system" fn JNI_OnLoad(vm: JavaVM, _: *mut c_void) -> jint {
     let mut env = vm.get_env().expect("Cannot get reference to the JNIEnv");
-    let b = env
-        .find_class("rs/tts/Bridge")
-        .expect("Failed to find `Bridge`");
-    let b = env
-        .new_global_ref(b)
-        .expect("Failed to create `Bridge` `GlobalRef`");
-    let mut bridge = BRIDGE.lock().unwrap();
-    *bridge = Some(b);
+    set_bridge(&mut env).expect("Failed to find `Bridge`");
     JNI_VERSION_1_6
 }
 
+/// Caches the `rs.tts.Bridge` class, if it hasn't been already.
+pub(crate) fn set_bridge(env: &mut JNIEnv) -> Result<(), Error> {
+    let mut bridge = BRIDGE.lock();
+    if bridge.is_none() {
+        let b = env.find_class("rs/tts/Bridge")?;
+        let b = env.new_global_ref(b)?;
+        *bridge = Some(b);
+    }
+    Ok(())
+}
+
 #[no_mangle]
 #[allow(non_snake_case)]
 pub unsafe extern "C" fn Java_rs_tts_Bridge_onInit(mut env: JNIEnv, obj: JObject, status: jint) {
@@ -48,10 +144,32 @@ pub unsafe extern "C" fn Java_rs_tts_Bridge_onInit(mut env: JNIEnv, obj: JObject
         .expect("Failed to get backend ID")
         .i()
         .expect("Failed to cast to int") as u64;
-    let mut pending = PENDING_INITIALIZATIONS.write().unwrap();
-    (*pending).remove(&id);
+    // Hold the lock while flushing so utterances spoken concurrently can't
+    // jump ahead of the queued ones.
+    let mut pending = PENDING_INITIALIZATIONS.lock();
+    let initialization = (*pending).remove(&id);
     if status != 0 {
         error!("Failed to initialize TTS engine");
+        let mut failed = FAILED_INITIALIZATIONS.write();
+        (*failed).insert(id);
+        return;
+    }
+    let Some(PendingInitialization {
+        tts: Some(tts),
+        utterances,
+    }) = initialization
+    else {
+        return;
+    };
+    trace!(
+        "TTS engine initialized, speaking {} queued utterances",
+        utterances.len()
+    );
+    for utterance in utterances {
+        if let Err(e) = speak_utterance(&mut env, BackendId::Android(id), tts.as_obj(), &utterance)
+        {
+            error!("Failed to speak queued utterance: {:?}", e);
+        }
     }
 }
 
@@ -75,11 +193,7 @@ pub unsafe extern "C" fn Java_rs_tts_Bridge_onStart(
     .unwrap();
     let utterance_id = utterance_id.parse::<u64>().unwrap();
     let utterance_id = UtteranceId::Android(utterance_id);
-    let mut callbacks = CALLBACKS.lock().unwrap();
-    let cb = callbacks.get_mut(&backend_id).unwrap();
-    if let Some(f) = cb.utterance_begin.as_mut() {
-        f(utterance_id);
-    }
+    callbacks::fire_utterance(backend_id, UtteranceEvent::Begin, utterance_id);
 }
 
 #[no_mangle]
@@ -101,12 +215,13 @@ pub unsafe extern "C" fn Java_rs_tts_Bridge_onStop(
     .into_string()
     .unwrap();
     let utterance_id = utterance_id.parse::<u64>().unwrap();
-    let utterance_id = UtteranceId::Android(utterance_id);
-    let mut callbacks = CALLBACKS.lock().unwrap();
-    let cb = callbacks.get_mut(&backend_id).unwrap();
-    if let Some(f) = cb.utterance_end.as_mut() {
-        f(utterance_id);
+    UTTERANCE_TEXTS.lock().remove(&utterance_id);
+    unduck(&mut env, backend_id, utterance_id);
+    if finish_synthesis(utterance_id, false) {
+        return;
     }
+    let utterance_id = UtteranceId::Android(utterance_id);
+    callbacks::fire_utterance(backend_id, UtteranceEvent::End, utterance_id);
 }
 
 #[no_mangle]
@@ -128,12 +243,13 @@ pub unsafe extern "C" fn Java_rs_tts_Bridge_onDone(
     .into_string()
     .unwrap();
     let utterance_id = utterance_id.parse::<u64>().unwrap();
-    let utterance_id = UtteranceId::Android(utterance_id);
-    let mut callbacks = CALLBACKS.lock().unwrap();
-    let cb = callbacks.get_mut(&backend_id).unwrap();
-    if let Some(f) = cb.utterance_stop.as_mut() {
-        f(utterance_id);
+    UTTERANCE_TEXTS.lock().remove(&utterance_id);
+    unduck(&mut env, backend_id, utterance_id);
+    if finish_synthesis(utterance_id, true) {
+        return;
     }
+    let utterance_id = UtteranceId::Android(utterance_id);
+    callbacks::fire_utterance(backend_id, UtteranceEvent::Stop, utterance_id);
 }
 
 #[no_mangle]
@@ -155,136 +271,592 @@ pub unsafe extern "C" fn Java_rs_tts_Bridge_onError(
     .into_string()
     .unwrap();
     let utterance_id = utterance_id.parse::<u64>().unwrap();
+    UTTERANCE_TEXTS.lock().remove(&utterance_id);
+    unduck(&mut env, backend_id, utterance_id);
+    if finish_synthesis(utterance_id, false) {
+        return;
+    }
     let utterance_id = UtteranceId::Android(utterance_id);
-    let mut callbacks = CALLBACKS.lock().unwrap();
-    let cb = callbacks.get_mut(&backend_id).unwrap();
-    if let Some(f) = cb.utterance_end.as_mut() {
-        f(utterance_id);
+    callbacks::fire_utterance(backend_id, UtteranceEvent::End, utterance_id);
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+pub unsafe extern "C" fn Java_rs_tts_Bridge_onRangeStart(
+    mut env: JNIEnv,
+    obj: JObject,
+    utterance_id: JString,
+    start: jint,
+    end: jint,
+    frame: jint,
+) {
+    let backend_id = env
+        .get_field(obj, "backendId", "I")
+        .expect("Failed to get backend ID")
+        .i()
+        .expect("Failed to cast to int") as u64;
+    let backend_id = BackendId::Android(backend_id);
+    let utterance_id = CString::from(CStr::from_ptr(
+        env.get_string(&utterance_id).unwrap().as_ptr(),
+    ))
+    .into_string()
+    .unwrap();
+    let utterance_id = utterance_id.parse::<u64>().unwrap();
+    trace!(
+        "onRangeStart: utterance {}, {}..{}, frame {}",
+        utterance_id,
+        start,
+        end,
+        frame
+    );
+    if let Some(ranges) = SYNTHESIS_RANGES.lock().get_mut(&utterance_id) {
+        ranges.push((start, end, frame));
+        return;
+    }
+    // `start` and `end` are UTF-16 offsets into the utterance text.
+    let range = {
+        let texts = UTTERANCE_TEXTS.lock();
+        let Some(text) = texts.get(&utterance_id) else {
+            return;
+        };
+        let start = start.max(0) as usize;
+        let len = (end.max(0) as usize).saturating_sub(start);
+        utf16_range_to_byte_range(text, start, len)
+    };
+    let utterance_id = UtteranceId::Android(utterance_id);
+    callbacks::fire_word(backend_id, utterance_id, range);
+}
+
+fn next_utterance_id() -> u64 {
+    let mut utterance_id = NEXT_UTTERANCE_ID.lock();
+    let uid = *utterance_id;
+    *utterance_id += 1;
+    uid
+}
+
+/// An utterance spoken before the engine finished initializing.
+struct PendingUtterance {
+    id: u64,
+    /// The text to speak, or the path of the earcon to play.
+    text: String,
+    interrupt: bool,
+    volume: f32,
+    pan: Option<f32>,
+    earcon: bool,
+}
+
+/// State for a backend whose `onInit` hasn't yet fired.
+#[derive(Default)]
+struct PendingInitialization {
+    tts: Option<GlobalRef>,
+    utterances: Vec<PendingUtterance>,
+}
+
+impl PendingInitialization {
+    /// Drops all queued utterances.
+    fn clear(&mut self) {
+        let mut texts = UTTERANCE_TEXTS.lock();
+        for utterance in self.utterances.drain(..) {
+            texts.remove(&utterance.id);
+        }
     }
 }
 
 #[derive(Clone)]
 pub(crate) struct Android {
     id: BackendId,
+    bid: u64,
     tts: GlobalRef,
+    /// The package of the engine asked for, or `None` for the default.
+    engine: Option<String>,
+    /// Whether the engine reports progress to the bridge.
+    listening: bool,
     rate: f32,
     pitch: f32,
+    volume: f32,
 }
 
 impl Android {
     pub(crate) fn new() -> Result<Self, Error> {
+        Self::with_engine(None)
+    }
+
+    pub(crate) fn with_engine(engine: Option<&str>) -> Result<Self, Error> {
         info!("Initializing Android backend");
-        let mut backend_id = NEXT_BACKEND_ID.lock().unwrap();
+        let mut backend_id = NEXT_BACKEND_ID.lock();
         let bid = *backend_id;
         let id = BackendId::Android(bid);
         *backend_id += 1;
         drop(backend_id);
-        let ctx = ndk_context::android_context();
-        let vm = unsafe { jni::JavaVM::from_raw(ctx.vm().cast()) }?;
-        let context = unsafe { JObject::from_raw(ctx.context().cast()) };
+        let vm = Self::vm()?;
         let mut env = vm.attach_current_thread_permanently()?;
-        let bridge = BRIDGE.lock().unwrap();
-        if let Some(bridge) = &*bridge {
-            let bridge = env.new_object(bridge, "(I)V", &[(bid as jint).into()])?;
-            let tts = env.new_object(
+        let tts = Self::engine(&mut env, bid, engine)?;
+        Ok(Self {
+            id,
+            bid,
+            tts,
+            engine: engine.map(String::from),
+            listening: false,
+            rate: 1.,
+            pitch: 1.,
+            volume: 1.,
+        })
+    }
+
+    /// Creates a `TextToSpeech` for backend `bid`, which queues utterances until its `onInit` fires.
+    ///
+    /// Progress isn't reported until `listen()` is called.
+    fn engine(env: &mut JNIEnv, bid: u64, engine: Option<&str>) -> Result<GlobalRef, Error> {
+        let context = crate::android::context(env)?;
+        let bridge = BRIDGE.lock();
+        let Some(bridge) = &*bridge else {
+            return Err(Error::BackendUnavailable {
+                backend: Backends::Android,
+                reason:
+                    "the `rs.tts.Bridge` class wasn't found; check that it's packaged with the app"
+                        .into(),
+            });
+        };
+        let bridge = env.new_object(bridge, "(I)V", &[(bid as jint).into()])?;
+        // Register before constructing the engine, since `onInit` may fire
+        // on another thread before the constructor returns.
+        {
+            let mut pending = PENDING_INITIALIZATIONS.lock();
+            (*pending).insert(bid, Default::default());
+        }
+        let tts = if let Some(engine) = engine {
+            trace!("Using engine {}", engine);
+            let engine = env.new_string(engine)?;
+            env.new_object(
+                "android/speech/tts/TextToSpeech",
+                "(Landroid/content/Context;Landroid/speech/tts/TextToSpeech$OnInitListener;Ljava/lang/String;)V",
+                &[(&context).into(), (&bridge).into(), (&engine).into()],
+            )?
+        } else {
+            env.new_object(
                 "android/speech/tts/TextToSpeech",
                 "(Landroid/content/Context;Landroid/speech/tts/TextToSpeech$OnInitListener;)V",
                 &[(&context).into(), (&bridge).into()],
-            )?;
-            env.call_method(
-                &tts,
-                "setOnUtteranceProgressListener",
-                "(Landroid/speech/tts/UtteranceProgressListener;)I",
-                &[(&bridge).into()],
-            )?;
-            {
-                let mut pending = PENDING_INITIALIZATIONS.write().unwrap();
-                (*pending).insert(bid);
+            )?
+        };
+        let tts = env.new_global_ref(tts)?;
+        // Initialization completes asynchronously in `onInit`. Until then,
+        // calls to `speak` are queued rather than blocking here.
+        {
+            let mut pending = PENDING_INITIALIZATIONS.lock();
+            if let Some(initialization) = (*pending).get_mut(&bid) {
+                initialization.tts = Some(tts.clone());
             }
-            let tts = env.new_global_ref(tts)?;
-            // This hack makes my brain bleed.
-            const MAX_WAIT_TIME: Duration = Duration::from_millis(500);
-            let start = Instant::now();
-            // Wait a max of 500ms for initialization, then return an error to avoid hanging.
-            loop {
-                {
-                    let pending = PENDING_INITIALIZATIONS.read().unwrap();
-                    if !(*pending).contains(&bid) {
-                        break;
-                    }
-                    if start.elapsed() > MAX_WAIT_TIME {
-                        return Err(Error::OperationFailed);
-                    }
-                }
-                thread::sleep(Duration::from_millis(5));
+        }
+        Ok(tts)
+    }
+
+    /// Synthesizes `text` to a WAV file at `path`, returning the word ranges the engine reported as it went.
+    fn synthesize_file(&mut self, text: &str, path: &Path) -> Result<Vec<SynthesizedWord>, Error> {
+        if PENDING_INITIALIZATIONS.lock().contains_key(&self.bid) {
+            trace!("Engine still initializing, can't synthesize");
+            return Err(Error::OperationFailed);
+        }
+        let path = path.to_str().ok_or(Error::OperationFailed)?;
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        self.listen(&mut env)?;
+        let tts = self.tts.as_obj();
+        let text = env.new_string(text)?;
+        let params = speak_params(&mut env, self.volume, None)?;
+        let path = env.new_string(path)?;
+        let file = env.new_object("java/io/File", "(Ljava/lang/String;)V", &[(&path).into()])?;
+        let uid = next_utterance_id();
+        let utterance = env.new_string(uid.to_string())?;
+        let (tx, rx) = mpsc::sync_channel(1);
+        SYNTHESES.lock().insert(uid, tx);
+        SYNTHESIS_RANGES.lock().insert(uid, vec![]);
+        let rv = env.call_method(
+            tts,
+            "synthesizeToFile",
+            "(Ljava/lang/CharSequence;Landroid/os/Bundle;Ljava/io/File;Ljava/lang/String;)I",
+            &[
+                (&text).into(),
+                (&params).into(),
+                (&file).into(),
+                (&utterance).into(),
+            ],
+        );
+        match rv.map(|rv| rv.i()) {
+            Ok(Ok(0)) => {}
+            rv => {
+                SYNTHESES.lock().remove(&uid);
+                SYNTHESIS_RANGES.lock().remove(&uid);
+                rv??;
+                return Err(Error::OperationFailed);
             }
-            Ok(Self {
-                id,
-                tts,
-                rate: 1.,
-                pitch: 1.,
-            })
-        } else {
-            Err(Error::NoneError)
+        }
+        // Completion is reported to the `UtteranceProgressListener` on another thread.
+        let finished = rx.recv();
+        let ranges = SYNTHESIS_RANGES.lock().remove(&uid).unwrap_or_default();
+        match finished {
+            Ok(true) => Ok(ranges),
+            _ => Err(Error::OperationFailed),
         }
     }
 
-    fn vm() -> Result<JavaVM, jni::errors::Error> {
-        let ctx = ndk_context::android_context();
-        unsafe { jni::JavaVM::from_raw(ctx.vm().cast()) }
+    /// Registers for the engine's progress reports, which callbacks, ducking and synthesis need, if not yet done.
+    fn listen(&mut self, env: &mut JNIEnv) -> Result<(), Error> {
+        if self.listening {
+            return Ok(());
+        }
+        let bridge = BRIDGE.lock();
+        let Some(bridge) = &*bridge else {
+            return Err(Error::OperationFailed);
+        };
+        let bridge = env.new_object(bridge, "(I)V", &[(self.bid as jint).into()])?;
+        env.call_method(
+            self.tts.as_obj(),
+            "setOnUtteranceProgressListener",
+            "(Landroid/speech/tts/UtteranceProgressListener;)I",
+            &[(&bridge).into()],
+        )?;
+        self.listening = true;
+        Ok(())
     }
-}
 
-impl Backend for Android {
-    fn id(&self) -> Option<BackendId> {
-        Some(self.id)
+    fn vm() -> Result<JavaVM, Error> {
+        crate::android::vm()
     }
 
-    fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            pitch: true,
-            volume: false,
-            is_speaking: true,
-            utterance_callbacks: true,
-            voice: false,
-            get_voice: false,
+    /// Speaks `utterance`, or queues it until the engine finishes initializing.
+    fn enqueue(&mut self, utterance: PendingUtterance) -> Result<Option<UtteranceId>, Error> {
+        let uid = utterance.id;
+        {
+            let mut pending = PENDING_INITIALIZATIONS.lock();
+            if let Some(initialization) = (*pending).get_mut(&self.bid) {
+                trace!("Engine still initializing, queueing utterance {}", uid);
+                if utterance.interrupt {
+                    initialization.clear();
+                }
+                initialization.utterances.push(utterance);
+                return Ok(Some(UtteranceId::Android(uid)));
+            }
         }
+        if FAILED_INITIALIZATIONS.read().contains(&self.bid) {
+            return Err(Error::OperationFailed);
+        }
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        speak_utterance(&mut env, self.id, self.tts.as_obj(), &utterance)?;
+        Ok(Some(UtteranceId::Android(uid)))
     }
 
-    fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+    /// Returns the package of the engine in use, and its version.
+    fn engine_info(&self) -> Result<(Option<String>, Option<String>), Error> {
         let vm = Self::vm()?;
         let mut env = vm.get_env()?;
+        let package = match &self.engine {
+            Some(engine) => engine.clone(),
+            None => {
+                let default: JString = env
+                    .call_method(
+                        self.tts.as_obj(),
+                        "getDefaultEngine",
+                        "()Ljava/lang/String;",
+                        &[],
+                    )?
+                    .l()?
+                    .into();
+                if default.is_null() {
+                    return Ok((None, None));
+                }
+                let default: String = env.get_string(&default)?.into();
+                default
+            }
+        };
+        let context = crate::android::context(&mut env)?;
+        let manager = env
+            .call_method(
+                context.as_obj(),
+                "getPackageManager",
+                "()Landroid/content/pm/PackageManager;",
+                &[],
+            )?
+            .l()?;
+        let name = env.new_string(&package)?;
+        let info = env.call_method(
+            &manager,
+            "getPackageInfo",
+            "(Ljava/lang/String;I)Landroid/content/pm/PackageInfo;",
+            &[(&name).into(), 0.into()],
+        );
+        let info = match info {
+            Ok(info) => info.l()?,
+            // The engine may have been uninstalled since, which throws `NameNotFoundException`.
+            Err(jni::errors::Error::JavaException) => {
+                env.exception_clear()?;
+                return Ok((Some(package), None));
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let version: JString = env
+            .get_field(&info, "versionName", "Ljava/lang/String;")?
+            .l()?
+            .into();
+        let version = if version.is_null() {
+            None
+        } else {
+            Some(env.get_string(&version)?.into())
+        };
+        Ok((Some(package), version))
+    }
+
+    /// Calls `f` with each `android.speech.tts.Voice` known to the engine.
+    fn for_each_voice<F>(&self, env: &mut JNIEnv, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&mut JNIEnv, &JObject) -> Result<(), Error>,
+    {
         let tts = self.tts.as_obj();
-        let text = env.new_string(text)?;
-        let queue_mode = if interrupt { 0 } else { 1 };
-        let mut utterance_id = NEXT_UTTERANCE_ID.lock().unwrap();
-        let uid = *utterance_id;
-        *utterance_id += 1;
-        drop(utterance_id);
-        let id = UtteranceId::Android(uid);
-        let uid = env.new_string(uid.to_string())?;
-        let rv = env.call_method(
+        let voices = env
+            .call_method(tts, "getVoices", "()Ljava/util/Set;", &[])?
+            .l()?;
+        if voices.is_null() {
+            return Ok(());
+        }
+        let voices: JObjectArray = env
+            .call_method(&voices, "toArray", "()[Ljava/lang/Object;", &[])?
+            .l()?
+            .into();
+        let len = env.get_array_length(&voices)?;
+        for i in 0..len {
+            env.with_local_frame(16, |env| -> Result<(), Error> {
+                let voice = env.get_object_array_element(&voices, i)?;
+                f(env, &voice)
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the `Bundle` of parameters passed to `TextToSpeech.speak()`.
+fn speak_params<'local>(
+    env: &mut JNIEnv<'local>,
+    volume: f32,
+    pan: Option<f32>,
+) -> Result<JObject<'local>, Error> {
+    let params = env.new_object("android/os/Bundle", "()V", &[])?;
+    let mut floats = vec![("volume", volume)];
+    if let Some(pan) = pan {
+        floats.push(("pan", pan.clamp(-1., 1.)));
+    }
+    for (key, value) in floats {
+        let key = env.new_string(key)?;
+        env.call_method(
+            &params,
+            "putFloat",
+            "(Ljava/lang/String;F)V",
+            &[(&key).into(), (value as jfloat).into()],
+        )?;
+    }
+    Ok(params)
+}
+
+fn speak_utterance(
+    env: &mut JNIEnv,
+    backend_id: BackendId,
+    tts: &JObject,
+    utterance: &PendingUtterance,
+) -> Result<(), Error> {
+    duck(env, backend_id, utterance.id);
+    let text = env.new_string(&utterance.text)?;
+    let queue_mode = if utterance.interrupt { 0 } else { 1 };
+    let uid = env.new_string(utterance.id.to_string())?;
+    let params = speak_params(env, utterance.volume, utterance.pan)?;
+    let rv = if utterance.earcon {
+        // Earcons are played by name, so register the file under its own path.
+        env.call_method(
+            tts,
+            "addEarcon",
+            "(Ljava/lang/String;Ljava/lang/String;)I",
+            &[(&text).into(), (&text).into()],
+        )?;
+        env.call_method(
+            tts,
+            "playEarcon",
+            "(Ljava/lang/String;ILandroid/os/Bundle;Ljava/lang/String;)I",
+            &[
+                (&text).into(),
+                queue_mode.into(),
+                (&params).into(),
+                (&uid).into(),
+            ],
+        )?
+    } else {
+        env.call_method(
             tts,
             "speak",
             "(Ljava/lang/CharSequence;ILandroid/os/Bundle;Ljava/lang/String;)I",
             &[
                 (&text).into(),
                 queue_mode.into(),
-                (&JObject::null()).into(),
+                (&params).into(),
                 (&uid).into(),
             ],
-        )?;
-        let rv = rv.i()?;
-        if rv == 0 {
-            Ok(Some(id))
-        } else {
-            Err(Error::OperationFailed)
+        )?
+    };
+    let rv = rv.i()?;
+    if rv == 0 {
+        Ok(())
+    } else {
+        unduck(env, backend_id, utterance.id);
+        Err(Error::OperationFailed)
+    }
+}
+
+fn voice_name(env: &mut JNIEnv, voice: &JObject) -> Result<String, Error> {
+    let name: JString = env
+        .call_method(voice, "getName", "()Ljava/lang/String;", &[])?
+        .l()?
+        .into();
+    let name: String = env.get_string(&name)?.into();
+    Ok(name)
+}
+
+fn voice_from_java(env: &mut JNIEnv, voice: &JObject) -> Result<Option<Voice>, Error> {
+    if voice.is_null() {
+        return Ok(None);
+    }
+    let name = voice_name(env, voice)?;
+    let locale = env
+        .call_method(voice, "getLocale", "()Ljava/util/Locale;", &[])?
+        .l()?;
+    let language: JString = env
+        .call_method(&locale, "toLanguageTag", "()Ljava/lang/String;", &[])?
+        .l()?
+        .into();
+    let language: String = env.get_string(&language)?.into();
+    let language = match LanguageTag::parse(language) {
+        Ok(language) => language,
+        Err(e) => {
+            trace!("Skipping voice {} with unparseable language: {}", name, e);
+            return Ok(None);
+        }
+    };
+    let quality = env.call_method(voice, "getQuality", "()I", &[])?.i()?;
+    let quality = match quality {
+        100 => Some(VoiceQuality::VeryLow),
+        200 => Some(VoiceQuality::Low),
+        300 => Some(VoiceQuality::Normal),
+        400 => Some(VoiceQuality::High),
+        500 => Some(VoiceQuality::VeryHigh),
+        _ => None,
+    };
+    let network = env
+        .call_method(voice, "isNetworkConnectionRequired", "()Z", &[])?
+        .z()?;
+    Ok(Some(Voice {
+        id: name.clone(),
+        name,
+        gender: Gender::Unknown,
+        language,
+        personal: false,
+        quality,
+        network,
+    }))
+}
+
+impl Backend for Android {
+    fn id(&self) -> Option<BackendId> {
+        Some(self.id)
+    }
+
+    fn enable_callbacks(&mut self) -> Result<(), Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        self.listen(&mut env)
+    }
+
+    fn supported_features(&self) -> Features {
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::Voice)
+            .with(Capability::GetVoice)
+            .with(Capability::WordCallbacks)
+            .with(Capability::Synthesize)
+            .with(Capability::Earcons)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        let info = BackendInfo {
+            engine_name: Some("android.speech.tts.TextToSpeech".into()),
+            ..BackendInfo::new(Backends::Android)
+        };
+        match self.engine_info() {
+            Ok((synthesizer, engine_version)) => BackendInfo {
+                engine_version,
+                synthesizer,
+                ..info
+            },
+            Err(e) => {
+                warn!("Couldn't look up the TTS engine: {}", e);
+                info
+            }
         }
     }
 
+    fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        self.speak_with_options(text, interrupt, &UtteranceOptions::default())
+    }
+
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let utterance = PendingUtterance {
+            id: next_utterance_id(),
+            text: text.into(),
+            interrupt,
+            volume: self.volume,
+            pan: options.pan,
+            earcon: false,
+        };
+        // Only word boundaries need the text.
+        if self.listening {
+            UTTERANCE_TEXTS.lock().insert(utterance.id, text.into());
+        }
+        self.enqueue(utterance)
+    }
+
+    fn play_earcon(
+        &mut self,
+        earcon: &Earcon,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let Earcon::File(path) = earcon else {
+            return Err(Error::UnsupportedFeature);
+        };
+        self.enqueue(PendingUtterance {
+            id: next_utterance_id(),
+            text: path.to_string_lossy().into(),
+            interrupt,
+            volume: self.volume,
+            pan: None,
+            earcon: true,
+        })
+    }
+
     fn stop(&mut self) -> Result<(), Error> {
+        {
+            let mut pending = PENDING_INITIALIZATIONS.lock();
+            if let Some(initialization) = (*pending).get_mut(&self.bid) {
+                initialization.clear();
+                return Ok(());
+            }
+        }
         let vm = Self::vm()?;
         let mut env = vm.get_env()?;
         let tts = self.tts.as_obj();
@@ -297,6 +869,43 @@ impl Backend for Android {
         }
     }
 
+    fn reinitialize(&mut self) -> Result<(), Error> {
+        info!("Reinitializing Android backend");
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        // The old engine may well be dead already.
+        if env
+            .call_method(self.tts.as_obj(), "shutdown", "()V", &[])
+            .is_err()
+        {
+            env.exception_clear()?;
+        }
+        if let Some(mut initialization) = PENDING_INITIALIZATIONS.lock().remove(&self.bid) {
+            initialization.clear();
+        }
+        FAILED_INITIALIZATIONS.write().remove(&self.bid);
+        if let Some(active) = DUCKING.lock().get_mut(&self.id) {
+            if !active.is_empty() {
+                active.clear();
+                audio_focus(&mut env, false)?;
+            }
+        }
+        self.tts = Self::engine(&mut env, self.bid, self.engine.as_deref())?;
+        if self.listening {
+            self.listening = false;
+            self.listen(&mut env)?;
+        }
+        let tts = self.tts.as_obj();
+        env.call_method(
+            tts,
+            "setSpeechRate",
+            "(F)I",
+            &[(self.rate as jfloat).into()],
+        )?;
+        env.call_method(tts, "setPitch", "(F)I", &[(self.pitch as jfloat).into()])?;
+        Ok(())
+    }
+
     fn min_rate(&self) -> f32 {
         0.1
     }
@@ -360,26 +969,33 @@ impl Backend for Android {
     }
 
     fn min_volume(&self) -> f32 {
-        todo!()
+        0.
     }
 
     fn max_volume(&self) -> f32 {
-        todo!()
+        1.
     }
 
     fn normal_volume(&self) -> f32 {
-        todo!()
+        1.
     }
 
     fn get_volume(&self) -> Result<f32, Error> {
-        todo!()
+        Ok(self.volume)
     }
 
-    fn set_volume(&mut self, _volume: f32) -> Result<(), Error> {
-        todo!()
+    fn set_volume(&mut self, volume: f32) -> Result<(), Error> {
+        self.volume = volume;
+        Ok(())
     }
 
     fn is_speaking(&self) -> Result<bool, Error> {
+        {
+            let pending = PENDING_INITIALIZATIONS.lock();
+            if let Some(initialization) = (*pending).get(&self.bid) {
+                return Ok(!initialization.utterances.is_empty());
+            }
+        }
         let vm = Self::vm()?;
         let mut env = vm.get_env()?;
         let tts = self.tts.as_obj();
@@ -389,14 +1005,232 @@ impl Backend for Android {
     }
 
     fn voice(&self) -> Result<Option<Voice>, Error> {
-        unimplemented!()
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let tts = self.tts.as_obj();
+        let voice = env
+            .call_method(tts, "getVoice", "()Landroid/speech/tts/Voice;", &[])?
+            .l()?;
+        voice_from_java(&mut env, &voice)
     }
 
     fn voices(&self) -> Result<Vec<Voice>, Error> {
-        unimplemented!()
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let mut rv = vec![];
+        self.for_each_voice(&mut env, |env, voice| {
+            if let Some(voice) = voice_from_java(env, voice)? {
+                rv.push(voice);
+            }
+            Ok(())
+        })?;
+        Ok(rv)
+    }
+
+    fn set_voice(&mut self, voice: &Voice) -> Result<(), Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let mut result = None;
+        let tts = self.tts.clone();
+        self.for_each_voice(&mut env, |env, v| {
+            if result.is_none() && voice_name(env, v)? == voice.id {
+                let rv = env.call_method(
+                    tts.as_obj(),
+                    "setVoice",
+                    "(Landroid/speech/tts/Voice;)I",
+                    &[v.into()],
+                )?;
+                result = Some(rv.i()?);
+            }
+            Ok(())
+        })?;
+        match result {
+            Some(0) => Ok(()),
+            _ => Err(Error::OperationFailed),
+        }
     }
 
-    fn set_voice(&mut self, _voice: &Voice) -> Result<(), Error> {
-        unimplemented!()
+    fn engines(&self) -> Result<Vec<Engine>, Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let tts = self.tts.as_obj();
+        let default: JString = env
+            .call_method(tts, "getDefaultEngine", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let default: Option<String> = if default.is_null() {
+            None
+        } else {
+            Some(env.get_string(&default)?.into())
+        };
+        let engines = env
+            .call_method(tts, "getEngines", "()Ljava/util/List;", &[])?
+            .l()?;
+        let len = env.call_method(&engines, "size", "()I", &[])?.i()?;
+        let mut rv = vec![];
+        for i in 0..len {
+            env.with_local_frame(16, |env| -> Result<(), Error> {
+                let engine = env
+                    .call_method(&engines, "get", "(I)Ljava/lang/Object;", &[i.into()])?
+                    .l()?;
+                let name: JString = env
+                    .get_field(&engine, "name", "Ljava/lang/String;")?
+                    .l()?
+                    .into();
+                let name: String = env.get_string(&name)?.into();
+                let label: JString = env
+                    .get_field(&engine, "label", "Ljava/lang/String;")?
+                    .l()?
+                    .into();
+                let label: String = env.get_string(&label)?.into();
+                rv.push(Engine {
+                    default: default.as_deref() == Some(name.as_str()),
+                    name,
+                    label,
+                });
+                Ok(())
+            })?;
+        }
+        Ok(rv)
+    }
+
+    fn synthesize(&mut self, text: &str) -> Result<AudioBuffer, Error> {
+        Ok(self.synthesize_with_timings(text)?.0)
+    }
+
+    fn synthesize_to_file(&mut self, text: &str, path: &Path) -> Result<(), Error> {
+        self.synthesize_file(text, path)?;
+        Ok(())
+    }
+
+    fn synthesize_with_timings(
+        &mut self,
+        text: &str,
+    ) -> Result<(AudioBuffer, Vec<WordTiming>), Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let context = crate::android::context(&mut env)?;
+        let dir = env
+            .call_method(context.as_obj(), "getCacheDir", "()Ljava/io/File;", &[])?
+            .l()?;
+        let dir: JString = env
+            .call_method(&dir, "getAbsolutePath", "()Ljava/lang/String;", &[])?
+            .l()?
+            .into();
+        let dir: String = env.get_string(&dir)?.into();
+        let path = PathBuf::from(dir).join(format!("tts-rs-{}.wav", self.bid));
+        let ranges = self.synthesize_file(text, &path);
+        let bytes = fs::read(&path);
+        let _ = fs::remove_file(&path);
+        let mut ranges = ranges?;
+        let audio = AudioBuffer::from_wav(&bytes?)?;
+        ranges.sort_by_key(|&(_, _, frame)| frame);
+        let at = |frame: i32| {
+            Duration::from_secs_f64(frame.max(0) as f64 / audio.sample_rate.max(1) as f64)
+        };
+        // Each word lasts until the next begins, and the last until the audio ends.
+        let timings = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end, frame))| {
+                let begins = at(frame);
+                let ends = ranges
+                    .get(i + 1)
+                    .map_or(audio.duration(), |&(_, _, next)| at(next));
+                let start = start.max(0) as usize;
+                let len = (end.max(0) as usize).saturating_sub(start);
+                WordTiming {
+                    range: utf16_range_to_byte_range(text, start, len),
+                    start: begins,
+                    duration: ends.saturating_sub(begins),
+                }
+            })
+            .collect();
+        Ok((audio, timings))
+    }
+
+    fn set_ducking(&mut self, mode: DuckingMode) -> Result<(), Error> {
+        if let DuckingMode::Duck = mode {
+            // Focus is released when utterances finish.
+            let vm = Self::vm()?;
+            let mut env = vm.get_env()?;
+            self.listen(&mut env)?;
+        }
+        let mut ducking = DUCKING.lock();
+        match mode {
+            DuckingMode::Duck => {
+                ducking.entry(self.id).or_default();
+            }
+            DuckingMode::Off => {
+                if let Some(active) = ducking.remove(&self.id) {
+                    if !active.is_empty() {
+                        let vm = Self::vm()?;
+                        let mut env = vm.get_env()?;
+                        audio_focus(&mut env, false)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_audio_usage(&mut self, usage: AudioUsage) -> Result<(), Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let builder = env.new_object("android/media/AudioAttributes$Builder", "()V", &[])?;
+        env.call_method(
+            &builder,
+            "setUsage",
+            "(I)Landroid/media/AudioAttributes$Builder;",
+            &[usage.value().into()],
+        )?;
+        // AudioAttributes.CONTENT_TYPE_SPEECH
+        env.call_method(
+            &builder,
+            "setContentType",
+            "(I)Landroid/media/AudioAttributes$Builder;",
+            &[1.into()],
+        )?;
+        let attributes = env
+            .call_method(&builder, "build", "()Landroid/media/AudioAttributes;", &[])?
+            .l()?;
+        let rv = env.call_method(
+            self.tts.as_obj(),
+            "setAudioAttributes",
+            "(Landroid/media/AudioAttributes;)I",
+            &[(&attributes).into()],
+        )?;
+        let rv = rv.i()?;
+        if rv == 0 {
+            Ok(())
+        } else {
+            Err(Error::OperationFailed)
+        }
+    }
+
+    fn language_availability(
+        &self,
+        language: &LanguageTag<String>,
+    ) -> Result<LanguageAvailability, Error> {
+        let vm = Self::vm()?;
+        let mut env = vm.get_env()?;
+        let tag = env.new_string(language.as_str())?;
+        let locale = env
+            .call_static_method(
+                "java/util/Locale",
+                "forLanguageTag",
+                "(Ljava/lang/String;)Ljava/util/Locale;",
+                &[(&tag).into()],
+            )?
+            .l()?;
+        let rv = env
+            .call_method(
+                self.tts.as_obj(),
+                "isLanguageAvailable",
+                "(Ljava/util/Locale;)I",
+                &[(&locale).into()],
+            )?
+            .i()?;
+        Ok(LanguageAvailability::from_value(rv))
     }
 }
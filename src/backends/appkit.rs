@@ -1,41 +1,101 @@
-#[cfg(target_os = "macos")]
 use cocoa_foundation::base::{id, nil};
 use cocoa_foundation::foundation::NSString;
+use core_foundation::array::CFArray;
+use core_foundation::base::TCFType;
+use core_foundation::string::CFString;
 use log::{info, trace};
 use objc::declare::ClassDecl;
 use objc::runtime::*;
 use objc::*;
+use oxilangtag::LanguageTag;
+#[cfg(all(target_os = "macos", feature = "appkit"))]
+use parking_lot::Mutex;
 
-use crate::{Backend, BackendId, Error, Features, UtteranceId, Voice};
+use crate::{
+    callbacks, Backend, BackendId, BackendInfo, Backends, Capability, Error, Features, Gender,
+    UtteranceEvent, UtteranceId, Voice,
+};
+
+#[link(name = "AppKit", kind = "framework")]
+extern "C" {
+    static NSVoiceName: id;
+    static NSVoiceLocaleIdentifier: id;
+    static NSVoiceGender: id;
+}
 
 #[derive(Clone, Debug)]
-pub(crate) struct AppKit(*mut Object, *mut Object);
+pub(crate) struct AppKit(*mut Object, *mut Object, BackendId);
+
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+static NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+
+/// Returns the first utterance in the delegate's queue.
+unsafe fn first_utterance(delegate: &Object) -> Option<UtteranceId> {
+    let ids: id = *delegate.get_ivar("ids");
+    let utterance_id: id = msg_send!(ids, firstObject);
+    if utterance_id == nil {
+        return None;
+    }
+    let utterance_id: u64 = msg_send![utterance_id, unsignedLongLongValue];
+    Some(UtteranceId::AppKit(utterance_id))
+}
+
+/// Fires the callback for `event` on `utterance`.
+///
+/// Callbacks may speak or stop, so the delegate's queue must be up to date before calling this.
+unsafe fn fire_utterance_callback(
+    delegate: &Object,
+    event: UtteranceEvent,
+    utterance: Option<UtteranceId>,
+) {
+    let Some(utterance_id) = utterance else {
+        return;
+    };
+    let backend_id: u64 = *delegate.get_ivar("backend_id");
+    let backend_id = BackendId::AppKit(backend_id);
+    trace!("Firing {:?} for {:?}", event, utterance_id);
+    callbacks::fire_utterance(backend_id, event, utterance_id);
+}
 
 impl AppKit {
     pub(crate) fn new() -> Result<Self, Error> {
         info!("Initializing AppKit backend");
+        let is_main_thread: BOOL = unsafe { msg_send![class!(NSThread), isMainThread] };
+        if is_main_thread == NO {
+            return Err(Error::NotMainThread);
+        }
         unsafe {
             let obj: *mut Object = msg_send![class!(NSSpeechSynthesizer), new];
             let mut decl = ClassDecl::new("MyNSSpeechSynthesizerDelegate", class!(NSObject))
-                .ok_or(Error::OperationFailed)?;
+                .ok_or_else(|| Error::BackendUnavailable {
+                    backend: Backends::AppKit,
+                    reason: "couldn't declare the speech synthesizer delegate class".into(),
+                })?;
             decl.add_ivar::<id>("synth");
             decl.add_ivar::<id>("strings");
+            decl.add_ivar::<id>("ids");
+            decl.add_ivar::<u64>("backend_id");
 
-            extern "C" fn enqueue_and_speak(this: &Object, _: Sel, string: id) {
+            extern "C" fn enqueue_and_speak(this: &Object, _: Sel, string: id, utterance_id: u64) {
                 unsafe {
                     let strings: id = *this.get_ivar("strings");
                     let _: () = msg_send![strings, addObject: string];
+                    let ids: id = *this.get_ivar("ids");
+                    let utterance_id: id =
+                        msg_send![class!(NSNumber), numberWithUnsignedLongLong: utterance_id];
+                    let _: () = msg_send![ids, addObject: utterance_id];
                     let count: u32 = msg_send![strings, count];
                     if count == 1 {
                         let str: id = msg_send!(strings, firstObject);
                         let synth: id = *this.get_ivar("synth");
                         let _: BOOL = msg_send![synth, startSpeakingString: str];
+                        fire_utterance_callback(this, UtteranceEvent::Begin, first_utterance(this));
                     }
                 }
             }
             decl.add_method(
-                sel!(enqueueAndSpeak:),
-                enqueue_and_speak as extern "C" fn(&Object, Sel, id) -> (),
+                sel!(enqueueAndSpeak:utteranceId:),
+                enqueue_and_speak as extern "C" fn(&Object, Sel, id, u64) -> (),
             );
 
             extern "C" fn speech_synthesizer_did_finish_speaking(
@@ -46,15 +106,23 @@ impl AppKit {
             ) {
                 unsafe {
                     let strings: id = *this.get_ivar("strings");
+                    let ids: id = *this.get_ivar("ids");
                     let count: u32 = msg_send![strings, count];
                     if count > 0 {
+                        let ended = first_utterance(this);
                         let str: id = msg_send!(strings, firstObject);
                         let _: () = msg_send![str, release];
                         let _: () = msg_send!(strings, removeObjectAtIndex:0);
-                        if count > 1 {
+                        let _: () = msg_send!(ids, removeObjectAtIndex:0);
+                        let begun = if count > 1 {
                             let str: id = msg_send!(strings, firstObject);
                             let _: BOOL = msg_send![synth, startSpeakingString: str];
-                        }
+                            first_utterance(this)
+                        } else {
+                            None
+                        };
+                        fire_utterance_callback(this, UtteranceEvent::End, ended);
+                        fire_utterance_callback(this, UtteranceEvent::Begin, begun);
                     }
                 }
             }
@@ -67,13 +135,20 @@ impl AppKit {
             extern "C" fn clear_queue(this: &Object, _: Sel) {
                 unsafe {
                     let strings: id = *this.get_ivar("strings");
+                    let ids: id = *this.get_ivar("ids");
+                    let mut stopped = vec![];
                     let mut count: u32 = msg_send![strings, count];
                     while count > 0 {
+                        stopped.push(first_utterance(this));
                         let str: id = msg_send!(strings, firstObject);
                         let _: () = msg_send![str, release];
                         let _: () = msg_send!(strings, removeObjectAtIndex:0);
+                        let _: () = msg_send!(ids, removeObjectAtIndex:0);
                         count = msg_send![strings, count];
                     }
+                    for utterance in stopped {
+                        fire_utterance_callback(this, UtteranceEvent::Stop, utterance);
+                    }
                 }
             }
             decl.add_method(
@@ -92,24 +167,93 @@ impl AppKit {
                 .as_mut()
                 .ok_or(Error::OperationFailed)?
                 .set_ivar("strings", strings);
+            let ids: id = msg_send![class!(NSMutableArray), new];
+            delegate_obj
+                .as_mut()
+                .ok_or(Error::OperationFailed)?
+                .set_ivar("ids", ids);
+            let mut backend_id = NEXT_BACKEND_ID.lock();
+            let bid = *backend_id;
+            *backend_id += 1;
+            drop(backend_id);
+            delegate_obj
+                .as_mut()
+                .ok_or(Error::OperationFailed)?
+                .set_ivar("backend_id", bid);
             let _: Object = msg_send![obj, setDelegate: delegate_obj];
-            Ok(AppKit(obj, delegate_obj))
+            Ok(AppKit(obj, delegate_obj, BackendId::AppKit(bid)))
+        }
+    }
+}
+
+fn voice_from_identifier(identifier: id) -> Option<Voice> {
+    if identifier == nil {
+        return None;
+    }
+    unsafe {
+        let attributes: id = msg_send![class!(NSSpeechSynthesizer), attributesForVoice: identifier];
+        if attributes == nil {
+            return None;
+        }
+        let name: id = msg_send![attributes, objectForKey: NSVoiceName];
+        let locale: id = msg_send![attributes, objectForKey: NSVoiceLocaleIdentifier];
+        if name == nil || locale == nil {
+            return None;
         }
+        let gender: id = msg_send![attributes, objectForKey: NSVoiceGender];
+        let gender = if gender == nil {
+            Gender::Unknown
+        } else {
+            let gender = CFString::wrap_under_get_rule(gender as _).to_string();
+            // `VoiceGenderNeutral` replaced `VoiceGenderNeuter` in macOS 10.5.
+            match gender.as_str() {
+                "VoiceGenderMale" => Gender::Male,
+                "VoiceGenderFemale" => Gender::Female,
+                "VoiceGenderNeutral" | "VoiceGenderNeuter" => Gender::Neutral,
+                _ => Gender::Unknown,
+            }
+        };
+        let id = CFString::wrap_under_get_rule(identifier as _).to_string();
+        let name = CFString::wrap_under_get_rule(name as _).to_string();
+        // NSSpeechSynthesizer reports POSIX-style locales like `en_US`.
+        let locale = CFString::wrap_under_get_rule(locale as _)
+            .to_string()
+            .replace('_', "-");
+        let language = LanguageTag::parse(locale).ok()?;
+        Some(Voice {
+            id,
+            name,
+            gender,
+            language,
+            personal: false,
+            quality: None,
+            network: false,
+        })
     }
 }
 
 impl Backend for AppKit {
     fn id(&self) -> Option<BackendId> {
-        None
+        Some(self.2)
     }
 
     fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            volume: true,
-            is_speaking: true,
-            ..Default::default()
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::Voice)
+            .with(Capability::GetVoice)
+            .with(Capability::UtteranceCallbacks)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo {
+            engine_name: Some("NSSpeechSynthesizer".into()),
+            engine_version: Some(super::apple_os_version()),
+            ..BackendInfo::new(Backends::AppKit)
         }
     }
 
@@ -118,11 +262,15 @@ impl Backend for AppKit {
         if interrupt {
             self.stop()?;
         }
+        let mut utterance_id = NEXT_UTTERANCE_ID.lock();
+        let uid = *utterance_id;
+        *utterance_id += 1;
+        drop(utterance_id);
         unsafe {
             let str = NSString::alloc(nil).init_str(text);
-            let _: () = msg_send![self.1, enqueueAndSpeak: str];
+            let _: () = msg_send![self.1, enqueueAndSpeak: str utteranceId: uid];
         }
-        Ok(None)
+        Ok(Some(UtteranceId::AppKit(uid)))
     }
 
     fn stop(&mut self) -> Result<(), Error> {
@@ -209,15 +357,32 @@ impl Backend for AppKit {
     }
 
     fn voice(&self) -> Result<Option<Voice>, Error> {
-        unimplemented!()
+        let identifier: id = unsafe { msg_send![self.0, voice] };
+        Ok(voice_from_identifier(identifier))
     }
 
     fn voices(&self) -> Result<Vec<Voice>, Error> {
-        unimplemented!()
+        let voices: CFArray = unsafe {
+            CFArray::wrap_under_get_rule(msg_send![class!(NSSpeechSynthesizer), availableVoices])
+        };
+        let rv = voices
+            .iter()
+            .filter_map(|v| voice_from_identifier(*v as id))
+            .collect();
+        Ok(rv)
     }
 
-    fn set_voice(&mut self, _voice: &Voice) -> Result<(), Error> {
-        unimplemented!()
+    fn set_voice(&mut self, voice: &Voice) -> Result<(), Error> {
+        trace!("set_voice({:?})", voice);
+        let success: BOOL = unsafe {
+            let vid = NSString::alloc(nil).init_str(&voice.id);
+            msg_send![self.0, setVoice: vid]
+        };
+        if success == NO {
+            Err(Error::OperationFailed)
+        } else {
+            Ok(())
+        }
     }
 }
 
@@ -1,22 +1,76 @@
-#[cfg(any(target_os = "macos", target_os = "ios"))]
-use std::sync::Mutex;
-
-use cocoa_foundation::base::{id, nil, NO};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Once},
+};
+
+use block::ConcreteBlock;
+#[cfg(target_os = "ios")]
+use cocoa_foundation::base::YES;
+use cocoa_foundation::base::{id, nil, BOOL, NO};
 use cocoa_foundation::foundation::NSString;
 use core_foundation::array::CFArray;
 use core_foundation::base::TCFType;
 use core_foundation::string::CFString;
-use lazy_static::lazy_static;
 use log::{info, trace};
-use objc::runtime::{Object, Sel};
-use objc::{class, declare::ClassDecl, msg_send, sel, sel_impl};
+use objc::runtime::{Class, Object, Sel};
+use objc::{class, declare::ClassDecl, msg_send, sel, sel_impl, Encode, Encoding};
 use oxilangtag::LanguageTag;
+#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+use parking_lot::Mutex;
+
+use crate::{
+    callbacks, utf16_range_to_byte_range, voices, Backend, BackendId, BackendInfo, Backends,
+    Capability, Error, Features, Gender, PersonalVoiceAuthorizationStatus, UtteranceEvent,
+    UtteranceId, UtteranceOptions, Voice, VoiceQuality,
+};
+#[cfg(target_os = "ios")]
+use crate::{AudioSessionCategory, AudioSessionOptions, DuckingMode, OutputDevice};
+
+#[cfg(target_os = "ios")]
+#[link(name = "AVFoundation", kind = "framework")]
+extern "C" {
+    static AVAudioSessionCategoryAmbient: id;
+    static AVAudioSessionCategorySoloAmbient: id;
+    static AVAudioSessionCategoryPlayback: id;
+    static AVAudioSessionCategoryPlayAndRecord: id;
+    static AVAudioSessionPortBuiltInSpeaker: id;
+}
+
+#[cfg(target_os = "ios")]
+const AV_AUDIO_SESSION_CATEGORY_OPTION_MIX_WITH_OTHERS: u64 = 0x1;
+#[cfg(target_os = "ios")]
+const AV_AUDIO_SESSION_CATEGORY_OPTION_DUCK_OTHERS: u64 = 0x2;
+#[cfg(target_os = "ios")]
+const AV_AUDIO_SESSION_CATEGORY_OPTION_INTERRUPT_SPOKEN_AUDIO_AND_MIX_WITH_OTHERS: u64 = 0x11;
+#[cfg(target_os = "ios")]
+const AV_AUDIO_SESSION_PORT_OVERRIDE_NONE: u64 = 0;
+#[cfg(target_os = "ios")]
+const AV_AUDIO_SESSION_PORT_OVERRIDE_SPEAKER: u64 = 0x73706b72;
+
+const AV_SPEECH_SYNTHESIS_VOICE_TRAIT_IS_PERSONAL_VOICE: u64 = 0x2;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct NSRange {
+    location: usize,
+    length: usize,
+}
 
-use crate::{Backend, BackendId, Error, Features, Gender, UtteranceId, Voice, CALLBACKS};
+unsafe impl Encode for NSRange {
+    fn encode() -> Encoding {
+        let encoding = format!(
+            "{{_NSRange={}{}}}",
+            usize::encode().as_str(),
+            usize::encode().as_str()
+        );
+        unsafe { Encoding::from_str(&encoding) }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub(crate) struct AvFoundation {
-    id: BackendId,
+    id: u64,
+    /// `nil` until callbacks are enabled.
     delegate: *mut Object,
     synth: *mut Object,
     rate: f32,
@@ -25,124 +79,185 @@ pub(crate) struct AvFoundation {
     voice: Option<Voice>,
 }
 
-lazy_static! {
-    static ref NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
-}
-
-impl AvFoundation {
-    pub(crate) fn new() -> Result<Self, Error> {
-        info!("Initializing AVFoundation backend");
-        let mut decl = ClassDecl::new("MyNSSpeechSynthesizerDelegate", class!(NSObject))
-            .ok_or(Error::OperationFailed)?;
-        decl.add_ivar::<u64>("backend_id");
-
-        extern "C" fn speech_synthesizer_did_start_speech_utterance(
-            this: &Object,
-            _: Sel,
-            _synth: *const Object,
-            utterance: id,
-        ) {
-            trace!("speech_synthesizer_did_start_speech_utterance");
-            unsafe {
-                let backend_id: u64 = *this.get_ivar("backend_id");
-                let backend_id = BackendId::AvFoundation(backend_id);
-                trace!("Locking callbacks");
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                trace!("Locked");
-                let callbacks = callbacks.get_mut(&backend_id).unwrap();
-                if let Some(callback) = callbacks.utterance_begin.as_mut() {
-                    trace!("Calling utterance_begin");
-                    let utterance_id = UtteranceId::AvFoundation(utterance);
-                    callback(utterance_id);
-                    trace!("Called");
-                }
-            }
-            trace!("Done speech_synthesizer_did_start_speech_utterance");
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+static NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+/// The number each utterance that's queued or speaking is identified by, keyed by its address.
+static UTTERANCE_IDS: LazyLock<Mutex<HashMap<usize, u64>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the ID of `utterance`, numbering it if it's new, and forgetting it if `done`, since its address may then
+/// be reused.
+fn utterance_id(utterance: id, done: bool) -> UtteranceId {
+    let mut ids = UTTERANCE_IDS.lock();
+    let key = utterance as usize;
+    let n = match ids.get(&key) {
+        Some(&n) if done => {
+            ids.remove(&key);
+            n
         }
-
-        extern "C" fn speech_synthesizer_did_finish_speech_utterance(
-            this: &Object,
-            _: Sel,
-            _synth: *const Object,
-            utterance: id,
-        ) {
-            trace!("speech_synthesizer_did_finish_speech_utterance");
-            unsafe {
-                let backend_id: u64 = *this.get_ivar("backend_id");
-                let backend_id = BackendId::AvFoundation(backend_id);
-                trace!("Locking callbacks");
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                trace!("Locked");
-                let callbacks = callbacks.get_mut(&backend_id).unwrap();
-                if let Some(callback) = callbacks.utterance_end.as_mut() {
-                    trace!("Calling utterance_end");
-                    let utterance_id = UtteranceId::AvFoundation(utterance);
-                    callback(utterance_id);
-                    trace!("Called");
-                }
+        Some(&n) => n,
+        None => {
+            let mut next = NEXT_UTTERANCE_ID.lock();
+            let n = *next;
+            *next += 1;
+            if !done {
+                ids.insert(key, n);
             }
-            trace!("Done speech_synthesizer_did_finish_speech_utterance");
-        }
-
-        extern "C" fn speech_synthesizer_did_cancel_speech_utterance(
-            this: &Object,
-            _: Sel,
-            _synth: *const Object,
-            utterance: id,
-        ) {
-            trace!("speech_synthesizer_did_cancel_speech_utterance");
-            unsafe {
-                let backend_id: u64 = *this.get_ivar("backend_id");
-                let backend_id = BackendId::AvFoundation(backend_id);
-                trace!("Locking callbacks");
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                trace!("Locked");
-                let callbacks = callbacks.get_mut(&backend_id).unwrap();
-                if let Some(callback) = callbacks.utterance_stop.as_mut() {
-                    trace!("Calling utterance_stop");
-                    let utterance_id = UtteranceId::AvFoundation(utterance);
-                    callback(utterance_id);
-                    trace!("Called");
-                }
-            }
-            trace!("Done speech_synthesizer_did_cancel_speech_utterance");
+            n
         }
+    };
+    UtteranceId::AvFoundation(n)
+}
 
+/// Forgets cached voices whenever the system's change, as when the user downloads one.
+fn watch_voices() {
+    static WATCHING: Once = Once::new();
+    WATCHING.call_once(|| {
+        let block = ConcreteBlock::new(|_notification: id| {
+            trace!("Voices changed");
+            voices::invalidate();
+        });
+        let block = block.copy();
+        // Named rather than linked, since the constant is missing before iOS 17 and macOS 14, which never post it.
         unsafe {
-            decl.add_method(
-                sel!(speechSynthesizer:didStartSpeechUtterance:),
-                speech_synthesizer_did_start_speech_utterance
-                    as extern "C" fn(&Object, Sel, *const Object, id) -> (),
-            );
-            decl.add_method(
-                sel!(speechSynthesizer:didFinishSpeechUtterance:),
-                speech_synthesizer_did_finish_speech_utterance
-                    as extern "C" fn(&Object, Sel, *const Object, id) -> (),
-            );
-            decl.add_method(
-                sel!(speechSynthesizer:didCancelSpeechUtterance:),
-                speech_synthesizer_did_cancel_speech_utterance
-                    as extern "C" fn(&Object, Sel, *const Object, id) -> (),
-            );
+            let name = NSString::alloc(nil)
+                .init_str("AVSpeechSynthesisAvailableVoicesDidChangeNotification");
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: id = msg_send![
+                center,
+                addObserverForName: name
+                object: nil
+                queue: nil
+                usingBlock: &*block
+            ];
         }
+    });
+}
+
+extern "C" fn speech_synthesizer_did_start_speech_utterance(
+    this: &Object,
+    _: Sel,
+    _synth: *const Object,
+    utterance: id,
+) {
+    trace!("speech_synthesizer_did_start_speech_utterance");
+    unsafe {
+        let backend_id: u64 = *this.get_ivar("backend_id");
+        let backend_id = BackendId::AvFoundation(backend_id);
+        let utterance_id = utterance_id(utterance, false);
+        callbacks::fire_utterance(backend_id, UtteranceEvent::Begin, utterance_id);
+    }
+    trace!("Done speech_synthesizer_did_start_speech_utterance");
+}
+
+extern "C" fn speech_synthesizer_did_finish_speech_utterance(
+    this: &Object,
+    _: Sel,
+    _synth: *const Object,
+    utterance: id,
+) {
+    trace!("speech_synthesizer_did_finish_speech_utterance");
+    unsafe {
+        let backend_id: u64 = *this.get_ivar("backend_id");
+        let backend_id = BackendId::AvFoundation(backend_id);
+        let utterance_id = utterance_id(utterance, true);
+        callbacks::fire_utterance(backend_id, UtteranceEvent::End, utterance_id);
+    }
+    trace!("Done speech_synthesizer_did_finish_speech_utterance");
+}
+
+extern "C" fn speech_synthesizer_did_cancel_speech_utterance(
+    this: &Object,
+    _: Sel,
+    _synth: *const Object,
+    utterance: id,
+) {
+    trace!("speech_synthesizer_did_cancel_speech_utterance");
+    unsafe {
+        let backend_id: u64 = *this.get_ivar("backend_id");
+        let backend_id = BackendId::AvFoundation(backend_id);
+        let utterance_id = utterance_id(utterance, true);
+        callbacks::fire_utterance(backend_id, UtteranceEvent::Stop, utterance_id);
+    }
+    trace!("Done speech_synthesizer_did_cancel_speech_utterance");
+}
+
+extern "C" fn speech_synthesizer_will_speak_range_of_speech_string(
+    this: &Object,
+    _: Sel,
+    _synth: *const Object,
+    range: NSRange,
+    utterance: id,
+) {
+    trace!("speech_synthesizer_will_speak_range_of_speech_string");
+    unsafe {
+        let backend_id: u64 = *this.get_ivar("backend_id");
+        let backend_id = BackendId::AvFoundation(backend_id);
+        let text: CFString = CFString::wrap_under_get_rule(msg_send![utterance, speechString]);
+        let text = text.to_string();
+        let range = utf16_range_to_byte_range(&text, range.location, range.length);
+        trace!("Firing word boundary with {:?}", range);
+        let utterance_id = utterance_id(utterance, false);
+        callbacks::fire_word(backend_id, utterance_id, range);
+    }
+    trace!("Done speech_synthesizer_will_speak_range_of_speech_string");
+}
 
-        let delegate_class = decl.register();
-        let delegate_obj: *mut Object = unsafe { msg_send![delegate_class, new] };
-        let mut backend_id = NEXT_BACKEND_ID.lock().unwrap();
+/// Declares the delegate class that forwards the synthesizer's events to callbacks, once per process.
+fn delegate_class() -> Result<&'static Class, Error> {
+    static DELEGATE_CLASS: Mutex<Option<&'static Class>> = Mutex::new(None);
+    let mut class = DELEGATE_CLASS.lock();
+    if let Some(class) = *class {
+        return Ok(class);
+    }
+    let mut decl =
+        ClassDecl::new("TtsAVSpeechSynthesizerDelegate", class!(NSObject)).ok_or_else(|| {
+            Error::BackendUnavailable {
+                backend: Backends::AvFoundation,
+                reason: "couldn't declare the speech synthesizer delegate class".into(),
+            }
+        })?;
+    decl.add_ivar::<u64>("backend_id");
+    unsafe {
+        decl.add_method(
+            sel!(speechSynthesizer:didStartSpeechUtterance:),
+            speech_synthesizer_did_start_speech_utterance
+                as extern "C" fn(&Object, Sel, *const Object, id) -> (),
+        );
+        decl.add_method(
+            sel!(speechSynthesizer:didFinishSpeechUtterance:),
+            speech_synthesizer_did_finish_speech_utterance
+                as extern "C" fn(&Object, Sel, *const Object, id) -> (),
+        );
+        decl.add_method(
+            sel!(speechSynthesizer:didCancelSpeechUtterance:),
+            speech_synthesizer_did_cancel_speech_utterance
+                as extern "C" fn(&Object, Sel, *const Object, id) -> (),
+        );
+        decl.add_method(
+            sel!(speechSynthesizer:willSpeakRangeOfSpeechString:utterance:),
+            speech_synthesizer_will_speak_range_of_speech_string
+                as extern "C" fn(&Object, Sel, *const Object, NSRange, id) -> (),
+        );
+    }
+    let registered = decl.register();
+    *class = Some(registered);
+    Ok(registered)
+}
+
+impl AvFoundation {
+    /// Creates the synthesizer without a delegate, which `enable_callbacks()` adds once callbacks are used.
+    pub(crate) fn new() -> Result<Self, Error> {
+        info!("Initializing AVFoundation backend");
+        watch_voices();
+        let mut backend_id = NEXT_BACKEND_ID.lock();
         let rv = unsafe {
             trace!("Creating synth");
             let synth: *mut Object = msg_send![class!(AVSpeechSynthesizer), new];
             trace!("Allocated {:?}", synth);
-            delegate_obj
-                .as_mut()
-                .unwrap()
-                .set_ivar("backend_id", *backend_id);
-            trace!("Set backend ID in delegate");
-            let _: () = msg_send![synth, setDelegate: delegate_obj];
-            trace!("Assigned delegate: {:?}", delegate_obj);
             AvFoundation {
-                id: BackendId::AvFoundation(*backend_id),
-                delegate: delegate_obj,
+                id: *backend_id,
+                delegate: nil,
                 synth,
                 rate: 0.5,
                 volume: 1.,
@@ -153,28 +268,119 @@ impl AvFoundation {
         *backend_id += 1;
         Ok(rv)
     }
+
+    pub(crate) fn personal_voice_authorization_status() -> PersonalVoiceAuthorizationStatus {
+        let synthesizer = class!(AVSpeechSynthesizer);
+        unsafe {
+            let supported: BOOL = msg_send![
+                synthesizer,
+                respondsToSelector: sel!(personalVoiceAuthorizationStatus)
+            ];
+            if supported == NO {
+                return PersonalVoiceAuthorizationStatus::Unsupported;
+            }
+            let status: isize = msg_send![synthesizer, personalVoiceAuthorizationStatus];
+            personal_voice_authorization_status_from(status)
+        }
+    }
+
+    pub(crate) fn request_personal_voice_authorization(
+        callback: Box<dyn FnOnce(PersonalVoiceAuthorizationStatus) + Send>,
+    ) {
+        trace!("request_personal_voice_authorization()");
+        let synthesizer = class!(AVSpeechSynthesizer);
+        let supported: BOOL = unsafe {
+            msg_send![
+                synthesizer,
+                respondsToSelector: sel!(requestPersonalVoiceAuthorizationWithCompletionHandler:)
+            ]
+        };
+        if supported == NO {
+            callback(PersonalVoiceAuthorizationStatus::Unsupported);
+            return;
+        }
+        let callback = Mutex::new(Some(callback));
+        let handler = ConcreteBlock::new(move |status: isize| {
+            trace!("Personal voice authorization status: {}", status);
+            if let Some(callback) = callback.lock().take() {
+                callback(personal_voice_authorization_status_from(status));
+            }
+        });
+        let handler = handler.copy();
+        unsafe {
+            let _: () = msg_send![
+                synthesizer,
+                requestPersonalVoiceAuthorizationWithCompletionHandler: &*handler
+            ];
+        }
+    }
+}
+
+fn personal_voice_authorization_status_from(status: isize) -> PersonalVoiceAuthorizationStatus {
+    match status {
+        1 => PersonalVoiceAuthorizationStatus::Denied,
+        2 => PersonalVoiceAuthorizationStatus::Unsupported,
+        3 => PersonalVoiceAuthorizationStatus::Authorized,
+        _ => PersonalVoiceAuthorizationStatus::NotDetermined,
+    }
 }
 
 impl Backend for AvFoundation {
     fn id(&self) -> Option<BackendId> {
-        Some(self.id)
+        Some(BackendId::AvFoundation(self.id))
+    }
+
+    fn enable_callbacks(&mut self) -> Result<(), Error> {
+        if self.delegate != nil {
+            return Ok(());
+        }
+        let delegate_class = delegate_class()?;
+        unsafe {
+            let delegate: *mut Object = msg_send![delegate_class, new];
+            delegate
+                .as_mut()
+                .ok_or(Error::OperationFailed)?
+                .set_ivar("backend_id", self.id);
+            let _: () = msg_send![self.synth, setDelegate: delegate];
+            trace!("Assigned delegate: {:?}", delegate);
+            self.delegate = delegate;
+        }
+        Ok(())
     }
 
     fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            pitch: true,
-            volume: true,
-            is_speaking: true,
-            voice: true,
-            get_voice: false,
-            utterance_callbacks: true,
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::Voice)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::WordCallbacks)
+            .set(Capability::OutputDevice, cfg!(target_os = "ios"))
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo {
+            engine_name: Some("AVSpeechSynthesizer".into()),
+            engine_version: Some(super::apple_os_version()),
+            ..BackendInfo::new(Backends::AvFoundation)
         }
     }
 
     fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
-        trace!("speak({}, {})", text, interrupt);
+        self.speak_with_options(text, interrupt, &UtteranceOptions::default())
+    }
+
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        trace!("speak_with_options({}, {}, {:?})", text, interrupt, options);
         if interrupt && self.is_speaking()? {
             self.stop()?;
         }
@@ -199,11 +405,23 @@ impl Backend for AvFoundation {
                 let v: id = msg_send![class!(AVSpeechSynthesisVoice), voiceWithIdentifier: vid];
                 let _: () = msg_send![utterance, setVoice: v];
             }
+            if let Some(delay) = options.pre_utterance_delay {
+                trace!("Setting pre-utterance delay to {:?}", delay);
+                let _: () = msg_send![utterance, setPreUtteranceDelay: delay.as_secs_f64()];
+            }
+            if let Some(delay) = options.post_utterance_delay {
+                trace!("Setting post-utterance delay to {:?}", delay);
+                let _: () = msg_send![utterance, setPostUtteranceDelay: delay.as_secs_f64()];
+            }
+        }
+        // Numbered before it's queued, so its events can't race the ID.
+        let utterance_id = utterance_id(utterance, false);
+        unsafe {
             trace!("Enqueuing");
             let _: () = msg_send![self.synth, speakUtterance: utterance];
             trace!("Done queuing");
         }
-        Ok(Some(UtteranceId::AvFoundation(utterance)))
+        Ok(Some(utterance_id))
     }
 
     fn stop(&mut self) -> Result<(), Error> {
@@ -303,21 +521,41 @@ impl Backend for AvFoundation {
                 let name: CFString =
                     unsafe { CFString::wrap_under_get_rule(msg_send![*v as *const Object, name]) };
                 let gender: i64 = unsafe { msg_send![*v as *const Object, gender] };
+                // `AVSpeechSynthesisVoiceGenderUnspecified` is 0.
                 let gender = match gender {
-                    1 => Some(Gender::Male),
-                    2 => Some(Gender::Female),
-                    _ => None,
+                    1 => Gender::Male,
+                    2 => Gender::Female,
+                    _ => Gender::Unknown,
                 };
                 let language: CFString = unsafe {
                     CFString::wrap_under_get_rule(msg_send![*v as *const Object, language])
                 };
                 let language = language.to_string();
                 let language = LanguageTag::parse(language).unwrap();
+                let quality: isize = unsafe { msg_send![*v as *const Object, quality] };
+                let quality = match quality {
+                    1 => Some(VoiceQuality::Normal),
+                    2 => Some(VoiceQuality::High),
+                    3 => Some(VoiceQuality::VeryHigh),
+                    _ => None,
+                };
+                let has_traits: BOOL = unsafe {
+                    msg_send![*v as *const Object, respondsToSelector: sel!(voiceTraits)]
+                };
+                let personal = if has_traits == NO {
+                    false
+                } else {
+                    let traits: u64 = unsafe { msg_send![*v as *const Object, voiceTraits] };
+                    traits & AV_SPEECH_SYNTHESIS_VOICE_TRAIT_IS_PERSONAL_VOICE != 0
+                };
                 Voice {
                     id: id.to_string(),
                     name: name.to_string(),
                     gender,
                     language,
+                    personal,
+                    quality,
+                    network: false,
                 }
             })
             .collect();
@@ -328,12 +566,138 @@ impl Backend for AvFoundation {
         self.voice = Some(voice.clone());
         Ok(())
     }
+
+    #[cfg(target_os = "ios")]
+    fn set_audio_session_options(&mut self, options: &AudioSessionOptions) -> Result<(), Error> {
+        trace!("set_audio_session_options({:?})", options);
+        let mut category_options = 0;
+        if options.mix_with_others {
+            category_options |= AV_AUDIO_SESSION_CATEGORY_OPTION_MIX_WITH_OTHERS;
+        }
+        if options.duck_others {
+            category_options |= AV_AUDIO_SESSION_CATEGORY_OPTION_DUCK_OTHERS;
+        }
+        if options.interrupt_spoken_audio {
+            category_options |=
+                AV_AUDIO_SESSION_CATEGORY_OPTION_INTERRUPT_SPOKEN_AUDIO_AND_MIX_WITH_OTHERS;
+        }
+        unsafe {
+            let category = match options.category {
+                AudioSessionCategory::Ambient => AVAudioSessionCategoryAmbient,
+                AudioSessionCategory::SoloAmbient => AVAudioSessionCategorySoloAmbient,
+                AudioSessionCategory::Playback => AVAudioSessionCategoryPlayback,
+                AudioSessionCategory::PlayAndRecord => AVAudioSessionCategoryPlayAndRecord,
+            };
+            let session: id = msg_send![class!(AVAudioSession), sharedInstance];
+            let mut error: id = nil;
+            let success: BOOL = msg_send![session, setCategory: category withOptions: category_options error: &mut error];
+            if success == NO {
+                return Err(Error::OperationFailed);
+            }
+            let uses_application_audio_session = if options.uses_application_audio_session {
+                YES
+            } else {
+                NO
+            };
+            let _: () = msg_send![
+                self.synth,
+                setUsesApplicationAudioSession: uses_application_audio_session
+            ];
+        }
+        Ok(())
+    }
+
+    /// Toggles the shared audio session's duck option, keeping its category and other options.
+    #[cfg(target_os = "ios")]
+    fn set_ducking(&mut self, mode: DuckingMode) -> Result<(), Error> {
+        trace!("set_ducking({:?})", mode);
+        unsafe {
+            let session: id = msg_send![class!(AVAudioSession), sharedInstance];
+            let category: id = msg_send![session, category];
+            let mut category_options: u64 = msg_send![session, categoryOptions];
+            match mode {
+                // Ducking requires mixing.
+                DuckingMode::Duck => {
+                    category_options |= AV_AUDIO_SESSION_CATEGORY_OPTION_MIX_WITH_OTHERS
+                        | AV_AUDIO_SESSION_CATEGORY_OPTION_DUCK_OTHERS
+                }
+                DuckingMode::Off => {
+                    category_options &= !AV_AUDIO_SESSION_CATEGORY_OPTION_DUCK_OTHERS
+                }
+            }
+            let mut error: id = nil;
+            let success: BOOL = msg_send![session, setCategory: category withOptions: category_options error: &mut error];
+            if success == NO {
+                return Err(Error::OperationFailed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists the outputs in the current route, plus the built-in speaker, which is the only one apps can force.
+    #[cfg(target_os = "ios")]
+    fn output_devices(&self) -> Result<Vec<OutputDevice>, Error> {
+        let speaker =
+            unsafe { CFString::wrap_under_get_rule(AVAudioSessionPortBuiltInSpeaker as _) }
+                .to_string();
+        let outputs: CFArray = unsafe {
+            let session: id = msg_send![class!(AVAudioSession), sharedInstance];
+            let route: id = msg_send![session, currentRoute];
+            CFArray::wrap_under_get_rule(msg_send![route, outputs])
+        };
+        let mut rv: Vec<OutputDevice> = outputs
+            .iter()
+            .map(|port| {
+                let port_type: CFString = unsafe {
+                    CFString::wrap_under_get_rule(msg_send![*port as *const Object, portType])
+                };
+                let name: CFString = unsafe {
+                    CFString::wrap_under_get_rule(msg_send![*port as *const Object, portName])
+                };
+                OutputDevice {
+                    id: port_type.to_string(),
+                    name: name.to_string(),
+                }
+            })
+            .collect();
+        if !rv.iter().any(|device| device.id == speaker) {
+            rv.push(OutputDevice {
+                id: speaker,
+                name: "Speaker".into(),
+            });
+        }
+        Ok(rv)
+    }
+
+    #[cfg(target_os = "ios")]
+    fn set_output_device(&mut self, device: Option<&OutputDevice>) -> Result<(), Error> {
+        trace!("set_output_device({:?})", device);
+        let speaker =
+            unsafe { CFString::wrap_under_get_rule(AVAudioSessionPortBuiltInSpeaker as _) }
+                .to_string();
+        let port_override = match device {
+            Some(device) if device.id == speaker => AV_AUDIO_SESSION_PORT_OVERRIDE_SPEAKER,
+            _ => AV_AUDIO_SESSION_PORT_OVERRIDE_NONE,
+        };
+        unsafe {
+            let session: id = msg_send![class!(AVAudioSession), sharedInstance];
+            let mut error: id = nil;
+            let success: BOOL =
+                msg_send![session, overrideOutputAudioPort: port_override error: &mut error];
+            if success == NO {
+                return Err(Error::OperationFailed);
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Drop for AvFoundation {
     fn drop(&mut self) {
         unsafe {
-            let _: Object = msg_send![self.delegate, release];
+            if self.delegate != nil {
+                let _: Object = msg_send![self.delegate, release];
+            }
             let _: Object = msg_send![self.synth, release];
         }
     }
@@ -0,0 +1,268 @@
+//! A backend for tests, which queues utterances like a real engine but only moves through them when a test calls
+//! `advance()`, so the order callbacks fire in is known.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::LazyLock,
+};
+
+use parking_lot::Mutex;
+
+use crate::{
+    callbacks, Backend, BackendId, BackendInfo, Backends, Capability, Error, Features, Tts,
+    UtteranceEvent, UtteranceId, Voice,
+};
+
+/// Numbers start far above any a real backend reaches, so they never share callbacks with one.
+static NEXT_ID: Mutex<u64> = Mutex::new(1 << 48);
+
+#[derive(Default)]
+struct Queue {
+    utterances: VecDeque<UtteranceId>,
+    /// Whether the utterance at the front has begun.
+    begun: bool,
+}
+
+static QUEUES: LazyLock<Mutex<HashMap<BackendId, Queue>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn next_id() -> u64 {
+    let mut next = NEXT_ID.lock();
+    *next += 1;
+    *next
+}
+
+/// A backend ID of a kind this platform has, numbered `n`.
+pub(crate) fn backend_id(n: u64) -> BackendId {
+    #[cfg(target_os = "android")]
+    return BackendId::Android(n);
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    return BackendId::AvFoundation(n);
+    #[cfg(target_os = "linux")]
+    return BackendId::SpeechDispatcher(n as usize);
+    #[cfg(target_arch = "wasm32")]
+    return BackendId::Web(n);
+    #[cfg(windows)]
+    return BackendId::WinRt(n);
+}
+
+/// An utterance ID of a kind this platform has, numbered `n`.
+pub(crate) fn utterance_id(n: u64) -> UtteranceId {
+    #[cfg(target_os = "android")]
+    return UtteranceId::Android(n);
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    return UtteranceId::AvFoundation(n);
+    #[cfg(target_os = "linux")]
+    return UtteranceId::SpeechDispatcher(n);
+    #[cfg(target_arch = "wasm32")]
+    return UtteranceId::Web(n);
+    #[cfg(windows)]
+    return UtteranceId::WinRt(n);
+}
+
+/// Begins the utterance at the front of `backend`'s queue, or if it has begun, ends it and begins the next, firing
+/// callbacks as an engine would.
+pub(crate) fn advance(backend: BackendId) {
+    let mut events = vec![];
+    {
+        let mut queues = QUEUES.lock();
+        let Some(queue) = queues.get_mut(&backend) else {
+            return;
+        };
+        if queue.begun {
+            if let Some(ended) = queue.utterances.pop_front() {
+                events.push((UtteranceEvent::End, ended));
+            }
+        }
+        queue.begun = !queue.utterances.is_empty();
+        if let Some(&next) = queue.utterances.front() {
+            events.push((UtteranceEvent::Begin, next));
+        }
+    }
+    for (event, utterance) in events {
+        callbacks::fire_utterance(backend, event, utterance);
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Fake {
+    id: BackendId,
+    rate: f32,
+    pitch: f32,
+    volume: f32,
+}
+
+impl Fake {
+    pub(crate) fn new() -> Self {
+        let id = backend_id(next_id());
+        QUEUES.lock().insert(id, Default::default());
+        Fake {
+            id,
+            rate: 1.,
+            pitch: 1.,
+            volume: 1.,
+        }
+    }
+}
+
+impl Backend for Fake {
+    fn id(&self) -> Option<BackendId> {
+        Some(self.id)
+    }
+
+    fn supported_features(&self) -> Features {
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::WordCallbacks)
+            .with(Capability::Queue)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo::new(Backends::Noop)
+    }
+
+    fn speak(&mut self, _text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        if interrupt {
+            self.stop()?;
+        }
+        let utterance = utterance_id(next_id());
+        if let Some(queue) = QUEUES.lock().get_mut(&self.id) {
+            queue.utterances.push_back(utterance);
+        }
+        Ok(Some(utterance))
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        let stopped: Vec<UtteranceId> = match QUEUES.lock().get_mut(&self.id) {
+            Some(queue) => {
+                queue.begun = false;
+                queue.utterances.drain(..).collect()
+            }
+            None => vec![],
+        };
+        for utterance in stopped {
+            callbacks::fire_utterance(self.id, UtteranceEvent::Stop, utterance);
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self) -> Result<(), Error> {
+        let skipped = match QUEUES.lock().get_mut(&self.id) {
+            Some(queue) => {
+                queue.begun = false;
+                queue.utterances.pop_front()
+            }
+            None => None,
+        };
+        if let Some(skipped) = skipped {
+            callbacks::fire_utterance(self.id, UtteranceEvent::Stop, skipped);
+            advance(self.id);
+        }
+        Ok(())
+    }
+
+    fn min_rate(&self) -> f32 {
+        0.1
+    }
+
+    fn max_rate(&self) -> f32 {
+        10.
+    }
+
+    fn normal_rate(&self) -> f32 {
+        1.
+    }
+
+    fn get_rate(&self) -> Result<f32, Error> {
+        Ok(self.rate)
+    }
+
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        self.rate = rate;
+        Ok(())
+    }
+
+    fn min_pitch(&self) -> f32 {
+        0.
+    }
+
+    fn max_pitch(&self) -> f32 {
+        2.
+    }
+
+    fn normal_pitch(&self) -> f32 {
+        1.
+    }
+
+    fn get_pitch(&self) -> Result<f32, Error> {
+        Ok(self.pitch)
+    }
+
+    fn set_pitch(&mut self, pitch: f32) -> Result<(), Error> {
+        self.pitch = pitch;
+        Ok(())
+    }
+
+    fn min_volume(&self) -> f32 {
+        0.
+    }
+
+    fn max_volume(&self) -> f32 {
+        1.
+    }
+
+    fn normal_volume(&self) -> f32 {
+        1.
+    }
+
+    fn get_volume(&self) -> Result<f32, Error> {
+        Ok(self.volume)
+    }
+
+    fn set_volume(&mut self, volume: f32) -> Result<(), Error> {
+        self.volume = volume;
+        Ok(())
+    }
+
+    fn is_speaking(&self) -> Result<bool, Error> {
+        Ok(self.queue_len()? > 0)
+    }
+
+    fn queue_len(&self) -> Result<usize, Error> {
+        Ok(QUEUES
+            .lock()
+            .get(&self.id)
+            .map_or(0, |queue| queue.utterances.len()))
+    }
+
+    fn voices(&self) -> Result<Vec<Voice>, Error> {
+        Ok(vec![])
+    }
+
+    fn voice(&self) -> Result<Option<Voice>, Error> {
+        Ok(None)
+    }
+
+    fn set_voice(&mut self, _voice: &Voice) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+}
+
+impl Tts {
+    /// Creates a `Tts` speaking through a new `Fake` backend.
+    pub(crate) fn fake() -> Tts {
+        Tts::from_backend(Fake::new())
+    }
+}
+
+impl Drop for Fake {
+    fn drop(&mut self) {
+        QUEUES.lock().remove(&self.id);
+    }
+}
@@ -1,41 +1,66 @@
-#[cfg(target_os = "linux")]
+#[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
 mod speech_dispatcher;
 
 #[cfg(all(windows, feature = "tolk"))]
 mod tolk;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "winrt"))]
 mod winrt;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
 mod web;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "appkit"))]
 mod appkit;
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
 mod av_foundation;
 
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", feature = "android"))]
 mod android;
 
-#[cfg(target_os = "linux")]
+mod noop;
+
+#[cfg(test)]
+pub(crate) mod fake;
+
+#[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
 pub(crate) use self::speech_dispatcher::*;
 
 #[cfg(all(windows, feature = "tolk"))]
 pub(crate) use self::tolk::*;
 
-#[cfg(windows)]
+#[cfg(all(windows, feature = "winrt"))]
 pub(crate) use self::winrt::*;
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
 pub(crate) use self::web::*;
 
-#[cfg(target_os = "macos")]
+#[cfg(all(target_os = "macos", feature = "appkit"))]
 pub(crate) use self::appkit::*;
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
 pub(crate) use self::av_foundation::*;
 
-#[cfg(target_os = "android")]
+#[cfg(all(target_os = "android", feature = "android"))]
 pub(crate) use self::android::*;
+
+pub(crate) use self::noop::*;
+
+/// Returns the OS version, such as `Version 14.5 (Build 23F79)`, which is also that of its speech synthesizers.
+#[cfg(any(
+    all(target_os = "macos", feature = "appkit"),
+    all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation")
+))]
+pub(crate) fn apple_os_version() -> String {
+    use cocoa_foundation::base::id;
+    use core_foundation::{base::TCFType, string::CFString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let info: id = msg_send![class!(NSProcessInfo), processInfo];
+        let version: CFString =
+            CFString::wrap_under_get_rule(msg_send![info, operatingSystemVersionString]);
+        version.to_string()
+    }
+}
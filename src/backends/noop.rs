@@ -0,0 +1,158 @@
+use log::{info, trace};
+use parking_lot::Mutex;
+
+use crate::{
+    Backend, BackendId, BackendInfo, Backends, Capability, Error, Features, UtteranceId, Voice,
+};
+
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+
+/// A backend that silently discards speech, for when no real backend is available.
+#[derive(Clone, Debug)]
+pub(crate) struct Noop {
+    id: BackendId,
+    /// The backend this one stands in for, if it wasn't available.
+    fallback_from: Option<Backends>,
+    rate: f32,
+    pitch: f32,
+    volume: f32,
+}
+
+impl Noop {
+    pub(crate) fn new() -> Self {
+        info!("Initializing Noop backend");
+        let mut backend_id = NEXT_BACKEND_ID.lock();
+        let id = BackendId::Noop(*backend_id);
+        *backend_id += 1;
+        Noop {
+            id,
+            fallback_from: None,
+            rate: 1.,
+            pitch: 1.,
+            volume: 1.,
+        }
+    }
+
+    /// Creates a backend standing in for `backend`, which isn't available.
+    // Only the `web_noop_fallback` feature uses this.
+    #[allow(dead_code)]
+    pub(crate) fn fallback(backend: Backends) -> Self {
+        Noop {
+            fallback_from: Some(backend),
+            ..Noop::new()
+        }
+    }
+}
+
+impl Backend for Noop {
+    fn id(&self) -> Option<BackendId> {
+        Some(self.id)
+    }
+
+    fn supported_features(&self) -> Features {
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            // Nothing is spoken, so callbacks can be set but never fire.
+            .with(Capability::UtteranceCallbacks)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo {
+            fallback_from: self.fallback_from,
+            ..BackendInfo::new(Backends::Noop)
+        }
+    }
+
+    fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        trace!("speak({}, {})", text, interrupt);
+        Ok(None)
+    }
+
+    fn stop(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn min_rate(&self) -> f32 {
+        0.1
+    }
+
+    fn max_rate(&self) -> f32 {
+        10.
+    }
+
+    fn normal_rate(&self) -> f32 {
+        1.
+    }
+
+    fn get_rate(&self) -> Result<f32, Error> {
+        Ok(self.rate)
+    }
+
+    fn set_rate(&mut self, rate: f32) -> Result<(), Error> {
+        self.rate = rate;
+        Ok(())
+    }
+
+    fn min_pitch(&self) -> f32 {
+        0.
+    }
+
+    fn max_pitch(&self) -> f32 {
+        2.
+    }
+
+    fn normal_pitch(&self) -> f32 {
+        1.
+    }
+
+    fn get_pitch(&self) -> Result<f32, Error> {
+        Ok(self.pitch)
+    }
+
+    fn set_pitch(&mut self, pitch: f32) -> Result<(), Error> {
+        self.pitch = pitch;
+        Ok(())
+    }
+
+    fn min_volume(&self) -> f32 {
+        0.
+    }
+
+    fn max_volume(&self) -> f32 {
+        1.
+    }
+
+    fn normal_volume(&self) -> f32 {
+        1.
+    }
+
+    fn get_volume(&self) -> Result<f32, Error> {
+        Ok(self.volume)
+    }
+
+    fn set_volume(&mut self, volume: f32) -> Result<(), Error> {
+        self.volume = volume;
+        Ok(())
+    }
+
+    fn is_speaking(&self) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    fn voices(&self) -> Result<Vec<Voice>, Error> {
+        Ok(vec![])
+    }
+
+    fn voice(&self) -> Result<Option<Voice>, Error> {
+        Ok(None)
+    }
+
+    fn set_voice(&mut self, _voice: &Voice) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+}
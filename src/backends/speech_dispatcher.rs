@@ -1,106 +1,208 @@
-#[cfg(target_os = "linux")]
-use std::{collections::HashMap, sync::Mutex};
+#[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+use std::{collections::HashMap, sync::LazyLock};
 
-use lazy_static::*;
 use log::{info, trace};
 use oxilangtag::LanguageTag;
+use parking_lot::Mutex;
 use speech_dispatcher::*;
 
-use crate::{Backend, BackendId, Error, Features, UtteranceId, Voice, CALLBACKS};
+use crate::{
+    callbacks, Backend, BackendId, BackendInfo, Backends, Capability, CapitalLetterMode, Earcon,
+    Error, Features, Gender, PunctuationLevel, SsmlDialect, UtteranceEvent, UtteranceId,
+    UtteranceOptions, Voice,
+};
 
 #[derive(Clone, Debug)]
 pub(crate) struct SpeechDispatcher(Connection);
 
-lazy_static! {
-    static ref SPEAKING: Mutex<HashMap<usize, bool>> = {
-        let m: HashMap<usize, bool> = HashMap::new();
-        Mutex::new(m)
-    };
-}
+static SPEAKING: LazyLock<Mutex<HashMap<usize, bool>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
 
 impl SpeechDispatcher {
     pub(crate) fn new() -> std::result::Result<Self, Error> {
         info!("Initializing SpeechDispatcher backend");
-        let connection = speech_dispatcher::Connection::open("tts", "tts", "tts", Mode::Threaded)?;
+        let connection = speech_dispatcher::Connection::open("tts", "tts", "tts", Mode::Threaded)
+            .map_err(|e| Error::BackendUnavailable {
+                backend: Backends::SpeechDispatcher,
+                reason: format!(
+                    "couldn't connect to speech-dispatcher ({e}); check that it's installed and that `spd-say hello` works"
+                ),
+            })?;
         let sd = SpeechDispatcher(connection);
-        let mut speaking = SPEAKING.lock().unwrap();
+        let mut speaking = SPEAKING.lock();
         speaking.insert(sd.0.client_id(), false);
         sd.0.on_begin(Some(Box::new(|msg_id, client_id| {
-            let mut speaking = SPEAKING.lock().unwrap();
+            let mut speaking = SPEAKING.lock();
             speaking.insert(client_id, true);
-            let mut callbacks = CALLBACKS.lock().unwrap();
+            drop(speaking);
             let backend_id = BackendId::SpeechDispatcher(client_id);
-            let cb = callbacks.get_mut(&backend_id).unwrap();
             let utterance_id = UtteranceId::SpeechDispatcher(msg_id as u64);
-            if let Some(f) = cb.utterance_begin.as_mut() {
-                f(utterance_id);
-            }
+            callbacks::fire_utterance(backend_id, UtteranceEvent::Begin, utterance_id);
         })));
         sd.0.on_end(Some(Box::new(|msg_id, client_id| {
-            let mut speaking = SPEAKING.lock().unwrap();
+            let mut speaking = SPEAKING.lock();
             speaking.insert(client_id, false);
-            let mut callbacks = CALLBACKS.lock().unwrap();
+            drop(speaking);
             let backend_id = BackendId::SpeechDispatcher(client_id);
-            let cb = callbacks.get_mut(&backend_id).unwrap();
             let utterance_id = UtteranceId::SpeechDispatcher(msg_id as u64);
-            if let Some(f) = cb.utterance_end.as_mut() {
-                f(utterance_id);
-            }
+            callbacks::fire_utterance(backend_id, UtteranceEvent::End, utterance_id);
         })));
         sd.0.on_cancel(Some(Box::new(|msg_id, client_id| {
-            let mut speaking = SPEAKING.lock().unwrap();
+            let mut speaking = SPEAKING.lock();
             speaking.insert(client_id, false);
-            let mut callbacks = CALLBACKS.lock().unwrap();
+            drop(speaking);
             let backend_id = BackendId::SpeechDispatcher(client_id);
-            let cb = callbacks.get_mut(&backend_id).unwrap();
             let utterance_id = UtteranceId::SpeechDispatcher(msg_id as u64);
-            if let Some(f) = cb.utterance_stop.as_mut() {
-                f(utterance_id);
-            }
+            callbacks::fire_utterance(backend_id, UtteranceEvent::Stop, utterance_id);
         })));
         sd.0.on_pause(Some(Box::new(|_msg_id, client_id| {
-            let mut speaking = SPEAKING.lock().unwrap();
+            let mut speaking = SPEAKING.lock();
             speaking.insert(client_id, false);
         })));
         sd.0.on_resume(Some(Box::new(|_msg_id, client_id| {
-            let mut speaking = SPEAKING.lock().unwrap();
+            let mut speaking = SPEAKING.lock();
             speaking.insert(client_id, true);
         })));
         Ok(sd)
     }
 }
 
+fn punctuation_from(level: PunctuationLevel) -> Punctuation {
+    match level {
+        PunctuationLevel::None => Punctuation::None,
+        PunctuationLevel::Some => Punctuation::Some,
+        #[cfg(any(feature = "speech_dispatcher_0_10", feature = "speech_dispatcher_0_11"))]
+        PunctuationLevel::Most => Punctuation::Most,
+        #[cfg(not(any(feature = "speech_dispatcher_0_10", feature = "speech_dispatcher_0_11")))]
+        PunctuationLevel::Most => Punctuation::All,
+        PunctuationLevel::All => Punctuation::All,
+    }
+}
+
+fn capital_letters_from(mode: CapitalLetterMode) -> CapitalLetters {
+    match mode {
+        CapitalLetterMode::None => CapitalLetters::None,
+        CapitalLetterMode::Spell => CapitalLetters::Spell,
+        CapitalLetterMode::Icon => CapitalLetters::Icon,
+    }
+}
+
 impl Backend for SpeechDispatcher {
     fn id(&self) -> Option<BackendId> {
         Some(BackendId::SpeechDispatcher(self.0.client_id()))
     }
 
     fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            pitch: true,
-            volume: true,
-            is_speaking: true,
-            voice: true,
-            get_voice: false,
-            utterance_callbacks: true,
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::Voice)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::Earcons)
+            .with(Capability::Ssml)
+            .with(Capability::Priorities)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        // Replies look like `251-espeak-ng\r\n251 OK GET RETURNED\r\n`, with data on the lines continued by a hyphen.
+        let synthesizer = self
+            .0
+            .send_data("GET OUTPUT_MODULE\r\n", true)
+            .and_then(|reply| {
+                reply.lines().find_map(|line| {
+                    let (code, module) = line.split_at_checked(4)?;
+                    code.ends_with('-').then(|| module.trim().to_string())
+                })
+            });
+        BackendInfo {
+            engine_name: Some("Speech Dispatcher".into()),
+            synthesizer,
+            ..BackendInfo::new(Backends::SpeechDispatcher)
         }
     }
 
     fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
-        trace!("speak({}, {})", text, interrupt);
+        self.speak_with_options(text, interrupt, &UtteranceOptions::default())
+    }
+
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        trace!("speak_with_options({}, {}, {:?})", text, interrupt, options);
         if interrupt {
             self.stop()?;
         }
-        let single_char = text.to_string().capacity() == 1;
-        if single_char {
-            self.0.set_punctuation(Punctuation::All)?;
+        // A lone punctuation mark would otherwise be silent.
+        let punctuation = options
+            .punctuation
+            .or((text.len() == 1).then_some(PunctuationLevel::All));
+        if let Some(punctuation) = punctuation {
+            self.0.set_punctuation(punctuation_from(punctuation))?;
+        }
+        if let Some(capital_letters) = options.capital_letters {
+            self.0
+                .set_capital_letters(capital_letters_from(capital_letters))?;
         }
         let id = self.0.say(Priority::Important, text);
-        if single_char {
+        // Settings apply to every message after them, so restore Speech Dispatcher's defaults.
+        if punctuation.is_some() {
             self.0.set_punctuation(Punctuation::None)?;
         }
+        if options.capital_letters.is_some() {
+            self.0.set_capital_letters(CapitalLetters::None)?;
+        }
+        if let Some(id) = id {
+            Ok(Some(UtteranceId::SpeechDispatcher(id)))
+        } else {
+            Err(Error::NoneError)
+        }
+    }
+
+    fn play_earcon(
+        &mut self,
+        earcon: &Earcon,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        trace!("play_earcon({:?}, {})", earcon, interrupt);
+        let Earcon::Named(name) = earcon else {
+            return Err(Error::UnsupportedFeature);
+        };
+        if interrupt {
+            self.stop()?;
+        }
+        // `Connection::sound_icon()` sends the name as a character to spell, so send the SSIP commands ourselves, at
+        // the priority speech is queued with.
+        for command in [
+            "SET SELF PRIORITY important".to_string(),
+            format!("SOUND_ICON {name}"),
+        ] {
+            let reply = self.0.send_data(format!("{command}\r\n"), true);
+            if !reply.is_some_and(|reply| reply.starts_with('2')) {
+                return Err(Error::OperationFailed);
+            }
+        }
+        Ok(None)
+    }
+
+    fn ssml_dialect(&self) -> SsmlDialect {
+        SsmlDialect::SpeechDispatcher
+    }
+
+    fn speak_ssml(&mut self, ssml: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        trace!("speak_ssml({}, {})", ssml, interrupt);
+        if interrupt {
+            self.stop()?;
+        }
+        self.0.set_data_mode(DataMode::SSML)?;
+        let id = self.0.say(Priority::Important, ssml);
+        self.0.set_data_mode(DataMode::Text)?;
         if let Some(id) = id {
             Ok(Some(UtteranceId::SpeechDispatcher(id)))
         } else {
@@ -114,6 +216,13 @@ impl Backend for SpeechDispatcher {
         Ok(())
     }
 
+    fn skip(&mut self) -> Result<(), Error> {
+        trace!("skip()");
+        // Unlike cancelling, stopping leaves the rest of the queue to be spoken.
+        self.0.stop()?;
+        Ok(())
+    }
+
     fn min_rate(&self) -> f32 {
         -100.
     }
@@ -178,7 +287,7 @@ impl Backend for SpeechDispatcher {
     }
 
     fn is_speaking(&self) -> Result<bool, Error> {
-        let speaking = SPEAKING.lock().unwrap();
+        let speaking = SPEAKING.lock();
         let is_speaking = speaking.get(&self.0.client_id()).unwrap();
         Ok(*is_speaking)
     }
@@ -192,8 +301,11 @@ impl Backend for SpeechDispatcher {
             .map(|v| Voice {
                 id: v.name.clone(),
                 name: v.name.clone(),
-                gender: None,
+                gender: Gender::Unknown,
                 language: LanguageTag::parse(v.language.clone()).unwrap(),
+                personal: false,
+                quality: None,
+                network: false,
             })
             .collect::<Vec<Voice>>();
         Ok(rv)
@@ -216,7 +328,7 @@ impl Backend for SpeechDispatcher {
 
 impl Drop for SpeechDispatcher {
     fn drop(&mut self) {
-        let mut speaking = SPEAKING.lock().unwrap();
+        let mut speaking = SPEAKING.lock();
         speaking.remove(&self.0.client_id());
     }
 }
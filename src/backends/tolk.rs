@@ -4,19 +4,22 @@ use std::sync::Arc;
 use log::{info, trace};
 use tolk::Tolk as TolkPtr;
 
-use crate::{Backend, BackendId, Error, Features, UtteranceId, Voice};
+use crate::{Backend, BackendId, Backends, Capability, Error, Features, UtteranceId, Voice};
 
 #[derive(Clone, Debug)]
 pub(crate) struct Tolk(Arc<TolkPtr>);
 
 impl Tolk {
-    pub(crate) fn new() -> Option<Self> {
+    pub(crate) fn new() -> Result<Self, Error> {
         info!("Initializing Tolk backend");
         let tolk = TolkPtr::new();
         if tolk.detect_screen_reader().is_some() {
-            Some(Tolk(tolk))
+            Ok(Tolk(tolk))
         } else {
-            None
+            Err(Error::BackendUnavailable {
+                backend: Backends::Tolk,
+                reason: "no running screen reader detected".into(),
+            })
         }
     }
 }
@@ -27,9 +30,17 @@ impl Backend for Tolk {
     }
 
     fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            ..Default::default()
+        Features::builder()
+            .with(Capability::Stop)
+            .set(Capability::Braille, self.0.has_braille())
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo {
+            engine_name: Some("Tolk".into()),
+            synthesizer: self.0.detect_screen_reader(),
+            ..BackendInfo::new(Backends::Tolk)
         }
     }
 
@@ -1,17 +1,23 @@
-#[cfg(target_arch = "wasm32")]
-use std::sync::Mutex;
+#[cfg(all(target_arch = "wasm32", feature = "web"))]
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+};
 
-use lazy_static::lazy_static;
-use log::{info, trace};
+use log::{info, trace, warn};
 use oxilangtag::LanguageTag;
+use parking_lot::Mutex;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{
-    SpeechSynthesisErrorCode, SpeechSynthesisErrorEvent, SpeechSynthesisEvent,
+    SpeechSynthesis, SpeechSynthesisErrorCode, SpeechSynthesisErrorEvent, SpeechSynthesisEvent,
     SpeechSynthesisUtterance, SpeechSynthesisVoice,
 };
 
-use crate::{Backend, BackendId, Error, Features, UtteranceId, Voice, CALLBACKS};
+use crate::{
+    callbacks, utf16_range_to_byte_range, voices, Backend, BackendId, BackendInfo, Backends,
+    Capability, Error, Features, Gender, UtteranceEvent, UtteranceId, UtteranceOptions, Voice,
+};
 
 #[derive(Clone, Debug)]
 pub struct Web {
@@ -20,24 +26,148 @@ pub struct Web {
     pitch: f32,
     volume: f32,
     voice: Option<SpeechSynthesisVoice>,
+    long_utterance_workaround: bool,
+}
+
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+static NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+
+/// Keeps an utterance's event handlers alive until it ends or errors.
+struct UtteranceHandlers {
+    backend_id: BackendId,
+    _chunks: Vec<ChunkHandlers>,
+}
+
+/// The handlers for one `SpeechSynthesisUtterance`, of which an utterance may be split into several.
+struct ChunkHandlers {
+    utterance: SpeechSynthesisUtterance,
+    _start: Closure<dyn Fn(SpeechSynthesisEvent)>,
+    _end: Closure<dyn Fn(SpeechSynthesisEvent)>,
+    _error: Closure<dyn Fn(SpeechSynthesisErrorEvent)>,
+    _boundary: Closure<dyn Fn(SpeechSynthesisEvent)>,
+}
+
+impl Drop for ChunkHandlers {
+    fn drop(&mut self) {
+        // Detach the handlers so the browser can't call into freed closures.
+        self.utterance.set_onstart(None);
+        self.utterance.set_onend(None);
+        self.utterance.set_onerror(None);
+        self.utterance.set_onboundary(None);
+    }
 }
 
-lazy_static! {
-    static ref NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
-    static ref UTTERANCE_MAPPINGS: Mutex<Vec<(BackendId, UtteranceId)>> = Mutex::new(Vec::new());
-    static ref NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+/// A timer that periodically cycles `pause()`/`resume()` so Chromium doesn't cut off long speech.
+struct KeepAlive {
+    handle: i32,
+    _callback: Closure<dyn Fn()>,
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        if let Some(window) = web_sys::window() {
+            window.clear_interval_with_handle(self.handle);
+        }
+    }
+}
+
+const KEEP_ALIVE_INTERVAL_MS: i32 = 10_000;
+
+/// The longest piece of text spoken as a single `SpeechSynthesisUtterance` when the long utterance workaround is enabled.
+const MAX_CHUNK_LEN: usize = 200;
+
+thread_local! {
+    static UTTERANCES: RefCell<HashMap<UtteranceId, UtteranceHandlers>> =
+        RefCell::new(HashMap::new());
+    static KEEP_ALIVES: RefCell<HashMap<BackendId, KeepAlive>> = RefCell::new(HashMap::new());
+    static WATCHING_VOICES: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Splits `text` into pieces of at most `MAX_CHUNK_LEN` bytes, preferring to break after sentences and then between words.
+///
+/// Returns each piece along with its byte offset into `text`.
+fn chunk_text(text: &str) -> Vec<(usize, &str)> {
+    let mut chunks = vec![];
+    let mut offset = 0;
+    while text.len() - offset > MAX_CHUNK_LEN {
+        let rest = &text[offset..];
+        let mut limit = MAX_CHUNK_LEN;
+        while !rest.is_char_boundary(limit) {
+            limit -= 1;
+        }
+        let window = &rest[..limit];
+        let split = window
+            .rfind(['.', '!', '?', '\n'])
+            .map(|i| i + 1)
+            .or_else(|| window.rfind(char::is_whitespace).filter(|i| *i > 0))
+            .unwrap_or(limit);
+        chunks.push((offset, &rest[..split]));
+        offset += split;
+    }
+    chunks.push((offset, &text[offset..]));
+    chunks
+}
+
+/// Releases an utterance's handlers, returning `false` if it had already finished.
+///
+/// wasm-bindgen defers freeing a closure dropped from inside its own invocation, so this is safe to call from a handler.
+fn finish_utterance(utterance_id: UtteranceId) -> bool {
+    let handlers = UTTERANCES.with(|utterances| utterances.borrow_mut().remove(&utterance_id));
+    handlers.is_some()
+}
+
+fn unavailable(reason: &str) -> Error {
+    Error::BackendUnavailable {
+        backend: Backends::Web,
+        reason: reason.into(),
+    }
+}
+
+/// Returns the `speechSynthesis` object, which is missing in workers, some WebViews and some privacy-focused browsers.
+fn speech_synthesis() -> Result<SpeechSynthesis, Error> {
+    let window = web_sys::window().ok_or_else(|| unavailable("no window"))?;
+    match window.speech_synthesis() {
+        Ok(speech_synthesis) if !speech_synthesis.is_undefined() && !speech_synthesis.is_null() => {
+            Ok(speech_synthesis)
+        }
+        _ => Err(unavailable("Web Speech API missing")),
+    }
+}
+
+/// Forgets cached voices whenever the browser's change, as they do once it has loaded them.
+fn watch_voices(speech_synthesis: &SpeechSynthesis) {
+    if WATCHING_VOICES.replace(true) {
+        return;
+    }
+    let callback = Closure::<dyn Fn()>::new(|| {
+        trace!("Voices changed");
+        voices::invalidate();
+    });
+    // Listening rather than setting `onvoiceschanged` leaves the app's own handler alone.
+    match speech_synthesis
+        .add_event_listener_with_callback("voiceschanged", callback.as_ref().unchecked_ref())
+    {
+        // It listens for as long as the page lives.
+        Ok(()) => callback.forget(),
+        Err(e) => {
+            warn!("Couldn't watch for voice changes: {:?}", e);
+            WATCHING_VOICES.set(false);
+        }
+    }
 }
 
 impl Web {
     pub fn new() -> Result<Self, Error> {
         info!("Initializing Web backend");
-        let mut backend_id = NEXT_BACKEND_ID.lock().unwrap();
+        watch_voices(&speech_synthesis()?);
+        let mut backend_id = NEXT_BACKEND_ID.lock();
         let rv = Web {
             id: BackendId::Web(*backend_id),
             rate: 1.,
             pitch: 1.,
             volume: 1.,
             voice: None,
+            long_utterance_workaround: false,
         };
         *backend_id += 1;
         Ok(rv)
@@ -50,83 +180,138 @@ impl Backend for Web {
     }
 
     fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            pitch: true,
-            volume: true,
-            is_speaking: true,
-            voice: true,
-            get_voice: true,
-            utterance_callbacks: true,
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::Voice)
+            .with(Capability::GetVoice)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::WordCallbacks)
+            .with(Capability::Pause)
+            .with(Capability::Queue)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        BackendInfo {
+            engine_name: Some("Web Speech API".into()),
+            synthesizer: web_sys::window().and_then(|window| window.navigator().user_agent().ok()),
+            ..BackendInfo::new(Backends::Web)
         }
     }
 
     fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
-        trace!("speak({}, {})", text, interrupt);
-        let utterance = SpeechSynthesisUtterance::new_with_text(text).unwrap();
-        utterance.set_rate(self.rate);
-        utterance.set_pitch(self.pitch);
-        utterance.set_volume(self.volume);
-        if self.voice.is_some() {
-            utterance.set_voice(self.voice.as_ref());
-        }
+        self.speak_with_options(text, interrupt, &UtteranceOptions::default())
+    }
+
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        trace!("speak_with_options({}, {}, {:?})", text, interrupt, options);
         let id = self.id().unwrap();
-        let mut uid = NEXT_UTTERANCE_ID.lock().unwrap();
+        let mut uid = NEXT_UTTERANCE_ID.lock();
         let utterance_id = UtteranceId::Web(*uid);
         *uid += 1;
         drop(uid);
-        let mut mappings = UTTERANCE_MAPPINGS.lock().unwrap();
-        mappings.push((self.id, utterance_id));
-        drop(mappings);
-        let callback = Closure::wrap(Box::new(move |_evt: SpeechSynthesisEvent| {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let callback = callbacks.get_mut(&id).unwrap();
-            if let Some(f) = callback.utterance_begin.as_mut() {
-                f(utterance_id);
+        let chunks = if self.long_utterance_workaround {
+            chunk_text(text)
+        } else {
+            vec![(0, text)]
+        };
+        let last = chunks.len() - 1;
+        let mut handlers = vec![];
+        for (i, (offset, chunk)) in chunks.into_iter().enumerate() {
+            let utterance = SpeechSynthesisUtterance::new_with_text(chunk).unwrap();
+            utterance.set_rate(self.rate);
+            utterance.set_pitch(self.pitch);
+            utterance.set_volume(self.volume);
+            if self.voice.is_some() {
+                utterance.set_voice(self.voice.as_ref());
             }
-        }) as Box<dyn Fn(_)>);
-        utterance.set_onstart(Some(callback.as_ref().unchecked_ref()));
-        let callback = Closure::wrap(Box::new(move |_evt: SpeechSynthesisEvent| {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let callback = callbacks.get_mut(&id).unwrap();
-            if let Some(f) = callback.utterance_end.as_mut() {
-                f(utterance_id);
+            if let Some(language) = &options.language {
+                utterance.set_lang(language.as_str());
             }
-            let mut mappings = UTTERANCE_MAPPINGS.lock().unwrap();
-            mappings.retain(|v| v.1 != utterance_id);
-        }) as Box<dyn Fn(_)>);
-        utterance.set_onend(Some(callback.as_ref().unchecked_ref()));
-        let callback = Closure::wrap(Box::new(move |evt: SpeechSynthesisErrorEvent| {
-            if evt.error() == SpeechSynthesisErrorCode::Canceled {
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                let callback = callbacks.get_mut(&id).unwrap();
-                if let Some(f) = callback.utterance_stop.as_mut() {
-                    f(utterance_id);
+            let start = Closure::wrap(Box::new(move |_evt: SpeechSynthesisEvent| {
+                if i != 0 {
+                    return;
                 }
-            }
-            let mut mappings = UTTERANCE_MAPPINGS.lock().unwrap();
-            mappings.retain(|v| v.1 != utterance_id);
-        }) as Box<dyn Fn(_)>);
-        utterance.set_onerror(Some(callback.as_ref().unchecked_ref()));
+                callbacks::fire_utterance(id, UtteranceEvent::Begin, utterance_id);
+            }) as Box<dyn Fn(_)>);
+            utterance.set_onstart(Some(start.as_ref().unchecked_ref()));
+            let end = Closure::wrap(Box::new(move |_evt: SpeechSynthesisEvent| {
+                if i != last || !finish_utterance(utterance_id) {
+                    return;
+                }
+                callbacks::fire_utterance(id, UtteranceEvent::End, utterance_id);
+            }) as Box<dyn Fn(_)>);
+            utterance.set_onend(Some(end.as_ref().unchecked_ref()));
+            let error = Closure::wrap(Box::new(move |evt: SpeechSynthesisErrorEvent| {
+                if !finish_utterance(utterance_id) {
+                    return;
+                }
+                if evt.error() == SpeechSynthesisErrorCode::Canceled {
+                    callbacks::fire_utterance(id, UtteranceEvent::Stop, utterance_id);
+                }
+            }) as Box<dyn Fn(_)>);
+            utterance.set_onerror(Some(error.as_ref().unchecked_ref()));
+            let text = chunk.to_string();
+            let boundary = Closure::wrap(Box::new(move |evt: SpeechSynthesisEvent| {
+                if evt.name().as_deref() != Some("word") {
+                    trace!("Ignoring {:?} boundary", evt.name());
+                    return;
+                }
+                let start = evt.char_index() as usize;
+                let range = match evt.char_length() {
+                    Some(len) if len > 0 => utf16_range_to_byte_range(&text, start, len as usize),
+                    // Some browsers don't report a length, so assume the word runs to the next whitespace.
+                    _ => {
+                        let start = utf16_range_to_byte_range(&text, start, 0).start;
+                        let end = text[start..]
+                            .find(char::is_whitespace)
+                            .map_or(text.len(), |i| start + i);
+                        start..end
+                    }
+                };
+                let range = (offset + range.start)..(offset + range.end);
+                callbacks::fire_word(id, utterance_id, range);
+            }) as Box<dyn Fn(_)>);
+            utterance.set_onboundary(Some(boundary.as_ref().unchecked_ref()));
+            handlers.push(ChunkHandlers {
+                utterance,
+                _start: start,
+                _end: end,
+                _error: error,
+                _boundary: boundary,
+            });
+        }
         if interrupt {
             self.stop()?;
         }
-        if let Some(window) = web_sys::window() {
-            let speech_synthesis = window.speech_synthesis().unwrap();
-            speech_synthesis.speak(&utterance);
-            Ok(Some(utterance_id))
-        } else {
-            Err(Error::NoneError)
+        let speech_synthesis = speech_synthesis()?;
+        for chunk in &handlers {
+            speech_synthesis.speak(&chunk.utterance);
         }
+        UTTERANCES.with(|utterances| {
+            utterances.borrow_mut().insert(
+                utterance_id,
+                UtteranceHandlers {
+                    backend_id: id,
+                    _chunks: handlers,
+                },
+            )
+        });
+        Ok(Some(utterance_id))
     }
 
     fn stop(&mut self) -> Result<(), Error> {
         trace!("stop()");
-        if let Some(window) = web_sys::window() {
-            let speech_synthesis = window.speech_synthesis().unwrap();
-            speech_synthesis.cancel();
-        }
+        speech_synthesis()?.cancel();
         Ok(())
     }
 
@@ -195,70 +380,129 @@ impl Backend for Web {
 
     fn is_speaking(&self) -> Result<bool, Error> {
         trace!("is_speaking()");
-        if let Some(window) = web_sys::window() {
-            match window.speech_synthesis() {
-                Ok(speech_synthesis) => Ok(speech_synthesis.speaking()),
-                Err(e) => Err(Error::JavaScriptError(e)),
-            }
-        } else {
-            Err(Error::NoneError)
+        Ok(speech_synthesis()?.speaking())
+    }
+
+    fn pause(&mut self) -> Result<(), Error> {
+        trace!("pause()");
+        speech_synthesis()?.pause();
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), Error> {
+        trace!("resume()");
+        speech_synthesis()?.resume();
+        Ok(())
+    }
+
+    fn is_paused(&self) -> Result<bool, Error> {
+        trace!("is_paused()");
+        Ok(speech_synthesis()?.paused())
+    }
+
+    fn is_pending(&self) -> Result<bool, Error> {
+        trace!("is_pending()");
+        Ok(speech_synthesis()?.pending())
+    }
+
+    fn queue_len(&self) -> Result<usize, Error> {
+        let len = UTTERANCES.with(|utterances| {
+            utterances
+                .borrow()
+                .values()
+                .filter(|v| v.backend_id == self.id)
+                .count()
+        });
+        Ok(len)
+    }
+
+    fn set_long_utterance_workaround(&mut self, enabled: bool) -> Result<(), Error> {
+        trace!("set_long_utterance_workaround({})", enabled);
+        self.long_utterance_workaround = enabled;
+        if !enabled {
+            let keep_alive = KEEP_ALIVES.with(|k| k.borrow_mut().remove(&self.id));
+            drop(keep_alive);
+            return Ok(());
         }
+        if KEEP_ALIVES.with(|k| k.borrow().contains_key(&self.id)) {
+            return Ok(());
+        }
+        let window = web_sys::window().ok_or_else(|| unavailable("no window"))?;
+        let callback = Closure::wrap(Box::new(|| {
+            if let Ok(speech_synthesis) = speech_synthesis() {
+                if speech_synthesis.speaking() && !speech_synthesis.paused() {
+                    speech_synthesis.pause();
+                    speech_synthesis.resume();
+                }
+            }
+        }) as Box<dyn Fn()>);
+        let handle = window
+            .set_interval_with_callback_and_timeout_and_arguments_0(
+                callback.as_ref().unchecked_ref(),
+                KEEP_ALIVE_INTERVAL_MS,
+            )
+            .map_err(Error::JavaScriptError)?;
+        KEEP_ALIVES.with(|k| {
+            k.borrow_mut().insert(
+                self.id,
+                KeepAlive {
+                    handle,
+                    _callback: callback,
+                },
+            )
+        });
+        Ok(())
     }
 
     fn voice(&self) -> Result<Option<Voice>, Error> {
         if let Some(voice) = &self.voice {
             Ok(Some(voice.clone().into()))
         } else {
-            if let Some(window) = web_sys::window() {
-                let speech_synthesis = window.speech_synthesis().unwrap();
-                for voice in speech_synthesis.get_voices().iter() {
-                    let voice: SpeechSynthesisVoice = voice.into();
-                    if voice.default() {
-                        return Ok(Some(voice.into()));
-                    }
+            for voice in speech_synthesis()?.get_voices().iter() {
+                let voice: SpeechSynthesisVoice = voice.into();
+                if voice.default() {
+                    return Ok(Some(voice.into()));
                 }
-            } else {
-                return Err(Error::NoneError);
             }
             Ok(None)
         }
     }
 
     fn voices(&self) -> Result<Vec<Voice>, Error> {
-        if let Some(window) = web_sys::window() {
-            let speech_synthesis = window.speech_synthesis().unwrap();
-            let mut rv: Vec<Voice> = vec![];
-            for v in speech_synthesis.get_voices().iter() {
-                let v: SpeechSynthesisVoice = v.into();
-                rv.push(v.into());
-            }
-            Ok(rv)
-        } else {
-            Err(Error::NoneError)
+        let mut rv: Vec<Voice> = vec![];
+        for v in speech_synthesis()?.get_voices().iter() {
+            let v: SpeechSynthesisVoice = v.into();
+            rv.push(v.into());
         }
+        Ok(rv)
     }
 
     fn set_voice(&mut self, voice: &Voice) -> Result<(), Error> {
-        if let Some(window) = web_sys::window() {
-            let speech_synthesis = window.speech_synthesis().unwrap();
-            for v in speech_synthesis.get_voices().iter() {
-                let v: SpeechSynthesisVoice = v.into();
-                if v.voice_uri() == voice.id {
-                    self.voice = Some(v);
-                    return Ok(());
-                }
+        for v in speech_synthesis()?.get_voices().iter() {
+            let v: SpeechSynthesisVoice = v.into();
+            if v.voice_uri() == voice.id {
+                self.voice = Some(v);
+                return Ok(());
             }
-            Err(Error::OperationFailed)
-        } else {
-            Err(Error::NoneError)
         }
+        Err(Error::OperationFailed)
     }
 }
 
 impl Drop for Web {
     fn drop(&mut self) {
-        let mut mappings = UTTERANCE_MAPPINGS.lock().unwrap();
-        mappings.retain(|v| v.0 != self.id);
+        let handlers: Vec<UtteranceHandlers> = UTTERANCES.with(|utterances| {
+            let mut utterances = utterances.borrow_mut();
+            let ids: Vec<UtteranceId> = utterances
+                .iter()
+                .filter(|(_, v)| v.backend_id == self.id)
+                .map(|(k, _)| *k)
+                .collect();
+            ids.iter().filter_map(|id| utterances.remove(id)).collect()
+        });
+        drop(handlers);
+        let keep_alive = KEEP_ALIVES.with(|k| k.borrow_mut().remove(&self.id));
+        drop(keep_alive);
     }
 }
 
@@ -268,8 +512,11 @@ impl From<SpeechSynthesisVoice> for Voice {
         Voice {
             id: other.voice_uri(),
             name: other.name(),
-            gender: None,
+            gender: Gender::Unknown,
             language,
+            personal: false,
+            quality: None,
+            network: !other.local_service(),
         }
     }
 }
@@ -1,22 +1,48 @@
-#[cfg(windows)]
+#[cfg(all(windows, feature = "winrt"))]
 use std::{
     collections::{HashMap, VecDeque},
-    sync::Mutex,
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    thread,
+    time::Duration,
 };
 
-use lazy_static::lazy_static;
 use log::{info, trace};
 use oxilangtag::LanguageTag;
+use parking_lot::Mutex;
 use windows::{
-    Foundation::TypedEventHandler,
+    core::{Interface, HSTRING},
+    Devices::Enumeration::DeviceInformation,
+    Foundation::{
+        Collections::{CollectionChange, IVectorChangedEventArgs},
+        TimeSpan, TypedEventHandler,
+    },
     Media::{
-        Core::MediaSource,
-        Playback::{MediaPlayer, MediaPlayerAudioCategory},
-        SpeechSynthesis::{SpeechSynthesizer, VoiceGender, VoiceInformation},
+        Core::{MediaCueEventArgs, MediaSource, SpeechCue},
+        Devices::{AudioDeviceRole, MediaDevice},
+        Playback::{
+            CurrentMediaPlaybackItemChangedEventArgs, MediaPlaybackItem, MediaPlaybackList,
+            MediaPlaybackTimedMetadataTrackList, MediaPlayer, MediaPlayerAudioCategory,
+            TimedMetadataTrackPresentationMode,
+        },
+        SpeechSynthesis::{
+            SpeechSynthesisStream, SpeechSynthesizer, VoiceGender, VoiceInformation,
+        },
     },
+    Storage::{StorageFile, Streams::DataReader},
+    System::Profile::AnalyticsInfo,
 };
 
-use crate::{Backend, BackendId, Error, Features, Gender, UtteranceId, Voice, CALLBACKS};
+#[cfg(feature = "audio-output")]
+use crate::audio::AudioOutput;
+use crate::{
+    callbacks, panics, utf16_range_to_byte_range, AudioBuffer, Backend, BackendId, BackendInfo,
+    Backends, Capability, Earcon, Error, Features, Gender, OutputDevice, SsmlDialect, StopStyle,
+    UtteranceEvent, UtteranceId, Voice, WordTiming,
+};
+#[cfg(feature = "audio-output")]
+use crate::{MixPolicy, UtteranceOptions};
 
 impl From<windows::core::Error> for Error {
     fn from(e: windows::core::Error) -> Self {
@@ -33,202 +59,747 @@ pub struct WinRt {
     pitch: f32,
     volume: f32,
     voice: VoiceInformation,
+    stop_style: StopStyle,
+    /// The loudness to normalize speech to, in dBFS RMS.
+    #[cfg(feature = "audio-output")]
+    target_loudness: Option<f32>,
+    /// Where speech plays, unless it couldn't be opened and a `MediaPlayer` plays it instead.
+    #[cfg(feature = "audio-output")]
+    audio: Option<AudioOutput>,
 }
 
+#[derive(Clone)]
 struct Utterance {
     id: UtteranceId,
     text: String,
+    ssml: bool,
     rate: f32,
     pitch: f32,
     volume: f32,
     voice: VoiceInformation,
+    /// A sound file to play instead of speaking `text`.
+    earcon: Option<PathBuf>,
+    /// The synthesized speech, once it's in the playback list.
+    item: Option<MediaPlaybackItem>,
+    synthesizing: bool,
+    begun: bool,
 }
 
-lazy_static! {
-    static ref NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
-    static ref NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
-    static ref BACKEND_TO_SPEECH_SYNTHESIZER: Mutex<HashMap<BackendId, SpeechSynthesizer>> = {
-        let v: HashMap<BackendId, SpeechSynthesizer> = HashMap::new();
-        Mutex::new(v)
-    };
-    static ref BACKEND_TO_MEDIA_PLAYER: Mutex<HashMap<BackendId, MediaPlayer>> = {
-        let v: HashMap<BackendId, MediaPlayer> = HashMap::new();
-        Mutex::new(v)
-    };
-    static ref UTTERANCES: Mutex<HashMap<BackendId, VecDeque<Utterance>>> = {
-        let utterances: HashMap<BackendId, VecDeque<Utterance>> = HashMap::new();
-        Mutex::new(utterances)
+fn synthesize_stream(
+    synth: &SpeechSynthesizer,
+    text: &str,
+    ssml: bool,
+) -> windows::core::Result<SpeechSynthesisStream> {
+    if ssml {
+        synth.SynthesizeSsmlToStreamAsync(&text.into())?.get()
+    } else {
+        synth.SynthesizeTextToStreamAsync(&text.into())?.get()
+    }
+}
+
+static NEXT_BACKEND_ID: Mutex<u64> = Mutex::new(0);
+static NEXT_UTTERANCE_ID: Mutex<u64> = Mutex::new(0);
+static BACKEND_TO_SPEECH_SYNTHESIZER: LazyLock<Mutex<HashMap<BackendId, SpeechSynthesizer>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static BACKEND_TO_MEDIA_PLAYER: LazyLock<Mutex<HashMap<BackendId, MediaPlayer>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static BACKEND_TO_PLAYBACK_LIST: LazyLock<Mutex<HashMap<BackendId, MediaPlaybackList>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static UTTERANCES: LazyLock<Mutex<HashMap<BackendId, VecDeque<Utterance>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Synthesizes `utterance` into an item for the playback list, which reports its words to `backend`'s callbacks as
+/// they're spoken.
+fn synthesize(
+    backend: BackendId,
+    synth: &SpeechSynthesizer,
+    utterance: &Utterance,
+) -> windows::core::Result<MediaPlaybackItem> {
+    if let Some(path) = &utterance.earcon {
+        let file = StorageFile::GetFileFromPathAsync(&HSTRING::from(path.as_os_str()))?.get()?;
+        return MediaPlaybackItem::Create(&MediaSource::CreateFromStorageFile(&file)?);
+    }
+    let options = synth.Options()?;
+    options.SetSpeakingRate(utterance.rate.into())?;
+    options.SetAudioPitch(utterance.pitch.into())?;
+    options.SetAudioVolume(utterance.volume.into())?;
+    options.SetIncludeWordBoundaryMetadata(true)?;
+    synth.SetVoice(&utterance.voice)?;
+    let stream = synthesize_stream(synth, &utterance.text, utterance.ssml)?;
+    let content_type = stream.ContentType()?;
+    let source = MediaSource::CreateFromStream(&stream, &content_type)?;
+    let item = MediaPlaybackItem::Create(&source)?;
+    let tracks = item.TimedMetadataTracks()?;
+    for index in 0..tracks.Size()? {
+        watch_words(backend, utterance, &tracks, index)?;
+    }
+    // Tracks may only show up once the item is opened.
+    let utterance = utterance.clone();
+    item.TimedMetadataTracksChanged(&TypedEventHandler::new(
+        move |item: &Option<MediaPlaybackItem>, args: &Option<IVectorChangedEventArgs>| {
+            let (Some(item), Some(args)) = (item, args) else {
+                return Ok(());
+            };
+            if args.CollectionChange()? != CollectionChange::ItemInserted {
+                return Ok(());
+            }
+            watch_words(
+                backend,
+                &utterance,
+                &item.TimedMetadataTracks()?,
+                args.Index()?,
+            )
+        },
+    ))?;
+    Ok(item)
+}
+
+/// Fires word boundary callbacks from the track at `index`, if it's the one marking words.
+fn watch_words(
+    backend: BackendId,
+    utterance: &Utterance,
+    tracks: &MediaPlaybackTimedMetadataTrackList,
+    index: u32,
+) -> windows::core::Result<()> {
+    let track = tracks.GetAt(index)?;
+    if track.Id()? != "SpeechWord" {
+        return Ok(());
+    }
+    // Cues are only raised for tracks the app presents itself.
+    tracks.SetPresentationMode(
+        index,
+        TimedMetadataTrackPresentationMode::ApplicationPresented,
+    )?;
+    let id = utterance.id;
+    let text = utterance.text.clone();
+    track.CueEntered(&TypedEventHandler::new(
+        move |_, args: &Option<MediaCueEventArgs>| {
+            let Some(args) = args else {
+                return Ok(());
+            };
+            let cue: SpeechCue = args.Cue()?.cast()?;
+            let start = cue.StartPositionInInput()?.Value()?.max(0) as usize;
+            // The end is the last UTF-16 unit of the word, not the one after it.
+            let end = cue.EndPositionInInput()?.Value()?.max(0) as usize + 1;
+            let range = utf16_range_to_byte_range(&text, start, end.saturating_sub(start));
+            callbacks::fire_word(backend, id, range);
+            Ok(())
+        },
+    ))?;
+    Ok(())
+}
+
+/// Synthesizes the playing and next utterances, if they aren't already, and appends them to `backend`'s playback
+/// list, so each utterance is ready to start the moment the one before it ends.
+fn prepare(backend: BackendId) -> windows::core::Result<()> {
+    let Some(synth) = BACKEND_TO_SPEECH_SYNTHESIZER.lock().get(&backend).cloned() else {
+        return Ok(());
     };
+    loop {
+        let utterance = {
+            let mut utterances = UTTERANCES.lock();
+            let Some(utterances) = utterances.get_mut(&backend) else {
+                return Ok(());
+            };
+            // Synthesize one at a time, so items are appended in order.
+            if utterances.iter().any(|u| u.synthesizing) {
+                return Ok(());
+            }
+            match utterances.iter_mut().take(2).find(|u| u.item.is_none()) {
+                Some(utterance) => {
+                    utterance.synthesizing = true;
+                    utterance.clone()
+                }
+                None => return Ok(()),
+            }
+        };
+        let item = match synthesize(backend, &synth, &utterance) {
+            Ok(item) => item,
+            Err(e) => {
+                // Drop what can't be spoken rather than stalling the queue behind it.
+                let dropped = UTTERANCES.lock().get_mut(&backend).and_then(|utterances| {
+                    let position = utterances.iter().position(|u| u.id == utterance.id)?;
+                    utterances.remove(position)
+                });
+                if dropped.is_some() {
+                    callbacks::fire_utterance(backend, UtteranceEvent::Stop, utterance.id);
+                }
+                panics::report(backend, e.into());
+                continue;
+            }
+        };
+        let play = {
+            let mut utterances = UTTERANCES.lock();
+            let Some(utterances) = utterances.get_mut(&backend) else {
+                return Ok(());
+            };
+            // The utterance may have been stopped while it was synthesized.
+            let Some(position) = utterances.iter().position(|u| u.id == utterance.id) else {
+                continue;
+            };
+            utterances[position].synthesizing = false;
+            // Appending while the queue is locked keeps it in step with the list.
+            if let Some(list) = BACKEND_TO_PLAYBACK_LIST.lock().get(&backend) {
+                list.Items()?.Append(&item)?;
+            }
+            utterances[position].item = Some(item.clone());
+            // Nothing is playing before it, so start it.
+            (position == 0).then_some(item)
+        };
+        if let Some(item) = play {
+            play_from(backend, &item)?;
+        }
+    }
+}
+
+/// Ramps `player`'s volume down to silence over `duration`, leaving it silent. Blocks until it has.
+fn fade_out(player: &MediaPlayer, duration: Duration) -> windows::core::Result<()> {
+    const STEP: Duration = Duration::from_millis(5);
+    let volume = player.Volume()?;
+    let steps = (duration.as_millis() / STEP.as_millis()).max(1) as u32;
+    for step in 1..=steps {
+        player.SetVolume(volume * f64::from(steps - step) / f64::from(steps))?;
+        thread::sleep(STEP);
+    }
+    Ok(())
+}
+
+/// Plays `backend`'s playback list from `item`.
+fn play_from(backend: BackendId, item: &MediaPlaybackItem) -> windows::core::Result<()> {
+    let list = BACKEND_TO_PLAYBACK_LIST.lock().get(&backend).cloned();
+    let player = BACKEND_TO_MEDIA_PLAYER.lock().get(&backend).cloned();
+    if let (Some(list), Some(player)) = (list, player) {
+        let mut index = 0;
+        if list.Items()?.IndexOf(item, &mut index)? {
+            list.MoveTo(index)?;
+            player.Play()?;
+        }
+    }
+    Ok(())
+}
+
+fn backend_for<T: PartialEq>(map: &Mutex<HashMap<BackendId, T>>, sender: &T) -> Option<BackendId> {
+    let map = map.lock();
+    map.iter().find(|v| v.1 == sender).map(|(id, _)| *id)
 }
 
 impl WinRt {
     pub fn new() -> std::result::Result<Self, Error> {
         info!("Initializing WinRT backend");
-        let synth = SpeechSynthesizer::new()?;
-        let player = MediaPlayer::new()?;
-        player.SetRealTimePlayback(true)?;
-        player.SetAudioCategory(MediaPlayerAudioCategory::Speech)?;
-        let mut backend_id = NEXT_BACKEND_ID.lock().unwrap();
+        if SpeechSynthesizer::AllVoices()?.Size()? == 0 {
+            return Err(Error::BackendUnavailable {
+                backend: Backends::WinRt,
+                reason: "no speech voices installed".into(),
+            });
+        }
+        let mut backend_id = NEXT_BACKEND_ID.lock();
         let bid = BackendId::WinRt(*backend_id);
         *backend_id += 1;
         drop(backend_id);
+        let (synth, player) = Self::engine(bid)?;
+        #[cfg(feature = "audio-output")]
+        let audio = match AudioOutput::new(bid, UtteranceId::WinRt) {
+            Ok(audio) => Some(audio),
+            Err(e) => {
+                log::warn!("Playing speech through a MediaPlayer, since {}", e);
+                None
+            }
+        };
+        Ok(Self {
+            id: bid,
+            synth,
+            player,
+            rate: 1.,
+            pitch: 1.,
+            volume: 1.,
+            voice: SpeechSynthesizer::DefaultVoice()?,
+            stop_style: StopStyle::default(),
+            #[cfg(feature = "audio-output")]
+            target_loudness: None,
+            #[cfg(feature = "audio-output")]
+            audio,
+        })
+    }
+
+    /// Creates a synthesizer and a player for `bid`, replacing any it had, with an empty queue.
+    fn engine(bid: BackendId) -> std::result::Result<(SpeechSynthesizer, MediaPlayer), Error> {
+        let synth = SpeechSynthesizer::new().map_err(|e| Error::BackendUnavailable {
+            backend: Backends::WinRt,
+            reason: format!("couldn't create speech synthesizer: {e}"),
+        })?;
+        let player = MediaPlayer::new()?;
+        player.SetRealTimePlayback(true)?;
+        player.SetAudioCategory(MediaPlayerAudioCategory::Speech)?;
+        // Queued utterances are appended to a playback list as they're synthesized, so they play without gaps.
+        let list = MediaPlaybackList::new()?;
+        player.SetSource(&list)?;
         {
-            let mut utterances = UTTERANCES.lock().unwrap();
+            let mut utterances = UTTERANCES.lock();
             utterances.insert(bid, VecDeque::new());
         }
-        let mut backend_to_media_player = BACKEND_TO_MEDIA_PLAYER.lock().unwrap();
+        let mut backend_to_media_player = BACKEND_TO_MEDIA_PLAYER.lock();
         backend_to_media_player.insert(bid, player.clone());
         drop(backend_to_media_player);
-        let mut backend_to_speech_synthesizer = BACKEND_TO_SPEECH_SYNTHESIZER.lock().unwrap();
+        let mut backend_to_playback_list = BACKEND_TO_PLAYBACK_LIST.lock();
+        backend_to_playback_list.insert(bid, list.clone());
+        drop(backend_to_playback_list);
+        let mut backend_to_speech_synthesizer = BACKEND_TO_SPEECH_SYNTHESIZER.lock();
         backend_to_speech_synthesizer.insert(bid, synth.clone());
         drop(backend_to_speech_synthesizer);
-        let bid_clone = bid;
+        list.CurrentItemChanged(&TypedEventHandler::new(
+            |sender: &Option<MediaPlaybackList>,
+             args: &Option<CurrentMediaPlaybackItemChangedEventArgs>| {
+                let (Some(sender), Some(args)) = (sender, args) else {
+                    return Ok(());
+                };
+                let Some(id) = backend_for(&BACKEND_TO_PLAYBACK_LIST, sender) else {
+                    return Ok(());
+                };
+                // The list is cleared when speech stops, which leaves no current item.
+                let Ok(item) = args.NewItem() else {
+                    return Ok(());
+                };
+                let (ended, begun) = {
+                    let mut utterances = UTTERANCES.lock();
+                    let Some(utterances) = utterances.get_mut(&id) else {
+                        return Ok(());
+                    };
+                    let Some(position) = utterances
+                        .iter()
+                        .position(|u| u.item.as_ref() == Some(&item))
+                    else {
+                        return Ok(());
+                    };
+                    let ended: Vec<UtteranceId> =
+                        utterances.drain(..position).map(|u| u.id).collect();
+                    let current = &mut utterances[0];
+                    let begun = (!current.begun).then(|| {
+                        current.begun = true;
+                        current.id
+                    });
+                    (ended, begun)
+                };
+                // Finished items aren't played again, so don't let them pile up.
+                if let Ok(old) = args.OldItem() {
+                    let items = sender.Items()?;
+                    let mut index = 0;
+                    if items.IndexOf(&old, &mut index)? {
+                        items.RemoveAt(index)?;
+                    }
+                }
+                for utterance in ended {
+                    callbacks::fire_utterance(id, UtteranceEvent::End, utterance);
+                }
+                if let Some(begun) = begun {
+                    callbacks::fire_utterance(id, UtteranceEvent::Begin, begun);
+                }
+                // Get the utterance after this one ready while this one plays.
+                prepare(id)
+            },
+        ))?;
         player.MediaEnded(&TypedEventHandler::new(
-            move |sender: &Option<MediaPlayer>, _args| {
-                if let Some(sender) = sender {
-                    let backend_to_media_player = BACKEND_TO_MEDIA_PLAYER.lock().unwrap();
-                    let id = backend_to_media_player.iter().find(|v| v.1 == sender);
-                    if let Some((id, _)) = id {
-                        let mut utterances = UTTERANCES.lock().unwrap();
-                        if let Some(utterances) = utterances.get_mut(id) {
-                            if let Some(utterance) = utterances.pop_front() {
-                                let mut callbacks = CALLBACKS.lock().unwrap();
-                                let callbacks = callbacks.get_mut(id).unwrap();
-                                if let Some(callback) = callbacks.utterance_end.as_mut() {
-                                    callback(utterance.id);
-                                }
-                                if let Some(utterance) = utterances.front() {
-                                    let backend_to_speech_synthesizer =
-                                        BACKEND_TO_SPEECH_SYNTHESIZER.lock().unwrap();
-                                    let id = backend_to_speech_synthesizer
-                                        .iter()
-                                        .find(|v| *v.0 == bid_clone);
-                                    if let Some((_, tts)) = id {
-                                        tts.Options()?.SetSpeakingRate(utterance.rate.into())?;
-                                        tts.Options()?.SetAudioPitch(utterance.pitch.into())?;
-                                        tts.Options()?.SetAudioVolume(utterance.volume.into())?;
-                                        tts.SetVoice(&utterance.voice)?;
-                                        let text = &utterance.text;
-                                        let stream =
-                                            tts.SynthesizeTextToStreamAsync(&text.into())?.get()?;
-                                        let content_type = stream.ContentType()?;
-                                        let source =
-                                            MediaSource::CreateFromStream(&stream, &content_type)?;
-                                        sender.SetSource(&source)?;
-                                        sender.Play()?;
-                                        if let Some(callback) = callbacks.utterance_begin.as_mut() {
-                                            callback(utterance.id);
-                                        }
-                                    }
-                                }
-                            }
+            |sender: &Option<MediaPlayer>, _args| {
+                let Some(sender) = sender else {
+                    return Ok(());
+                };
+                let Some(id) = backend_for(&BACKEND_TO_MEDIA_PLAYER, sender) else {
+                    return Ok(());
+                };
+                // The list ran out, so the next utterance, if any, wasn't appended in time.
+                let (ended, next) = {
+                    let mut utterances = UTTERANCES.lock();
+                    let Some(utterances) = utterances.get_mut(&id) else {
+                        return Ok(());
+                    };
+                    if !utterances.front().is_some_and(|u| u.begun) {
+                        return Ok(());
+                    }
+                    let ended = utterances.pop_front().map(|u| u.id);
+                    (ended, utterances.front().map(|u| u.item.clone()))
+                };
+                if let Some(ended) = ended {
+                    callbacks::fire_utterance(id, UtteranceEvent::End, ended);
+                }
+                match next {
+                    Some(Some(item)) => play_from(id, &item),
+                    Some(None) => prepare(id),
+                    None => {
+                        if let Some(list) = BACKEND_TO_PLAYBACK_LIST.lock().get(&id) {
+                            list.Items()?.Clear()?;
                         }
+                        Ok(())
                     }
                 }
-                Ok(())
             },
         ))?;
-        Ok(Self {
-            id: bid,
-            synth,
-            player,
-            rate: 1.,
-            pitch: 1.,
-            volume: 1.,
-            voice: SpeechSynthesizer::DefaultVoice()?,
-        })
-    }
-}
-
-impl Backend for WinRt {
-    fn id(&self) -> Option<BackendId> {
-        Some(self.id)
+        Ok((synth, player))
     }
 
-    fn supported_features(&self) -> Features {
-        Features {
-            stop: true,
-            rate: true,
-            pitch: true,
-            volume: true,
-            is_speaking: true,
-            voice: true,
-            get_voice: true,
-            utterance_callbacks: true,
-        }
-    }
-
-    fn speak(
+    fn enqueue(
         &mut self,
         text: &str,
         interrupt: bool,
+        ssml: bool,
+        earcon: Option<PathBuf>,
     ) -> std::result::Result<Option<UtteranceId>, Error> {
         if interrupt && self.is_speaking()? {
             self.stop()?;
         }
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return Ok(Some(self.play(audio, text, ssml, earcon, None)));
+        }
         let utterance_id = {
-            let mut uid = NEXT_UTTERANCE_ID.lock().unwrap();
+            let mut uid = NEXT_UTTERANCE_ID.lock();
             let utterance_id = UtteranceId::WinRt(*uid);
             *uid += 1;
             utterance_id
         };
-        let mut no_utterances = false;
         {
-            let mut utterances = UTTERANCES.lock().unwrap();
+            let mut utterances = UTTERANCES.lock();
             if let Some(utterances) = utterances.get_mut(&self.id) {
-                no_utterances = utterances.is_empty();
-                let utterance = Utterance {
+                utterances.push_back(Utterance {
                     id: utterance_id,
                     text: text.into(),
+                    ssml,
                     rate: self.rate,
                     pitch: self.pitch,
                     volume: self.volume,
                     voice: self.voice.clone(),
-                };
-                utterances.push_back(utterance);
+                    earcon,
+                    item: None,
+                    synthesizing: false,
+                    begun: false,
+                });
             }
         }
-        if no_utterances {
-            self.synth.Options()?.SetSpeakingRate(self.rate.into())?;
-            self.synth.Options()?.SetAudioPitch(self.pitch.into())?;
-            self.synth.Options()?.SetAudioVolume(self.volume.into())?;
-            self.synth.SetVoice(&self.voice)?;
-            let stream = self
-                .synth
-                .SynthesizeTextToStreamAsync(&text.into())?
-                .get()?;
-            let content_type = stream.ContentType()?;
-            let source = MediaSource::CreateFromStream(&stream, &content_type)?;
-            self.player.SetSource(&source)?;
-            self.player.Play()?;
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let callbacks = callbacks.get_mut(&self.id).unwrap();
-            if let Some(callback) = callbacks.utterance_begin.as_mut() {
-                callback(utterance_id);
+        // Synthesis takes a while, so it's started on another thread rather than holding up the caller.
+        let id = self.id;
+        thread::spawn(move || {
+            if let Err(e) = prepare(id) {
+                panics::report(id, e.into());
             }
-        }
+        });
         Ok(Some(utterance_id))
     }
 
+    /// Queues `text` on `audio`, synthesizing it on another thread, or the sound file `earcon`, which must be WAV,
+    /// panned to `pan` if set.
+    #[cfg(feature = "audio-output")]
+    fn play(
+        &self,
+        audio: &AudioOutput,
+        text: &str,
+        ssml: bool,
+        earcon: Option<PathBuf>,
+        pan: Option<f32>,
+    ) -> UtteranceId {
+        let (utterance_id, pending) = audio.push();
+        let (id, text, rate, pitch, volume, voice) = (
+            self.id,
+            text.to_string(),
+            self.rate,
+            self.pitch,
+            self.volume,
+            self.voice.clone(),
+        );
+        let target_loudness = self.target_loudness;
+        thread::spawn(move || {
+            let audio = match earcon {
+                Some(path) => fs::read(path)
+                    .map_err(Error::from)
+                    .and_then(|bytes| AudioBuffer::from_wav(&bytes))
+                    .map(|buffer| (buffer, vec![])),
+                None => synthesize_pcm(&text, ssml, rate, pitch, volume, &voice).map(
+                    |(buffer, words)| match target_loudness {
+                        Some(target) => (buffer.normalized(target), words),
+                        None => (buffer, words),
+                    },
+                ),
+            };
+            match audio {
+                Ok((buffer, words)) => {
+                    let buffer = match pan {
+                        Some(pan) => buffer.panned(pan),
+                        None => buffer,
+                    };
+                    pending.play(buffer, &words)
+                }
+                Err(e) => {
+                    pending.fail();
+                    panics::report(id, e);
+                }
+            }
+        });
+        utterance_id
+    }
+}
+
+/// Synthesizes `text` to PCM, with when each word begins, on a synthesizer of its own, so it doesn't race the
+/// playback queue's for its options.
+fn synthesize_pcm(
+    text: &str,
+    ssml: bool,
+    rate: f32,
+    pitch: f32,
+    volume: f32,
+    voice: &VoiceInformation,
+) -> Result<(AudioBuffer, Vec<WordTiming>), Error> {
+    let synth = SpeechSynthesizer::new()?;
+    let options = synth.Options()?;
+    options.SetSpeakingRate(rate.into())?;
+    options.SetAudioPitch(pitch.into())?;
+    options.SetAudioVolume(volume.into())?;
+    options.SetIncludeWordBoundaryMetadata(true)?;
+    synth.SetVoice(voice)?;
+    let stream = synthesize_stream(&synth, text, ssml)?;
+    let len = stream.Size()? as u32;
+    let reader = DataReader::CreateDataReader(&stream.GetInputStreamAt(0)?)?;
+    reader.LoadAsync(len)?.get()?;
+    let mut bytes = vec![0; len as usize];
+    reader.ReadBytes(&mut bytes)?;
+    let audio = AudioBuffer::from_wav(&bytes)?;
+    let mut timings = vec![];
+    for track in &stream.TimedMetadataTracks()? {
+        if track.Id()? != "SpeechWord" {
+            continue;
+        }
+        for cue in &track.Cues()? {
+            let cue: SpeechCue = cue.cast()?;
+            let start = cue.StartPositionInInput()?.Value()?.max(0) as usize;
+            // The end is the last UTF-16 unit of the word, not the one after it.
+            let end = cue.EndPositionInInput()?.Value()?.max(0) as usize + 1;
+            // Time spans count 100-nanosecond ticks.
+            let ticks = |span: TimeSpan| Duration::from_nanos(span.Duration.max(0) as u64 * 100);
+            timings.push(WordTiming {
+                range: utf16_range_to_byte_range(text, start, end.saturating_sub(start)),
+                start: ticks(cue.StartTime()?),
+                duration: ticks(cue.Duration()?),
+            });
+        }
+    }
+    Ok((audio, timings))
+}
+
+impl Backend for WinRt {
+    fn id(&self) -> Option<BackendId> {
+        Some(self.id)
+    }
+
+    fn supported_features(&self) -> Features {
+        #[cfg(feature = "audio-output")]
+        let audio = self.audio.is_some();
+        #[cfg(not(feature = "audio-output"))]
+        let audio = false;
+        Features::builder()
+            .with(Capability::Stop)
+            .with(Capability::Rate)
+            .with(Capability::Pitch)
+            .with(Capability::Volume)
+            .with(Capability::IsSpeaking)
+            .with(Capability::Voice)
+            .with(Capability::GetVoice)
+            .with(Capability::UtteranceCallbacks)
+            .with(Capability::WordCallbacks)
+            .with(Capability::Synthesize)
+            .with(Capability::OutputDevice)
+            .with(Capability::Earcons)
+            .with(Capability::Ssml)
+            .set(Capability::Pause, audio)
+            .set(Capability::Queue, audio)
+            .build()
+    }
+
+    fn backend_info(&self) -> BackendInfo {
+        // Voice IDs are registry paths, such as
+        // `HKEY_LOCAL_MACHINE\SOFTWARE\Microsoft\Speech_OneCore\Voices\Tokens\MSTTS_V110_enUS_DavidM`, naming the
+        // speech platform that provides the voice.
+        let synthesizer = self.voice.Id().ok().and_then(|id| {
+            id.to_string()
+                .split('\\')
+                .find(|part| part.starts_with("Speech"))
+                .map(String::from)
+        });
+        // The Windows version, packed into 16-bit fields.
+        let engine_version = AnalyticsInfo::VersionInfo()
+            .and_then(|info| info.DeviceFamilyVersion())
+            .ok()
+            .and_then(|version| version.to_string().parse::<u64>().ok())
+            .map(|v| {
+                format!(
+                    "{}.{}.{}.{}",
+                    v >> 48,
+                    (v >> 32) & 0xffff,
+                    (v >> 16) & 0xffff,
+                    v & 0xffff
+                )
+            });
+        BackendInfo {
+            engine_name: Some("Windows.Media.SpeechSynthesis".into()),
+            engine_version,
+            synthesizer,
+            ..BackendInfo::new(Backends::WinRt)
+        }
+    }
+
+    fn speak(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+    ) -> std::result::Result<Option<UtteranceId>, Error> {
+        self.enqueue(text, interrupt, false, None)
+    }
+
+    #[cfg(feature = "audio-output")]
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> std::result::Result<Option<UtteranceId>, Error> {
+        let Some(audio) = self.audio.clone() else {
+            return self.speak(text, interrupt);
+        };
+        if interrupt {
+            self.stop()?;
+        }
+        Ok(Some(self.play(&audio, text, false, None, options.pan)))
+    }
+
+    fn ssml_dialect(&self) -> SsmlDialect {
+        SsmlDialect::Full
+    }
+
+    fn speak_ssml(
+        &mut self,
+        ssml: &str,
+        interrupt: bool,
+    ) -> std::result::Result<Option<UtteranceId>, Error> {
+        self.enqueue(ssml, interrupt, true, None)
+    }
+
+    fn synthesize(&mut self, text: &str) -> Result<AudioBuffer, Error> {
+        Ok(self.synthesize_with_timings(text)?.0)
+    }
+
+    fn synthesize_to_file(&mut self, text: &str, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.synthesize(text)?.to_wav())?;
+        Ok(())
+    }
+
+    fn synthesize_with_timings(
+        &mut self,
+        text: &str,
+    ) -> Result<(AudioBuffer, Vec<WordTiming>), Error> {
+        synthesize_pcm(text, false, self.rate, self.pitch, self.volume, &self.voice)
+    }
+
+    fn play_earcon(
+        &mut self,
+        earcon: &Earcon,
+        interrupt: bool,
+    ) -> std::result::Result<Option<UtteranceId>, Error> {
+        match earcon {
+            Earcon::File(path) => self.enqueue("", interrupt, false, Some(path.clone())),
+            Earcon::Named(_) => Err(Error::UnsupportedFeature),
+        }
+    }
+
     fn stop(&mut self) -> std::result::Result<(), Error> {
         trace!("stop()");
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            audio.stop(match self.stop_style {
+                StopStyle::Immediate => Duration::ZERO,
+                StopStyle::Fade(duration) => duration,
+            });
+            return Ok(());
+        }
         if !self.is_speaking()? {
             return Ok(());
         }
-        let mut utterances = UTTERANCES.lock().unwrap();
-        if let Some(utterances) = utterances.get(&self.id) {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let callbacks = callbacks.get_mut(&self.id).unwrap();
-            if let Some(callback) = callbacks.utterance_stop.as_mut() {
-                for utterance in utterances {
-                    callback(utterance.id);
-                }
+        let stopped: Vec<UtteranceId> = {
+            let mut utterances = UTTERANCES.lock();
+            match utterances.get_mut(&self.id) {
+                Some(utterances) => utterances.drain(..).map(|u| u.id).collect(),
+                None => vec![],
             }
+        };
+        let volume = self.player.Volume()?;
+        if let StopStyle::Fade(duration) = self.stop_style {
+            fade_out(&self.player, duration)?;
         }
-        if let Some(utterances) = utterances.get_mut(&self.id) {
-            utterances.clear();
+        self.player.Pause()?;
+        if let Some(list) = BACKEND_TO_PLAYBACK_LIST.lock().get(&self.id) {
+            list.Items()?.Clear()?;
         }
+        self.player.SetVolume(volume)?;
+        for utterance in stopped {
+            callbacks::fire_utterance(self.id, UtteranceEvent::Stop, utterance);
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self) -> std::result::Result<(), Error> {
+        trace!("skip()");
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            audio.skip();
+            return Ok(());
+        }
+        let (skipped, next) = {
+            let mut utterances = UTTERANCES.lock();
+            let Some(utterances) = utterances.get_mut(&self.id) else {
+                return Ok(());
+            };
+            let Some(skipped) = utterances.pop_front() else {
+                return Ok(());
+            };
+            (skipped, utterances.front().map(|u| u.item.clone()))
+        };
         self.player.Pause()?;
+        let list = BACKEND_TO_PLAYBACK_LIST.lock().get(&self.id).cloned();
+        if let (Some(item), Some(list)) = (&skipped.item, list) {
+            let items = list.Items()?;
+            let mut index = 0;
+            if items.IndexOf(item, &mut index)? {
+                items.RemoveAt(index)?;
+            }
+        }
+        callbacks::fire_utterance(self.id, UtteranceEvent::Stop, skipped.id);
+        match next {
+            Some(Some(item)) => play_from(self.id, &item)?,
+            // The next utterance isn't synthesized yet, and starts once it is.
+            Some(None) => {
+                let id = self.id;
+                thread::spawn(move || {
+                    if let Err(e) = prepare(id) {
+                        panics::report(id, e.into());
+                    }
+                });
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    fn reinitialize(&mut self) -> std::result::Result<(), Error> {
+        info!("Reinitializing WinRT backend");
+        // The old player's events are ignored once it's gone from the maps. Silence it as best it can be.
+        BACKEND_TO_PLAYBACK_LIST.lock().remove(&self.id);
+        BACKEND_TO_MEDIA_PLAYER.lock().remove(&self.id);
+        let _ = self.player.Pause();
+        let (synth, player) = Self::engine(self.id)?;
+        if let Ok(device) = self.player.AudioDevice() {
+            player.SetAudioDevice(&device)?;
+        }
+        let options = synth.Options()?;
+        options.SetSpeakingRate(self.rate.into())?;
+        options.SetAudioPitch(self.pitch.into())?;
+        options.SetAudioVolume(self.volume.into())?;
+        synth.SetVoice(&self.voice)?;
+        self.synth = synth;
+        self.player = player;
         Ok(())
     }
 
@@ -245,8 +816,7 @@ impl Backend for WinRt {
     }
 
     fn get_rate(&self) -> std::result::Result<f32, Error> {
-        let rate = self.synth.Options()?.SpeakingRate()?;
-        Ok(rate as f32)
+        Ok(self.rate)
     }
 
     fn set_rate(&mut self, rate: f32) -> std::result::Result<(), Error> {
@@ -267,8 +837,7 @@ impl Backend for WinRt {
     }
 
     fn get_pitch(&self) -> std::result::Result<f32, Error> {
-        let pitch = self.synth.Options()?.AudioPitch()?;
-        Ok(pitch as f32)
+        Ok(self.pitch)
     }
 
     fn set_pitch(&mut self, pitch: f32) -> std::result::Result<(), Error> {
@@ -289,8 +858,7 @@ impl Backend for WinRt {
     }
 
     fn get_volume(&self) -> std::result::Result<f32, Error> {
-        let volume = self.synth.Options()?.AudioVolume()?;
-        Ok(volume as f32)
+        Ok(self.volume)
     }
 
     fn set_volume(&mut self, volume: f32) -> std::result::Result<(), Error> {
@@ -299,14 +867,55 @@ impl Backend for WinRt {
     }
 
     fn is_speaking(&self) -> std::result::Result<bool, Error> {
-        let utterances = UTTERANCES.lock().unwrap();
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return Ok(audio.is_speaking());
+        }
+        let utterances = UTTERANCES.lock();
         let utterances = utterances.get(&self.id).unwrap();
         Ok(!utterances.is_empty())
     }
 
+    fn pause(&mut self) -> std::result::Result<(), Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            audio.pause();
+            return Ok(());
+        }
+        Err(Error::UnsupportedFeature)
+    }
+
+    fn resume(&mut self) -> std::result::Result<(), Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            audio.resume();
+            return Ok(());
+        }
+        Err(Error::UnsupportedFeature)
+    }
+
+    fn is_paused(&self) -> std::result::Result<bool, Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return Ok(audio.is_paused());
+        }
+        Err(Error::UnsupportedFeature)
+    }
+
+    fn is_pending(&self) -> std::result::Result<bool, Error> {
+        Ok(self.queue_len()? > 1)
+    }
+
+    fn queue_len(&self) -> std::result::Result<usize, Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return Ok(audio.queue_len());
+        }
+        Err(Error::UnsupportedFeature)
+    }
+
     fn voice(&self) -> Result<Option<Voice>, Error> {
-        let voice = self.synth.Voice()?;
-        let voice = voice.try_into()?;
+        let voice = self.voice.clone().try_into()?;
         Ok(Some(voice))
     }
 
@@ -328,16 +937,72 @@ impl Backend for WinRt {
         }
         Err(Error::OperationFailed)
     }
+
+    fn output_devices(&self) -> Result<Vec<OutputDevice>, Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return audio.output_devices();
+        }
+        let selector = MediaDevice::GetAudioRenderSelector()?;
+        let mut rv: Vec<OutputDevice> = vec![];
+        for device in DeviceInformation::FindAllAsyncAqsFilter(&selector)?.get()? {
+            rv.push(OutputDevice {
+                id: device.Id()?.try_into()?,
+                name: device.Name()?.try_into()?,
+            });
+        }
+        Ok(rv)
+    }
+
+    fn set_stop_style(&mut self, style: StopStyle) -> Result<(), Error> {
+        self.stop_style = style;
+        Ok(())
+    }
+
+    #[cfg(feature = "audio-output")]
+    fn set_mix_policy(&mut self, policy: MixPolicy) -> Result<(), Error> {
+        let Some(audio) = &self.audio else {
+            return Err(Error::UnsupportedFeature);
+        };
+        audio.set_mix_policy(policy);
+        Ok(())
+    }
+
+    #[cfg(feature = "audio-output")]
+    fn set_target_loudness(&mut self, loudness: Option<f32>) -> Result<(), Error> {
+        if self.audio.is_none() {
+            return Err(Error::UnsupportedFeature);
+        }
+        self.target_loudness = loudness;
+        Ok(())
+    }
+
+    fn set_output_device(&mut self, device: Option<&OutputDevice>) -> Result<(), Error> {
+        #[cfg(feature = "audio-output")]
+        if let Some(audio) = &self.audio {
+            return audio.set_output_device(device);
+        }
+        let id = match device {
+            Some(device) => device.id.as_str().into(),
+            None => MediaDevice::GetDefaultAudioRenderId(AudioDeviceRole::Default)?,
+        };
+        let device = DeviceInformation::CreateFromIdAsync(&id)?.get()?;
+        self.player.SetAudioDevice(&device)?;
+        Ok(())
+    }
 }
 
 impl Drop for WinRt {
     fn drop(&mut self) {
         let id = self.id;
-        let mut backend_to_media_player = BACKEND_TO_MEDIA_PLAYER.lock().unwrap();
+        let mut backend_to_media_player = BACKEND_TO_MEDIA_PLAYER.lock();
         backend_to_media_player.remove(&id);
-        let mut backend_to_speech_synthesizer = BACKEND_TO_SPEECH_SYNTHESIZER.lock().unwrap();
+        let mut backend_to_playback_list = BACKEND_TO_PLAYBACK_LIST.lock();
+        backend_to_playback_list.remove(&id);
+        drop(backend_to_playback_list);
+        let mut backend_to_speech_synthesizer = BACKEND_TO_SPEECH_SYNTHESIZER.lock();
         backend_to_speech_synthesizer.remove(&id);
-        let mut utterances = UTTERANCES.lock().unwrap();
+        let mut utterances = UTTERANCES.lock();
         utterances.remove(&id);
     }
 }
@@ -347,18 +1012,21 @@ impl TryInto<Voice> for VoiceInformation {
 
     fn try_into(self) -> Result<Voice, Self::Error> {
         let gender = self.Gender()?;
-        let gender = if gender == VoiceGender::Male {
-            Gender::Male
-        } else {
-            Gender::Female
+        let gender = match gender {
+            VoiceGender::Male => Gender::Male,
+            VoiceGender::Female => Gender::Female,
+            _ => Gender::Unknown,
         };
         let language: String = self.Language()?.try_into()?;
         let language = LanguageTag::parse(language).unwrap();
         Ok(Voice {
             id: self.Id()?.try_into()?,
             name: self.DisplayName()?.try_into()?,
-            gender: Some(gender),
+            gender,
             language,
+            personal: false,
+            quality: None,
+            network: false,
         })
     }
 }
@@ -0,0 +1,246 @@
+//! Synthesizes many texts in one call, as when generating a game's voice lines at build time, optionally spread over
+//! instances of the backend on threads of their own.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use log::warn;
+use parking_lot::Mutex;
+
+use crate::{AudioBuffer, Error, Tts, Voice};
+
+/// Text to synthesize in a batch, with any settings that differ from the `Tts`'s own.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BatchItem {
+    pub text: String,
+    pub voice: Option<Voice>,
+    pub rate: Option<f32>,
+    pub pitch: Option<f32>,
+    pub volume: Option<f32>,
+}
+
+impl BatchItem {
+    /// Creates an item synthesizing `text` with the `Tts`'s own settings.
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Stops a batch before its remaining items begin. Clones share their state, so one can be kept to cancel a batch
+/// from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// How `Tts::synthesize_batch_with()` runs a batch.
+pub struct BatchOptions {
+    /// How many items to synthesize at once. Beyond the first, each creates an instance of the backend on a thread of
+    /// its own, which backends without an ID, and those that must stay on the main thread, can't.
+    pub parallelism: usize,
+    /// Called on the calling thread as items finish, with how many have and how many there are.
+    pub progress: Option<Box<dyn FnMut(usize, usize)>>,
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            parallelism: 1,
+            progress: None,
+            cancellation: None,
+        }
+    }
+}
+
+/// The settings an instance synthesizes with, where they're supported.
+#[derive(Clone, Default, PartialEq)]
+struct Settings {
+    voice: Option<Voice>,
+    rate: Option<f32>,
+    pitch: Option<f32>,
+    volume: Option<f32>,
+}
+
+impl Settings {
+    fn of(tts: &Tts) -> Self {
+        Self {
+            voice: tts.voice().ok().flatten(),
+            rate: tts.get_rate().ok(),
+            pitch: tts.get_pitch().ok(),
+            volume: tts.get_volume().ok(),
+        }
+    }
+
+    /// Returns these settings, overridden by those of `item`.
+    fn with(&self, item: &BatchItem) -> Self {
+        Self {
+            voice: item.voice.clone().or_else(|| self.voice.clone()),
+            rate: item.rate.or(self.rate),
+            pitch: item.pitch.or(self.pitch),
+            volume: item.volume.or(self.volume),
+        }
+    }
+
+    /// Changes `tts`, which has these settings, to `settings`, skipping what's already set.
+    fn change(&mut self, tts: &mut Tts, settings: &Settings) -> Result<(), Error> {
+        if self.voice != settings.voice {
+            if let Some(voice) = &settings.voice {
+                tts.set_voice(voice)?;
+            }
+            self.voice = settings.voice.clone();
+        }
+        if self.rate != settings.rate {
+            if let Some(rate) = settings.rate {
+                tts.set_rate(rate)?;
+            }
+            self.rate = settings.rate;
+        }
+        if self.pitch != settings.pitch {
+            if let Some(pitch) = settings.pitch {
+                tts.set_pitch(pitch)?;
+            }
+            self.pitch = settings.pitch;
+        }
+        if self.volume != settings.volume {
+            if let Some(volume) = settings.volume {
+                tts.set_volume(volume)?;
+            }
+            self.volume = settings.volume;
+        }
+        Ok(())
+    }
+}
+
+/// An item ready to synthesize: its index, preprocessed text and settings.
+type Job = (usize, String, Settings);
+
+fn synthesize(
+    tts: &mut Tts,
+    current: &mut Settings,
+    (_, text, settings): &Job,
+) -> Result<AudioBuffer, Error> {
+    // Leave the settings as they were if they couldn't all be applied, so the next item starts from what's known.
+    let mut changed = current.clone();
+    let result = changed.change(tts, settings);
+    *current = match result {
+        Ok(()) => changed,
+        Err(_) => Settings::of(tts),
+    };
+    result?;
+    tts.0.write().synthesize(text)
+}
+
+pub(crate) fn run(
+    tts: &mut Tts,
+    items: &[BatchItem],
+    options: BatchOptions,
+) -> Vec<Result<AudioBuffer, Error>> {
+    let BatchOptions {
+        parallelism,
+        mut progress,
+        cancellation,
+    } = options;
+    let total = items.len();
+    let own = Settings::of(tts);
+    // Text is filtered here, since other instances have none of this one's filters or lexicon.
+    let jobs: VecDeque<Job> = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let language = item.voice.as_ref().map(|voice| &voice.language);
+            let text = tts.preprocess(&item.text, language).into_owned();
+            (i, text, own.with(item))
+        })
+        .collect();
+    let jobs = Mutex::new(jobs);
+    let next = || {
+        if cancellation.as_ref().is_some_and(|c| c.is_cancelled()) {
+            None
+        } else {
+            jobs.lock().pop_front()
+        }
+    };
+    let backend = tts.0.read().id().map(|id| id.backend());
+    let workers = match backend {
+        Some(_) => parallelism.clamp(1, total.max(1)) - 1,
+        None => 0,
+    };
+    let mut results: Vec<Option<Result<AudioBuffer, Error>>> = (0..total).map(|_| None).collect();
+    let mut done = 0;
+    let mut finish = |i: usize, result| {
+        results[i] = Some(result);
+        done += 1;
+        if let Some(progress) = progress.as_mut() {
+            progress(done, total);
+        }
+    };
+    thread::scope(|scope| {
+        let (sender, received) = mpsc::channel();
+        for _ in 0..workers {
+            let (sender, next) = (sender.clone(), &next);
+            let spawned = thread::Builder::new()
+                .name("tts-batch".into())
+                .spawn_scoped(scope, move || {
+                    let mut worker = match backend.map(Tts::new) {
+                        Some(Ok(worker)) => worker,
+                        Some(Err(e)) => {
+                            warn!("Failed to create a backend for batch synthesis: {}", e);
+                            return;
+                        }
+                        None => return,
+                    };
+                    let mut current = Settings::default();
+                    while let Some(job) = next() {
+                        let result = synthesize(&mut worker, &mut current, &job);
+                        if sender.send((job.0, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            if let Err(e) = spawned {
+                warn!("Failed to start a thread for batch synthesis: {}", e);
+            }
+        }
+        drop(sender);
+        let mut current = own.clone();
+        while let Some(job) = next() {
+            let result = synthesize(tts, &mut current, &job);
+            finish(job.0, result);
+            while let Ok((i, result)) = received.try_recv() {
+                finish(i, result);
+            }
+        }
+        for (i, result) in received {
+            finish(i, result);
+        }
+        if let Err(e) = current.change(tts, &own) {
+            warn!("Failed to restore settings after batch synthesis: {}", e);
+        }
+    });
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or(Err(Error::Cancelled)))
+        .collect()
+}
@@ -0,0 +1,159 @@
+//! [Bevy](https://bevyengine.org) integration.
+//!
+//! Enabled with the `bevy` feature. Add [`TtsPlugin`] to your app, then speak through the [`Tts`] resource and read
+//! [`UtteranceBegin`], [`UtteranceEnd`] and [`UtteranceStop`] events from your systems:
+//!
+//! ```no_run
+//! use bevy_app::prelude::*;
+//! use bevy_ecs::prelude::*;
+//! use tts::bevy::{Tts, TtsPlugin, UtteranceEnd};
+//!
+//! fn greet(tts: Res<Tts>) {
+//!     tts.lock().speak("Hello, world.", false).unwrap();
+//! }
+//!
+//! fn finished(mut events: EventReader<UtteranceEnd>) {
+//!     for event in events.read() {
+//!         println!("Finished {:?}", event.utterance_id);
+//!     }
+//! }
+//!
+//! App::new()
+//!     .add_plugins(TtsPlugin)
+//!     .add_systems(Startup, greet)
+//!     .add_systems(Update, finished)
+//!     .run();
+//! ```
+
+use std::sync::mpsc::{channel, Receiver};
+
+use bevy_app::{App, Plugin, PreUpdate};
+use bevy_ecs::prelude::*;
+use parking_lot::{Mutex, MutexGuard};
+
+use crate::{Features, UtteranceId};
+
+/// The speech synthesizer, as a resource.
+///
+/// Systems may run on any thread, so the synthesizer is behind a lock. Hold it only as long as needed.
+#[derive(Resource)]
+pub struct Tts(Mutex<crate::Tts>);
+
+// All access to the inner `Tts` goes through the mutex, and its `Rc` is never cloned, so this is sound.
+unsafe impl Send for Tts {}
+
+unsafe impl Sync for Tts {}
+
+impl Tts {
+    /// Locks the synthesizer for use. The lock isn't poisoned if a system panics while holding it.
+    pub fn lock(&self) -> MutexGuard<'_, crate::Tts> {
+        self.0.lock()
+    }
+}
+
+/// Sent when an utterance begins.
+#[derive(Event, Debug)]
+pub struct UtteranceBegin {
+    pub utterance_id: UtteranceId,
+}
+
+/// Sent when an utterance finishes.
+#[derive(Event, Debug)]
+pub struct UtteranceEnd {
+    pub utterance_id: UtteranceId,
+}
+
+/// Sent when an utterance is stopped.
+#[derive(Event, Debug)]
+pub struct UtteranceStop {
+    pub utterance_id: UtteranceId,
+}
+
+enum Callback {
+    Begin(UtteranceId),
+    End(UtteranceId),
+    Stop(UtteranceId),
+}
+
+/// Carries callbacks from the backend to the ECS.
+#[derive(Resource)]
+struct Callbacks(Mutex<Receiver<Callback>>);
+
+fn send_events(
+    callbacks: Res<Callbacks>,
+    mut begin: EventWriter<UtteranceBegin>,
+    mut end: EventWriter<UtteranceEnd>,
+    mut stop: EventWriter<UtteranceStop>,
+) {
+    for callback in callbacks.0.lock().try_iter() {
+        match callback {
+            Callback::Begin(utterance_id) => {
+                begin.write(UtteranceBegin { utterance_id });
+            }
+            Callback::End(utterance_id) => {
+                end.write(UtteranceEnd { utterance_id });
+            }
+            Callback::Stop(utterance_id) => {
+                stop.write(UtteranceStop { utterance_id });
+            }
+        }
+    }
+}
+
+/// Marks systems that must run on the main thread.
+#[cfg(target_os = "macos")]
+struct MainThread;
+
+/// Apple backends deliver callbacks through the main thread's run loop, which Bevy's default runner doesn't pump.
+#[cfg(target_os = "macos")]
+fn pump_run_loop(_: NonSend<MainThread>) {
+    crate::Tts::pump_run_loop(std::time::Duration::ZERO);
+}
+
+/// Creates the [`Tts`] resource with the default backend, and sends utterance events.
+///
+/// If no backend is available, the error is logged and the resource isn't inserted, so use `Option<Res<Tts>>` if
+/// speech is optional.
+pub struct TtsPlugin;
+
+impl Plugin for TtsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<UtteranceBegin>()
+            .add_event::<UtteranceEnd>()
+            .add_event::<UtteranceStop>();
+        let tts = match crate::Tts::default() {
+            Ok(tts) => tts,
+            Err(e) => {
+                log::error!("Failed to initialize TTS: {}", e);
+                return;
+            }
+        };
+        let Features {
+            utterance_callbacks,
+            ..
+        } = tts.supported_features();
+        if utterance_callbacks {
+            let (tx, rx) = channel();
+            let t = tx.clone();
+            let begin = tts.on_utterance_begin(Some(Box::new(move |id| {
+                let _ = t.send(Callback::Begin(id));
+            })));
+            let t = tx.clone();
+            let end = tts.on_utterance_end(Some(Box::new(move |id| {
+                let _ = t.send(Callback::End(id));
+            })));
+            let stop = tts.on_utterance_stop(Some(Box::new(move |id| {
+                let _ = tx.send(Callback::Stop(id));
+            })));
+            if let Err(e) = begin.and(end).and(stop) {
+                log::error!("Failed to register TTS callbacks: {}", e);
+            }
+            app.insert_resource(Callbacks(Mutex::new(rx)))
+                .add_systems(PreUpdate, send_events);
+        }
+        #[cfg(target_os = "macos")]
+        app.insert_non_send_resource(MainThread)
+            .add_systems(PreUpdate, pump_run_loop.before(send_events));
+        app.insert_resource(Tts(Mutex::new(tts)));
+    }
+}
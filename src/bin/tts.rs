@@ -0,0 +1,133 @@
+//! A command-line speech synthesizer, built with the `cli` feature.
+//!
+//! Speaks its arguments, a file, or standard input:
+//!
+//! ```sh
+//! tts --wait "Hello, world."
+//! tts --list-voices
+//! echo "Hello, world." | tts --rate 1.5 --output hello.wav
+//! ```
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, Read},
+    path::PathBuf,
+    process::ExitCode,
+    time::Duration,
+};
+
+use clap::Parser;
+use tts::{Backends, Error, Features, Tts};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Speaks text with the platform's text-to-speech engine"
+)]
+struct Args {
+    /// Text to speak. Read from --file or standard input if omitted.
+    text: Vec<String>,
+    /// Read the text to speak from a file.
+    #[arg(short, long, conflicts_with = "text")]
+    file: Option<PathBuf>,
+    /// The backend to use, instead of the platform's default.
+    #[arg(short, long)]
+    backend: Option<String>,
+    /// The voice to use, by ID, name or stable ID.
+    #[arg(short, long)]
+    voice: Option<String>,
+    /// The speech rate, in the backend's units.
+    #[arg(short, long)]
+    rate: Option<f32>,
+    /// The speech pitch, in the backend's units.
+    #[arg(short, long)]
+    pitch: Option<f32>,
+    /// The speech volume, in the backend's units.
+    #[arg(long)]
+    volume: Option<f32>,
+    /// List the available voices and exit.
+    #[arg(long)]
+    list_voices: bool,
+    /// Wait for speech to finish before exiting.
+    #[arg(short, long)]
+    wait: bool,
+    /// Write the speech to a WAV file instead of speaking it.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn text(args: &Args) -> io::Result<String> {
+    if !args.text.is_empty() {
+        Ok(args.text.join(" "))
+    } else if let Some(file) = &args.file {
+        fs::read_to_string(file)
+    } else {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text)?;
+        Ok(text)
+    }
+}
+
+fn wait(tts: &Tts) -> Result<(), Error> {
+    while tts.is_speaking()? {
+        #[cfg(target_os = "macos")]
+        Tts::pump_run_loop(Duration::from_millis(100));
+        #[cfg(not(target_os = "macos"))]
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut tts = match &args.backend {
+        Some(name) => {
+            Tts::new(Backends::from_name(name).ok_or(format!("Unknown backend: {name}"))?)?
+        }
+        None => Tts::default()?,
+    };
+    if args.list_voices {
+        for voice in tts.voices()? {
+            println!("{}\t{}\t{}", voice.id(), voice.name(), voice.language());
+        }
+        return Ok(());
+    }
+    if let Some(id) = &args.voice {
+        tts.set_voice_by_id(id)?;
+    }
+    if let Some(rate) = args.rate {
+        tts.set_rate(rate)?;
+    }
+    if let Some(pitch) = args.pitch {
+        tts.set_pitch(pitch)?;
+    }
+    if let Some(volume) = args.volume {
+        tts.set_volume(volume)?;
+    }
+    if let Some(output) = &args.output {
+        tts.synthesize_to_file(text(&args)?, output)?;
+        return Ok(());
+    }
+    // Files and standard input are spoken as they're read, so long documents and pipes start speaking right away.
+    if !args.text.is_empty() {
+        tts.speak(args.text.join(" "), false)?;
+    } else if let Some(file) = &args.file {
+        tts.speak_reader(BufReader::new(File::open(file)?), false)?;
+    } else {
+        tts.speak_reader(io::stdin().lock(), false)?;
+    }
+    let Features { is_speaking, .. } = tts.supported_features();
+    if args.wait && is_speaking {
+        wait(&tts)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("tts: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
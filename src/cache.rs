@@ -0,0 +1,199 @@
+//! An on-disk cache of synthesized speech.
+
+use std::{
+    cmp::Reverse,
+    fs::{self, File},
+    io,
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use log::{trace, warn};
+
+use crate::{AudioBuffer, Error};
+
+/// The extension of the cache's files, so it only ever removes its own.
+const EXTENSION: &str = "ttscache";
+
+/// Stores synthesized audio on disk, keyed by text, voice, rate, pitch, volume and backend, so repeated prompts
+/// needn't be synthesized again.
+///
+/// Once the cache grows past its size limit, the least recently used audio is evicted. Only the cache's own `.ttscache`
+/// files are evicted or cleared, so the directory can be shared. Set it with `Tts::set_synthesis_cache()`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SynthesisCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl SynthesisCache {
+    /// Creates a cache in `dir`, creating the directory if needed, holding at most `max_size` bytes of audio.
+    pub fn new<P: Into<PathBuf>>(dir: P, max_size: u64) -> Result<Self, Error> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_size })
+    }
+
+    /// Removes all cached audio.
+    pub fn clear(&self) -> Result<(), Error> {
+        for (path, _, _) in self.entries()? {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Names the file for `key` by its hash, which is stable across runs and Rust versions, unlike `DefaultHasher`.
+    /// Keys whose hashes collide share a file, which holds whichever was stored last.
+    fn path(&self, key: &str) -> PathBuf {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in key.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        self.dir.join(format!("{hash:016x}.{EXTENSION}"))
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<AudioBuffer> {
+        let path = self.path(key);
+        let bytes = fs::read(&path).ok()?;
+        // Each file starts with its key's length and the key, so a colliding key isn't given another's audio.
+        let (len, rest) = bytes.split_first_chunk::<8>()?;
+        let (stored, wav) = rest.split_at_checked(u64::from_le_bytes(*len).try_into().ok()?)?;
+        if stored != key.as_bytes() {
+            trace!("Synthesis cache collision: {:?}", path);
+            return None;
+        }
+        trace!("Synthesis cache hit: {:?}", path);
+        // Record the access for eviction.
+        if let Err(e) = File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()))
+        {
+            warn!("Failed to touch {:?}: {}", path, e);
+        }
+        AudioBuffer::from_wav(wav).ok()
+    }
+
+    pub(crate) fn insert(&self, key: &str, audio: &AudioBuffer) -> Result<(), Error> {
+        let path = self.path(key);
+        // Write, then rename, so a concurrent reader never sees a partial file.
+        let tmp = path.with_extension(format!("{EXTENSION}-tmp"));
+        let mut bytes = (key.len() as u64).to_le_bytes().to_vec();
+        bytes.extend_from_slice(key.as_bytes());
+        bytes.extend(audio.to_wav());
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &path)?;
+        self.evict()
+    }
+
+    fn entries(&self) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let metadata = fs::metadata(&path)?;
+            entries.push((path, metadata.len(), metadata.modified()?));
+        }
+        Ok(entries)
+    }
+
+    fn evict(&self) -> Result<(), Error> {
+        let mut entries = self.entries()?;
+        // Most recently used first.
+        entries.sort_by_key(|(_, _, modified)| Reverse(*modified));
+        let mut size = 0;
+        for (path, len, _) in entries {
+            size += len;
+            if size > self.max_size {
+                trace!("Evicting {:?} from synthesis cache", path);
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{process, time::Duration};
+
+    use super::*;
+
+    /// Creates a cache in a new directory named for `test`, holding `entries` entries.
+    fn cache(test: &str, entries: u64) -> SynthesisCache {
+        let dir = std::env::temp_dir().join(format!("tts-cache-{}-{test}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = SynthesisCache::new(dir, u64::MAX).unwrap();
+        cache.insert("x", &audio()).unwrap();
+        let size = fs::metadata(cache.path("x")).unwrap().len();
+        cache.clear().unwrap();
+        SynthesisCache {
+            max_size: size * entries,
+            ..cache
+        }
+    }
+
+    fn audio() -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 16000,
+            channels: 1,
+            samples: vec![1000; 160],
+        }
+    }
+
+    fn age(cache: &SynthesisCache, key: &str, by: Duration) {
+        let file = File::options().write(true).open(cache.path(key)).unwrap();
+        file.set_modified(SystemTime::now() - by).unwrap();
+    }
+
+    #[test]
+    fn entries_are_only_found_by_their_own_key() {
+        let cache = cache("keys", 4);
+        cache.insert("a", &audio()).unwrap();
+        assert_eq!(cache.get("a"), Some(audio()));
+        assert_eq!(cache.get("b"), None);
+        // As if "b" hashed to the same name as "a".
+        fs::rename(cache.path("a"), cache.path("b")).unwrap();
+        assert_eq!(cache.get("b"), None);
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn least_recently_used_entries_are_evicted_past_the_limit() {
+        let cache = cache("eviction", 2);
+        cache.insert("a", &audio()).unwrap();
+        cache.insert("b", &audio()).unwrap();
+        age(&cache, "a", Duration::from_secs(20));
+        age(&cache, "b", Duration::from_secs(10));
+        // Using "a" makes "b" the least recently used.
+        assert!(cache.get("a").is_some());
+        cache.insert("c", &audio()).unwrap();
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn clearing_leaves_other_files_alone() {
+        let cache = cache("clear", 4);
+        let (song, notes) = (cache.dir.join("song.wav"), cache.dir.join("notes.txt"));
+        fs::write(&song, audio().to_wav()).unwrap();
+        fs::write(&notes, "notes").unwrap();
+        cache.insert("a", &audio()).unwrap();
+        cache.clear().unwrap();
+        assert!(cache.get("a").is_none());
+        assert!(song.exists() && notes.exists());
+        // Nor does eviction count or remove them.
+        let cache = SynthesisCache {
+            max_size: 0,
+            ..cache
+        };
+        cache.insert("a", &audio()).unwrap();
+        assert!(song.exists() && notes.exists());
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
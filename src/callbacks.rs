@@ -0,0 +1,541 @@
+//! Stores each backend's callbacks and dispatches events to them without holding any lock, so callbacks can call
+//! back into `Tts`, for instance to speak the next utterance or to wait for speech to finish.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    ops::Range,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, LazyLock},
+    thread,
+    time::Duration,
+};
+
+use parking_lot::{Mutex, RwLock};
+
+use crate::{
+    panics, BackendId, SentenceCallback, UtteranceEvent, UtteranceId, Viseme, VisemeCallback,
+    WordBoundaryCallback,
+};
+
+/// A callback that can be taken out of the map it's stored in to be called, then put back unless it was replaced
+/// in the meantime.
+pub(crate) struct Slot<T> {
+    callback: Option<T>,
+    generation: u64,
+}
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            callback: None,
+            generation: 0,
+        }
+    }
+}
+
+impl<T> Slot<T> {
+    pub(crate) fn set(&mut self, callback: Option<T>) {
+        self.callback = callback;
+        self.generation += 1;
+    }
+
+    pub(crate) fn take(&mut self) -> Option<(T, u64)> {
+        Some((self.callback.take()?, self.generation))
+    }
+
+    pub(crate) fn restore(&mut self, (callback, generation): (T, u64)) {
+        if self.generation == generation {
+            self.callback = Some(callback);
+        }
+    }
+}
+
+enum Event {
+    Utterance(UtteranceEvent, UtteranceId),
+    Word(UtteranceId, Range<usize>),
+    Sentence(UtteranceId, Range<usize>),
+    Viseme(UtteranceId, Viseme, Duration),
+}
+
+#[derive(Default)]
+pub(crate) struct CallbackSet {
+    pub(crate) utterance_begin: Slot<Box<dyn FnMut(UtteranceId)>>,
+    pub(crate) utterance_end: Slot<Box<dyn FnMut(UtteranceId)>>,
+    pub(crate) utterance_stop: Slot<Box<dyn FnMut(UtteranceId)>>,
+    pub(crate) word_boundary: Slot<WordBoundaryCallback>,
+    pub(crate) sentence_begin: Slot<SentenceCallback>,
+    /// Whether a sentence callback was set, as opposed to just the crate's bookkeeping.
+    pub(crate) sentences: bool,
+    pub(crate) viseme: Slot<VisemeCallback>,
+    /// Whether a viseme callback was set, so the text of each utterance must be kept to estimate them.
+    pub(crate) visemes: bool,
+}
+
+impl CallbackSet {
+    pub(crate) fn utterance(
+        &mut self,
+        event: UtteranceEvent,
+    ) -> &mut Slot<Box<dyn FnMut(UtteranceId)>> {
+        match event {
+            UtteranceEvent::Begin => &mut self.utterance_begin,
+            UtteranceEvent::End => &mut self.utterance_end,
+            UtteranceEvent::Stop => &mut self.utterance_stop,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Callbacks {
+    /// The crate's bookkeeping, wrapped around the callbacks set through handles without their own.
+    pub(crate) shared: CallbackSet,
+    /// The callbacks of each handle from `Tts::with_own_callbacks()`, which the shared set notifies.
+    subscribers: BTreeMap<u64, CallbackSet>,
+    next_subscriber: u64,
+    /// Events that arrived while a callback was running, to be dispatched after it returns.
+    queued: VecDeque<Event>,
+    dispatching: bool,
+}
+
+unsafe impl Send for Callbacks {}
+
+unsafe impl Sync for Callbacks {}
+
+type Shared = Arc<Mutex<Callbacks>>;
+
+/// Each backend's callbacks, locked separately so one backend's events don't wait on another's.
+static CALLBACKS: LazyLock<RwLock<HashMap<BackendId, Shared>>> = LazyLock::new(Default::default);
+
+/// Stores `callbacks` for `backend`, unless it already has some.
+pub(crate) fn insert(backend: BackendId, callbacks: Callbacks) {
+    let mut all = CALLBACKS.write();
+    all.entry(backend)
+        .or_insert_with(|| Arc::new(Mutex::new(callbacks)));
+}
+
+/// Whether `backend`'s events are wired up, which happens once callbacks are first used.
+pub(crate) fn contains(backend: BackendId) -> bool {
+    CALLBACKS.read().contains_key(&backend)
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    CALLBACKS.write().remove(&backend);
+}
+
+/// Whether any of `backend`'s handles listen for sentences, so text must be spoken a sentence at a time.
+pub(crate) fn wants_sentences(backend: BackendId) -> bool {
+    get(backend).is_some_and(|callbacks| {
+        let callbacks = callbacks.lock();
+        callbacks.shared.sentences || callbacks.subscribers.values().any(|set| set.sentences)
+    })
+}
+
+/// Whether any of `backend`'s handles listen for visemes.
+pub(crate) fn wants_visemes(backend: BackendId) -> bool {
+    get(backend).is_some_and(|callbacks| {
+        let callbacks = callbacks.lock();
+        callbacks.shared.visemes || callbacks.subscribers.values().any(|set| set.visemes)
+    })
+}
+
+fn get(backend: BackendId) -> Option<Shared> {
+    CALLBACKS.read().get(&backend).cloned()
+}
+
+/// Registers a set of callbacks for one handle, removing it when dropped.
+pub(crate) struct Subscription {
+    backend: BackendId,
+    id: u64,
+}
+
+impl Subscription {
+    pub(crate) fn new(backend: BackendId) -> Option<Self> {
+        let callbacks = get(backend)?;
+        let mut callbacks = callbacks.lock();
+        let id = callbacks.next_subscriber;
+        callbacks.next_subscriber += 1;
+        callbacks.subscribers.insert(id, Default::default());
+        Some(Self { backend, id })
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(callbacks) = get(self.backend) {
+            callbacks.lock().subscribers.remove(&self.id);
+        }
+    }
+}
+
+/// Runs `f` with the shared callbacks of `backend`, or those of `subscriber`.
+pub(crate) fn with_set<R, F>(backend: BackendId, subscriber: Option<u64>, f: F) -> Option<R>
+where
+    F: FnOnce(&mut CallbackSet) -> R,
+{
+    let callbacks = get(backend)?;
+    let mut callbacks = callbacks.lock();
+    let set = match subscriber {
+        Some(subscriber) => callbacks.subscribers.get_mut(&subscriber)?,
+        None => &mut callbacks.shared,
+    };
+    Some(f(set))
+}
+
+/// Takes the callback `select` picks out, calls it with `f` while no lock is held, then puts it back.
+fn call<T, S, F>(backend: BackendId, subscriber: Option<u64>, select: S, f: F)
+where
+    S: Fn(&mut CallbackSet) -> &mut Slot<T>,
+    F: FnOnce(&mut T),
+{
+    let taken = with_set(backend, subscriber, |set| select(set).take()).flatten();
+    let Some(mut taken) = taken else {
+        return;
+    };
+    // Put the callback back even if it panics, so it isn't lost to every later event.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&mut taken.0)));
+    with_set(backend, subscriber, |set| select(set).restore(taken));
+    if let Err(payload) = result {
+        panic::resume_unwind(payload);
+    }
+}
+
+fn subscribers(backend: BackendId) -> Vec<u64> {
+    get(backend)
+        .map(|callbacks| {
+            let callbacks = callbacks.lock();
+            callbacks.subscribers.keys().copied().collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Calls each subscriber's callback for `event` on `utterance`.
+pub(crate) fn notify_utterance(backend: BackendId, event: UtteranceEvent, utterance: UtteranceId) {
+    for subscriber in subscribers(backend) {
+        call(
+            backend,
+            Some(subscriber),
+            |set| set.utterance(event),
+            |callback| panics::guard(backend, || callback(utterance)),
+        );
+    }
+}
+
+/// Calls each subscriber's word boundary callback.
+pub(crate) fn notify_word(backend: BackendId, utterance: UtteranceId, range: Range<usize>) {
+    for subscriber in subscribers(backend) {
+        call(
+            backend,
+            Some(subscriber),
+            |set| &mut set.word_boundary,
+            |callback| panics::guard(backend, || callback(utterance, range.clone())),
+        );
+    }
+}
+
+/// Calls each subscriber's sentence callback.
+pub(crate) fn notify_sentence(backend: BackendId, utterance: UtteranceId, range: Range<usize>) {
+    for subscriber in subscribers(backend) {
+        call(
+            backend,
+            Some(subscriber),
+            |set| &mut set.sentence_begin,
+            |callback| panics::guard(backend, || callback(utterance, range.clone())),
+        );
+    }
+}
+
+/// Calls each subscriber's viseme callback.
+pub(crate) fn notify_viseme(
+    backend: BackendId,
+    utterance: UtteranceId,
+    viseme: Viseme,
+    at: Duration,
+) {
+    for subscriber in subscribers(backend) {
+        call(
+            backend,
+            Some(subscriber),
+            |set| &mut set.viseme,
+            |callback| panics::guard(backend, || callback(utterance, viseme, at)),
+        );
+    }
+}
+
+/// Calls `backend`'s callback for `event` on `utterance`.
+// Unused when every backend is disabled.
+#[allow(dead_code)]
+pub(crate) fn fire_utterance(backend: BackendId, event: UtteranceEvent, utterance: UtteranceId) {
+    fire(backend, Event::Utterance(event, utterance));
+}
+
+/// Calls `backend`'s word boundary callback.
+// Not every backend reports words.
+#[allow(dead_code)]
+pub(crate) fn fire_word(backend: BackendId, utterance: UtteranceId, range: Range<usize>) {
+    fire(backend, Event::Word(utterance, range));
+}
+
+/// Calls `backend`'s sentence callback.
+pub(crate) fn fire_sentence(backend: BackendId, utterance: UtteranceId, range: Range<usize>) {
+    fire(backend, Event::Sentence(utterance, range));
+}
+
+/// Calls `backend`'s viseme callback.
+pub(crate) fn fire_viseme(
+    backend: BackendId,
+    utterance: UtteranceId,
+    viseme: Viseme,
+    at: Duration,
+) {
+    fire(backend, Event::Viseme(utterance, viseme, at));
+}
+
+/// Marks dispatch as over if a callback unwinds out of it, so later events aren't queued forever behind it.
+struct Dispatching<'a>(&'a Shared);
+
+impl Drop for Dispatching<'_> {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            self.0.lock().dispatching = false;
+        }
+    }
+}
+
+/// Queues `event`, then, unless another call is already dispatching `backend`'s events, dispatches them in order.
+///
+/// Callbacks run one at a time, and an event raised from inside a callback, such as a stop caused by calling
+/// `Tts::stop()`, is dispatched once it returns.
+fn fire(backend: BackendId, event: Event) {
+    let Some(shared) = get(backend) else {
+        return;
+    };
+    {
+        let mut callbacks = shared.lock();
+        callbacks.queued.push_back(event);
+        if callbacks.dispatching {
+            return;
+        }
+        callbacks.dispatching = true;
+    }
+    dispatch(backend, &shared);
+}
+
+/// Runs `f`, which holds the lock on `backend`'s `Tts`, holding back the events it fires until it returns, so
+/// callbacks can call back into the `Tts` rather than deadlocking on that lock.
+pub(crate) fn deferred<R, F: FnOnce() -> R>(backend: Option<BackendId>, f: F) -> R {
+    let Some((backend, shared)) = backend.and_then(|backend| Some((backend, get(backend)?))) else {
+        return f();
+    };
+    // Events are already being dispatched, here from a callback or on another thread, and that loop picks up what `f`
+    // fires.
+    if std::mem::replace(&mut shared.lock().dispatching, true) {
+        return f();
+    }
+    let result = {
+        let _dispatching = Dispatching(&shared);
+        f()
+    };
+    dispatch(backend, &shared);
+    result
+}
+
+/// Dispatches `backend`'s queued events in order, until none are left.
+fn dispatch(backend: BackendId, shared: &Shared) {
+    let _dispatching = Dispatching(shared);
+    loop {
+        let event = {
+            let mut callbacks = shared.lock();
+            let Some(event) = callbacks.queued.pop_front() else {
+                callbacks.dispatching = false;
+                return;
+            };
+            event
+        };
+        match event {
+            Event::Utterance(event, utterance) => call(
+                backend,
+                None,
+                |set| set.utterance(event),
+                |callback| callback(utterance),
+            ),
+            Event::Word(utterance, range) => call(
+                backend,
+                None,
+                |set| &mut set.word_boundary,
+                |callback| callback(utterance, range),
+            ),
+            Event::Sentence(utterance, range) => call(
+                backend,
+                None,
+                |set| &mut set.sentence_begin,
+                |callback| callback(utterance, range),
+            ),
+            Event::Viseme(utterance, viseme, at) => call(
+                backend,
+                None,
+                |set| &mut set.viseme,
+                |callback| callback(utterance, viseme, at),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    use super::*;
+    use crate::{
+        backends::fake::{self, Fake},
+        Backend, Tts,
+    };
+
+    type Log = Rc<RefCell<Vec<(&'static str, UtteranceId)>>>;
+
+    /// Records each utterance event of `tts` in the returned log.
+    fn log_events(tts: &Tts) -> Log {
+        let log: Log = Default::default();
+        for (name, event) in [
+            ("begin", UtteranceEvent::Begin),
+            ("end", UtteranceEvent::End),
+            ("stop", UtteranceEvent::Stop),
+        ] {
+            let log = log.clone();
+            let callback: Box<dyn FnMut(UtteranceId)> =
+                Box::new(move |id| log.borrow_mut().push((name, id)));
+            match event {
+                UtteranceEvent::Begin => tts.on_utterance_begin(Some(callback)),
+                UtteranceEvent::End => tts.on_utterance_end(Some(callback)),
+                UtteranceEvent::Stop => tts.on_utterance_stop(Some(callback)),
+            }
+            .unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn callbacks_can_speak() {
+        let mut tts = Tts::fake();
+        let backend = tts.0.read().id().unwrap();
+        let log = log_events(&tts);
+        let first = tts.speak("one", false).unwrap().unwrap();
+        let spoken = Rc::new(Cell::new(None));
+        let (mut handle, record, said) = (tts.clone(), log.clone(), spoken.clone());
+        tts.on_utterance_end(Some(Box::new(move |id| {
+            record.borrow_mut().push(("end", id));
+            if said.get().is_none() {
+                said.set(handle.speak("two", false).unwrap());
+            }
+        })))
+        .unwrap();
+        fake::advance(backend);
+        fake::advance(backend);
+        let second = spoken.get().unwrap();
+        assert_eq!(*log.borrow(), [("begin", first), ("end", first)]);
+        assert_eq!(tts.queue_len().unwrap(), 1);
+        fake::advance(backend);
+        fake::advance(backend);
+        assert_eq!(
+            *log.borrow(),
+            [
+                ("begin", first),
+                ("end", first),
+                ("begin", second),
+                ("end", second)
+            ]
+        );
+        assert!(!tts.is_speaking().unwrap());
+        tts.on_utterance_end(None).unwrap();
+    }
+
+    #[test]
+    fn events_fired_during_a_callback_wait_for_it() {
+        let mut tts = Tts::fake();
+        let backend = tts.0.read().id().unwrap();
+        let log = log_events(&tts);
+        let first = tts.speak("one", false).unwrap().unwrap();
+        let second = tts.speak("two", false).unwrap().unwrap();
+        let (mut handle, record) = (tts.clone(), log.clone());
+        tts.on_utterance_begin(Some(Box::new(move |id| {
+            record.borrow_mut().push(("begin", id));
+            handle.stop().unwrap();
+            // Waiting returns once nothing's queued, rather than for callbacks still to come.
+            #[cfg(not(target_arch = "wasm32"))]
+            handle.wait().unwrap();
+            record.borrow_mut().push(("waited", id));
+        })))
+        .unwrap();
+        fake::advance(backend);
+        assert_eq!(
+            *log.borrow(),
+            [
+                ("begin", first),
+                ("waited", first),
+                ("stop", first),
+                ("stop", second)
+            ]
+        );
+        tts.on_utterance_begin(None).unwrap();
+    }
+
+    #[test]
+    fn stop_and_skip_callbacks_can_call_back() {
+        let mut tts = Tts::fake();
+        let backend = tts.0.read().id().unwrap();
+        let log = log_events(&tts);
+        let first = tts.speak("one", false).unwrap().unwrap();
+        let second = tts.speak("two", false).unwrap().unwrap();
+        fake::advance(backend);
+        let (handle, record) = (tts.clone(), log.clone());
+        tts.on_utterance_stop(Some(Box::new(move |id| {
+            record.borrow_mut().push(("stop", id));
+            assert_eq!(handle.queue_len().unwrap(), 1);
+        })))
+        .unwrap();
+        tts.skip().unwrap();
+        assert_eq!(
+            *log.borrow(),
+            [("begin", first), ("stop", first), ("begin", second)]
+        );
+        let (mut handle, spoken) = (tts.clone(), Rc::new(Cell::new(None)));
+        let said = spoken.clone();
+        tts.on_utterance_stop(Some(Box::new(move |_| {
+            said.set(handle.speak("again", false).unwrap());
+        })))
+        .unwrap();
+        tts.stop().unwrap();
+        assert!(spoken.get().is_some());
+        assert_eq!(tts.queue_len().unwrap(), 1);
+        tts.on_utterance_stop(None).unwrap();
+    }
+
+    #[test]
+    fn dispatch_recovers_from_an_unwinding_callback() {
+        let backend = Fake::new();
+        let id = backend.id().unwrap();
+        let calls = Rc::new(Cell::new(0));
+        let mut callbacks = Callbacks::default();
+        let count = calls.clone();
+        callbacks.shared.utterance_end.set(Some(Box::new(move |_| {
+            count.set(count.get() + 1);
+            if count.get() == 1 {
+                panic!("first");
+            }
+        })));
+        insert(id, callbacks);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            fire_utterance(id, UtteranceEvent::End, fake::utterance_id(1));
+        }));
+        assert!(result.is_err());
+        // The callback was put back, and the next event is dispatched rather than queued behind the panic.
+        fire_utterance(id, UtteranceEvent::End, fake::utterance_id(2));
+        assert_eq!(calls.get(), 2);
+        remove(id);
+    }
+}
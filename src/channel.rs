@@ -0,0 +1,162 @@
+//! Named queues of speech multiplexed onto one backend, so one can be stopped without silencing the others.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use parking_lot::Mutex;
+
+use crate::{speaking, BackendId, Error, Politeness, Tts, UtteranceId, UtteranceOptions};
+
+/// Speech queued on a channel, kept so it can be spoken again if another channel is stopped.
+struct Queued {
+    channel: String,
+    text: String,
+    options: Option<UtteranceOptions>,
+    utterance: u64,
+}
+
+/// Unfinished speech on each backend, oldest first.
+static QUEUES: LazyLock<Mutex<HashMap<BackendId, Vec<Queued>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Takes the unfinished speech queued on `backend`.
+fn take(backend: BackendId) -> Vec<Queued> {
+    let mut queues = QUEUES.lock();
+    let mut queued = queues.remove(&backend).unwrap_or_default();
+    queued.retain(|q| speaking::is_pending(backend, q.utterance));
+    queued
+}
+
+fn push(backend: BackendId, queued: Queued) {
+    let mut queues = QUEUES.lock();
+    let queue = queues.entry(backend).or_default();
+    queue.retain(|q| speaking::is_pending(backend, q.utterance));
+    queue.push(queued);
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    QUEUES.lock().remove(&backend);
+}
+
+/// A named queue of speech, such as "ui", "narration" or "chat", sharing a backend with other channels.
+///
+/// Interrupting or stopping a channel only silences its own speech. The backend has one queue, so speech from
+/// other channels that hadn't finished is then spoken again from the start, with new utterance IDs. Get one with
+/// `Tts::channel()`.
+#[derive(Clone)]
+pub struct Channel {
+    tts: Tts,
+    name: String,
+}
+
+impl Channel {
+    pub(crate) fn new(tts: Tts, name: String) -> Self {
+        Self { tts, name }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn backend_id(&self) -> Result<BackendId, Error> {
+        self.tts.0.read().id().ok_or(Error::UnsupportedFeature)
+    }
+
+    fn enqueue(
+        &mut self,
+        text: String,
+        interrupt: bool,
+        options: Option<&UtteranceOptions>,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let id = self.backend_id()?;
+        if interrupt {
+            self.stop()?;
+        }
+        let utterance = match options {
+            Some(options) => self.tts.speak_with_options(text.as_str(), false, options)?,
+            None => self.tts.speak(text.as_str(), false)?,
+        };
+        if let Some(utterance) = &utterance {
+            push(
+                id,
+                Queued {
+                    channel: self.name.clone(),
+                    text,
+                    options: options.cloned(),
+                    utterance: utterance.as_u64(),
+                },
+            );
+        }
+        Ok(utterance)
+    }
+
+    /// Speaks the specified text on this channel, optionally interrupting this channel's current speech.
+    pub fn speak<S: Into<String>>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        self.enqueue(text.into(), interrupt, None)
+    }
+
+    /// Speaks the specified text on this channel with options that apply only to this utterance.
+    pub fn speak_with_options<S: Into<String>>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        self.enqueue(text.into(), interrupt, Some(options))
+    }
+
+    /// Speaks the specified text on this channel as an ARIA live region would. Assertive speech interrupts this
+    /// channel's current speech.
+    pub fn speak_with_politeness<S: Into<String>>(
+        &mut self,
+        text: S,
+        politeness: Politeness,
+    ) -> Result<Option<UtteranceId>, Error> {
+        match politeness {
+            Politeness::Off => Ok(None),
+            Politeness::Polite => self.speak(text, false),
+            Politeness::Assertive => self.speak(text, true),
+        }
+    }
+
+    /// Stops this channel's speech, leaving other channels' speech queued.
+    pub fn stop(&mut self) -> Result<&Self, Error> {
+        let id = self.backend_id()?;
+        let queued = take(id);
+        if !queued.iter().any(|q| q.channel == self.name) {
+            for q in queued {
+                push(id, q);
+            }
+            return Ok(self);
+        }
+        self.tts.stop()?;
+        for q in queued.into_iter().filter(|q| q.channel != self.name) {
+            let language = q.options.as_ref().and_then(|o| o.language.as_ref());
+            let text = self.tts.preprocess(&q.text, language);
+            if let Some(utterance) = self.tts.submit(&text, false, q.options.as_ref())? {
+                push(
+                    id,
+                    Queued {
+                        utterance: utterance.as_u64(),
+                        ..q
+                    },
+                );
+            }
+        }
+        Ok(self)
+    }
+
+    /// Whether this channel has speech queued or speaking.
+    pub fn is_speaking(&self) -> Result<bool, Error> {
+        let id = self.backend_id()?;
+        let queues = QUEUES.lock();
+        Ok(queues.get(&id).is_some_and(|queued| {
+            queued
+                .iter()
+                .any(|q| q.channel == self.name && speaking::is_pending(id, q.utterance))
+        }))
+    }
+}
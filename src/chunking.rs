@@ -0,0 +1,275 @@
+//! Splits long text into several backend utterances, presented to callers as one.
+//!
+//! The first chunk's ID stands for the whole text. Begin callbacks fire for the first chunk, end callbacks for the
+//! last, stop callbacks once for the whole text, and word boundaries are offset into the original text. When text is
+//! split into sentences, each chunk's begin also fires a sentence callback with its range in the original text.
+
+use std::{collections::HashMap, ops::Range, sync::LazyLock};
+
+use parking_lot::Mutex;
+
+use crate::{callbacks, BackendId, UtteranceEvent, UtteranceId, WordBoundaryCallback};
+
+struct Chunk {
+    /// The ID of the first chunk, which stands for the whole text.
+    utterance: u64,
+    /// The bytes of the whole text this chunk holds.
+    range: Range<usize>,
+}
+
+struct Group {
+    /// Chunks not yet finished or stopped.
+    remaining: usize,
+    stopped: bool,
+    /// Whether each chunk is a sentence, to be reported as it begins.
+    sentences: bool,
+}
+
+#[derive(Default)]
+struct Chunking {
+    max_len: Option<usize>,
+    chunks: HashMap<u64, Chunk>,
+    groups: HashMap<u64, Group>,
+}
+
+static CHUNKING: LazyLock<Mutex<HashMap<BackendId, Chunking>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Whether `c`, followed by `next`, ends a sentence.
+fn ends_sentence(c: char, next: Option<char>) -> bool {
+    match c {
+        '.' | '!' | '?' | ';' => next.is_none_or(char::is_whitespace),
+        '\n' | '。' | '！' | '？' => true,
+        _ => false,
+    }
+}
+
+/// Splits `text` into ranges of at most `max_len` characters, breaking after sentences where possible, then at
+/// whitespace. Whitespace between chunks is dropped.
+pub(crate) fn split(text: &str, max_len: usize) -> Vec<Range<usize>> {
+    let max_len = max_len.max(1);
+    let mut ranges = vec![];
+    let mut start = 0;
+    while start < text.len() {
+        let rest = &text[start..];
+        let Some((limit, _)) = rest.char_indices().nth(max_len) else {
+            ranges.push(start..text.len());
+            break;
+        };
+        let window = &rest[..limit];
+        let sentence_end = window
+            .char_indices()
+            .rev()
+            .find(|&(i, c)| ends_sentence(c, rest[i + c.len_utf8()..].chars().next()))
+            .map(|(i, c)| i + c.len_utf8());
+        let end = sentence_end
+            .or_else(|| window.rfind(char::is_whitespace).filter(|&i| i > 0))
+            .unwrap_or(limit);
+        ranges.push(start..start + end);
+        start += end;
+        start += text[start..].len() - text[start..].trim_start().len();
+    }
+    ranges
+}
+
+/// Splits `text` into sentences, dropping the whitespace between them.
+pub(crate) fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut ranges = vec![];
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if start.is_none() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+        let next = chars.peek().map(|&(_, next)| next);
+        if ends_sentence(c, next) {
+            if let Some(start) = start.take() {
+                ranges.push(start..i + c.len_utf8());
+            }
+        }
+    }
+    if let Some(start) = start {
+        ranges.push(start..start + text[start..].trim_end().len());
+    }
+    ranges
+}
+
+/// Returns the start of the sentence containing byte `offset` of `text`.
+pub(crate) fn sentence_start(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let start = before
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| ends_sentence(c, text[i + c.len_utf8()..].chars().next()))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    start + text[start..].len() - text[start..].trim_start().len()
+}
+
+pub(crate) fn max_len(backend: BackendId) -> Option<usize> {
+    let chunking = CHUNKING.lock();
+    chunking.get(&backend)?.max_len
+}
+
+pub(crate) fn set_max_len(backend: BackendId, max_len: Option<usize>) {
+    let mut chunking = CHUNKING.lock();
+    chunking.entry(backend).or_default().max_len = max_len;
+}
+
+/// Records the first of `count` chunks, holding `range` of the whole text, whose ID stands for the whole text.
+/// `sentences` says whether each chunk is a sentence.
+pub(crate) fn add_group(
+    backend: BackendId,
+    utterance: u64,
+    count: usize,
+    range: Range<usize>,
+    sentences: bool,
+) {
+    let mut chunking = CHUNKING.lock();
+    let chunking = chunking.entry(backend).or_default();
+    chunking
+        .chunks
+        .insert(utterance, Chunk { utterance, range });
+    chunking.groups.insert(
+        utterance,
+        Group {
+            remaining: count,
+            stopped: false,
+            sentences,
+        },
+    );
+}
+
+/// Records a later chunk of `utterance`, holding `range` of the whole text, or `None` if the backend didn't return
+/// its ID.
+pub(crate) fn add_chunk(
+    backend: BackendId,
+    utterance: u64,
+    chunk: Option<u64>,
+    range: Range<usize>,
+) {
+    let mut chunking = CHUNKING.lock();
+    let chunking = chunking.entry(backend).or_default();
+    match chunk {
+        Some(chunk) => {
+            chunking.chunks.insert(chunk, Chunk { utterance, range });
+        }
+        // Without an ID, no callbacks will arrive for this chunk.
+        None => {
+            if let Some(group) = chunking.groups.get_mut(&utterance) {
+                group.remaining = group.remaining.saturating_sub(1);
+            }
+        }
+    }
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    CHUNKING.lock().remove(&backend);
+}
+
+enum Translation {
+    /// Not a chunk, so passed on as is.
+    Pass,
+    /// An intermediate chunk event, which isn't passed on.
+    Drop,
+    /// Passed on as an event for the whole text, with this ID.
+    Renumber(u64),
+}
+
+fn translate(backend: BackendId, event: UtteranceEvent, chunk: u64) -> Translation {
+    let mut chunking = CHUNKING.lock();
+    let Some(chunking) = chunking.get_mut(&backend) else {
+        return Translation::Pass;
+    };
+    let Some(utterance) = chunking.chunks.get(&chunk).map(|c| c.utterance) else {
+        return Translation::Pass;
+    };
+    let forward = match event {
+        UtteranceEvent::Begin => chunk == utterance,
+        UtteranceEvent::End | UtteranceEvent::Stop => {
+            chunking.chunks.remove(&chunk);
+            let Some(group) = chunking.groups.get_mut(&utterance) else {
+                return Translation::Drop;
+            };
+            group.remaining = group.remaining.saturating_sub(1);
+            let forward = match event {
+                UtteranceEvent::Stop => !std::mem::replace(&mut group.stopped, true),
+                _ => group.remaining == 0 && !group.stopped,
+            };
+            if group.remaining == 0 {
+                chunking.groups.remove(&utterance);
+            }
+            forward
+        }
+    };
+    if forward {
+        Translation::Renumber(utterance)
+    } else {
+        Translation::Drop
+    }
+}
+
+/// Returns the utterance and range of the sentence `chunk` holds, if it's one.
+fn sentence(backend: BackendId, chunk: u64) -> Option<(u64, Range<usize>)> {
+    let chunking = CHUNKING.lock();
+    let chunking = chunking.get(&backend)?;
+    let chunk = chunking.chunks.get(&chunk)?;
+    let group = chunking.groups.get(&chunk.utterance)?;
+    group
+        .sentences
+        .then(|| (chunk.utterance, chunk.range.clone()))
+}
+
+/// Wraps an utterance callback to translate chunk events into events for the whole text, firing sentence callbacks
+/// as sentences begin.
+pub(crate) fn utterance_callback(
+    backend: BackendId,
+    event: UtteranceEvent,
+    mut callback: Box<dyn FnMut(UtteranceId)>,
+) -> Box<dyn FnMut(UtteranceId)> {
+    Box::new(move |id: UtteranceId| {
+        let sentence = match event {
+            UtteranceEvent::Begin => sentence(backend, id.as_u64()),
+            _ => None,
+        }
+        .map(|(utterance, range)| (id.with_u64(utterance), range));
+        match translate(backend, event, id.as_u64()) {
+            Translation::Pass => callback(id),
+            Translation::Drop => {}
+            Translation::Renumber(utterance) => callback(id.with_u64(utterance)),
+        }
+        // Dispatched once this callback returns, so the utterance begins before its first sentence.
+        if let Some((utterance, range)) = sentence {
+            callbacks::fire_sentence(backend, utterance, range);
+        }
+    })
+}
+
+/// Wraps a word boundary callback to offset chunk ranges into the whole text.
+pub(crate) fn word_callback(
+    backend: BackendId,
+    mut callback: WordBoundaryCallback,
+) -> WordBoundaryCallback {
+    Box::new(move |id, range| {
+        let chunk = {
+            let chunking = CHUNKING.lock();
+            chunking
+                .get(&backend)
+                .and_then(|c| c.chunks.get(&id.as_u64()))
+                .map(|c| (c.utterance, c.range.start))
+        };
+        match chunk {
+            Some((utterance, offset)) => callback(
+                id.with_u64(utterance),
+                range.start + offset..range.end + offset,
+            ),
+            None => callback(id, range),
+        }
+    })
+}
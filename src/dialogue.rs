@@ -0,0 +1,68 @@
+//! Scripted conversations between voices, spoken as one utterance.
+
+use crate::{speaking, BackendId, Error, Tts, UtteranceId, Voice};
+
+/// A conversation between voices, such as characters in interactive fiction, spoken as one utterance.
+///
+/// Every line is queued with its voice in one go, so no line can pick up a voice meant for another. Callbacks fire
+/// as for one utterance: begin for the first line, end for the last, and stop once, and word boundaries index into
+/// the lines, after filters and the lexicon, joined by newlines. Get one with `Tts::dialogue()`.
+#[derive(Clone)]
+pub struct Dialogue {
+    tts: Tts,
+    lines: Vec<(Voice, String)>,
+    /// The utterance standing for the lines, once spoken.
+    utterance: Option<u64>,
+}
+
+impl Dialogue {
+    pub(crate) fn new(tts: Tts) -> Self {
+        Self {
+            tts,
+            lines: vec![],
+            utterance: None,
+        }
+    }
+
+    fn backend_id(&self) -> Result<BackendId, Error> {
+        self.tts.0.read().id().ok_or(Error::UnsupportedFeature)
+    }
+
+    /// Adds a line spoken by `voice`.
+    pub fn line<S: Into<String>>(&mut self, voice: &Voice, text: S) -> &mut Self {
+        self.lines.push((voice.clone(), text.into()));
+        self
+    }
+
+    /// Speaks the lines, optionally interrupting current speech, and returns the ID standing for them all.
+    ///
+    /// The voice in use beforehand is restored afterward if the backend can report it. Otherwise, the last line's
+    /// voice remains.
+    pub fn speak(&mut self, interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        if self.lines.is_empty() {
+            return Ok(None);
+        }
+        let utterance = self.tts.speak_dialogue(&self.lines, interrupt)?;
+        self.utterance = utterance.as_ref().map(|u| u.as_u64());
+        Ok(utterance)
+    }
+
+    /// Whether the lines are speaking or queued.
+    pub fn is_speaking(&self) -> Result<bool, Error> {
+        let id = self.backend_id()?;
+        Ok(self
+            .utterance
+            .is_some_and(|utterance| speaking::is_pending(id, utterance)))
+    }
+
+    /// Stops the lines if they're speaking or queued.
+    ///
+    /// Backends stop all speech at once, so this also stops anything queued after the lines.
+    pub fn cancel(&mut self) -> Result<&Self, Error> {
+        if self.is_speaking()? {
+            self.tts.stop()?;
+        }
+        self.utterance = None;
+        Ok(self)
+    }
+}
@@ -0,0 +1,843 @@
+//! Bindings to this library to allow it to be called from C/C++.
+//!
+//! Enabled with the `ffi` feature. Functions that can fail return a `TtsErrorCode`, and
+//! `tts_get_error_message()` describes the most recent failure in more detail. Null `Tts` and output pointers are
+//! reported as `TtsErrorCode::NullPointer` rather than dereferenced.
+
+use std::{
+    ffi::{c_char, c_void, CStr},
+    ptr,
+};
+
+use parking_lot::Mutex;
+
+use crate::{Backends, Capability, Error, Tts, UtteranceId};
+
+/// The outcome of a fallible FFI call.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TtsErrorCode {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer,
+    /// A string argument wasn't valid UTF-8.
+    InvalidString,
+    /// The backend doesn't support this operation.
+    UnsupportedFeature,
+    /// A value was outside the supported range.
+    OutOfRange,
+    /// The requested backend isn't available on this system.
+    BackendUnavailable,
+    /// The call must be made from the main thread.
+    NotMainThread,
+    /// A value expected from the backend wasn't received.
+    NoValue,
+    /// An I/O error occurred.
+    Io,
+    /// The operation failed in the backend.
+    OperationFailed,
+    /// The platform reported an error, such as a Speech Dispatcher, WinRT, JNI or JavaScript error.
+    Platform,
+}
+
+impl From<&Error> for TtsErrorCode {
+    fn from(e: &Error) -> Self {
+        match e {
+            Error::Io(_) => TtsErrorCode::Io,
+            Error::NoneError => TtsErrorCode::NoValue,
+            Error::OperationFailed => TtsErrorCode::OperationFailed,
+            Error::UnsupportedFeature => TtsErrorCode::UnsupportedFeature,
+            Error::BackendUnavailable { .. } => TtsErrorCode::BackendUnavailable,
+            Error::OutOfRange => TtsErrorCode::OutOfRange,
+            #[cfg(target_os = "macos")]
+            Error::NotMainThread => TtsErrorCode::NotMainThread,
+            #[allow(unreachable_patterns)]
+            _ => TtsErrorCode::Platform,
+        }
+    }
+}
+
+/// The most recent error, shared across threads so it can be retrieved from any of them.
+static LAST_ERROR: Mutex<Option<(TtsErrorCode, String)>> = Mutex::new(None);
+
+fn set_last_error<S: Into<String>>(code: TtsErrorCode, message: S) -> TtsErrorCode {
+    let mut last = LAST_ERROR.lock();
+    *last = Some((code, message.into()));
+    code
+}
+
+fn report(e: Error) -> TtsErrorCode {
+    set_last_error((&e).into(), e.to_string())
+}
+
+/// Returns the code of the most recent error, or `Ok` if there is none.
+#[no_mangle]
+pub extern "C" fn tts_get_error_code() -> TtsErrorCode {
+    match &*LAST_ERROR.lock() {
+        Some((code, _)) => *code,
+        None => TtsErrorCode::Ok,
+    }
+}
+
+/// Copies a NUL-terminated description of the most recent error into `buf`, truncating it to fit `len` bytes.
+/// Returns the length of the full message, excluding the terminator, or 0 if there is no error.
+///
+/// # Safety
+///
+/// `buf` must be null or writable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn tts_get_error_message(buf: *mut c_char, len: usize) -> usize {
+    let last = LAST_ERROR.lock();
+    let Some((_, message)) = &*last else {
+        if !buf.is_null() && len > 0 {
+            *buf = 0;
+        }
+        return 0;
+    };
+    if !buf.is_null() && len > 0 {
+        let n = message.len().min(len - 1);
+        ptr::copy_nonoverlapping(message.as_ptr().cast::<c_char>(), buf, n);
+        *buf.add(n) = 0;
+    }
+    message.len()
+}
+
+/// Clears the most recent error.
+#[no_mangle]
+pub extern "C" fn tts_clear_error() {
+    let mut last = LAST_ERROR.lock();
+    *last = None;
+}
+
+/// Runs `f` against `tts`, recording any error.
+unsafe fn with_tts<F>(tts: *mut Tts, f: F) -> TtsErrorCode
+where
+    F: FnOnce(&mut Tts) -> Result<(), Error>,
+{
+    if tts.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null TTS object");
+    }
+    match f(&mut *tts) {
+        Ok(()) => TtsErrorCode::Ok,
+        Err(e) => report(e),
+    }
+}
+
+/// Stores what `f` returns for `tts` in `out`, recording any error.
+unsafe fn get<T, F>(tts: *mut Tts, out: *mut T, f: F) -> TtsErrorCode
+where
+    F: FnOnce(&mut Tts) -> Result<T, Error>,
+{
+    if out.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null output pointer");
+    }
+    with_tts(tts, |tts| {
+        *out = f(tts)?;
+        Ok(())
+    })
+}
+
+/// Stores what `f` returns for `tts`, which isn't changed, in `out`.
+unsafe fn read<T, F>(tts: *const Tts, out: *mut T, f: F) -> TtsErrorCode
+where
+    F: FnOnce(&Tts) -> T,
+{
+    if tts.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null TTS object");
+    }
+    if out.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null output pointer");
+    }
+    *out = f(&*tts);
+    TtsErrorCode::Ok
+}
+
+/// Borrows a NUL-terminated text argument, recording an error if it's null or not UTF-8.
+unsafe fn text_arg<'a>(text: *const c_char) -> Result<&'a str, TtsErrorCode> {
+    if text.is_null() {
+        return Err(set_last_error(TtsErrorCode::NullPointer, "Null text"));
+    }
+    CStr::from_ptr(text)
+        .to_str()
+        .map_err(|e| set_last_error(TtsErrorCode::InvalidString, e.to_string()))
+}
+
+unsafe fn create(tts: Result<Tts, Error>, out: *mut *mut Tts) -> TtsErrorCode {
+    if out.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null output pointer");
+    }
+    match tts {
+        Ok(tts) => {
+            *out = Box::into_raw(Box::new(tts));
+            TtsErrorCode::Ok
+        }
+        Err(e) => {
+            *out = ptr::null_mut();
+            report(e)
+        }
+    }
+}
+
+/// Looks up a backend by its value in `Backends`, recording an error if this build doesn't have it.
+fn backend_arg(backend: u32) -> Result<Backends, TtsErrorCode> {
+    let backends = [
+        #[cfg(all(target_os = "android", feature = "android"))]
+        Backends::Android,
+        #[cfg(all(target_os = "macos", feature = "appkit"))]
+        Backends::AppKit,
+        #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+        Backends::AvFoundation,
+        #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+        Backends::SpeechDispatcher,
+        #[cfg(all(windows, feature = "tolk"))]
+        Backends::Tolk,
+        #[cfg(all(target_arch = "wasm32", feature = "web"))]
+        Backends::Web,
+        #[cfg(all(windows, feature = "winrt"))]
+        Backends::WinRt,
+        Backends::Noop,
+    ];
+    backends
+        .into_iter()
+        .find(|b| *b as u32 == backend)
+        .ok_or_else(|| {
+            set_last_error(
+                TtsErrorCode::BackendUnavailable,
+                format!("Backend {backend} isn't available in this build"),
+            )
+        })
+}
+
+/// Create a new `Tts` instance with the specified backend, one of the values of `Backends`, storing it in `out`.
+/// Fails with `BackendUnavailable` for backends this build of the library doesn't have.
+///
+/// # Safety
+///
+/// `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_new(backend: u32, out: *mut *mut Tts) -> TtsErrorCode {
+    match backend_arg(backend) {
+        Ok(backend) => create(Tts::new(backend), out),
+        Err(code) => {
+            if !out.is_null() {
+                *out = ptr::null_mut();
+            }
+            code
+        }
+    }
+}
+
+/// Create a new `Tts` instance with the default backend for this platform, storing it in `out`.
+///
+/// # Safety
+///
+/// `out` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_default(out: *mut *mut Tts) -> TtsErrorCode {
+    create(Tts::default(), out)
+}
+
+/// Free the memory associated with a `Tts` object.
+/// Does nothing if `tts` is null.
+///
+/// # Safety
+///
+/// `tts` must be null or a pointer returned by `tts_new` or `tts_default`, and must not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn tts_free(tts: *mut Tts) {
+    if !tts.is_null() {
+        drop(Box::from_raw(tts));
+    }
+}
+
+/// A capability a backend may have, as queried with `tts_supports()`. Values are fixed, and new capabilities are
+/// only ever added at the end.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TtsCapability {
+    IsSpeaking = 0,
+    Pitch = 1,
+    Rate = 2,
+    Stop = 3,
+    UtteranceCallbacks = 4,
+    Voice = 5,
+    GetVoice = 6,
+    Volume = 7,
+    WordCallbacks = 8,
+    Synthesize = 9,
+    Pause = 10,
+    Queue = 11,
+    OutputDevice = 12,
+    Earcons = 13,
+    Ssml = 14,
+    Braille = 15,
+    Priorities = 16,
+}
+
+impl From<TtsCapability> for Capability {
+    fn from(capability: TtsCapability) -> Self {
+        match capability {
+            TtsCapability::IsSpeaking => Capability::IsSpeaking,
+            TtsCapability::Pitch => Capability::Pitch,
+            TtsCapability::Rate => Capability::Rate,
+            TtsCapability::Stop => Capability::Stop,
+            TtsCapability::UtteranceCallbacks => Capability::UtteranceCallbacks,
+            TtsCapability::Voice => Capability::Voice,
+            TtsCapability::GetVoice => Capability::GetVoice,
+            TtsCapability::Volume => Capability::Volume,
+            TtsCapability::WordCallbacks => Capability::WordCallbacks,
+            TtsCapability::Synthesize => Capability::Synthesize,
+            TtsCapability::Pause => Capability::Pause,
+            TtsCapability::Queue => Capability::Queue,
+            TtsCapability::OutputDevice => Capability::OutputDevice,
+            TtsCapability::Earcons => Capability::Earcons,
+            TtsCapability::Ssml => Capability::Ssml,
+            TtsCapability::Braille => Capability::Braille,
+            TtsCapability::Priorities => Capability::Priorities,
+        }
+    }
+}
+
+/// Returns whether this `Tts` object supports `capability`, one of the values of `TtsCapability`, or false if `tts` is null or `capability` isn't one
+/// this library knows of.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_supports(tts: *const Tts, capability: u32) -> bool {
+    if tts.is_null() {
+        return false;
+    }
+    match capability_arg(capability) {
+        Some(capability) => (*tts).supported_features().supports(capability.into()),
+        None => false,
+    }
+}
+
+/// Converts a raw `TtsCapability` from C, which may hold a value newer than this library.
+fn capability_arg(capability: u32) -> Option<TtsCapability> {
+    use TtsCapability::*;
+    [
+        IsSpeaking,
+        Pitch,
+        Rate,
+        Stop,
+        UtteranceCallbacks,
+        Voice,
+        GetVoice,
+        Volume,
+        WordCallbacks,
+        Synthesize,
+        Pause,
+        Queue,
+        OutputDevice,
+        Earcons,
+        Ssml,
+        Braille,
+        Priorities,
+    ]
+    .into_iter()
+    .find(|&c| c as u32 == capability)
+}
+
+/// Speaks the specified text, optionally interrupting current speech.
+/// If `utterance` is not null, it is set to a pointer to the utterance ID, which must be freed with
+/// `tts_free_utterance`, or to null if the backend doesn't report IDs.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer, `text` a valid NUL-terminated string, and `utterance` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_speak(
+    tts: *mut Tts,
+    text: *const c_char,
+    interrupt: bool,
+    utterance: *mut *mut UtteranceId,
+) -> TtsErrorCode {
+    let text = match text_arg(text) {
+        Ok(text) => text,
+        Err(code) => return code,
+    };
+    with_tts(tts, |tts| {
+        let id = tts.speak(text, interrupt)?;
+        if !utterance.is_null() {
+            *utterance = match id {
+                Some(id) => Box::into_raw(Box::new(id)),
+                None => ptr::null_mut(),
+            };
+        }
+        Ok(())
+    })
+}
+
+/// Free the memory associated with an `UtteranceId`.
+/// Does nothing if `utterance` is null.
+///
+/// # Safety
+///
+/// `utterance` must be null or a pointer returned by `tts_speak`, and must not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn tts_free_utterance(utterance: *mut UtteranceId) {
+    if !utterance.is_null() {
+        drop(Box::from_raw(utterance));
+    }
+}
+
+/// Synthesizes the specified text to 16-bit PCM audio without speaking it, blocking until synthesis completes.
+/// On success, `buf` is set to the interleaved samples, which must be freed with `tts_free_buffer`, and `len` to
+/// their number. `sample_rate` and `channels` may be null if they aren't needed.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer, `text` a valid NUL-terminated string, `buf` and `len` writable, and
+/// `sample_rate` and `channels` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_synthesize(
+    tts: *mut Tts,
+    text: *const c_char,
+    buf: *mut *mut i16,
+    len: *mut usize,
+    sample_rate: *mut u32,
+    channels: *mut u16,
+) -> TtsErrorCode {
+    if buf.is_null() || len.is_null() {
+        return set_last_error(TtsErrorCode::NullPointer, "Null output pointer");
+    }
+    *buf = ptr::null_mut();
+    *len = 0;
+    let text = match text_arg(text) {
+        Ok(text) => text,
+        Err(code) => return code,
+    };
+    with_tts(tts, |tts| {
+        let audio = tts.synthesize(text)?;
+        if !sample_rate.is_null() {
+            *sample_rate = audio.sample_rate;
+        }
+        if !channels.is_null() {
+            *channels = audio.channels;
+        }
+        let samples = audio.samples.into_boxed_slice();
+        *len = samples.len();
+        *buf = Box::into_raw(samples).cast();
+        Ok(())
+    })
+}
+
+/// Free the samples returned by `tts_synthesize`.
+/// Does nothing if `buf` is null.
+///
+/// # Safety
+///
+/// `buf` and `len` must be null or exactly as returned by `tts_synthesize`, and `buf` must not be used afterward.
+#[no_mangle]
+pub unsafe extern "C" fn tts_free_buffer(buf: *mut i16, len: usize) {
+    if !buf.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(buf, len)));
+    }
+}
+
+/// Stops current speech.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_stop(tts: *mut Tts) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.stop().map(|_| ()))
+}
+
+/// Stores the minimum rate for this speech synthesizer in `rate`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_min_rate(tts: *const Tts, rate: *mut f32) -> TtsErrorCode {
+    read(tts, rate, |tts| tts.min_rate())
+}
+
+/// Stores the maximum rate for this speech synthesizer in `rate`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_max_rate(tts: *const Tts, rate: *mut f32) -> TtsErrorCode {
+    read(tts, rate, |tts| tts.max_rate())
+}
+
+/// Stores the normal rate for this speech synthesizer in `rate`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_normal_rate(tts: *const Tts, rate: *mut f32) -> TtsErrorCode {
+    read(tts, rate, |tts| tts.normal_rate())
+}
+
+/// Gets the current speech rate, storing it in `rate`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `rate` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_get_rate(tts: *mut Tts, rate: *mut f32) -> TtsErrorCode {
+    get(tts, rate, |tts| tts.get_rate())
+}
+
+/// Sets the desired speech rate.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_set_rate(tts: *mut Tts, rate: f32) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.set_rate(rate).map(|_| ()))
+}
+
+/// Stores the minimum pitch for this speech synthesizer in `pitch`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_min_pitch(tts: *const Tts, pitch: *mut f32) -> TtsErrorCode {
+    read(tts, pitch, |tts| tts.min_pitch())
+}
+
+/// Stores the maximum pitch for this speech synthesizer in `pitch`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_max_pitch(tts: *const Tts, pitch: *mut f32) -> TtsErrorCode {
+    read(tts, pitch, |tts| tts.max_pitch())
+}
+
+/// Stores the normal pitch for this speech synthesizer in `pitch`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_normal_pitch(tts: *const Tts, pitch: *mut f32) -> TtsErrorCode {
+    read(tts, pitch, |tts| tts.normal_pitch())
+}
+
+/// Gets the current speech pitch, storing it in `pitch`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `pitch` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_get_pitch(tts: *mut Tts, pitch: *mut f32) -> TtsErrorCode {
+    get(tts, pitch, |tts| tts.get_pitch())
+}
+
+/// Sets the desired speech pitch.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_set_pitch(tts: *mut Tts, pitch: f32) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.set_pitch(pitch).map(|_| ()))
+}
+
+/// Stores the minimum volume for this speech synthesizer in `volume`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_min_volume(tts: *const Tts, volume: *mut f32) -> TtsErrorCode {
+    read(tts, volume, |tts| tts.min_volume())
+}
+
+/// Stores the maximum volume for this speech synthesizer in `volume`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_max_volume(tts: *const Tts, volume: *mut f32) -> TtsErrorCode {
+    read(tts, volume, |tts| tts.max_volume())
+}
+
+/// Stores the normal volume for this speech synthesizer in `volume`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_normal_volume(tts: *const Tts, volume: *mut f32) -> TtsErrorCode {
+    read(tts, volume, |tts| tts.normal_volume())
+}
+
+/// Gets the current speech volume, storing it in `volume`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `volume` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_get_volume(tts: *mut Tts, volume: *mut f32) -> TtsErrorCode {
+    get(tts, volume, |tts| tts.get_volume())
+}
+
+/// Sets the desired speech volume.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_set_volume(tts: *mut Tts, volume: f32) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.set_volume(volume).map(|_| ()))
+}
+
+/// Returns whether this speech synthesizer is speaking, storing it in `speaking`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `speaking` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_is_speaking(tts: *mut Tts, speaking: *mut bool) -> TtsErrorCode {
+    get(tts, speaking, |tts| tts.is_speaking())
+}
+
+/// Pauses speech, which can be continued with `tts_resume`.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_pause(tts: *mut Tts) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.pause().map(|_| ()))
+}
+
+/// Resumes speech paused with `tts_pause`.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_resume(tts: *mut Tts) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.resume().map(|_| ()))
+}
+
+/// Stops the utterance being spoken and moves on to the next one queued.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_skip(tts: *mut Tts) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.skip().map(|_| ()))
+}
+
+/// Blocks until speech has finished, including while it's paused.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer.
+#[cfg(not(target_arch = "wasm32"))]
+#[no_mangle]
+pub unsafe extern "C" fn tts_wait(tts: *mut Tts) -> TtsErrorCode {
+    with_tts(tts, |tts| tts.wait().map(|_| ()))
+}
+
+/// Returns whether speech is paused, storing it in `paused`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `paused` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_is_paused(tts: *mut Tts, paused: *mut bool) -> TtsErrorCode {
+    get(tts, paused, |tts| tts.is_paused())
+}
+
+/// Returns the number of utterances from this instance that are speaking or queued, storing it in `len`.
+///
+/// # Safety
+///
+/// `tts` must be null or a valid `Tts` pointer, and `len` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn tts_queue_len(tts: *mut Tts, len: *mut usize) -> TtsErrorCode {
+    get(tts, len, |tts| tts.queue_len())
+}
+
+/// A callback receiving an utterance ID, valid only for the duration of the call, and the `user_data` it was registered with.
+/// May be null.
+pub type TtsUtteranceCallback =
+    Option<extern "C" fn(utterance: *const UtteranceId, user_data: *mut c_void)>;
+
+fn utterance_callback(
+    callback: TtsUtteranceCallback,
+    user_data: *mut c_void,
+) -> Option<Box<dyn FnMut(UtteranceId)>> {
+    callback.map(|callback| {
+        Box::new(move |id: UtteranceId| callback(&id, user_data)) as Box<dyn FnMut(UtteranceId)>
+    })
+}
+
+/// Calls `callback` when this speech synthesizer begins speaking an utterance.
+/// Pass a null `callback` to remove it.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+#[no_mangle]
+pub unsafe extern "C" fn tts_on_utterance_begin(
+    tts: *mut Tts,
+    callback: TtsUtteranceCallback,
+    user_data: *mut c_void,
+) -> TtsErrorCode {
+    with_tts(tts, |tts| {
+        tts.on_utterance_begin(utterance_callback(callback, user_data))
+    })
+}
+
+/// Calls `callback` when this speech synthesizer finishes speaking an utterance.
+/// Pass a null `callback` to remove it.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+#[no_mangle]
+pub unsafe extern "C" fn tts_on_utterance_end(
+    tts: *mut Tts,
+    callback: TtsUtteranceCallback,
+    user_data: *mut c_void,
+) -> TtsErrorCode {
+    with_tts(tts, |tts| {
+        tts.on_utterance_end(utterance_callback(callback, user_data))
+    })
+}
+
+/// Calls `callback` when this speech synthesizer is stopped with utterances still in its queue.
+/// Pass a null `callback` to remove it.
+///
+/// # Safety
+///
+/// `tts` must be a valid `Tts` pointer, and `user_data` must remain valid for as long as the callback is registered.
+#[no_mangle]
+pub unsafe extern "C" fn tts_on_utterance_stop(
+    tts: *mut Tts,
+    callback: TtsUtteranceCallback,
+    user_data: *mut c_void,
+) -> TtsErrorCode {
+    with_tts(tts, |tts| {
+        tts.on_utterance_stop(utterance_callback(callback, user_data))
+    })
+}
+
+/// Returns whether two utterance IDs refer to the same utterance, such as one from `tts_speak` and one passed to a callback.
+/// Returns `false` if either is null.
+///
+/// # Safety
+///
+/// `a` and `b` must each be null or a valid `UtteranceId` pointer.
+#[no_mangle]
+pub unsafe extern "C" fn tts_utterance_id_eq(a: *const UtteranceId, b: *const UtteranceId) -> bool {
+    if a.is_null() || b.is_null() {
+        return false;
+    }
+    *a == *b
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn null_pointers_are_reported() {
+        let mut tts = Tts::new(Backends::Noop).unwrap();
+        let mut value = 0.;
+        unsafe {
+            assert_eq!(
+                tts_min_rate(ptr::null(), &mut value),
+                TtsErrorCode::NullPointer
+            );
+            assert_eq!(
+                tts_normal_volume(&tts, ptr::null_mut()),
+                TtsErrorCode::NullPointer
+            );
+            assert_eq!(
+                tts_get_pitch(ptr::null_mut(), &mut value),
+                TtsErrorCode::NullPointer
+            );
+            assert_eq!(
+                tts_queue_len(&mut tts, ptr::null_mut()),
+                TtsErrorCode::NullPointer
+            );
+            assert_eq!(tts_max_rate(&tts, &mut value), TtsErrorCode::Ok);
+            assert_eq!(value, tts.max_rate());
+        }
+    }
+
+    #[test]
+    fn unknown_backends_are_rejected() {
+        let mut tts = ptr::null_mut();
+        unsafe {
+            assert_eq!(
+                tts_new(u32::MAX, &mut tts),
+                TtsErrorCode::BackendUnavailable
+            );
+            assert!(tts.is_null());
+            assert_eq!(tts_new(Backends::Noop as u32, &mut tts), TtsErrorCode::Ok);
+            tts_free(tts);
+        }
+    }
+
+    #[test]
+    fn capabilities_are_queried_one_at_a_time() {
+        for (i, &capability) in Capability::ALL.iter().enumerate() {
+            assert_eq!(
+                capability_arg(i as u32).map(Capability::from),
+                Some(capability)
+            );
+        }
+        assert_eq!(capability_arg(Capability::ALL.len() as u32), None);
+        let tts = Tts::new(Backends::Noop).unwrap();
+        unsafe {
+            assert!(tts_supports(&tts, TtsCapability::UtteranceCallbacks as u32));
+            assert!(!tts_supports(&tts, TtsCapability::Synthesize as u32));
+            assert!(!tts_supports(&tts, u32::MAX));
+            assert!(!tts_supports(
+                ptr::null(),
+                TtsCapability::UtteranceCallbacks as u32
+            ));
+        }
+    }
+
+    #[test]
+    fn panicking_callbacks_dont_unwind_into_c() {
+        let mut tts = Tts::fake();
+        tts.on_utterance_stop(Some(Box::new(|_| panic!("stop"))))
+            .unwrap();
+        tts.speak("Hello", false).unwrap();
+        unsafe {
+            assert_eq!(tts_stop(&mut tts), TtsErrorCode::Ok);
+            let mut speaking = true;
+            assert_eq!(tts_is_speaking(&mut tts, &mut speaking), TtsErrorCode::Ok);
+            assert!(!speaking);
+        }
+    }
+
+    #[cfg(feature = "cbindgen")]
+    #[test]
+    fn header_is_current() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/tts.h"));
+        let checked_in = include_str!("../include/tts.h");
+        assert!(
+            generated == checked_in,
+            "include/tts.h is out of date. Copy {}/tts.h over it.",
+            env!("OUT_DIR")
+        );
+    }
+}
@@ -0,0 +1,338 @@
+//! Verbalizes source code, naming its punctuation, reading identifiers as words and announcing indentation.
+
+use crate::{LanguageTag, TextFilter};
+
+/// Names of symbols. Where one symbol starts another, the longer comes first.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("===", "triple equals"),
+    ("!==", "not triple equals"),
+    ("...", "dot dot dot"),
+    ("->", "arrow"),
+    ("=>", "fat arrow"),
+    ("::", "colon colon"),
+    ("==", "equals equals"),
+    ("!=", "not equals"),
+    ("<=", "less or equal"),
+    (">=", "greater or equal"),
+    ("&&", "and and"),
+    ("||", "or or"),
+    ("+=", "plus equals"),
+    ("-=", "minus equals"),
+    ("*=", "star equals"),
+    ("/=", "slash equals"),
+    ("**", "star star"),
+    ("..", "dot dot"),
+    ("//", "slash slash"),
+    ("/*", "slash star"),
+    ("*/", "star slash"),
+    ("!", "bang"),
+    ("\"", "quote"),
+    ("#", "hash"),
+    ("$", "dollar"),
+    ("%", "percent"),
+    ("&", "and"),
+    ("'", "tick"),
+    ("(", "left paren"),
+    (")", "right paren"),
+    ("*", "star"),
+    ("+", "plus"),
+    (",", "comma"),
+    ("-", "minus"),
+    (".", "dot"),
+    ("/", "slash"),
+    (":", "colon"),
+    (";", "semicolon"),
+    ("<", "less than"),
+    ("=", "equals"),
+    (">", "greater than"),
+    ("?", "question"),
+    ("@", "at"),
+    ("[", "left bracket"),
+    ("\\", "backslash"),
+    ("]", "right bracket"),
+    ("^", "caret"),
+    ("_", "underscore"),
+    ("`", "backtick"),
+    ("{", "left brace"),
+    ("|", "bar"),
+    ("}", "right brace"),
+    ("~", "tilde"),
+];
+
+/// Languages whose indentation is significant, by the names and extensions editors commonly use for them.
+const SIGNIFICANT_INDENTATION: &[&str] = &[
+    "coffeescript",
+    "elm",
+    "fsharp",
+    "haskell",
+    "hs",
+    "makefile",
+    "nim",
+    "py",
+    "python",
+    "sass",
+    "yaml",
+    "yml",
+];
+
+/// Spaces per indentation level when the text doesn't say.
+const DEFAULT_INDENT_WIDTH: usize = 4;
+
+/// When `CodeFilter` announces indentation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Indentation {
+    /// Never.
+    Off,
+    /// When a line is indented differently from the one before, as in "indent 2".
+    Changes,
+    /// On every indented line.
+    EveryLine,
+    /// `Changes` for languages whose indentation is significant, such as Python and YAML, and `Off` otherwise.
+    #[default]
+    Auto,
+}
+
+/// A text filter that reads source code aloud, so `if (maxLen >= 10) {` is spoken as "if left paren max Len greater
+/// or equal 10 right paren left brace".
+///
+/// Symbols are named, with operators like `->` and `::` named as a whole, and can be renamed with `set_symbol()`.
+/// Apostrophes within words, as in comments, are left alone. Speak code with `Tts::speak_code()`, or add this with
+/// `Tts::add_filter()` to read everything as code.
+#[derive(Clone, Debug)]
+pub struct CodeFilter {
+    /// The programming language, such as `rust` or `python`, if known.
+    pub language: Option<String>,
+    /// Whether to read identifiers like `parseHTTPResponse` and `max_len` as separate words.
+    pub split_identifiers: bool,
+    pub indentation: Indentation,
+    /// Spaces per indentation level, or `None` to use the narrowest indentation in the text. A tab is always one
+    /// level.
+    pub indent_width: Option<usize>,
+    /// Symbols with their names, longest first.
+    symbols: Vec<(String, String)>,
+}
+
+impl Default for CodeFilter {
+    fn default() -> Self {
+        Self {
+            language: None,
+            split_identifiers: true,
+            indentation: Default::default(),
+            indent_width: None,
+            symbols: SYMBOLS
+                .iter()
+                .map(|&(symbol, name)| (symbol.into(), name.into()))
+                .collect(),
+        }
+    }
+}
+
+impl CodeFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Speaks `symbol`, which may be several characters long, as `name`.
+    pub fn set_symbol<S: Into<String>, N: Into<String>>(
+        &mut self,
+        symbol: S,
+        name: N,
+    ) -> &mut Self {
+        let symbol = symbol.into();
+        if symbol.is_empty() {
+            return self;
+        }
+        self.symbols.retain(|(s, _)| *s != symbol);
+        let i = self
+            .symbols
+            .partition_point(|(s, _)| s.len() >= symbol.len());
+        self.symbols.insert(i, (symbol, name.into()));
+        self
+    }
+
+    /// Leaves `symbol` for the engine to read, or not, as it usually would.
+    pub fn remove_symbol(&mut self, symbol: &str) -> &mut Self {
+        self.symbols.retain(|(s, _)| s != symbol);
+        self
+    }
+
+    /// Returns `text` as it should be spoken, treating it as code in `language` if given, or in `self.language`.
+    pub(crate) fn verbalize(&self, text: &str, language: Option<&str>) -> String {
+        let language = language.or(self.language.as_deref());
+        let indentation = match self.indentation {
+            Indentation::Auto
+                if language.is_some_and(|language| {
+                    SIGNIFICANT_INDENTATION
+                        .iter()
+                        .any(|l| l.eq_ignore_ascii_case(language))
+                }) =>
+            {
+                Indentation::Changes
+            }
+            Indentation::Auto => Indentation::Off,
+            indentation => indentation,
+        };
+        let width = self
+            .indent_width
+            .or_else(|| narrowest_indentation(text))
+            .unwrap_or(DEFAULT_INDENT_WIDTH)
+            .max(1);
+        let mut spoken = String::with_capacity(text.len() * 2);
+        let mut previous_level = 0;
+        for (i, line) in text.lines().enumerate() {
+            if i > 0 {
+                spoken.push('\n');
+            }
+            let content = line.trim_start();
+            if content.is_empty() {
+                continue;
+            }
+            let indent = &line[..line.len() - content.len()];
+            let level = indent
+                .chars()
+                .map(|c| if c == '\t' { width } else { 1 })
+                .sum::<usize>()
+                / width;
+            let announce = match indentation {
+                Indentation::Changes => level != previous_level,
+                Indentation::EveryLine => level > 0,
+                _ => false,
+            };
+            if announce {
+                push_word(&mut spoken, &format!("indent {level}"));
+            }
+            previous_level = level;
+            self.verbalize_line(&mut spoken, content);
+        }
+        spoken
+    }
+
+    fn verbalize_line(&self, spoken: &mut String, line: &str) {
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            if c.is_whitespace() {
+                rest = &rest[c.len_utf8()..];
+                continue;
+            }
+            if c.is_alphanumeric() || c == '_' {
+                let len = word_len(rest);
+                let word = &rest[..len];
+                if self.split_identifiers {
+                    self.push_identifier(spoken, word);
+                } else {
+                    push_word(spoken, word);
+                }
+                rest = &rest[len..];
+                continue;
+            }
+            let named = self
+                .symbols
+                .iter()
+                .find(|(symbol, _)| rest.starts_with(symbol.as_str()));
+            match named {
+                Some((symbol, name)) => {
+                    push_word(spoken, name);
+                    rest = &rest[symbol.len()..];
+                }
+                None => {
+                    push_word(spoken, &rest[..c.len_utf8()]);
+                    rest = &rest[c.len_utf8()..];
+                }
+            }
+        }
+    }
+
+    /// Adds `identifier` as separate words, naming underscores that don't separate words.
+    fn push_identifier(&self, spoken: &mut String, identifier: &str) {
+        let underscore = self
+            .symbols
+            .iter()
+            .find(|(symbol, _)| symbol == "_")
+            .map(|(_, name)| name.as_str());
+        let trimmed = identifier.trim_matches('_');
+        if trimmed.is_empty() {
+            for _ in 0..identifier.len() {
+                push_word(spoken, underscore.unwrap_or("_"));
+            }
+            return;
+        }
+        let leading = identifier.len() - identifier.trim_start_matches('_').len();
+        let trailing = identifier.len() - identifier.trim_end_matches('_').len();
+        for _ in 0..leading {
+            push_word(spoken, underscore.unwrap_or("_"));
+        }
+        for part in trimmed.split('_').filter(|part| !part.is_empty()) {
+            for word in split_words(part) {
+                push_word(spoken, word);
+            }
+        }
+        for _ in 0..trailing {
+            push_word(spoken, underscore.unwrap_or("_"));
+        }
+    }
+}
+
+impl TextFilter for CodeFilter {
+    fn filter(&self, text: &str, _language: Option<&LanguageTag<String>>) -> String {
+        self.verbalize(text, None)
+    }
+}
+
+/// Adds `word` to `spoken`, separated from what came before on the line.
+fn push_word(spoken: &mut String, word: &str) {
+    if !spoken.is_empty() && !spoken.ends_with(['\n', ' ']) {
+        spoken.push(' ');
+    }
+    spoken.push_str(word);
+}
+
+/// The length in bytes of the identifier, number or word at the start of `text`, including apostrophes within words,
+/// as in comments.
+fn word_len(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+    let mut previous = None;
+    while let Some((i, c)) = chars.next() {
+        let within_word = c == '\''
+            && previous.is_some_and(char::is_alphabetic)
+            && chars.peek().is_some_and(|&(_, next)| next.is_alphabetic());
+        if !(c.is_alphanumeric() || c == '_' || within_word) {
+            return i;
+        }
+        previous = Some(c);
+    }
+    text.len()
+}
+
+/// The fewest spaces any line is indented by, if any are.
+fn narrowest_indentation(text: &str) -> Option<usize> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches(' ').len())
+        .filter(|&spaces| spaces > 0)
+        .min()
+}
+
+/// Splits `part` of an identifier where its case changes, as in `parseHTTPResponse`, or where letters meet digits.
+fn split_words(part: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = part.char_indices().collect();
+    let mut words = vec![];
+    let mut start = 0;
+    for i in 1..chars.len() {
+        let (at, c) = chars[i];
+        let previous = chars[i - 1].1;
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let boundary = (previous.is_lowercase() && c.is_uppercase())
+            || (previous.is_uppercase()
+                && c.is_uppercase()
+                && next.is_some_and(char::is_lowercase))
+            || (previous.is_alphabetic() && c.is_numeric())
+            || (previous.is_numeric() && c.is_alphabetic());
+        if boundary {
+            words.push(&part[start..at]);
+            start = at;
+        }
+    }
+    words.push(&part[start..]);
+    words
+}
@@ -0,0 +1,117 @@
+//! Describes emoji and symbols, which most engines skip or read as codepoints.
+
+use crate::{LanguageTag, TextFilter};
+
+/// Symbols that aren't emoji but that engines commonly skip, sorted for binary search.
+const SYMBOLS: &[(char, &str)] = &[
+    ('§', "section"),
+    ('°', "degrees"),
+    ('±', "plus or minus"),
+    ('¶', "pilcrow"),
+    ('×', "times"),
+    ('÷', "divided by"),
+    ('†', "dagger"),
+    ('‡', "double dagger"),
+    ('•', "bullet"),
+    ('←', "left arrow"),
+    ('↑', "up arrow"),
+    ('→', "right arrow"),
+    ('↓', "down arrow"),
+    ('⇐', "left double arrow"),
+    ('⇒', "right double arrow"),
+    ('⇔', "left-right double arrow"),
+    ('√', "square root"),
+    ('∞', "infinity"),
+    ('≈', "approximately equal to"),
+    ('≠', "not equal to"),
+    ('≤', "less than or equal to"),
+    ('≥', "greater than or equal to"),
+    ('★', "star"),
+    ('☆', "white star"),
+    ('✓', "check mark"),
+    ('✗', "ballot x"),
+];
+
+/// The most characters in an emoji sequence this filter recognizes, such as a family joined with zero-width joiners.
+const MAX_SEQUENCE_LEN: usize = 10;
+
+/// A text filter that replaces emoji and common symbols with their descriptions, so "😀" is spoken as "grinning
+/// face" and "→" as "right arrow".
+///
+/// Descriptions are the English names from the Unicode Common Locale Data Repository. Enabled with the `emoji`
+/// feature. Add it with `Tts::add_filter()`.
+#[derive(Clone, Debug, Default)]
+pub struct EmojiFilter {
+    /// Whether to describe skin tones, as in "thumbs up, medium skin tone".
+    pub skin_tones: bool,
+}
+
+impl EmojiFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the description of the emoji or symbol at the start of `text`, and its length in bytes.
+    fn describe(&self, text: &str) -> Option<(String, usize)> {
+        let first = text.chars().next()?;
+        if let Ok(i) = SYMBOLS.binary_search_by_key(&first, |&(c, _)| c) {
+            return Some((SYMBOLS[i].1.to_string(), first.len_utf8()));
+        }
+        // Only keycaps start with ASCII, and they're at least two characters long.
+        let keycap = matches!(first, '#' | '*' | '0'..='9');
+        if first.is_ascii() && !keycap {
+            return None;
+        }
+        let ends: Vec<usize> = text
+            .char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .take(MAX_SEQUENCE_LEN)
+            .skip(keycap as usize)
+            .collect();
+        let (emoji, len) = ends
+            .into_iter()
+            .rev()
+            .find_map(|end| emojis::get(&text[..end]).map(|emoji| (emoji, end)))?;
+        let emoji = match emoji.skin_tone() {
+            Some(_) if !self.skin_tones => emoji
+                .with_skin_tone(emojis::SkinTone::Default)
+                .unwrap_or(emoji),
+            _ => emoji,
+        };
+        // Names such as "flag: Canada" and "keycap: 1" read better with a comma.
+        Some((emoji.name().replacen(':', ",", 1), len))
+    }
+}
+
+impl TextFilter for EmojiFilter {
+    fn filter(&self, text: &str, _language: Option<&LanguageTag<String>>) -> String {
+        let mut filtered = String::with_capacity(text.len());
+        let mut rest = text;
+        // Whether the last thing written was a description, which needs separating from what follows.
+        let mut described = false;
+        while let Some(c) = rest.chars().next() {
+            if let Some((description, len)) = self.describe(rest) {
+                if filtered
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| !c.is_whitespace())
+                {
+                    filtered.push(' ');
+                }
+                filtered.push_str(&description);
+                described = true;
+                rest = &rest[len..];
+            } else {
+                if described && !c.is_whitespace() && !c.is_ascii_punctuation() {
+                    filtered.push(' ');
+                }
+                filtered.push(c);
+                described = false;
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        filtered
+    }
+}
@@ -0,0 +1,560 @@
+//! Turns markup into speakable text, verbalizing structure such as headings and lists.
+
+use crate::{LanguageTag, TextFilter};
+
+/// HTML elements whose content isn't read.
+const HIDDEN: &[&str] = &["head", "noscript", "script", "style", "template", "title"];
+
+/// HTML elements that start a new line of speech.
+const BLOCKS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "br",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "main",
+    "nav",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tr",
+];
+
+/// Inline HTML elements read apart from the text around them. Others, such as `b` and `span`, can split words, so
+/// they're read as part of the text.
+const SEPARATED: &[&str] = &[
+    "a", "button", "input", "label", "option", "select", "textarea",
+];
+
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("apos", "'"),
+    ("copy", "©"),
+    ("gt", ">"),
+    ("hellip", "…"),
+    ("ldquo", "“"),
+    ("lsquo", "‘"),
+    ("lt", "<"),
+    ("mdash", "—"),
+    ("nbsp", " "),
+    ("ndash", "–"),
+    ("quot", "\""),
+    ("rdquo", "”"),
+    ("reg", "®"),
+    ("rsquo", "’"),
+    ("trade", "™"),
+];
+
+/// Builds speakable text, a line per block.
+#[derive(Default)]
+struct Writer {
+    text: String,
+    /// The next number of each open list, or `None` if it's unordered.
+    lists: Vec<Option<u64>>,
+    /// Whether whitespace was skipped since the last text.
+    space: bool,
+    /// Whether the next text is a new item on the same line, as after an image.
+    pause: bool,
+    /// Whether the current table cell is the first in its row.
+    first_cell: bool,
+}
+
+impl Writer {
+    /// Adds text, collapsing whitespace.
+    fn text(&mut self, text: &str) {
+        for c in text.chars() {
+            if c.is_whitespace() {
+                self.space = true;
+                continue;
+            }
+            if self.pause && !self.text.is_empty() && !self.text.ends_with('\n') {
+                self.text.truncate(self.text.trim_end_matches(' ').len());
+                self.text.push_str(", ");
+            } else if self.space && !self.text.is_empty() && !self.text.ends_with(['\n', ' ']) {
+                self.text.push(' ');
+            }
+            self.space = false;
+            self.pause = false;
+            self.text.push(c);
+        }
+    }
+
+    /// Adds preformatted text, keeping its lines.
+    fn preformatted(&mut self, text: &str) {
+        for line in text.lines() {
+            self.text(line);
+            self.block();
+        }
+    }
+
+    /// Ends the current line, as a sentence so the engine pauses.
+    fn block(&mut self) {
+        self.text.truncate(self.text.trim_end_matches(' ').len());
+        if self.text.ends_with(char::is_alphanumeric) {
+            self.text.push('.');
+        }
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.space = false;
+        self.pause = false;
+    }
+
+    /// Keeps what comes next from running into the text before it, as at the edges of a link.
+    fn separate(&mut self) {
+        self.space = true;
+    }
+
+    fn heading(&mut self, level: usize) {
+        self.block();
+        self.text(&format!("Heading level {level}, "));
+    }
+
+    fn start_list(&mut self, start: Option<u64>) {
+        self.block();
+        self.text("List.");
+        self.block();
+        self.lists.push(start);
+    }
+
+    fn end_list(&mut self) {
+        self.block();
+        self.lists.pop();
+        self.text("End of list.");
+        self.block();
+    }
+
+    fn item(&mut self) {
+        self.block();
+        if let Some(Some(number)) = self.lists.last_mut() {
+            let label = format!("{number}. ");
+            *number += 1;
+            self.text(&label);
+        }
+    }
+
+    fn image(&mut self, alt: &str) {
+        if !alt.trim().is_empty() {
+            self.separate();
+            self.text("image, ");
+            self.text(alt);
+            self.pause = true;
+        }
+    }
+
+    fn cell(&mut self) {
+        if !std::mem::replace(&mut self.first_cell, false) {
+            self.text.truncate(self.text.trim_end_matches(' ').len());
+            self.text.push_str(", ");
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.block();
+        self.text.truncate(self.text.trim_end().len());
+        self.text
+    }
+}
+
+/// Decodes character references such as `&amp;` and `&#8212;`, leaving unknown ones alone.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        decoded.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let reference = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| &rest[1..end + 1]);
+        let c = reference.and_then(|reference| {
+            if let Some(hex) = reference
+                .strip_prefix("#x")
+                .or_else(|| reference.strip_prefix("#X"))
+            {
+                u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            } else if let Some(decimal) = reference.strip_prefix('#') {
+                decimal.parse().ok().and_then(char::from_u32)
+            } else {
+                ENTITIES
+                    .iter()
+                    .find(|(name, _)| *name == reference)
+                    .and_then(|(_, c)| c.chars().next())
+            }
+        });
+        match (reference, c) {
+            (Some(reference), Some(c)) => {
+                decoded.push(c);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// An HTML tag, as its lowercase name, whether it closes an element, and its attributes.
+struct Tag<'a> {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, &'a str)>,
+}
+
+impl Tag<'_> {
+    fn attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| decode_entities(value))
+    }
+}
+
+/// Parses the tag at the start of `html`, returning it and its length, or `None` if it isn't one.
+fn tag(html: &str) -> Option<(Tag<'_>, usize)> {
+    let rest = html.strip_prefix('<')?;
+    let (closing, rest) = match rest.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let name_len = rest
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(rest.len());
+    if name_len == 0 || !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = rest[..name_len].to_ascii_lowercase();
+    let mut rest = &rest[name_len..];
+    let mut attributes = vec![];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if let Some(after) = rest.strip_prefix('>') {
+            return Some((
+                Tag {
+                    name,
+                    closing,
+                    attributes,
+                },
+                html.len() - after.len(),
+            ));
+        }
+        let attribute_len =
+            rest.find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))?;
+        if attribute_len == 0 {
+            return None;
+        }
+        let attribute = rest[..attribute_len].to_ascii_lowercase();
+        rest = rest[attribute_len..].trim_start();
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, after) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = after[1..].find(quote)?;
+                    (&after[1..end + 1], &after[end + 2..])
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = after;
+            value
+        } else {
+            ""
+        };
+        attributes.push((attribute, value));
+    }
+}
+
+/// Reads HTML into `writer`.
+fn read_html(html: &str, writer: &mut Writer) {
+    let mut rest = html;
+    let mut preformatted = 0_usize;
+    while !rest.is_empty() {
+        let text_len = rest.find('<').unwrap_or(rest.len());
+        if text_len > 0 {
+            let text = decode_entities(&rest[..text_len]);
+            if preformatted > 0 {
+                writer.preformatted(&text);
+            } else {
+                writer.text(&text);
+            }
+            rest = &rest[text_len..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+        let Some((tag, len)) = tag(rest) else {
+            writer.text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[len..];
+        let name = tag.name.as_str();
+        if !tag.closing && HIDDEN.contains(&name) {
+            let end = format!("</{name}");
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&end)
+                .and_then(|start| rest[start..].find('>').map(|end| &rest[start + end + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+        match (name, tag.closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                writer.heading(name[1..].parse().unwrap_or(1));
+            }
+            ("ul" | "menu", false) => writer.start_list(None),
+            ("ol", false) => {
+                let start = tag.attribute("start").and_then(|s| s.parse().ok());
+                writer.start_list(Some(start.unwrap_or(1)));
+            }
+            ("ul" | "ol" | "menu", true) => writer.end_list(),
+            ("li", false) => writer.item(),
+            ("img", false) => writer.image(&tag.attribute("alt").unwrap_or_default()),
+            ("blockquote", false) => {
+                writer.block();
+                writer.text("Quote.");
+                writer.block();
+            }
+            ("blockquote", true) => {
+                writer.block();
+                writer.text("End of quote.");
+                writer.block();
+            }
+            ("tr", _) => {
+                writer.block();
+                writer.first_cell = true;
+            }
+            ("td" | "th", false) => writer.cell(),
+            ("pre", closing) => {
+                writer.block();
+                preformatted = if closing {
+                    preformatted.saturating_sub(1)
+                } else {
+                    preformatted + 1
+                };
+            }
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => writer.block(),
+            (name, _) if BLOCKS.contains(&name) => writer.block(),
+            (name, _) if SEPARATED.contains(&name) => writer.separate(),
+            _ => {}
+        }
+    }
+}
+
+/// A text filter that reads HTML, skipping tags and scripts, announcing headings, lists and quotes, and reading the
+/// alternative text of images. Images, links and form controls are kept apart from the text around them.
+///
+/// Add it with `Tts::add_filter()`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HtmlFilter;
+
+impl HtmlFilter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl TextFilter for HtmlFilter {
+    fn filter(&self, text: &str, _language: Option<&LanguageTag<String>>) -> String {
+        let mut writer = Writer::default();
+        read_html(text, &mut writer);
+        writer.finish()
+    }
+}
+
+/// A text filter that reads Markdown, including any HTML in it, announcing headings, lists, quotes and code blocks,
+/// and reading link labels and the alternative text of images.
+///
+/// Enabled with the `markdown` feature. Add it with `Tts::add_filter()`.
+#[cfg(feature = "markdown")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownFilter;
+
+#[cfg(feature = "markdown")]
+impl MarkdownFilter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(feature = "markdown")]
+impl TextFilter for MarkdownFilter {
+    fn filter(&self, text: &str, _language: Option<&LanguageTag<String>>) -> String {
+        use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+        let mut writer = Writer::default();
+        let mut code_block = false;
+        // The alternative text of an image is its content, which is read as a whole.
+        let mut alt: Option<String> = None;
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_FOOTNOTES;
+        for event in Parser::new_ext(text, options) {
+            if let Some(alt) = alt.as_mut() {
+                match event {
+                    Event::End(TagEnd::Image) => {}
+                    Event::Text(text) | Event::Code(text) => {
+                        alt.push_str(&text);
+                        continue;
+                    }
+                    _ => continue,
+                }
+            }
+            match event {
+                Event::Start(Tag::Heading { level, .. }) => writer.heading(level as usize),
+                Event::Start(Tag::List(start)) => writer.start_list(start),
+                Event::End(TagEnd::List(_)) => writer.end_list(),
+                Event::Start(Tag::Item) => writer.item(),
+                Event::Start(Tag::BlockQuote(_)) => {
+                    writer.block();
+                    writer.text("Quote.");
+                    writer.block();
+                }
+                Event::End(TagEnd::BlockQuote(_)) => {
+                    writer.block();
+                    writer.text("End of quote.");
+                    writer.block();
+                }
+                Event::Start(Tag::CodeBlock(_)) => {
+                    writer.block();
+                    writer.text("Code.");
+                    writer.block();
+                    code_block = true;
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    writer.block();
+                    writer.text("End of code.");
+                    writer.block();
+                    code_block = false;
+                }
+                Event::Start(Tag::Link { .. }) | Event::End(TagEnd::Link) => writer.separate(),
+                Event::Start(Tag::Image { .. }) => alt = Some(String::new()),
+                Event::End(TagEnd::Image) => writer.image(&alt.take().unwrap_or_default()),
+                Event::Start(Tag::TableRow | Tag::TableHead) => {
+                    writer.block();
+                    writer.first_cell = true;
+                }
+                Event::Start(Tag::TableCell) => writer.cell(),
+                Event::Start(Tag::Paragraph)
+                | Event::End(
+                    TagEnd::Paragraph
+                    | TagEnd::Heading(_)
+                    | TagEnd::Item
+                    | TagEnd::TableHead
+                    | TagEnd::TableRow
+                    | TagEnd::Table,
+                ) => writer.block(),
+                Event::Text(text) if code_block => writer.preformatted(&text),
+                Event::Text(text) | Event::Code(text) => writer.text(&text),
+                Event::Html(html) | Event::InlineHtml(html) => read_html(&html, &mut writer),
+                Event::SoftBreak => writer.text(" "),
+                Event::HardBreak | Event::Rule => writer.block(),
+                Event::TaskListMarker(checked) => writer.text(if checked {
+                    "checked, "
+                } else {
+                    "not checked, "
+                }),
+                _ => {}
+            }
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_is_read_as_text() {
+        for (html, expected) in [
+            (
+                "<img alt=\"cat\"><a href=\"/\">link</a>",
+                "image, cat, link.",
+            ),
+            ("See <img alt=\"a cat\"> here", "See image, a cat, here."),
+            ("<img src=\"spacer.gif\" alt=\"\">Hi", "Hi."),
+            ("<a href=\"/a\">one</a><a href=\"/b\">two</a>", "one two."),
+            ("<button>OK</button><button>Cancel</button>", "OK Cancel."),
+            ("un<b>believ</b>able", "unbelievable."),
+            (
+                "<h2>Title</h2><p>Fish &amp; chips</p>",
+                "Heading level 2, Title.\nFish & chips.",
+            ),
+            (
+                "<ul><li>a</li><li>b</li></ul>",
+                "List.\na.\nb.\nEnd of list.",
+            ),
+            (
+                "<ol start=\"3\"><li>x</li><li>y</li></ol>",
+                "List.\n3. x.\n4. y.\nEnd of list.",
+            ),
+            (
+                "<table><tr><th>A</th><th>B</th></tr><tr><td>1</td><td>2</td></tr></table>",
+                "A, B.\n1, 2.",
+            ),
+            (
+                "<blockquote>Quoted</blockquote>",
+                "Quote.\nQuoted.\nEnd of quote.",
+            ),
+            (
+                "<head><title>T</title></head><script>x()</script><!-- c -->Body",
+                "Body.",
+            ),
+            ("<pre>a\n  b</pre>", "a.\nb."),
+            ("a < b &#8212; &unknown; &#x41;", "a < b — &unknown; A."),
+        ] {
+            assert_eq!(HtmlFilter.filter(html, None), expected, "{html}");
+        }
+    }
+
+    #[cfg(feature = "markdown")]
+    #[test]
+    fn markdown_is_read_as_text() {
+        for (markdown, expected) in [
+            ("![cat](cat.png)[link](/)", "image, cat, link."),
+            ("[one](/a)[two](/b)", "one two."),
+            (
+                "# Title\n\nSome *text*.",
+                "Heading level 1, Title.\nSome text.",
+            ),
+            ("- a\n- b", "List.\na.\nb.\nEnd of list."),
+            ("3. x\n4. y", "List.\n3. x.\n4. y.\nEnd of list."),
+            ("- [x] done", "List.\nchecked, done.\nEnd of list."),
+            ("> Quoted", "Quote.\nQuoted.\nEnd of quote."),
+            ("```\nlet x;\n```", "Code.\nlet x;\nEnd of code."),
+            ("| A | B |\n|---|---|\n| 1 | 2 |", "A, B.\n1, 2."),
+            ("Text <b>bold</b>", "Text bold."),
+        ] {
+            assert_eq!(
+                MarkdownFilter.filter(markdown, None),
+                expected,
+                "{markdown}"
+            );
+        }
+    }
+}
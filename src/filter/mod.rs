@@ -0,0 +1,39 @@
+//! Preprocessing applied to text before it reaches the backend.
+
+use crate::{LanguageTag, Lexicon};
+
+mod code;
+#[cfg(feature = "emoji")]
+mod emoji;
+mod markup;
+mod numbers;
+
+pub use self::code::*;
+#[cfg(feature = "emoji")]
+pub use self::emoji::*;
+pub use self::markup::*;
+pub use self::numbers::*;
+
+/// A preprocessing step run on text before it's spoken or synthesized, such as expanding abbreviations or masking
+/// profanity.
+///
+/// Add filters with `Tts::add_filter()`. Closures taking the text and its language implement this trait.
+pub trait TextFilter: Send + Sync {
+    /// Returns `text` as it should be spoken. `language` is the language of the utterance or current voice, if known.
+    fn filter(&self, text: &str, language: Option<&LanguageTag<String>>) -> String;
+}
+
+impl<F> TextFilter for F
+where
+    F: Fn(&str, Option<&LanguageTag<String>>) -> String + Send + Sync,
+{
+    fn filter(&self, text: &str, language: Option<&LanguageTag<String>>) -> String {
+        self(text, language)
+    }
+}
+
+impl TextFilter for Lexicon {
+    fn filter(&self, text: &str, _language: Option<&LanguageTag<String>>) -> String {
+        self.apply(text)
+    }
+}
@@ -0,0 +1,705 @@
+//! Expands numbers, dates, times, currency and units into words, since engines read them inconsistently.
+
+use crate::{LanguageTag, TextFilter};
+
+const ONES: [&str; 20] = [
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+const SCALES: [&str; 7] = [
+    "",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+    "quadrillion",
+    "quintillion",
+];
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// The most days in each month, allowing for leap years.
+const MONTH_DAYS: [u64; 12] = [31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+
+/// Singular and plural names.
+type Name = (&'static str, &'static str);
+
+/// Currency symbols, with the names of the unit and subunit.
+const CURRENCIES: &[(char, Name, Option<Name>)] = &[
+    ('$', ("dollar", "dollars"), Some(("cent", "cents"))),
+    ('£', ("pound", "pounds"), Some(("penny", "pence"))),
+    ('¥', ("yen", "yen"), None),
+    ('€', ("euro", "euros"), Some(("cent", "cents"))),
+];
+
+/// Unit symbols with their singular and plural names. Where one symbol starts another, the longer comes first.
+const UNITS: &[(&str, &str, &str)] = &[
+    ("%", "percent", "percent"),
+    ("°C", "degree Celsius", "degrees Celsius"),
+    ("°F", "degree Fahrenheit", "degrees Fahrenheit"),
+    ("°", "degree", "degrees"),
+    ("km/h", "kilometer per hour", "kilometers per hour"),
+    ("km", "kilometer", "kilometers"),
+    ("kph", "kilometer per hour", "kilometers per hour"),
+    ("mph", "mile per hour", "miles per hour"),
+    ("cm", "centimeter", "centimeters"),
+    ("mm", "millimeter", "millimeters"),
+    ("mi", "mile", "miles"),
+    ("ms", "millisecond", "milliseconds"),
+    ("m", "meter", "meters"),
+    ("ft", "foot", "feet"),
+    ("kg", "kilogram", "kilograms"),
+    ("mg", "milligram", "milligrams"),
+    ("g", "gram", "grams"),
+    ("lbs", "pound", "pounds"),
+    ("lb", "pound", "pounds"),
+    ("oz", "ounce", "ounces"),
+    ("ml", "milliliter", "milliliters"),
+    ("L", "liter", "liters"),
+    ("kB", "kilobyte", "kilobytes"),
+    ("KB", "kilobyte", "kilobytes"),
+    ("MB", "megabyte", "megabytes"),
+    ("GB", "gigabyte", "gigabytes"),
+    ("TB", "terabyte", "terabytes"),
+    ("Hz", "hertz", "hertz"),
+    ("kHz", "kilohertz", "kilohertz"),
+    ("MHz", "megahertz", "megahertz"),
+    ("GHz", "gigahertz", "gigahertz"),
+    ("kW", "kilowatt", "kilowatts"),
+    ("W", "watt", "watts"),
+    ("V", "volt", "volts"),
+    ("mAh", "milliamp hour", "milliamp hours"),
+];
+
+/// A text filter that expands numbers, ordinals, dates, times, currency and units into words, so "3/4/2024" is
+/// spoken as "March fourth, twenty twenty-four" and "$5.99" as "five dollars and ninety-nine cents".
+///
+/// Only English is supported so far, and text in other languages is left alone. Dates are read month first in the
+/// United States, or if no region is given, and day first elsewhere. In amounts of money and measurements, a comma
+/// before other than three digits, as in "€5,50" or "3,5 km", is read as a decimal point. Text shaped like a date or
+/// time that isn't one, such as "13/13/2024" or "25:99", and dotted versions like "1.2.3", are left as they are. Add
+/// it with `Tts::add_filter()`.
+#[derive(Clone, Debug, Default)]
+pub struct NumberFilter {
+    /// The language to read numbers in, overriding that of the utterance or voice.
+    pub language: Option<LanguageTag<String>>,
+}
+
+impl NumberFilter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl TextFilter for NumberFilter {
+    fn filter(&self, text: &str, language: Option<&LanguageTag<String>>) -> String {
+        let language = self.language.as_ref().or(language);
+        let month_first = match language {
+            Some(language) if !language.primary_language().eq_ignore_ascii_case("en") => {
+                return text.to_string();
+            }
+            Some(language) => language
+                .region()
+                .is_none_or(|region| region.eq_ignore_ascii_case("US")),
+            None => true,
+        };
+        let mut filtered = String::with_capacity(text.len());
+        let mut rest = text;
+        let mut previous: Option<char> = None;
+        while let Some(c) = rest.chars().next() {
+            let len = if previous.is_some_and(char::is_alphanumeric) {
+                // Digits within words, like "mp3" or "v1.2", are left alone.
+                None
+            } else {
+                verbalize(rest, month_first).map(|(words, len)| {
+                    filtered.push_str(&words);
+                    len
+                })
+            };
+            let len = len.unwrap_or_else(|| {
+                let len = if c.is_alphanumeric() {
+                    token_len(rest)
+                } else {
+                    c.len_utf8()
+                };
+                filtered.push_str(&rest[..len]);
+                len
+            });
+            previous = rest[..len].chars().next_back();
+            rest = &rest[len..];
+        }
+        filtered
+    }
+}
+
+/// Returns the length of the word at the start of `text`, including punctuation between its letters and digits.
+fn token_len(text: &str) -> usize {
+    let mut len = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let joins = matches!(c, '.' | ',' | ':' | '/' | '-')
+            && chars.peek().is_some_and(|(_, c)| c.is_alphanumeric());
+        if !c.is_alphanumeric() && !joins {
+            break;
+        }
+        len = i + c.len_utf8();
+    }
+    len
+}
+
+/// Returns the length of the run of ASCII digits at the start of `text`.
+fn digits_len(text: &str) -> usize {
+    text.find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len())
+}
+
+/// Whether `text` starts with `separator` followed by a digit.
+fn continues(text: &str, separator: char) -> bool {
+    text.strip_prefix(separator)
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Whether `text` doesn't continue the word before it.
+fn at_boundary(text: &str) -> bool {
+    !text.starts_with(char::is_alphanumeric)
+}
+
+/// Returns the words for the number, date, time, currency or measurement at the start of `text`, and its length in
+/// bytes.
+fn verbalize(text: &str, month_first: bool) -> Option<(String, usize)> {
+    currency(text)
+        .or_else(|| date(text, month_first))
+        .or_else(|| time(text))
+        .or_else(|| digit_groups(text))
+        .or_else(|| measurement(text))
+}
+
+/// A number with optional thousands separators and decimal part, as its integer digits, decimal digits and length.
+struct Number<'a> {
+    negative: bool,
+    integer: String,
+    decimals: Option<&'a str>,
+    len: usize,
+    grouped: bool,
+}
+
+impl Number<'_> {
+    fn words(&self) -> String {
+        let mut words = if self.negative {
+            "minus ".to_string()
+        } else {
+            String::new()
+        };
+        words.push_str(&integer(&self.integer));
+        if let Some(decimals) = self.decimals {
+            words.push_str(" point ");
+            words.push_str(&digits(decimals));
+        }
+        words
+    }
+
+    fn is_one(&self) -> bool {
+        !self.negative && self.integer == "1" && self.decimals.is_none()
+    }
+}
+
+/// Parses the number at the start of `text`, taking a comma before other than three digits as a decimal comma if
+/// `decimal_comma` is set.
+fn number(text: &str, decimal_comma: bool) -> Option<Number<'_>> {
+    let negative = text.starts_with('-');
+    let start = negative as usize;
+    let len = digits_len(&text[start..]);
+    if len == 0 {
+        return None;
+    }
+    let mut integer = text[start..start + len].to_string();
+    let mut end = start + len;
+    let mut grouped = false;
+    // Thousands separators only count between groups of three.
+    if len <= 3 {
+        while text[end..].starts_with(',') && digits_len(&text[end + 1..]) == 3 {
+            integer.push_str(&text[end + 1..end + 4]);
+            end += 4;
+            grouped = true;
+        }
+    }
+    let mut decimals = None;
+    if continues(&text[end..], '.') {
+        let len = digits_len(&text[end + 1..]);
+        decimals = Some(&text[end + 1..end + 1 + len]);
+        end += 1 + len;
+        // Dotted versions and addresses, like 1.2.3, aren't numbers.
+        if continues(&text[end..], '.') {
+            return None;
+        }
+    } else if decimal_comma && !grouped && continues(&text[end..], ',') {
+        // Unless it's a list, as in 1,2,3.
+        let len = digits_len(&text[end + 1..]);
+        let rest = &text[end + 1 + len..];
+        if len != 3 && !continues(rest, ',') && !continues(rest, '.') {
+            decimals = Some(&text[end + 1..end + 1 + len]);
+            end += 1 + len;
+        }
+    }
+    Some(Number {
+        negative,
+        integer,
+        decimals,
+        len: end,
+        grouped,
+    })
+}
+
+fn currency(text: &str) -> Option<(String, usize)> {
+    let symbol = text.chars().next()?;
+    let &(_, unit, subunit) = CURRENCIES.iter().find(|(c, _, _)| *c == symbol)?;
+    let start = symbol.len_utf8();
+    let amount = number(&text[start..], true).filter(|n| !n.negative)?;
+    let len = start + amount.len;
+    if !at_boundary(&text[len..]) {
+        return None;
+    }
+    let name = |(one, many): Name, is_one: bool| {
+        if is_one {
+            one
+        } else {
+            many
+        }
+    };
+    let words = match (amount.decimals, subunit) {
+        (Some(decimals), Some(subunit)) if decimals.len() <= 2 => {
+            let cents: u64 = format!("{decimals:0<2}").parse().ok()?;
+            let mut words = format!(
+                "{} {}",
+                integer(&amount.integer),
+                name(unit, amount.integer == "1")
+            );
+            if cents > 0 {
+                words.push_str(&format!(
+                    " and {} {}",
+                    cardinal(cents),
+                    name(subunit, cents == 1)
+                ));
+            }
+            words
+        }
+        _ => format!("{} {}", amount.words(), name(unit, amount.is_one())),
+    };
+    Some((words, len))
+}
+
+fn date(text: &str, month_first: bool) -> Option<(String, usize)> {
+    let field = |text: &str, min: usize, max: usize| -> Option<(u64, usize)> {
+        let len = digits_len(text);
+        if len < min || len > max {
+            return None;
+        }
+        Some((text[..len].parse().ok()?, len))
+    };
+    let (month, day, year, year_digits, len) = if let Some((year, 4)) = field(text, 4, 4) {
+        // ISO 8601, as in 2024-03-04.
+        let rest = text[4..].strip_prefix('-')?;
+        let (month, month_len) = field(rest, 2, 2)?;
+        let (day, day_len) = field(rest[month_len..].strip_prefix('-')?, 2, 2)?;
+        (month, day, year, 4, 4 + 1 + month_len + 1 + day_len)
+    } else {
+        let (first, first_len) = field(text, 1, 2)?;
+        let rest = text[first_len..].strip_prefix('/')?;
+        let (second, second_len) = field(rest, 1, 2)?;
+        let rest = rest[second_len..].strip_prefix('/')?;
+        let (year, year_len) = field(rest, 2, 4).filter(|&(_, len)| len != 3)?;
+        let (month, day) = if month_first {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        (
+            month,
+            day,
+            year,
+            year_len,
+            first_len + second_len + year_len + 2,
+        )
+    };
+    if !at_boundary(&text[len..]) {
+        return None;
+    }
+    if !(1..=12).contains(&month) || !(1..=MONTH_DAYS[month as usize - 1]).contains(&day) {
+        // Shaped like a date but not one, as in 13/13/2024, so left alone.
+        return Some((text[..len].to_string(), len));
+    }
+    let month = MONTHS[month as usize - 1];
+    let year = if year_digits == 4 {
+        self::year(year)
+    } else {
+        two_digits(year)
+    };
+    let words = if month_first {
+        format!("{month} {}, {year}", ordinal(day))
+    } else {
+        format!("the {} of {month}, {year}", ordinal(day))
+    };
+    Some((words, len))
+}
+
+fn time(text: &str) -> Option<(String, usize)> {
+    let hour_len = digits_len(text);
+    if hour_len == 0 || !continues(&text[hour_len..], ':') {
+        return None;
+    }
+    let hour = text[..hour_len].parse().unwrap_or(u64::MAX);
+    let mut fields = vec![hour];
+    let mut len = hour_len;
+    let mut valid = hour_len <= 2 && hour <= 23;
+    while continues(&text[len..], ':') {
+        let field_len = digits_len(&text[len + 1..]);
+        let field = text[len + 1..len + 1 + field_len]
+            .parse()
+            .unwrap_or(u64::MAX);
+        valid &= field_len == 2 && field <= 59;
+        fields.push(field);
+        len += 1 + field_len;
+    }
+    valid &= fields.len() <= 3;
+    if !valid {
+        // Shaped like a time but not one, as in 25:99 or 12:5, so left alone.
+        return at_boundary(&text[len..]).then(|| (text[..len].to_string(), len));
+    }
+    let suffix = [" am", " pm", " a.m.", " p.m.", "am", "pm", "a.m.", "p.m."]
+        .into_iter()
+        .find(|suffix| {
+            let rest = &text[len..];
+            rest.get(..suffix.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(suffix))
+                && (suffix.ends_with('.') || at_boundary(&rest[suffix.len()..]))
+        });
+    let period = suffix.map(|suffix| {
+        len += suffix.len();
+        if suffix.trim_start().starts_with(['a', 'A']) {
+            "a m"
+        } else {
+            "p m"
+        }
+    });
+    if !at_boundary(&text[len..]) {
+        return None;
+    }
+    let mut words = cardinal(hour);
+    match (fields[1], period) {
+        (0, Some(_)) => {}
+        (0, None) => words.push_str(" o'clock"),
+        (minute, _) => {
+            words.push(' ');
+            words.push_str(&two_digits(minute));
+        }
+    }
+    if let Some(period) = period {
+        words.push(' ');
+        words.push_str(period);
+    }
+    if let Some(&second) = fields.get(2) {
+        let unit = if second == 1 { "second" } else { "seconds" };
+        words.push_str(&format!(" and {} {unit}", cardinal(second)));
+    }
+    Some((words, len))
+}
+
+/// Reads digit groups joined by hyphens, such as ranges and phone numbers.
+fn digit_groups(text: &str) -> Option<(String, usize)> {
+    let mut groups = vec![];
+    let mut len = 0;
+    loop {
+        let group = digits_len(&text[len..]);
+        if group == 0 {
+            return None;
+        }
+        groups.push(&text[len..len + group]);
+        len += group;
+        if !text[len..].starts_with('-') || digits_len(&text[len + 1..]) == 0 {
+            break;
+        }
+        len += 1;
+    }
+    if groups.len() < 2 || !at_boundary(&text[len..]) {
+        return None;
+    }
+    let total: usize = groups.iter().map(|g| g.len()).sum();
+    let words = if groups.len() == 2 && total < 7 {
+        format!("{} to {}", integer(groups[0]), integer(groups[1]))
+    } else {
+        groups
+            .into_iter()
+            .map(digits)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Some((words, len))
+}
+
+/// Reads a number, optionally as an ordinal or followed by a unit.
+fn measurement(text: &str) -> Option<(String, usize)> {
+    // A decimal comma only counts before a unit, as in 3,5 km, since elsewhere it may separate a list.
+    if let Some(measured) = number(text, true).and_then(|number| with_unit(text, &number)) {
+        return Some(measured);
+    }
+    let number = number(text, false)?;
+    let rest = &text[number.len..];
+    if number.decimals.is_none() && !number.negative {
+        if let Some(suffix) = ["st", "nd", "rd", "th"].into_iter().find(|suffix| {
+            rest.get(..2)
+                .is_some_and(|s| s.eq_ignore_ascii_case(suffix))
+                && at_boundary(&rest[2..])
+        }) {
+            let value: u64 = number.integer.parse().ok()?;
+            return Some((ordinal(value), number.len + suffix.len()));
+        }
+    }
+    if let Some(measured) = with_unit(text, &number) {
+        return Some(measured);
+    }
+    if !at_boundary(rest) {
+        return None;
+    }
+    let is_year = !number.negative
+        && !number.grouped
+        && number.decimals.is_none()
+        && number.integer.len() == 4
+        && matches!(number.integer.parse::<u64>(), Ok(1100..=1999 | 2010..=2099));
+    let words = if is_year {
+        year(number.integer.parse().ok()?)
+    } else {
+        number.words()
+    };
+    Some((words, number.len))
+}
+
+/// Reads `number`, at the start of `text`, with the unit after it, if there is one.
+fn with_unit(text: &str, number: &Number) -> Option<(String, usize)> {
+    let rest = &text[number.len..];
+    let spaced = rest.strip_prefix(' ').unwrap_or(rest);
+    let &(symbol, one, many) = UNITS.iter().find(|(symbol, _, _)| {
+        spaced.starts_with(symbol) && at_boundary(&spaced[symbol.len()..])
+    })?;
+    let len = text.len() - spaced.len() + symbol.len();
+    let unit = if number.is_one() { one } else { many };
+    Some((format!("{} {unit}", number.words()), len))
+}
+
+/// Reads each digit.
+fn digits(digits: &str) -> String {
+    digits
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|d| ONES[d as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reads an integer, or its digits if it has leading zeros or is too long.
+fn integer(integer: &str) -> String {
+    match integer.parse() {
+        Ok(n) if integer.len() == 1 || !integer.starts_with('0') => cardinal(n),
+        _ => digits(integer),
+    }
+}
+
+fn below_thousand(n: u64) -> String {
+    let (hundreds, rest) = (n / 100, n % 100);
+    let rest = match rest {
+        0 => String::new(),
+        1..=19 => ONES[rest as usize].to_string(),
+        _ if rest % 10 == 0 => TENS[rest as usize / 10].to_string(),
+        _ => format!("{}-{}", TENS[rest as usize / 10], ONES[rest as usize % 10]),
+    };
+    match (hundreds, rest.is_empty()) {
+        (0, _) => rest,
+        (_, true) => format!("{} hundred", ONES[hundreds as usize]),
+        (_, false) => format!("{} hundred {rest}", ONES[hundreds as usize]),
+    }
+}
+
+fn cardinal(n: u64) -> String {
+    if n == 0 {
+        return ONES[0].to_string();
+    }
+    let mut groups = vec![];
+    let mut n = n;
+    let mut scale = 0;
+    while n > 0 {
+        let group = n % 1000;
+        if group > 0 {
+            let words = below_thousand(group);
+            groups.push(match SCALES[scale] {
+                "" => words,
+                scale => format!("{words} {scale}"),
+            });
+        }
+        n /= 1000;
+        scale += 1;
+    }
+    groups.reverse();
+    groups.join(" ")
+}
+
+fn ordinal(n: u64) -> String {
+    let cardinal = cardinal(n);
+    let irregular = [
+        ("one", "first"),
+        ("two", "second"),
+        ("three", "third"),
+        ("five", "fifth"),
+        ("eight", "eighth"),
+        ("nine", "ninth"),
+        ("twelve", "twelfth"),
+    ];
+    for (word, ordinal) in irregular {
+        if let Some(prefix) = cardinal.strip_suffix(word) {
+            return format!("{prefix}{ordinal}");
+        }
+    }
+    match cardinal.strip_suffix('y') {
+        Some(prefix) => format!("{prefix}ieth"),
+        None => format!("{cardinal}th"),
+    }
+}
+
+/// Reads two digits as in years and times, as in "oh five" or "forty-two".
+fn two_digits(n: u64) -> String {
+    if n < 10 {
+        format!("oh {}", ONES[n as usize])
+    } else {
+        cardinal(n)
+    }
+}
+
+/// Reads a year, as in "nineteen ninety-nine", "nineteen hundred" or "two thousand five".
+fn year(n: u64) -> String {
+    let (century, rest) = (n / 100, n % 100);
+    if !(10..100).contains(&century) || (n % 1000 < 10 && century % 10 == 0) {
+        cardinal(n)
+    } else if rest == 0 {
+        format!("{} hundred", cardinal(century))
+    } else {
+        format!("{} {}", cardinal(century), two_digits(rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(text: &str, language: Option<&str>) -> String {
+        let language = language.map(|tag| LanguageTag::parse(tag.to_string()).unwrap());
+        NumberFilter::new().filter(text, language.as_ref())
+    }
+
+    #[test]
+    fn expands_numbers_dates_times_currency_and_units() {
+        for (text, expected) in [
+            ("3/4/2024", "March fourth, twenty twenty-four"),
+            ("2024-03-04", "March fourth, twenty twenty-four"),
+            ("on 12/25/99.", "on December twenty-fifth, ninety-nine."),
+            ("12:05", "twelve oh five"),
+            ("9:00", "nine o'clock"),
+            ("7:30 pm", "seven thirty p m"),
+            ("12:30:05", "twelve thirty and five seconds"),
+            ("$5.99", "five dollars and ninety-nine cents"),
+            ("$1", "one dollar"),
+            ("£2.50", "two pounds and fifty pence"),
+            ("€5,50", "five euros and fifty cents"),
+            ("3,5 km", "three point five kilometers"),
+            (
+                "1,234 people",
+                "one thousand two hundred thirty-four people",
+            ),
+            ("1 km", "one kilometer"),
+            ("50%", "fifty percent"),
+            ("-3.5°C", "minus three point five degrees Celsius"),
+            ("21st", "twenty-first"),
+            ("in 1999", "in nineteen ninety-nine"),
+            ("10-20", "ten to twenty"),
+            (
+                "555-123-4567",
+                "five five five, one two three, four five six seven",
+            ),
+            ("1,2,3", "one,two,three"),
+            ("007", "zero zero seven"),
+        ] {
+            assert_eq!(filter(text, None), expected, "{text}");
+        }
+    }
+
+    #[test]
+    fn leaves_what_only_looks_like_a_number_alone() {
+        for text in [
+            "version 1.2.3",
+            "192.168.0.1",
+            "13/13/2024",
+            "2/30/2024",
+            "2024-13-45",
+            "25:99",
+            "12:5",
+            "123:45",
+            "mp3",
+            "v1.2",
+        ] {
+            assert_eq!(filter(text, None), text);
+        }
+    }
+
+    #[test]
+    fn follows_the_language() {
+        for (text, language, expected) in [
+            ("3/4/2024", "en-US", "March fourth, twenty twenty-four"),
+            (
+                "3/4/2024",
+                "en-GB",
+                "the third of April, twenty twenty-four",
+            ),
+            ("3/4/2024", "fr-FR", "3/4/2024"),
+            ("$5.99", "de", "$5.99"),
+        ] {
+            assert_eq!(
+                filter(text, Some(language)),
+                expected,
+                "{text} in {language}"
+            );
+        }
+    }
+}
@@ -0,0 +1,533 @@
+//! Converts synthesized audio to the sample rate, channel count and encoding an integration needs, such as 8 kHz
+//! mono μ-law for telephony, and encodes it as files.
+
+use std::f64::consts::PI;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::time::Duration;
+
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+use audiopus::{coder::Encoder, Application, Bitrate, Channels, SampleRate};
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use crate::AudioBuffer;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::Error;
+
+/// How samples are encoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SampleEncoding {
+    /// Signed 16-bit little-endian PCM.
+    #[default]
+    Pcm16,
+    /// 8-bit G.711 μ-law, as North American and Japanese telephony uses.
+    MuLaw,
+    /// 8-bit G.711 A-law, as telephony elsewhere uses.
+    ALaw,
+}
+
+impl SampleEncoding {
+    /// Returns how many bytes each sample takes.
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            Self::Pcm16 => 2,
+            Self::MuLaw | Self::ALaw => 1,
+        }
+    }
+}
+
+/// The format to synthesize audio in, as with `Tts::synthesize_as()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioFormat {
+    /// Samples per second, per channel.
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub encoding: SampleEncoding,
+}
+
+impl AudioFormat {
+    /// 8 kHz mono μ-law, for telephony.
+    pub const TELEPHONY: Self = Self {
+        sample_rate: 8000,
+        channels: 1,
+        encoding: SampleEncoding::MuLaw,
+    };
+
+    /// Returns this format with 16-bit PCM at `sample_rate` and `channels`.
+    pub fn pcm16(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            encoding: SampleEncoding::Pcm16,
+        }
+    }
+
+    /// Converts `audio` to this format.
+    pub fn encode(&self, audio: &AudioBuffer) -> EncodedAudio {
+        let audio = audio.convert(self.sample_rate, self.channels);
+        let mut data = Vec::with_capacity(audio.samples.len() * self.encoding.bytes_per_sample());
+        match self.encoding {
+            SampleEncoding::Pcm16 => {
+                for sample in &audio.samples {
+                    data.extend_from_slice(&sample.to_le_bytes());
+                }
+            }
+            SampleEncoding::MuLaw => data.extend(audio.samples.iter().map(|&s| mu_law(s))),
+            SampleEncoding::ALaw => data.extend(audio.samples.iter().map(|&s| a_law(s))),
+        }
+        EncodedAudio {
+            format: *self,
+            data,
+        }
+    }
+}
+
+/// Audio encoded in a requested format, as interleaved samples without a header.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct EncodedAudio {
+    pub format: AudioFormat,
+    pub data: Vec<u8>,
+}
+
+/// The kind of file to synthesize speech to, as with `Tts::synthesize_to_file_as()`.
+///
+/// MP3 isn't one, since there's no MP3 encoder to build with yet. Synthesize to WAV or Opus and convert the file
+/// with another tool.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FileFormat {
+    /// 16-bit PCM WAV.
+    #[default]
+    Wav,
+    /// Opus in an Ogg container, as `.opus` files hold. Enabled with the `opus` feature.
+    #[cfg(feature = "opus")]
+    Opus(OpusOptions),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileFormat {
+    /// Returns the format suggested by the extension of `path`: Opus for `.opus` and `.ogg` if the `opus` feature is
+    /// enabled, and otherwise WAV.
+    ///
+    /// Returns `Error::UnsupportedFeature` for `.mp3`, and for `.opus` and `.ogg` without the `opus` feature, rather
+    /// than writing WAV to a file named as something else.
+    pub fn from_path(path: &Path) -> Result<Self, Error> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+        match extension.as_deref() {
+            #[cfg(feature = "opus")]
+            Some("opus" | "ogg") => Ok(Self::Opus(Default::default())),
+            #[cfg(not(feature = "opus"))]
+            Some("opus" | "ogg") => Err(Error::UnsupportedFeature),
+            Some("mp3") => Err(Error::UnsupportedFeature),
+            _ => Ok(Self::Wav),
+        }
+    }
+
+    /// Encodes `audio` as a file of this format.
+    pub fn encode(&self, audio: &AudioBuffer) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Wav => Ok(audio.to_wav()),
+            #[cfg(feature = "opus")]
+            Self::Opus(options) => encode_opus(audio, options),
+        }
+    }
+}
+
+/// How to encode Opus.
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpusOptions {
+    /// The bitrate in bits per second, such as 24000 for clear speech, or `None` to let the encoder choose.
+    pub bitrate: Option<u32>,
+    /// Whether the bitrate varies with how hard the audio is to encode, which sounds better at the same size. With
+    /// it off, every frame takes the same space, as streaming over constrained links may want.
+    pub vbr: bool,
+}
+
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+impl Default for OpusOptions {
+    fn default() -> Self {
+        Self {
+            bitrate: None,
+            vbr: true,
+        }
+    }
+}
+
+/// The rate Opus streams are timed at, whatever their input.
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+const OPUS_RATE: u32 = 48000;
+
+/// Samples per channel in each Opus frame, 20 ms at 48 kHz.
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+const OPUS_FRAME: usize = 960;
+
+/// The largest packet Opus is recommended to produce.
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+const OPUS_MAX_PACKET: usize = 4000;
+
+/// Encodes `audio` as an Ogg Opus file, as RFC 7845 lays out.
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+fn encode_opus(audio: &AudioBuffer, options: &OpusOptions) -> Result<Vec<u8>, Error> {
+    // Opus is mono or stereo, and encoded at 48 kHz so any rate resamples cleanly.
+    let (channels, channel_count) = match audio.channels {
+        1 => (Channels::Mono, 1),
+        _ => (Channels::Stereo, 2),
+    };
+    let input_rate = audio.sample_rate;
+    let audio = audio.convert(OPUS_RATE, channel_count);
+    let mut encoder = Encoder::new(SampleRate::Hz48000, channels, Application::Audio)?;
+    encoder.set_bitrate(match options.bitrate {
+        Some(bitrate) => Bitrate::BitsPerSecond(bitrate.min(i32::MAX as u32) as i32),
+        None => Bitrate::Auto,
+    })?;
+    encoder.set_vbr(options.vbr)?;
+    let pre_skip = encoder.lookahead()?;
+    // Each stream in a file needs a serial number, but a file holds only this one.
+    let serial = 0;
+    let mut writer = PacketWriter::new(Vec::new());
+    let mut head = b"OpusHead".to_vec();
+    head.push(1);
+    head.push(channel_count as u8);
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&input_rate.to_le_bytes());
+    // No output gain, and the channel mapping for mono and stereo.
+    head.extend_from_slice(&[0, 0, 0]);
+    writer.write_packet(head.into(), serial, PacketWriteEndInfo::EndPage, 0)?;
+    let vendor = concat!("tts-rs ", env!("CARGO_PKG_VERSION"));
+    let mut tags = b"OpusTags".to_vec();
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    writer.write_packet(tags.into(), serial, PacketWriteEndInfo::EndPage, 0)?;
+    // Pad with silence so the encoder's lookahead flushes the end, and to fill the last frame.
+    let frame_len = OPUS_FRAME * channel_count as usize;
+    let frames = audio.samples.len() / channel_count as usize;
+    let mut samples = audio.samples;
+    samples.resize(
+        samples.len() + pre_skip as usize * channel_count as usize,
+        0,
+    );
+    samples.resize(samples.len().div_ceil(frame_len).max(1) * frame_len, 0);
+    let end = pre_skip as u64 + frames as u64;
+    let mut packet = [0; OPUS_MAX_PACKET];
+    let count = samples.len() / frame_len;
+    for (i, frame) in samples.chunks_exact(frame_len).enumerate() {
+        let len = encoder.encode(frame, &mut packet)?;
+        // The granule position counts samples through the end of the page, leaving out the padding.
+        let position = (((i + 1) * OPUS_FRAME) as u64).min(end);
+        let info = if i + 1 == count {
+            PacketWriteEndInfo::EndStream
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        writer.write_packet(packet[..len].into(), serial, info, position)?;
+    }
+    Ok(writer.into_inner())
+}
+
+/// How many input samples on either side of each output sample the resampling filter reaches, at the lower of the
+/// two rates. More is sharper but slower.
+const FILTER_HALF_WIDTH: f64 = 16.;
+
+/// How long each block of audio is when measuring loudness.
+const LOUDNESS_BLOCK: Duration = Duration::from_millis(100);
+
+/// Blocks quieter than this, in dBFS, are silence between words and don't count toward loudness.
+const SILENCE_THRESHOLD: f32 = -60.;
+
+/// The most normalization raises quiet audio, in dB, so faint noise isn't amplified into hiss.
+const MAX_GAIN: f32 = 24.;
+
+impl AudioBuffer {
+    /// Returns this audio resampled to `sample_rate` and mixed to `channels`, unchanged if it already matches.
+    ///
+    /// Mono is copied to every channel, and other layouts are mixed to mono first unless the counts match.
+    pub fn convert(&self, sample_rate: u32, channels: u16) -> AudioBuffer {
+        if self.sample_rate == 0 || self.channels == 0 || sample_rate == 0 || channels == 0 {
+            return AudioBuffer {
+                sample_rate,
+                channels,
+                samples: vec![],
+            };
+        }
+        let audio = self.mix(channels);
+        if audio.sample_rate == sample_rate {
+            return audio;
+        }
+        audio.resample(sample_rate)
+    }
+
+    /// Returns this audio panned from -1.0 for full left to 1.0 for full right, as stereo.
+    ///
+    /// Mono is panned with constant power, so it sounds equally loud wherever it is, and stereo is balanced by
+    /// lowering the opposite channel. Audio with more channels is returned unchanged.
+    pub fn panned(&self, pan: f32) -> AudioBuffer {
+        let pan = pan.clamp(-1., 1.);
+        let scale = |sample: i16, gain: f32| (sample as f32 * gain) as i16;
+        let samples = match self.channels {
+            1 => {
+                let angle = (pan + 1.) * std::f32::consts::FRAC_PI_4;
+                let (left, right) = (angle.cos(), angle.sin());
+                self.samples
+                    .iter()
+                    .flat_map(|&s| [scale(s, left), scale(s, right)])
+                    .collect()
+            }
+            2 => {
+                let (left, right) = ((1. - pan).min(1.), (1. + pan).min(1.));
+                self.samples
+                    .chunks_exact(2)
+                    .flat_map(|frame| [scale(frame[0], left), scale(frame[1], right)])
+                    .collect()
+            }
+            _ => return self.clone(),
+        };
+        AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: 2,
+            samples,
+        }
+    }
+
+    /// Returns the loudness of this audio in dBFS RMS, leaving out the silence between words, or `None` if it's all
+    /// silence.
+    pub fn loudness(&self) -> Option<f32> {
+        let block_len = (self.sample_rate as f64 * LOUDNESS_BLOCK.as_secs_f64()) as usize
+            * self.channels as usize;
+        let threshold = 10f64.powf(SILENCE_THRESHOLD as f64 / 10.);
+        let (sum, count) = self
+            .samples
+            .chunks(block_len.max(1))
+            .map(|block| {
+                let sum: f64 = block
+                    .iter()
+                    .map(|&s| (s as f64 / i16::MAX as f64).powi(2))
+                    .sum();
+                (sum, block.len())
+            })
+            .filter(|&(sum, len)| sum / len as f64 > threshold)
+            .fold((0., 0), |(sum, count), (block_sum, len)| {
+                (sum + block_sum, count + len)
+            });
+        (count > 0).then(|| (10. * (sum / count as f64).log10()) as f32)
+    }
+
+    /// Returns this audio scaled to `target` dBFS RMS, as measured by `loudness()`.
+    ///
+    /// Quiet audio is raised no further than keeps its peak from clipping, and by at most 24 dB, so faint noise isn't
+    /// amplified into hiss. Silence is returned unchanged.
+    pub fn normalized(&self, target: f32) -> AudioBuffer {
+        let Some(loudness) = self.loudness() else {
+            return self.clone();
+        };
+        let peak = self
+            .samples
+            .iter()
+            .map(|&s| s.unsigned_abs())
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        let gain = 10f32
+            .powf((target - loudness).min(MAX_GAIN) / 20.)
+            .min(i16::MAX as f32 / peak as f32);
+        AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            samples: self
+                .samples
+                .iter()
+                .map(|&s| (s as f32 * gain) as i16)
+                .collect(),
+        }
+    }
+
+    fn mix(&self, channels: u16) -> AudioBuffer {
+        let from = self.channels as usize;
+        let to = channels as usize;
+        let samples = if from == to {
+            self.samples.clone()
+        } else {
+            self.samples
+                .chunks_exact(from)
+                .flat_map(|frame| {
+                    let mono = frame.iter().map(|&s| s as i32).sum::<i32>() / from as i32;
+                    std::iter::repeat_n(mono as i16, to)
+                })
+                .collect()
+        };
+        AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels,
+            samples,
+        }
+    }
+
+    /// Resamples with a windowed sinc filter, which cuts frequencies above the lower rate's limit so downsampling
+    /// doesn't alias.
+    fn resample(&self, sample_rate: u32) -> AudioBuffer {
+        let channels = self.channels as usize;
+        let frames = self.samples.len() / channels;
+        let ratio = self.sample_rate as f64 / sample_rate as f64;
+        // The cutoff, as a fraction of the input's Nyquist frequency.
+        let cutoff = (1. / ratio).min(1.);
+        let reach = FILTER_HALF_WIDTH / cutoff;
+        let out_frames = (frames as f64 / ratio).round() as usize;
+        let mut samples = Vec::with_capacity(out_frames * channels);
+        for frame in 0..out_frames {
+            let center = frame as f64 * ratio;
+            let first = (center - reach).ceil().max(0.) as usize;
+            let last = ((center + reach).floor() as usize).min(frames.saturating_sub(1));
+            for channel in 0..channels {
+                let mut sum = 0.;
+                for input in first..=last {
+                    let x = input as f64 - center;
+                    let t = PI * x * cutoff;
+                    let sinc = if t == 0. { 1. } else { t.sin() / t };
+                    // A Hann window tapers the filter to zero at its edges.
+                    let window = 0.5 + 0.5 * (PI * x / reach).cos();
+                    sum += self.samples[input * channels + channel] as f64 * sinc * window;
+                }
+                let sample = sum * cutoff;
+                samples.push(sample.clamp(i16::MIN as f64, i16::MAX as f64) as i16);
+            }
+        }
+        AudioBuffer {
+            sample_rate,
+            channels: self.channels,
+            samples,
+        }
+    }
+}
+
+/// Encodes `sample` as G.711 μ-law.
+fn mu_law(sample: i16) -> u8 {
+    const BIAS: i32 = 0x84;
+    const CLIP: i32 = 32635;
+    let sign = if sample < 0 { 0x80 } else { 0 };
+    let magnitude = (sample as i32).abs().min(CLIP) + BIAS;
+    // The bias puts the top bit between bits 7 and 14.
+    let exponent = 8 - (magnitude as u16).leading_zeros() as i32;
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0f;
+    !(sign | (exponent << 4) as u8 | mantissa as u8)
+}
+
+/// Encodes `sample` as G.711 A-law.
+fn a_law(sample: i16) -> u8 {
+    // A-law works on 13-bit samples.
+    let sample = sample as i32 >> 3;
+    let (sign, magnitude) = if sample >= 0 {
+        (0x80, sample)
+    } else {
+        (0, -sample - 1)
+    };
+    let (exponent, mantissa) = if magnitude < 32 {
+        (0, magnitude >> 1)
+    } else {
+        let exponent = 31 - (magnitude as u32).leading_zeros() as i32 - 4;
+        (exponent, (magnitude >> exponent) & 0x0f)
+    };
+    (sign | (exponent << 4) as u8 | mantissa as u8) ^ 0x55
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio(channels: u16, samples: Vec<i16>) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 16000,
+            channels,
+            samples,
+        }
+    }
+
+    #[test]
+    fn mono_pans_with_constant_power() {
+        let mono = audio(1, vec![10000; 4]);
+        let center = mono.panned(0.);
+        assert_eq!(center.channels, 2);
+        assert_eq!(center.samples, [7071; 8]);
+        assert_eq!(mono.panned(-1.).samples[..2], [10000, 0]);
+        assert_eq!(mono.panned(5.).samples[..2], [0, 10000]);
+    }
+
+    #[test]
+    fn stereo_pans_by_lowering_the_other_side() {
+        let stereo = audio(2, vec![10000, 10000]);
+        assert_eq!(stereo.panned(0.).samples, [10000, 10000]);
+        assert_eq!(stereo.panned(0.5).samples, [5000, 10000]);
+        assert_eq!(stereo.panned(-1.).samples, [10000, 0]);
+        let surround = audio(6, vec![10000; 6]);
+        assert_eq!(surround.panned(1.), surround);
+    }
+
+    #[test]
+    fn loudness_leaves_out_silence() {
+        // A tenth of full scale is -20 dBFS, and the blocks of silence around it don't lower that.
+        let mut samples = vec![0; 1600];
+        samples.extend([3277; 3200]);
+        samples.extend([3; 1600]);
+        let speech = audio(1, samples);
+        assert!((speech.loudness().unwrap() + 20.).abs() < 0.01);
+        let silence = audio(1, vec![3; 3200]);
+        assert_eq!(silence.loudness(), None);
+        assert_eq!(silence.normalized(-20.), silence);
+        assert_eq!(audio(1, vec![]).loudness(), None);
+    }
+
+    #[test]
+    fn normalizing_reaches_the_target() {
+        let quiet = audio(1, vec![3277; 3200]);
+        for target in [-10., -30.] {
+            let loudness = quiet.normalized(target).loudness().unwrap();
+            assert!((loudness - target).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn normalizing_stops_short_of_clipping() {
+        let mut samples = vec![3277; 3200];
+        samples[100] = 16384;
+        let peaky = audio(1, samples).normalized(0.);
+        assert_eq!(peaky.samples[100], i16::MAX);
+        assert_eq!(peaky.samples[0], 6553);
+    }
+
+    #[test]
+    fn normalizing_raises_by_at_most_max_gain() {
+        // Reaching -10 dBFS from about -50 would take 40 dB.
+        let faint = audio(1, vec![100; 3200]);
+        assert_eq!(faint.normalized(-10.).samples, [1584; 3200]);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn paths_name_their_format() {
+        assert_eq!(
+            FileFormat::from_path(Path::new("line.wav")).ok(),
+            Some(FileFormat::Wav)
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("line")).ok(),
+            Some(FileFormat::Wav)
+        );
+        assert!(matches!(
+            FileFormat::from_path(Path::new("line.MP3")),
+            Err(Error::UnsupportedFeature)
+        ));
+        #[cfg(not(feature = "opus"))]
+        assert!(matches!(
+            FileFormat::from_path(Path::new("line.opus")),
+            Err(Error::UnsupportedFeature)
+        ));
+    }
+}
@@ -0,0 +1,158 @@
+//! Reports the words being spoken as byte ranges of the text the caller gave, for karaoke-style highlighting.
+//!
+//! Backends already convert their offsets, whether UTF-16 code units or characters, into byte ranges of the text
+//! they were given, and chunking offsets those into the whole text. What's left is undoing filters and the lexicon,
+//! which rewrite the text before it's spoken. The words of both texts are aligned, so a rewritten word highlights
+//! the words it replaced, and words that filters added highlight nothing.
+
+use std::{collections::HashMap, ops::Range, sync::LazyLock};
+
+use parking_lot::Mutex;
+
+use crate::{panics, BackendId, UtteranceEvent};
+
+/// How many words ahead to look for the texts to line up again after they differ.
+const WINDOW: usize = 8;
+
+/// Ranges of the spoken text paired with the ranges of the original they came from, in order.
+type Alignment = Vec<(Range<usize>, Range<usize>)>;
+
+struct Highlight {
+    callback: Option<Box<dyn FnMut(Range<usize>)>>,
+    original_len: usize,
+    /// `None` if the text was spoken as given.
+    alignment: Option<Alignment>,
+}
+
+#[derive(Default)]
+struct Highlights(HashMap<BackendId, HashMap<u64, Highlight>>);
+
+unsafe impl Send for Highlights {}
+
+static HIGHLIGHTS: LazyLock<Mutex<Highlights>> =
+    LazyLock::new(|| Mutex::new(Highlights::default()));
+
+fn words(text: &str) -> Vec<Range<usize>> {
+    text.split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            start..start + word.len()
+        })
+        .collect()
+}
+
+/// The range covering `words`, or an empty range at `at` if there are none.
+fn span(words: &[Range<usize>], at: usize) -> Range<usize> {
+    match (words.first(), words.last()) {
+        (Some(first), Some(last)) => first.start..last.end,
+        _ => at..at,
+    }
+}
+
+/// Pairs the words of `spoken` with those of `original`, matching identical words and pairing the differing runs
+/// between them.
+fn align(original: &str, spoken: &str) -> Alignment {
+    let (a, b) = (words(original), words(spoken));
+    let same = |i: usize, j: usize| original[a[i].clone()] == spoken[b[j].clone()];
+    let mut alignment = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if same(i, j) {
+            alignment.push((b[j].clone(), a[i].clone()));
+            i += 1;
+            j += 1;
+            continue;
+        }
+        // The nearest point at which both texts continue with the same word.
+        let resync = (1..=2 * WINDOW)
+            .flat_map(|skipped| (0..=skipped).map(move |di| (di, skipped - di)))
+            .filter(|&(di, dj)| di <= WINDOW && dj <= WINDOW)
+            .find(|&(di, dj)| i + di < a.len() && j + dj < b.len() && same(i + di, j + dj));
+        let (di, dj) = resync.unwrap_or((a.len() - i, b.len() - j));
+        if dj > 0 {
+            alignment.push((span(&b[j..j + dj], 0), span(&a[i..i + di], a[i].start)));
+        }
+        i += di;
+        j += dj;
+    }
+    if j < b.len() {
+        let at = a.last().map_or(0, |word| word.end);
+        alignment.push((span(&b[j..], 0), span(&a[i..], at)));
+    }
+    alignment
+}
+
+impl Highlight {
+    /// Maps `range` of the spoken text onto the original, or `None` if it covers only added words.
+    fn map(&self, range: Range<usize>) -> Option<Range<usize>> {
+        let Some(alignment) = &self.alignment else {
+            let end = range.end.min(self.original_len);
+            return Some(range.start.min(end)..end);
+        };
+        let first = alignment.partition_point(|(spoken, _)| spoken.end <= range.start);
+        alignment[first..]
+            .iter()
+            .take_while(|(spoken, _)| spoken.start < range.end.max(range.start + 1))
+            .map(|(_, original)| original.clone())
+            .filter(|original| !original.is_empty())
+            .reduce(|covered, original| covered.start..original.end)
+    }
+}
+
+/// Calls `callback` with the words of `original` as `utterance`, speaking `spoken`, reaches them.
+pub(crate) fn add(
+    backend: BackendId,
+    utterance: u64,
+    original: &str,
+    spoken: &str,
+    callback: Box<dyn FnMut(Range<usize>)>,
+) {
+    let highlight = Highlight {
+        callback: Some(callback),
+        original_len: original.len(),
+        alignment: (original != spoken).then(|| align(original, spoken)),
+    };
+    let mut highlights = HIGHLIGHTS.lock();
+    highlights
+        .0
+        .entry(backend)
+        .or_default()
+        .insert(utterance, highlight);
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    HIGHLIGHTS.lock().0.remove(&backend);
+}
+
+/// Forgets `utterance` once it ends or stops.
+pub(crate) fn utterance(backend: BackendId, event: UtteranceEvent, utterance: u64) {
+    if let UtteranceEvent::End | UtteranceEvent::Stop = event {
+        if let Some(highlights) = HIGHLIGHTS.lock().0.get_mut(&backend) {
+            highlights.remove(&utterance);
+        }
+    }
+}
+
+/// Highlights the word `utterance` reached, given as `range` of the spoken text.
+pub(crate) fn word(backend: BackendId, utterance: u64, range: Range<usize>) {
+    let highlight = {
+        let mut highlights = HIGHLIGHTS.lock();
+        highlights
+            .0
+            .get_mut(&backend)
+            .and_then(|highlights| highlights.get_mut(&utterance))
+            .and_then(|highlight| Some((highlight.map(range)?, highlight.callback.take()?)))
+    };
+    let Some((range, mut callback)) = highlight else {
+        return;
+    };
+    panics::guard(backend, || callback(range));
+    let mut highlights = HIGHLIGHTS.lock();
+    if let Some(highlight) = highlights
+        .0
+        .get_mut(&backend)
+        .and_then(|highlights| highlights.get_mut(&utterance))
+    {
+        highlight.callback = Some(callback);
+    }
+}
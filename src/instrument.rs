@@ -0,0 +1,45 @@
+//! [`tracing`](https://docs.rs/tracing) instrumentation, enabled with the `tracing` feature.
+//!
+//! Backend creation, speech and synthesis are wrapped in spans, as is each callback dispatch. When an utterance
+//! begins, its time to first audio, measured from the `speak` call that queued it, is logged as a
+//! `time_to_first_audio_ms` event with the `tts::latency` target. This needs the backend's events, which are only
+//! wired up once callbacks are used, or once a subscriber wants these events when speech is queued.
+
+use crate::{speaking, BackendId, UtteranceEvent, UtteranceId, WordBoundaryCallback};
+
+/// Wraps an utterance callback, if any, to trace its dispatch and log latency.
+pub(crate) fn utterance_callback(
+    backend: BackendId,
+    event: UtteranceEvent,
+    mut callback: Option<Box<dyn FnMut(UtteranceId)>>,
+) -> Box<dyn FnMut(UtteranceId)> {
+    Box::new(move |id: UtteranceId| {
+        let utterance = id.as_u64();
+        let _span = tracing::debug_span!("tts.callback", ?event, utterance).entered();
+        if let Some(callback) = callback.as_mut() {
+            callback(id);
+        }
+        if let UtteranceEvent::Begin = event {
+            if let Some(latency) = speaking::latency(backend, utterance) {
+                tracing::info!(
+                    target: "tts::latency",
+                    utterance,
+                    time_to_first_audio_ms = latency.as_secs_f64() * 1000.,
+                );
+            }
+        }
+    })
+}
+
+/// Wraps a word boundary callback to trace its dispatch.
+pub(crate) fn word_callback(mut callback: WordBoundaryCallback) -> WordBoundaryCallback {
+    Box::new(move |id, range| {
+        let _span = tracing::trace_span!(
+            "tts.callback",
+            event = "WordBoundary",
+            utterance = id.as_u64()
+        )
+        .entered();
+        callback(id, range);
+    })
+}
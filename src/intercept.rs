@@ -0,0 +1,93 @@
+//! Lets apps veto, rewrite or reroute every utterance from one place, rather than wrapping each call that speaks, and
+//! mutes speech on the way through.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
+
+use parking_lot::Mutex;
+
+use crate::{BackendId, Tts, UtteranceOptions};
+
+/// An utterance about to be given to the backend, as an interceptor sees it.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UtteranceRequest {
+    /// The text to speak, after filters and the lexicon.
+    pub text: String,
+    pub interrupt: bool,
+    pub options: UtteranceOptions,
+}
+
+/// What to do with an utterance once an interceptor has seen it, and perhaps rewritten it.
+#[derive(Clone)]
+pub enum InterceptAction {
+    /// Speak the utterance.
+    Speak,
+    /// Don't speak the utterance. Speaking it returns no utterance ID.
+    Drop,
+    /// Speak the utterance with another `Tts` instead, which returns that instance's utterance ID. Its filters,
+    /// lexicon and interceptor aren't applied again.
+    Reroute(Tts),
+}
+
+/// What happens to utterances while speech is muted with `Tts::set_muted()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MuteBehavior {
+    /// Speak utterances at the lowest volume, so they still return IDs and fire callbacks as if heard. Backends
+    /// without volume control discard utterances instead.
+    #[default]
+    Silence,
+    /// Don't speak utterances at all. Speaking returns no utterance ID, and no callbacks fire.
+    Discard,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Mute {
+    pub(crate) behavior: MuteBehavior,
+    pub(crate) muted: bool,
+    /// The volume to restore when unmuted, if the backend was silenced by lowering it.
+    pub(crate) volume: Option<f32>,
+}
+
+impl Mute {
+    /// Whether utterances are to be discarded rather than spoken.
+    pub(crate) fn discards(&self) -> bool {
+        self.muted && self.volume.is_none()
+    }
+}
+
+pub(crate) type Interceptor = dyn Fn(&mut UtteranceRequest) -> InterceptAction + Send + Sync;
+
+static INTERCEPTORS: LazyLock<Mutex<HashMap<BackendId, Arc<Interceptor>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static MUTES: LazyLock<Mutex<HashMap<BackendId, Mute>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set(backend: BackendId, interceptor: Option<Box<Interceptor>>) {
+    let mut interceptors = INTERCEPTORS.lock();
+    match interceptor {
+        Some(interceptor) => interceptors.insert(backend, interceptor.into()),
+        None => interceptors.remove(&backend),
+    };
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    INTERCEPTORS.lock().remove(&backend);
+    MUTES.lock().remove(&backend);
+}
+
+/// Returns `backend`'s interceptor, to be called without the lock held.
+pub(crate) fn interceptor(backend: BackendId) -> Option<Arc<Interceptor>> {
+    INTERCEPTORS.lock().get(&backend).cloned()
+}
+
+pub(crate) fn mute(backend: BackendId) -> Mute {
+    MUTES.lock().get(&backend).copied().unwrap_or_default()
+}
+
+pub(crate) fn set_mute(backend: BackendId, mute: Mute) {
+    MUTES.lock().insert(backend, mute);
+}
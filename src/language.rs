@@ -0,0 +1,68 @@
+//! Matches voice languages against requested ones, falling back from region to script to language as BCP 47 lookup
+//! does.
+
+use oxilangtag::LanguageTag;
+
+/// How closely a voice's language matches a requested one, from worst to best.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchQuality {
+    /// The languages differ.
+    #[default]
+    None,
+    /// Only the language matches, and the script differs, such as `zh-Hans` for `zh-Hant`. Usually not readable.
+    Language,
+    /// The language and script match, but the region differs or only one tag has one, such as `en-GB` for `en-US`.
+    Script,
+    /// The language, script and region match, but variants or extensions differ.
+    Region,
+    /// The tags are the same, ignoring case.
+    Exact,
+}
+
+/// The script `language` is written in, if it says or it's one of the few languages commonly written in several.
+fn script(language: &LanguageTag<String>) -> Option<&str> {
+    if let Some(script) = language.script() {
+        return Some(script);
+    }
+    if language.primary_language().eq_ignore_ascii_case("zh") {
+        return match language.region().map(|region| region.to_ascii_uppercase()) {
+            Some(region) if ["HK", "MO", "TW"].contains(&region.as_str()) => Some("Hant"),
+            _ => Some("Hans"),
+        };
+    }
+    None
+}
+
+fn same(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Returns how closely `available` matches `requested`.
+pub(crate) fn match_quality(
+    available: &LanguageTag<String>,
+    requested: &LanguageTag<String>,
+) -> MatchQuality {
+    if !available
+        .primary_language()
+        .eq_ignore_ascii_case(requested.primary_language())
+    {
+        return MatchQuality::None;
+    }
+    // A script that neither tag implies is assumed to be the same.
+    match (script(available), script(requested)) {
+        (Some(a), Some(b)) if !a.eq_ignore_ascii_case(b) => return MatchQuality::Language,
+        _ => {}
+    }
+    if !same(available.region(), requested.region()) {
+        MatchQuality::Script
+    } else if available.as_str().eq_ignore_ascii_case(requested.as_str()) {
+        MatchQuality::Exact
+    } else {
+        MatchQuality::Region
+    }
+}
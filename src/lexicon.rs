@@ -0,0 +1,111 @@
+//! Pronunciation dictionaries, applied to text before it reaches the backend.
+
+#[derive(Clone, Debug)]
+enum Entry {
+    Word {
+        word: String,
+        pronunciation: String,
+    },
+    #[cfg(feature = "regex")]
+    Regex {
+        regex: regex::Regex,
+        replacement: String,
+    },
+}
+
+/// Maps words and patterns to respellings, so jargon, usernames and brand names are pronounced consistently
+/// whichever engine speaks them.
+///
+/// Entries are applied in the order they were added. Set a lexicon with `Tts::set_lexicon()`.
+#[derive(Clone, Debug, Default)]
+pub struct Lexicon {
+    entries: Vec<Entry>,
+}
+
+impl Lexicon {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Pronounces `word` as `pronunciation` wherever it appears as a whole word, ignoring case.
+    pub fn add_word<W: Into<String>, P: Into<String>>(
+        &mut self,
+        word: W,
+        pronunciation: P,
+    ) -> &mut Self {
+        let word = word.into();
+        if !word.is_empty() {
+            self.entries.push(Entry::Word {
+                word,
+                pronunciation: pronunciation.into(),
+            });
+        }
+        self
+    }
+
+    /// Replaces matches of `regex` with `replacement`, which may refer to capture groups as `$1` or `$name`.
+    #[cfg(feature = "regex")]
+    pub fn add_regex<R: Into<String>>(&mut self, regex: regex::Regex, replacement: R) -> &mut Self {
+        self.entries.push(Entry::Regex {
+            regex,
+            replacement: replacement.into(),
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns `text` with every entry applied.
+    pub fn apply(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for entry in &self.entries {
+            text = match entry {
+                Entry::Word {
+                    word,
+                    pronunciation,
+                } => replace_word(&text, word, pronunciation),
+                #[cfg(feature = "regex")]
+                Entry::Regex { regex, replacement } => {
+                    regex.replace_all(&text, replacement.as_str()).into_owned()
+                }
+            };
+        }
+        text
+    }
+}
+
+/// Returns the length in bytes of `word` at the start of `text`, ignoring case.
+fn match_len(text: &str, word: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for expected in word.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(expected.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(i, _)| i))
+}
+
+fn replace_word(text: &str, word: &str, pronunciation: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut previous = None;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let at_boundary = !previous.is_some_and(char::is_alphanumeric);
+        let len = match_len(rest, word)
+            .filter(|_| at_boundary)
+            .filter(|&len| !rest[len..].starts_with(char::is_alphanumeric));
+        if let Some(len) = len {
+            replaced.push_str(pronunciation);
+            previous = rest[..len].chars().next_back();
+            rest = &rest[len..];
+        } else {
+            replaced.push(c);
+            previous = Some(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    replaced
+}
@@ -9,79 +9,240 @@
 //!  *   * AVFoundation on MacOS 10.14 and above, and iOS
 //!  * * Android
 //!  * * WebAssembly
+//!  * Backends can be left out by disabling their Cargo features, see the README.
 
-use std::collections::HashMap;
-#[cfg(target_os = "macos")]
+use std::borrow::Cow;
+use std::boxed::Box;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::VecDeque;
+use std::env;
+#[cfg(all(target_os = "macos", feature = "appkit", feature = "av_foundation"))]
 use std::ffi::CStr;
 use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::BufRead;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+use std::str::FromStr;
 #[cfg(windows)]
 use std::string::FromUtf16Error;
-use std::sync::Mutex;
-use std::{boxed::Box, sync::RwLock};
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
 
-#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[cfg(all(target_os = "macos", feature = "appkit", feature = "av_foundation"))]
 use cocoa_foundation::base::id;
-use dyn_clonable::*;
-use lazy_static::lazy_static;
 #[cfg(target_os = "macos")]
+use cocoa_foundation::base::{BOOL, NO};
+#[cfg(target_os = "macos")]
+use cocoa_foundation::foundation::NSDefaultRunLoopMode;
+use dyn_clonable::*;
+#[cfg(all(target_os = "macos", feature = "appkit", feature = "av_foundation"))]
 use libc::c_char;
 #[cfg(target_os = "macos")]
 use objc::{class, msg_send, sel, sel_impl};
 pub use oxilangtag::LanguageTag;
-#[cfg(target_os = "linux")]
+use parking_lot::{Mutex, RwLock};
+#[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
 use speech_dispatcher::Error as SpeechDispatcherError;
 use thiserror::Error;
 #[cfg(all(windows, feature = "tolk"))]
 use tolk::Tolk;
 
+#[cfg(all(target_os = "android", feature = "android"))]
+pub mod android;
+#[cfg(all(any(test, feature = "audio-output"), not(target_arch = "wasm32")))]
+mod audio;
 mod backends;
+#[cfg(not(target_arch = "wasm32"))]
+mod batch;
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(not(target_arch = "wasm32"))]
+mod cache;
+mod callbacks;
+mod channel;
+mod chunking;
+mod dialogue;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod format;
+mod highlight;
+#[cfg(feature = "tracing")]
+mod instrument;
+mod intercept;
+mod language;
+mod lexicon;
+#[cfg(all(feature = "node", not(target_arch = "wasm32")))]
+mod node;
+mod panics;
+mod policy;
+#[cfg(all(feature = "python", not(target_arch = "wasm32")))]
+mod python;
+mod speaking;
+mod spell;
+mod ssml;
+mod template;
+#[cfg(all(feature = "uniffi", not(target_arch = "wasm32")))]
+pub mod uniffi_api;
+mod viseme;
+mod voice_id;
+mod voices;
+#[cfg(all(target_arch = "wasm32", feature = "wasm-api"))]
+pub mod wasm_api;
+mod watchdog;
+#[cfg(not(target_arch = "wasm32"))]
+mod worker;
+
+use callbacks::{Callbacks, Subscription};
+use intercept::Interceptor;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use batch::{BatchItem, BatchOptions, CancellationToken};
+#[cfg(not(target_arch = "wasm32"))]
+pub use cache::SynthesisCache;
+pub use channel::Channel;
+pub use dialogue::Dialogue;
+#[cfg(feature = "emoji")]
+pub use filter::EmojiFilter;
+#[cfg(feature = "markdown")]
+pub use filter::MarkdownFilter;
+pub use filter::{CodeFilter, HtmlFilter, Indentation, NumberFilter, TextFilter};
+#[cfg(not(target_arch = "wasm32"))]
+pub use format::FileFormat;
+#[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+pub use format::OpusOptions;
+pub use format::{AudioFormat, EncodedAudio, SampleEncoding};
+pub use intercept::{InterceptAction, MuteBehavior, UtteranceRequest};
+pub use language::MatchQuality;
+pub use lexicon::Lexicon;
+pub use policy::AnnouncementPolicy;
+pub use spell::{SpellAlphabet, SpellStyle};
+pub use ssml::{Prosody, SsmlBuilder, SsmlDialect};
+pub use template::{Localized, TemplateResolver, Templates};
+pub use viseme::Viseme;
+pub use watchdog::Watchdog;
+#[cfg(not(target_arch = "wasm32"))]
+pub use worker::Worker;
+
+#[cfg(all(feature = "uniffi", not(target_arch = "wasm32")))]
+uniffi::setup_scaffolding!();
 
+// Discriminants are fixed, so C callers get the same values whichever platform and features the library is built for.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(C)]
 pub enum Backends {
-    #[cfg(target_os = "android")]
-    Android,
-    #[cfg(target_os = "macos")]
-    AppKit,
-    #[cfg(any(target_os = "macos", target_os = "ios"))]
-    AvFoundation,
-    #[cfg(target_os = "linux")]
-    SpeechDispatcher,
+    #[cfg(all(target_os = "android", feature = "android"))]
+    Android = 0,
+    /// `NSSpeechSynthesizer` is not thread-safe, so this backend must be created and used on the main thread.
+    #[cfg(all(target_os = "macos", feature = "appkit"))]
+    AppKit = 1,
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+    AvFoundation = 2,
+    #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+    SpeechDispatcher = 3,
     #[cfg(all(windows, feature = "tolk"))]
-    Tolk,
-    #[cfg(target_arch = "wasm32")]
-    Web,
-    #[cfg(windows)]
-    WinRt,
+    Tolk = 4,
+    #[cfg(all(target_arch = "wasm32", feature = "web"))]
+    Web = 5,
+    #[cfg(all(windows, feature = "winrt"))]
+    WinRt = 6,
+    /// Silently discards speech, for when no real backend is available.
+    Noop = 7,
+}
+
+impl Backends {
+    /// Looks up a backend by name, ignoring case, as accepted by the `TTS_BACKEND` environment variable.
+    ///
+    /// Returns `None` for unknown names and for backends not available in this build.
+    pub fn from_name(name: &str) -> Option<Backends> {
+        let backend = match name.to_lowercase().as_str() {
+            #[cfg(all(target_os = "android", feature = "android"))]
+            "android" => Backends::Android,
+            #[cfg(all(target_os = "macos", feature = "appkit"))]
+            "appkit" => Backends::AppKit,
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+            "avfoundation" | "av-foundation" => Backends::AvFoundation,
+            #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+            "speechdispatcher" | "speech-dispatcher" | "speechd" => Backends::SpeechDispatcher,
+            #[cfg(all(windows, feature = "tolk"))]
+            "tolk" => Backends::Tolk,
+            #[cfg(all(target_arch = "wasm32", feature = "web"))]
+            "web" => Backends::Web,
+            #[cfg(all(windows, feature = "winrt"))]
+            "winrt" => Backends::WinRt,
+            "noop" => Backends::Noop,
+            _ => return None,
+        };
+        Some(backend)
+    }
 }
 
 impl fmt::Display for Backends {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
-            #[cfg(target_os = "android")]
+            #[cfg(all(target_os = "android", feature = "android"))]
             Backends::Android => writeln!(f, "Android"),
-            #[cfg(target_os = "macos")]
+            #[cfg(all(target_os = "macos", feature = "appkit"))]
             Backends::AppKit => writeln!(f, "AppKit"),
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
             Backends::AvFoundation => writeln!(f, "AVFoundation"),
-            #[cfg(target_os = "linux")]
+            #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
             Backends::SpeechDispatcher => writeln!(f, "Speech Dispatcher"),
             #[cfg(all(windows, feature = "tolk"))]
             Backends::Tolk => writeln!(f, "Tolk"),
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(target_arch = "wasm32", feature = "web"))]
             Backends::Web => writeln!(f, "Web"),
-            #[cfg(windows)]
+            #[cfg(all(windows, feature = "winrt"))]
             Backends::WinRt => writeln!(f, "Windows Runtime"),
+            Backends::Noop => writeln!(f, "Noop"),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+/// Which engine a `Tts` speaks with, from `Tts::backend_info()`, for logs and bug reports.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BackendInfo {
+    pub backend: Backends,
+    /// The platform speech service, such as `Speech Dispatcher` or `AVSpeechSynthesizer`.
+    pub engine_name: Option<String>,
+    /// The version of the engine, or of the OS it ships with, if known.
+    pub engine_version: Option<String>,
+    /// What produces the audio, such as Speech Dispatcher's output module, the Android engine's package, the provider
+    /// WinRT voices are registered with, the browser's user agent, or the screen reader Tolk speaks through.
+    pub synthesizer: Option<String>,
+    /// The backend `Tts::default()` wanted, when it wasn't available and speech is discarded by `Backends::Noop`
+    /// instead, as with the `web_noop_fallback` feature.
+    pub fallback_from: Option<Backends>,
+}
+
+impl BackendInfo {
+    pub(crate) fn new(backend: Backends) -> Self {
+        Self {
+            backend,
+            engine_name: None,
+            engine_version: None,
+            synthesizer: None,
+            fallback_from: None,
+        }
+    }
+}
+
+// IDs stay per-platform, so that this and `UtteranceId` are never empty, even with every backend disabled.
+/// Identifies a backend instance. Its string form, such as `speechd:42`, is the same on every platform, and is how it's
+/// serialized. Parsing fails for backends the current platform doesn't have.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub enum BackendId {
     #[cfg(target_os = "android")]
     Android(u64),
+    #[cfg(target_os = "macos")]
+    AppKit(u64),
     #[cfg(any(target_os = "macos", target_os = "ios"))]
     AvFoundation(u64),
     #[cfg(target_os = "linux")]
@@ -90,43 +251,122 @@ pub enum BackendId {
     Web(u64),
     #[cfg(windows)]
     WinRt(u64),
+    Noop(u64),
 }
 
 impl fmt::Display for BackendId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         match self {
             #[cfg(target_os = "android")]
-            BackendId::Android(id) => writeln!(f, "Android({id})"),
+            BackendId::Android(id) => write!(f, "android:{id}"),
+            #[cfg(target_os = "macos")]
+            BackendId::AppKit(id) => write!(f, "appkit:{id}"),
             #[cfg(any(target_os = "macos", target_os = "ios"))]
-            BackendId::AvFoundation(id) => writeln!(f, "AvFoundation({id})"),
+            BackendId::AvFoundation(id) => write!(f, "avfoundation:{id}"),
             #[cfg(target_os = "linux")]
-            BackendId::SpeechDispatcher(id) => writeln!(f, "SpeechDispatcher({id})"),
+            BackendId::SpeechDispatcher(id) => write!(f, "speechd:{id}"),
             #[cfg(target_arch = "wasm32")]
-            BackendId::Web(id) => writeln!(f, "Web({id})"),
+            BackendId::Web(id) => write!(f, "web:{id}"),
             #[cfg(windows)]
-            BackendId::WinRt(id) => writeln!(f, "WinRT({id})"),
+            BackendId::WinRt(id) => write!(f, "winrt:{id}"),
+            BackendId::Noop(id) => write!(f, "noop:{id}"),
         }
     }
 }
 
-// # Note
-//
-// Most trait implementations are blocked by cocoa_foundation::base::id;
-// which is a type alias for objc::runtime::Object, which only implements Debug.
-#[derive(Debug)]
-#[cfg_attr(
-    not(any(target_os = "macos", target_os = "ios")),
-    derive(Clone, Copy, Eq, Hash, PartialEq, PartialOrd, Ord)
-)]
-#[cfg_attr(
-    all(feature = "serde", not(any(target_os = "macos", target_os = "ios"))),
-    derive(serde::Serialize, serde::Deserialize)
-)]
+impl FromStr for BackendId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = split_id(s)?;
+        let id = match kind {
+            #[cfg(target_os = "android")]
+            "android" => BackendId::Android(id),
+            #[cfg(target_os = "macos")]
+            "appkit" => BackendId::AppKit(id),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            "avfoundation" => BackendId::AvFoundation(id),
+            #[cfg(target_os = "linux")]
+            "speechd" => {
+                BackendId::SpeechDispatcher(id.try_into().map_err(|_| Error::InvalidId(s.into()))?)
+            }
+            #[cfg(target_arch = "wasm32")]
+            "web" => BackendId::Web(id),
+            #[cfg(windows)]
+            "winrt" => BackendId::WinRt(id),
+            "noop" => BackendId::Noop(id),
+            _ => return Err(Error::InvalidId(s.into())),
+        };
+        Ok(id)
+    }
+}
+
+/// Splits an ID's string form into its kind and number.
+fn split_id(s: &str) -> Result<(&str, u64), Error> {
+    s.split_once(':')
+        .and_then(|(kind, id)| Some((kind, id.parse().ok()?)))
+        .ok_or_else(|| Error::InvalidId(s.into()))
+}
+
+/// Serializes a type as its string form, and deserializes it by parsing that.
+macro_rules! serde_as_string {
+    ($t:ty) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $t {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $t {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = <Cow<str>>::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+serde_as_string!(BackendId);
+serde_as_string!(UtteranceId);
+
+impl BackendId {
+    /// Returns the kind of backend this instance is.
+    pub fn backend(&self) -> Backends {
+        match self {
+            #[cfg(all(target_os = "android", feature = "android"))]
+            BackendId::Android(_) => Backends::Android,
+            #[cfg(all(target_os = "macos", feature = "appkit"))]
+            BackendId::AppKit(_) => Backends::AppKit,
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+            BackendId::AvFoundation(_) => Backends::AvFoundation,
+            #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+            BackendId::SpeechDispatcher(_) => Backends::SpeechDispatcher,
+            #[cfg(all(target_arch = "wasm32", feature = "web"))]
+            BackendId::Web(_) => Backends::Web,
+            #[cfg(all(windows, feature = "winrt"))]
+            BackendId::WinRt(_) => Backends::WinRt,
+            BackendId::Noop(_) => Backends::Noop,
+            // Backends disabled at compile time never create IDs.
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Identifies an utterance. Like `BackendId`, its string form, such as `speechd:42`, is the same on every platform,
+/// and is how it's serialized.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
 pub enum UtteranceId {
     #[cfg(target_os = "android")]
     Android(u64),
+    #[cfg(target_os = "macos")]
+    AppKit(u64),
+    /// A key the backend maps to the `AVSpeechUtterance`, rather than the object itself, so IDs are plain values that
+    /// can be parsed and compared safely.
     #[cfg(any(target_os = "macos", target_os = "ios"))]
-    AvFoundation(id),
+    AvFoundation(u64),
     #[cfg(target_os = "linux")]
     SpeechDispatcher(u64),
     #[cfg(target_arch = "wasm32")]
@@ -135,32 +375,96 @@ pub enum UtteranceId {
     WinRt(u64),
 }
 
-// # Note
-//
-// Display is not implemented by cocoa_foundation::base::id;
-// which is a type alias for objc::runtime::Object, which only implements Debug.
-#[cfg(not(any(target_os = "macos", target_os = "ios")))]
 impl fmt::Display for UtteranceId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        match self {
+        let kind = match self {
             #[cfg(target_os = "android")]
-            UtteranceId::Android(id) => writeln!(f, "Android({id})"),
+            UtteranceId::Android(_) => "android",
+            #[cfg(target_os = "macos")]
+            UtteranceId::AppKit(_) => "appkit",
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            UtteranceId::AvFoundation(_) => "avfoundation",
             #[cfg(target_os = "linux")]
-            UtteranceId::SpeechDispatcher(id) => writeln!(f, "SpeechDispatcher({id})"),
+            UtteranceId::SpeechDispatcher(_) => "speechd",
             #[cfg(target_arch = "wasm32")]
-            UtteranceId::Web(id) => writeln!(f, "Web({})", id),
+            UtteranceId::Web(_) => "web",
             #[cfg(windows)]
-            UtteranceId::WinRt(id) => writeln!(f, "WinRt({id})"),
-        }
+            UtteranceId::WinRt(_) => "winrt",
+        };
+        write!(f, "{kind}:{}", self.as_u64())
+    }
+}
+
+impl FromStr for UtteranceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, _id) = split_id(s)?;
+        let id = match kind {
+            #[cfg(target_os = "android")]
+            "android" => UtteranceId::Android(_id),
+            #[cfg(target_os = "macos")]
+            "appkit" => UtteranceId::AppKit(_id),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            "avfoundation" => UtteranceId::AvFoundation(_id),
+            #[cfg(target_os = "linux")]
+            "speechd" => UtteranceId::SpeechDispatcher(_id),
+            #[cfg(target_arch = "wasm32")]
+            "web" => UtteranceId::Web(_id),
+            #[cfg(windows)]
+            "winrt" => UtteranceId::WinRt(_id),
+            _ => return Err(Error::InvalidId(s.into())),
+        };
+        Ok(id)
     }
 }
 
-unsafe impl Send for UtteranceId {}
+impl UtteranceId {
+    /// A number identifying this utterance among those spoken by its backend, for bindings and IPC that can't hold
+    /// the ID itself.
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            #[cfg(target_os = "android")]
+            UtteranceId::Android(id) => *id,
+            #[cfg(target_os = "macos")]
+            UtteranceId::AppKit(id) => *id,
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            UtteranceId::AvFoundation(id) => *id,
+            #[cfg(target_os = "linux")]
+            UtteranceId::SpeechDispatcher(id) => *id,
+            #[cfg(target_arch = "wasm32")]
+            UtteranceId::Web(id) => *id,
+            #[cfg(windows)]
+            UtteranceId::WinRt(id) => *id,
+        }
+    }
 
-unsafe impl Sync for UtteranceId {}
+    /// Returns an ID of the same kind as this one, numbered `n`. The inverse of `as_u64()`.
+    pub(crate) fn with_u64(&self, n: u64) -> UtteranceId {
+        match self {
+            #[cfg(target_os = "android")]
+            UtteranceId::Android(_) => UtteranceId::Android(n),
+            #[cfg(target_os = "macos")]
+            UtteranceId::AppKit(_) => UtteranceId::AppKit(n),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            UtteranceId::AvFoundation(_) => UtteranceId::AvFoundation(n),
+            #[cfg(target_os = "linux")]
+            UtteranceId::SpeechDispatcher(_) => UtteranceId::SpeechDispatcher(n),
+            #[cfg(target_arch = "wasm32")]
+            UtteranceId::Web(_) => UtteranceId::Web(n),
+            #[cfg(windows)]
+            UtteranceId::WinRt(_) => UtteranceId::WinRt(n),
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    all(feature = "uniffi", not(target_arch = "wasm32")),
+    derive(uniffi::Record)
+)]
+#[non_exhaustive]
 pub struct Features {
     pub is_speaking: bool,
     pub pitch: bool,
@@ -170,6 +474,18 @@ pub struct Features {
     pub voice: bool,
     pub get_voice: bool,
     pub volume: bool,
+    pub word_callbacks: bool,
+    pub synthesize: bool,
+    pub pause: bool,
+    pub queue: bool,
+    pub output_device: bool,
+    pub earcons: bool,
+    /// Whether `speak_ssml()` sends markup to the engine, rather than speaking its text.
+    pub ssml: bool,
+    /// Whether the backend's screen reader has a braille display.
+    pub braille: bool,
+    /// Whether the platform orders speech from every app by priority, as Speech Dispatcher does.
+    pub priorities: bool,
 }
 
 impl fmt::Display for Features {
@@ -182,6 +498,280 @@ impl Features {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Returns a builder for features, all unsupported until set. `Features` can't be built with a struct literal
+    /// outside this crate, so adding capabilities doesn't break code that builds it.
+    pub fn builder() -> FeaturesBuilder {
+        Default::default()
+    }
+
+    /// Whether `capability` is supported.
+    pub fn supports(&self, capability: Capability) -> bool {
+        let mut features = *self;
+        *features.flag_mut(capability)
+    }
+
+    /// Returns the supported capabilities.
+    pub fn capabilities(&self) -> impl Iterator<Item = Capability> + '_ {
+        Capability::ALL
+            .iter()
+            .copied()
+            .filter(|&capability| self.supports(capability))
+    }
+
+    fn flag_mut(&mut self, capability: Capability) -> &mut bool {
+        match capability {
+            Capability::IsSpeaking => &mut self.is_speaking,
+            Capability::Pitch => &mut self.pitch,
+            Capability::Rate => &mut self.rate,
+            Capability::Stop => &mut self.stop,
+            Capability::UtteranceCallbacks => &mut self.utterance_callbacks,
+            Capability::Voice => &mut self.voice,
+            Capability::GetVoice => &mut self.get_voice,
+            Capability::Volume => &mut self.volume,
+            Capability::WordCallbacks => &mut self.word_callbacks,
+            Capability::Synthesize => &mut self.synthesize,
+            Capability::Pause => &mut self.pause,
+            Capability::Queue => &mut self.queue,
+            Capability::OutputDevice => &mut self.output_device,
+            Capability::Earcons => &mut self.earcons,
+            Capability::Ssml => &mut self.ssml,
+            Capability::Braille => &mut self.braille,
+            Capability::Priorities => &mut self.priorities,
+        }
+    }
+}
+
+/// Builds `Features`, as returned by `Features::builder()`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FeaturesBuilder(Features);
+
+impl FeaturesBuilder {
+    /// Marks `capability` as supported.
+    pub fn with(self, capability: Capability) -> Self {
+        self.set(capability, true)
+    }
+
+    /// Marks `capability` as supported or not.
+    pub fn set(mut self, capability: Capability, supported: bool) -> Self {
+        *self.0.flag_mut(capability) = supported;
+        self
+    }
+
+    pub fn build(self) -> Features {
+        self.0
+    }
+}
+
+/// A capability a backend may have, one per field of `Features`, for querying them at runtime with
+/// `Features::supports()`, as when showing which backends support what.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+#[non_exhaustive]
+pub enum Capability {
+    IsSpeaking,
+    Pitch,
+    Rate,
+    Stop,
+    UtteranceCallbacks,
+    Voice,
+    GetVoice,
+    Volume,
+    WordCallbacks,
+    Synthesize,
+    Pause,
+    Queue,
+    OutputDevice,
+    Earcons,
+    Ssml,
+    Braille,
+    Priorities,
+}
+
+impl Capability {
+    /// Every capability, in the order of the fields of `Features`.
+    pub const ALL: &'static [Capability] = &[
+        Capability::IsSpeaking,
+        Capability::Pitch,
+        Capability::Rate,
+        Capability::Stop,
+        Capability::UtteranceCallbacks,
+        Capability::Voice,
+        Capability::GetVoice,
+        Capability::Volume,
+        Capability::WordCallbacks,
+        Capability::Synthesize,
+        Capability::Pause,
+        Capability::Queue,
+        Capability::OutputDevice,
+        Capability::Earcons,
+        Capability::Ssml,
+        Capability::Braille,
+        Capability::Priorities,
+    ];
+}
+
+/// Settings that apply to a single utterance.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UtteranceOptions {
+    /// Silence to insert before this utterance is spoken. Supported on AVFoundation.
+    pub pre_utterance_delay: Option<Duration>,
+    /// Silence to insert after this utterance is spoken. Supported on AVFoundation.
+    pub post_utterance_delay: Option<Duration>,
+    /// The language of this utterance, so the engine can pick a suitable voice for mixed-language content. Supported on the Web.
+    pub language: Option<LanguageTag<String>>,
+    /// Stereo position of this utterance, from -1.0 for full left to 1.0 for full right. Supported on Android, and on
+    /// WinRT with the `audio-output` feature.
+    pub pan: Option<f32>,
+    /// How much punctuation to speak, for reading identifiers and code. Supported by Speech Dispatcher.
+    pub punctuation: Option<PunctuationLevel>,
+    /// How to indicate capital letters. Supported by Speech Dispatcher.
+    pub capital_letters: Option<CapitalLetterMode>,
+}
+
+/// How much punctuation an utterance speaks aloud.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PunctuationLevel {
+    /// Only what the engine always speaks.
+    #[default]
+    None,
+    /// Punctuation that isn't part of ordinary prose, such as `@` and `#`.
+    Some,
+    /// Everything but the most common marks, such as commas and periods. Speech Dispatcher before 0.10 speaks all
+    /// punctuation instead.
+    Most,
+    /// Every punctuation mark.
+    All,
+}
+
+/// How an utterance indicates capital letters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CapitalLetterMode {
+    /// Capitals sound like any other letter.
+    #[default]
+    None,
+    /// Capitals are announced, as in "cap a".
+    Spell,
+    /// Capitals are preceded by a sound icon.
+    Icon,
+}
+
+/// A short sound cue, played in line with speech by `Tts::play_earcon()`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Earcon {
+    /// A sound the engine provides by name, such as Speech Dispatcher's sound icons, like `message`. Supported by
+    /// Speech Dispatcher.
+    Named(String),
+    /// A sound file. Supported on Android and WinRT, where it must be WAV with the `audio-output` feature.
+    File(PathBuf),
+}
+
+/// Synthesized speech as interleaved, signed 16-bit PCM samples.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AudioBuffer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<i16>,
+}
+
+impl AudioBuffer {
+    /// Returns the length of this audio.
+    pub fn duration(&self) -> Duration {
+        if self.sample_rate == 0 || self.channels == 0 {
+            return Duration::ZERO;
+        }
+        let frames = self.samples.len() as u64 / self.channels as u64;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Encodes this audio as a 16-bit PCM WAV file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn to_wav(&self) -> Vec<u8> {
+        let data_len = self.samples.len() as u32 * 2;
+        let block_align = self.channels * 2;
+        let mut bytes = Vec::with_capacity(44 + data_len as usize);
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&self.channels.to_le_bytes());
+        bytes.extend_from_slice(&self.sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(self.sample_rate * block_align as u32).to_le_bytes());
+        bytes.extend_from_slice(&block_align.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for sample in &self.samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Parses a 16-bit PCM WAV file.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn from_wav(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(Error::OperationFailed);
+        }
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        let mut format = None;
+        let mut offset = 12;
+        while offset + 8 <= bytes.len() {
+            let id = &bytes[offset..offset + 4];
+            let len = u32_at(offset + 4) as usize;
+            let start = offset + 8;
+            let end = start.saturating_add(len).min(bytes.len());
+            match id {
+                b"fmt " if end - start >= 16 => {
+                    let (tag, channels, sample_rate, bits) = (
+                        u16_at(start),
+                        u16_at(start + 2),
+                        u32_at(start + 4),
+                        u16_at(start + 14),
+                    );
+                    if tag != 1 || bits != 16 {
+                        return Err(Error::UnsupportedFeature);
+                    }
+                    format = Some((sample_rate, channels));
+                }
+                b"data" => {
+                    let (sample_rate, channels) = format.ok_or(Error::OperationFailed)?;
+                    let samples = bytes[start..end]
+                        .chunks_exact(2)
+                        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+                        .collect();
+                    return Ok(Self {
+                        sample_rate,
+                        channels,
+                        samples,
+                    });
+                }
+                _ => {}
+            }
+            // Chunks are padded to an even length.
+            offset = start.saturating_add(len).saturating_add(len & 1);
+        }
+        Err(Error::OperationFailed)
+    }
+}
+
+/// When a word is spoken in synthesized audio, as returned by `Tts::synthesize_with_timings()`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WordTiming {
+    /// The byte range of the text holding the word.
+    pub range: Range<usize>,
+    /// How far into the audio the word begins.
+    pub start: Duration,
+    pub duration: Duration,
 }
 
 #[derive(Debug, Error)]
@@ -195,20 +785,43 @@ pub enum Error {
     #[cfg(target_arch = "wasm32")]
     #[error("JavaScript error: [0]")]
     JavaScriptError(wasm_bindgen::JsValue),
-    #[cfg(target_os = "linux")]
+    #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
     #[error("Speech Dispatcher error: {0}")]
     SpeechDispatcher(#[from] SpeechDispatcherError),
-    #[cfg(windows)]
+    #[cfg(all(windows, feature = "winrt"))]
     #[error("WinRT error")]
     WinRt(windows::core::Error),
     #[cfg(windows)]
     #[error("UTF string conversion failed")]
     UtfStringConversionFailed(#[from] FromUtf16Error),
+    #[cfg(all(feature = "audio-output", not(target_arch = "wasm32")))]
+    #[error("Audio output error: {0}")]
+    AudioOutput(String),
+    #[cfg(all(feature = "opus", not(target_arch = "wasm32")))]
+    #[error("Opus error: {0}")]
+    Opus(#[from] audiopus::Error),
     #[error("Unsupported feature")]
     UnsupportedFeature,
+    #[error("{backend:?} backend unavailable: {reason}")]
+    BackendUnavailable { backend: Backends, reason: String },
     #[error("Out of range")]
     OutOfRange,
-    #[cfg(target_os = "android")]
+    #[error("Callback panicked: {0}")]
+    CallbackPanicked(String),
+    #[error("Backend stopped responding")]
+    BackendStalled,
+    #[error("Unknown template: {0}")]
+    UnknownTemplate(String),
+    #[error("No voice matches {0}")]
+    UnknownVoice(String),
+    #[error("Invalid ID: {0}")]
+    InvalidId(String),
+    #[error("Cancelled")]
+    Cancelled,
+    #[cfg(target_os = "macos")]
+    #[error("Must be called from the main thread")]
+    NotMainThread,
+    #[cfg(all(target_os = "android", feature = "android"))]
     #[error("JNI error: [0])]")]
     JNI(#[from] jni::errors::Error),
 }
@@ -216,9 +829,53 @@ pub enum Error {
 #[clonable]
 pub trait Backend: Clone {
     fn id(&self) -> Option<BackendId>;
+    /// Starts reporting utterance and word events, for backends that register delegates or listeners with the
+    /// platform only once callbacks are used.
+    fn enable_callbacks(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
     fn supported_features(&self) -> Features;
+    fn backend_info(&self) -> BackendInfo;
     fn speak(&mut self, text: &str, interrupt: bool) -> Result<Option<UtteranceId>, Error>;
+    fn speak_with_options(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        _options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        self.speak(text, interrupt)
+    }
     fn stop(&mut self) -> Result<(), Error>;
+    /// Replaces the engine with a new one, keeping the backend's ID and settings, and dropping speech queued on the
+    /// old one without callbacks.
+    fn reinitialize(&mut self) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn play_earcon(
+        &mut self,
+        _earcon: &Earcon,
+        _interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn synthesize(&mut self, _text: &str) -> Result<AudioBuffer, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn synthesize_to_file(&mut self, _text: &str, _path: &Path) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn synthesize_with_timings(
+        &mut self,
+        _text: &str,
+    ) -> Result<(AudioBuffer, Vec<WordTiming>), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn ssml_dialect(&self) -> SsmlDialect {
+        SsmlDialect::PlainText
+    }
+    fn speak_ssml(&mut self, _ssml: &str, _interrupt: bool) -> Result<Option<UtteranceId>, Error> {
+        Err(Error::UnsupportedFeature)
+    }
     fn min_rate(&self) -> f32;
     fn max_rate(&self) -> f32;
     fn normal_rate(&self) -> f32;
@@ -235,115 +892,708 @@ pub trait Backend: Clone {
     fn get_volume(&self) -> Result<f32, Error>;
     fn set_volume(&mut self, volume: f32) -> Result<(), Error>;
     fn is_speaking(&self) -> Result<bool, Error>;
+    fn pause(&mut self) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn resume(&mut self) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn skip(&mut self) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn is_paused(&self) -> Result<bool, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn is_pending(&self) -> Result<bool, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn queue_len(&self) -> Result<usize, Error> {
+        Err(Error::UnsupportedFeature)
+    }
     fn voices(&self) -> Result<Vec<Voice>, Error>;
     fn voice(&self) -> Result<Option<Voice>, Error>;
     fn set_voice(&mut self, voice: &Voice) -> Result<(), Error>;
+    fn output_devices(&self) -> Result<Vec<OutputDevice>, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn set_ducking(&mut self, _mode: DuckingMode) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn set_stop_style(&mut self, _style: StopStyle) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn set_target_loudness(&mut self, _loudness: Option<f32>) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn set_mix_policy(&mut self, _policy: MixPolicy) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    fn set_output_device(&mut self, _device: Option<&OutputDevice>) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    #[cfg(target_os = "ios")]
+    fn set_audio_session_options(&mut self, _options: &AudioSessionOptions) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn set_long_utterance_workaround(&mut self, _enabled: bool) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    #[cfg(all(target_os = "android", feature = "android"))]
+    fn engines(&self) -> Result<Vec<android::Engine>, Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    #[cfg(all(target_os = "android", feature = "android"))]
+    fn set_audio_usage(&mut self, _usage: android::AudioUsage) -> Result<(), Error> {
+        Err(Error::UnsupportedFeature)
+    }
+    #[cfg(all(target_os = "android", feature = "android"))]
+    fn language_availability(
+        &self,
+        _language: &LanguageTag<String>,
+    ) -> Result<android::LanguageAvailability, Error> {
+        Err(Error::UnsupportedFeature)
+    }
 }
 
-#[derive(Default)]
-struct Callbacks {
-    utterance_begin: Option<Box<dyn FnMut(UtteranceId)>>,
-    utterance_end: Option<Box<dyn FnMut(UtteranceId)>>,
-    utterance_stop: Option<Box<dyn FnMut(UtteranceId)>>,
+type WordBoundaryCallback = Box<dyn FnMut(UtteranceId, Range<usize>)>;
+
+type SentenceCallback = Box<dyn FnMut(UtteranceId, Range<usize>)>;
+
+type VisemeCallback = Box<dyn FnMut(UtteranceId, Viseme, Duration)>;
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum UtteranceEvent {
+    Begin,
+    End,
+    Stop,
+}
+
+/// Wraps an utterance callback with the crate's own bookkeeping, which runs whether or not one is set.
+fn utterance_callback(
+    backend: BackendId,
+    event: UtteranceEvent,
+    callback: Option<Box<dyn FnMut(UtteranceId)>>,
+) -> Option<Box<dyn FnMut(UtteranceId)>> {
+    let mut callback = callback;
+    let callback: Box<dyn FnMut(UtteranceId)> = Box::new(move |id| {
+        if let Some(callback) = callback.as_mut() {
+            panics::guard(backend, || callback(id));
+        }
+        highlight::utterance(backend, event, id.as_u64());
+        watchdog::utterance(backend, event, &id);
+        viseme::utterance(backend, event, &id);
+        callbacks::notify_utterance(backend, event, id);
+    });
+    let callback = speaking::utterance_callback(backend, event, Some(callback));
+    #[cfg(feature = "tracing")]
+    let callback = instrument::utterance_callback(backend, event, Some(callback));
+    let callback = chunking::utterance_callback(backend, event, callback);
+    Some(callback)
+}
+
+/// Wraps a sentence callback with the crate's own bookkeeping, which runs whether or not one is set.
+fn sentence_callback(backend: BackendId, callback: Option<SentenceCallback>) -> SentenceCallback {
+    let mut callback = callback;
+    Box::new(move |id, range| {
+        if let Some(callback) = callback.as_mut() {
+            panics::guard(backend, || callback(id, range.clone()));
+        }
+        callbacks::notify_sentence(backend, id, range);
+    })
+}
+
+/// Wraps a viseme callback with the crate's own bookkeeping, which runs whether or not one is set.
+fn viseme_callback(backend: BackendId, callback: Option<VisemeCallback>) -> VisemeCallback {
+    let mut callback = callback;
+    Box::new(move |id, viseme, at| {
+        if let Some(callback) = callback.as_mut() {
+            panics::guard(backend, || callback(id, viseme, at));
+        }
+        callbacks::notify_viseme(backend, id, viseme, at);
+    })
 }
 
-unsafe impl Send for Callbacks {}
+/// Wraps a word boundary callback with the crate's own bookkeeping, which runs whether or not one is set.
+fn word_callback(
+    backend: BackendId,
+    callback: Option<WordBoundaryCallback>,
+) -> WordBoundaryCallback {
+    let mut callback = callback;
+    let callback: WordBoundaryCallback = Box::new(move |id, range| {
+        speaking::word(backend, &id, range.start);
+        highlight::word(backend, id.as_u64(), range.clone());
+        viseme::word(backend, &id, range.clone());
+        if let Some(callback) = callback.as_mut() {
+            panics::guard(backend, || callback(id, range.clone()));
+        }
+        callbacks::notify_word(backend, id, range);
+    });
+    #[cfg(feature = "tracing")]
+    let callback = instrument::word_callback(callback);
+    chunking::word_callback(backend, callback)
+}
 
-unsafe impl Sync for Callbacks {}
+/// How many bytes of text `Tts::speak_reader()` gathers before speaking, if a paragraph runs longer.
+#[cfg(not(target_arch = "wasm32"))]
+const READ_LEN: usize = 2000;
 
-lazy_static! {
-    static ref CALLBACKS: Mutex<HashMap<BackendId, Callbacks>> = {
-        let m: HashMap<BackendId, Callbacks> = HashMap::new();
-        Mutex::new(m)
-    };
+/// How many utterances `Tts::speak_reader()` keeps queued ahead of speech.
+#[cfg(not(target_arch = "wasm32"))]
+const READ_AHEAD: usize = 2;
+
+/// What a `Tts` does with text before it reaches the backend, and when it's dropped, shared by its clones.
+#[derive(Default)]
+struct Settings {
+    filters: Vec<Arc<dyn TextFilter>>,
+    lexicon: Option<Lexicon>,
+    code_filter: CodeFilter,
+    drop_behavior: DropBehavior,
+    #[cfg(not(target_arch = "wasm32"))]
+    synthesis_cache: Option<SynthesisCache>,
 }
 
 #[derive(Clone)]
-pub struct Tts(Rc<RwLock<Box<dyn Backend>>>);
+pub struct Tts(
+    Rc<RwLock<Box<dyn Backend>>>,
+    /// This handle's own callbacks, from `with_own_callbacks()`.
+    Option<Rc<Subscription>>,
+    Rc<Mutex<Settings>>,
+);
 
+// Backends aren't all thread-safe, so these only hold if a `Tts` and its clones are used from one thread at a
+// time. `Worker` owns one on a thread of its own, for callers that can't ensure that.
 unsafe impl Send for Tts {}
 
 unsafe impl Sync for Tts {}
 
 impl Tts {
     /// Create a new `TTS` instance with the specified backend.
+    ///
+    /// The AppKit backend must be created on the main thread, and returns `Error::NotMainThread` otherwise.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", err))]
     pub fn new(backend: Backends) -> Result<Tts, Error> {
-        let backend = match backend {
-            #[cfg(target_os = "linux")]
+        match backend {
+            #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
             Backends::SpeechDispatcher => {
                 let tts = backends::SpeechDispatcher::new()?;
-                Ok(Tts(Rc::new(RwLock::new(Box::new(tts)))))
+                Ok(Tts::from_backend(tts))
             }
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(target_arch = "wasm32", feature = "web"))]
             Backends::Web => {
                 let tts = backends::Web::new()?;
-                Ok(Tts(Rc::new(RwLock::new(Box::new(tts)))))
+                Ok(Tts::from_backend(tts))
             }
             #[cfg(all(windows, feature = "tolk"))]
             Backends::Tolk => {
-                let tts = backends::Tolk::new();
-                if let Some(tts) = tts {
-                    Ok(Tts(Rc::new(RwLock::new(Box::new(tts)))))
-                } else {
-                    Err(Error::NoneError)
-                }
+                let tts = backends::Tolk::new()?;
+                Ok(Tts::from_backend(tts))
             }
-            #[cfg(windows)]
+            #[cfg(all(windows, feature = "winrt"))]
             Backends::WinRt => {
                 let tts = backends::WinRt::new()?;
-                Ok(Tts(Rc::new(RwLock::new(Box::new(tts)))))
+                Ok(Tts::from_backend(tts))
             }
-            #[cfg(target_os = "macos")]
-            Backends::AppKit => Ok(Tts(Rc::new(RwLock::new(
-                Box::new(backends::AppKit::new()?),
-            )))),
-            #[cfg(any(target_os = "macos", target_os = "ios"))]
-            Backends::AvFoundation => Ok(Tts(Rc::new(RwLock::new(Box::new(
-                backends::AvFoundation::new()?,
-            ))))),
-            #[cfg(target_os = "android")]
+            #[cfg(all(target_os = "macos", feature = "appkit"))]
+            Backends::AppKit => Ok(Tts::from_backend(backends::AppKit::new()?)),
+            #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+            Backends::AvFoundation => Ok(Tts::from_backend(backends::AvFoundation::new()?)),
+            #[cfg(all(target_os = "android", feature = "android"))]
             Backends::Android => {
                 let tts = backends::Android::new()?;
-                Ok(Tts(Rc::new(RwLock::new(Box::new(tts)))))
+                Ok(Tts::from_backend(tts))
             }
-        };
-        if let Ok(backend) = backend {
-            if let Some(id) = backend.0.read().unwrap().id() {
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                callbacks.insert(id, Callbacks::default());
+            Backends::Noop => Ok(Tts::from_backend(backends::Noop::new())),
+        }
+    }
+
+    /// Wraps `backend`, with no filters or other settings.
+    pub(crate) fn from_backend<B: Backend + 'static>(backend: B) -> Tts {
+        Tts(
+            Rc::new(RwLock::new(Box::new(backend))),
+            None,
+            Default::default(),
+        )
+    }
+
+    /// Create a new Android `TTS` instance using the engine with the given package name, such as `com.google.android.tts`.
+    ///
+    /// Use `Tts::engines()` to list the installed engines.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn new_with_engine(engine: &str) -> Result<Tts, Error> {
+        let tts = backends::Android::with_engine(Some(engine))?;
+        Ok(Tts::from_backend(tts))
+    }
+
+    /// Starts measuring latency once a tracing subscriber wants `tts::latency` events, rather than wiring up events
+    /// for every app built with the `tracing` feature.
+    #[cfg(feature = "tracing")]
+    fn trace_latency(&self) {
+        if tracing::enabled!(target: "tts::latency", tracing::Level::INFO)
+            && self.supported_features().utterance_callbacks
+        {
+            if let Err(e) = self.enable_callbacks() {
+                log::debug!("Couldn't enable callbacks to measure latency: {}", e);
             }
-            Ok(backend)
-        } else {
-            backend
         }
     }
 
-    #[allow(clippy::should_implement_trait)]
-    pub fn default() -> Result<Tts, Error> {
-        #[cfg(target_os = "linux")]
-        let tts = Tts::new(Backends::SpeechDispatcher);
-        #[cfg(all(windows, feature = "tolk"))]
-        let tts = if let Ok(tts) = Tts::new(Backends::Tolk) {
-            Ok(tts)
-        } else {
-            Tts::new(Backends::WinRt)
+    /// Wires up the backend's events the first time callbacks, or anything tracking speech through them, are used,
+    /// so apps that never use them don't register delegates or listeners with the platform.
+    fn enable_callbacks(&self) -> Result<(), Error> {
+        let Some(id) = self.0.read().id() else {
+            return Ok(());
         };
-        #[cfg(all(windows, not(feature = "tolk")))]
-        let tts = Tts::new(Backends::WinRt);
-        #[cfg(target_arch = "wasm32")]
-        let tts = Tts::new(Backends::Web);
-        #[cfg(target_os = "macos")]
-        let tts = unsafe {
-            // Needed because the Rust NSProcessInfo structs report bogus values, and I don't want to pull in a full bindgen stack.
-            let pi: id = msg_send![class!(NSProcessInfo), new];
-            let version: id = msg_send![pi, operatingSystemVersionString];
-            let str: *const c_char = msg_send![version, UTF8String];
-            let str = CStr::from_ptr(str);
-            let str = str.to_string_lossy();
-            let version: Vec<&str> = str.split(' ').collect();
-            let version = version[1];
-            let version_parts: Vec<&str> = version.split('.').collect();
-            let major_version: i8 = version_parts[0].parse().unwrap();
+        if callbacks::contains(id) {
+            return Ok(());
+        }
+        self.0.write().enable_callbacks()?;
+        let mut cb = Callbacks::default();
+        // Install the crate's own bookkeeping whether or not callbacks are set.
+        let Features {
+            utterance_callbacks,
+            word_callbacks,
+            ..
+        } = self.supported_features();
+        if utterance_callbacks {
+            for event in [
+                UtteranceEvent::Begin,
+                UtteranceEvent::End,
+                UtteranceEvent::Stop,
+            ] {
+                cb.shared
+                    .utterance(event)
+                    .set(utterance_callback(id, event, None));
+            }
+        }
+        if utterance_callbacks {
+            cb.shared
+                .sentence_begin
+                .set(Some(sentence_callback(id, None)));
+            cb.shared.viseme.set(Some(viseme_callback(id, None)));
+        }
+        if word_callbacks {
+            cb.shared.word_boundary.set(Some(word_callback(id, None)));
+        }
+        callbacks::insert(id, cb);
+        Ok(())
+    }
+
+    /// Speaks `text` from byte `start` with `speak`, in chunks if it's longer than the maximum utterance length, or a
+    /// sentence at a time if anything listens for sentences.
+    ///
+    /// `speak` is given the backend, the chunk, whether to interrupt, and whether the chunk is the first and last.
+    fn speak_chunks<F>(
+        &mut self,
+        text: &str,
+        start: usize,
+        interrupt: bool,
+        speak: F,
+    ) -> Result<Option<UtteranceId>, Error>
+    where
+        F: FnMut(&mut dyn Backend, &str, bool, bool, bool) -> Result<Option<UtteranceId>, Error>,
+    {
+        let id = self.0.read().id();
+        let rest = &text[start..];
+        let max_len = id.and_then(chunking::max_len);
+        let sentences = id.is_some_and(callbacks::wants_sentences);
+        let ranges = match max_len {
+            // Sentences longer than the maximum are split further.
+            _ if sentences => chunking::sentences(rest)
+                .into_iter()
+                .flat_map(|sentence| match max_len {
+                    Some(max_len) => chunking::split(&rest[sentence.clone()], max_len)
+                        .into_iter()
+                        .map(|range| sentence.start + range.start..sentence.start + range.end)
+                        .collect(),
+                    None => vec![sentence],
+                })
+                .collect(),
+            Some(max_len) => chunking::split(rest, max_len),
+            None => vec![],
+        };
+        if ranges.len() <= 1 && !sentences {
+            return self.speak_pieces(&[(rest, start)], interrupt, false, speak);
+        }
+        let pieces: Vec<(&str, usize)> = ranges
+            .into_iter()
+            .map(|range| (&rest[range.clone()], start + range.start))
+            .collect();
+        self.speak_pieces(&pieces, interrupt, sentences, speak)
+    }
+
+    /// Speaks `pieces` with `speak`, presented as one utterance. Each piece comes with its offset in the whole text,
+    /// and if `sentences`, is reported to sentence callbacks as it begins.
+    fn speak_pieces<F>(
+        &mut self,
+        pieces: &[(&str, usize)],
+        interrupt: bool,
+        sentences: bool,
+        mut speak: F,
+    ) -> Result<Option<UtteranceId>, Error>
+    where
+        F: FnMut(&mut dyn Backend, &str, bool, bool, bool) -> Result<Option<UtteranceId>, Error>,
+    {
+        let id = self.0.read().id();
+        callbacks::deferred(id, || {
+            let mut backend = self.0.write();
+            let Some((&(first, first_offset), rest)) = pieces.split_first() else {
+                return speak(&mut **backend, "", interrupt, true, true);
+            };
+            let utterance = speak(&mut **backend, first, interrupt, true, rest.is_empty())?;
+            // A lone piece needs no bookkeeping unless its word boundaries must be offset.
+            if rest.is_empty() && first_offset == 0 && !sentences {
+                return Ok(utterance);
+            }
+            // Without events, nothing would ever finish the group.
+            let group = id
+                .filter(|&id| callbacks::contains(id))
+                .zip(utterance.as_ref().map(|u| u.as_u64()));
+            if let Some((id, utterance)) = group {
+                chunking::add_group(
+                    id,
+                    utterance,
+                    pieces.len(),
+                    first_offset..first_offset + first.len(),
+                    sentences,
+                );
+            }
+            for (i, &(piece, offset)) in rest.iter().enumerate() {
+                let chunk = speak(&mut **backend, piece, false, false, i == rest.len() - 1)?;
+                if let Some((id, utterance)) = group {
+                    chunking::add_chunk(
+                        id,
+                        utterance,
+                        chunk.map(|c| c.as_u64()),
+                        offset..offset + piece.len(),
+                    );
+                }
+            }
+            Ok(utterance)
+        })
+    }
+
+    /// Splits text longer than `max_len` characters into several utterances, breaking between sentences where
+    /// possible, or speaks text whole if `None`.
+    ///
+    /// The pieces are presented as one utterance: `speak()` returns one ID, begin and end callbacks fire once for
+    /// the whole text, and word boundaries index into the text as given. Use this with backends that limit utterance
+    /// length, such as Android's 4000 characters, or that are slow to start speaking long text.
+    pub fn set_max_utterance_length(&mut self, max_len: Option<usize>) -> Result<&Self, Error> {
+        let id = self.0.read().id();
+        if let Some(id) = id {
+            if max_len.is_some() {
+                self.enable_callbacks()?;
+            }
+            chunking::set_max_len(id, max_len);
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Pronounces words as `lexicon` says whenever text is spoken or synthesized. `None` removes the lexicon.
+    ///
+    /// Word boundaries index into the text as the lexicon rewrote it.
+    pub fn set_lexicon(&mut self, lexicon: Option<Lexicon>) -> Result<&Self, Error> {
+        self.2.lock().lexicon = lexicon;
+        Ok(self)
+    }
+
+    /// Sets how `speak_code()` reads code, such as what each symbol is called.
+    pub fn set_code_filter(&mut self, filter: CodeFilter) -> Result<&Self, Error> {
+        self.2.lock().code_filter = filter;
+        Ok(self)
+    }
+
+    /// Sets the resolver `speak_template()` looks templates up with, or removes it if `None`.
+    pub fn set_template_resolver(
+        &mut self,
+        resolver: Option<Box<dyn TemplateResolver>>,
+    ) -> Result<&Self, Error> {
+        let id = self.0.read().id();
+        if let Some(id) = id {
+            template::set(id, resolver);
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Passes every utterance through `interceptor` just before it's spoken, to veto, rewrite or reroute it from
+    /// one place, or removes the interceptor if `None`.
+    ///
+    /// Interceptors see text spoken with `speak()` and its variants, and by channels, after filters and the
+    /// lexicon, but not SSML, spelling, dialogues or earcons. Word boundaries index into the text as rewritten.
+    pub fn set_interceptor(
+        &mut self,
+        interceptor: Option<Box<Interceptor>>,
+    ) -> Result<&Self, Error> {
+        let id = self.0.read().id();
+        if let Some(id) = id {
+            intercept::set(id, interceptor);
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Mutes or unmutes speech, as for a do-not-disturb mode, without changing how the app speaks.
+    ///
+    /// By default, muted utterances are spoken at the lowest volume, so apps relying on utterance IDs and callbacks
+    /// keep working, but `set_mute_behavior()` can discard them instead. The volume is restored when unmuted, and
+    /// `get_volume()` and `set_volume()` work with that volume meanwhile. Speech queued beforehand may still be
+    /// heard.
+    pub fn set_muted(&mut self, muted: bool) -> Result<&Self, Error> {
+        let Some(id) = self.0.read().id() else {
+            return Err(Error::UnsupportedFeature);
+        };
+        let mut mute = intercept::mute(id);
+        if mute.muted == muted {
+            return Ok(self);
+        }
+        if muted {
+            if mute.behavior == MuteBehavior::Silence && self.supported_features().volume {
+                let mut backend = self.0.write();
+                let volume = backend.get_volume()?;
+                let min = backend.min_volume();
+                backend.set_volume(min)?;
+                mute.volume = Some(volume);
+            }
+        } else if let Some(volume) = mute.volume.take() {
+            self.0.write().set_volume(volume)?;
+        }
+        mute.muted = muted;
+        intercept::set_mute(id, mute);
+        Ok(self)
+    }
+
+    /// Whether speech is muted with `set_muted()`.
+    pub fn is_muted(&self) -> bool {
+        let id = self.0.read().id();
+        id.is_some_and(|id| intercept::mute(id).muted)
+    }
+
+    /// Sets what happens to utterances while speech is muted, taking effect at once if it already is.
+    pub fn set_mute_behavior(&mut self, behavior: MuteBehavior) -> Result<&Self, Error> {
+        let Some(id) = self.0.read().id() else {
+            return Err(Error::UnsupportedFeature);
+        };
+        let muted = self.is_muted();
+        if muted {
+            self.set_muted(false)?;
+        }
+        let mut mute = intercept::mute(id);
+        mute.behavior = behavior;
+        intercept::set_mute(id, mute);
+        if muted {
+            self.set_muted(true)?;
+        }
+        Ok(self)
+    }
+
+    /// Whether utterances are discarded because speech is muted.
+    fn discards(&self) -> bool {
+        let id = self.0.read().id();
+        id.is_some_and(|id| intercept::mute(id).discards())
+    }
+
+    /// Sets what happens to queued and current speech when the last clone of this `Tts` is dropped.
+    ///
+    /// `DropBehavior::StopImmediately` requires stopping, and `DropBehavior::FinishQueue` requires `is_speaking()`
+    /// and isn't supported on the web, where dropping can't block.
+    pub fn set_drop_behavior(&mut self, behavior: DropBehavior) -> Result<&Self, Error> {
+        let Features {
+            stop, is_speaking, ..
+        } = self.supported_features();
+        let supported = match behavior {
+            DropBehavior::StopImmediately => stop,
+            DropBehavior::FinishQueue => is_speaking && !cfg!(target_arch = "wasm32"),
+            DropBehavior::Detach => true,
+        };
+        if supported {
+            self.2.lock().drop_behavior = behavior;
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Runs `filter` on text before it's spoken or synthesized. Filters run in the order they were added, then the
+    /// lexicon, if any.
+    ///
+    /// Word boundaries index into the text as the filters rewrote it.
+    pub fn add_filter(&mut self, filter: Box<dyn TextFilter>) -> Result<&Self, Error> {
+        self.2.lock().filters.push(filter.into());
+        Ok(self)
+    }
+
+    /// Removes all filters added with `add_filter()`.
+    pub fn clear_filters(&mut self) -> Result<&Self, Error> {
+        self.2.lock().filters.clear();
+        Ok(self)
+    }
+
+    /// Prepares text in `language`, or that of the current voice if `None`, to be given to the backend.
+    pub(crate) fn preprocess<'a>(
+        &self,
+        text: &'a str,
+        language: Option<&LanguageTag<String>>,
+    ) -> Cow<'a, str> {
+        // Filters are run unlocked, as they may take a while, and looking up the voice calls the backend.
+        let filters = self.2.lock().filters.clone();
+        let mut text = Cow::Borrowed(text);
+        if !filters.is_empty() {
+            let voice = match language {
+                Some(_) => None,
+                None => self.voice().ok().flatten(),
+            };
+            let language = language.or(voice.as_ref().map(|v| &v.language));
+            for filter in filters {
+                text = filter.filter(&text, language).into();
+            }
+        }
+        match &self.2.lock().lexicon {
+            Some(lexicon) => lexicon.apply(&text).into(),
+            None => text,
+        }
+    }
+
+    /// Records that an utterance of `len` bytes was queued, for tracking speaking state, measuring latency and
+    /// watching for the backend getting stuck.
+    fn spoken(&self, utterance: &Option<UtteranceId>, at: Instant, len: usize) {
+        if !self.supported_features().utterance_callbacks {
+            return;
+        }
+        let id = self.0.read().id().filter(|&id| callbacks::contains(id));
+        if let (Some(id), Some(utterance)) = (id, utterance) {
+            if speaking::spoken(id, utterance, at) {
+                watchdog::spoken(id, utterance, len);
+            }
+        }
+    }
+
+    /// Sets how announcements that come too quickly are dropped or replaced, applied to `speak()` and
+    /// `speak_with_options()`. The default speaks everything.
+    ///
+    /// Dropped announcements return no utterance ID.
+    pub fn set_announcement_policy(&mut self, policy: AnnouncementPolicy) -> Result<&Self, Error> {
+        let id = self.0.read().id();
+        if let Some(id) = id {
+            self.enable_callbacks()?;
+            policy::set(id, policy);
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Applies the announcement policy to `text`, returning whether to interrupt, or `None` to drop it.
+    fn announce(&self, text: &str, interrupt: bool) -> Option<bool> {
+        let Some(id) = self.0.read().id() else {
+            return Some(interrupt);
+        };
+        let Some((policy, Some(last))) = policy::get(id) else {
+            return Some(interrupt);
+        };
+        if policy
+            .duplicate_window
+            .is_some_and(|window| last.text == text && last.at.elapsed() < window)
+        {
+            log::debug!("Dropping duplicate announcement");
+            return None;
+        }
+        if policy.replace_unfinished && !interrupt {
+            let unfinished = if self.supported_features().utterance_callbacks {
+                last.utterance
+                    .is_some_and(|utterance| speaking::is_pending(id, utterance))
+            } else {
+                self.is_speaking().unwrap_or(false)
+            };
+            return Some(unfinished);
+        }
+        Some(interrupt)
+    }
+
+    fn announced(&self, text: &str, utterance: &Option<UtteranceId>) {
+        if let Some(id) = self.0.read().id() {
+            policy::announced(id, text, utterance.as_ref().map(|u| u.as_u64()));
+        }
+    }
+
+    /// Creates a `Tts` with the usual backend for this platform.
+    ///
+    /// The `TTS_BACKEND` environment variable, such as `noop`, `speechd` or `winrt`, overrides the backend, so speech
+    /// can be silenced or redirected without recompiling. `TTS_RATE` and `TTS_VOICE`, a voice ID, name or
+    /// `Voice::stable_id()` token, are then applied if set. Unknown or unsupported values are logged and ignored.
+    ///
+    /// On Linux, this fails with `Error::BackendUnavailable` when Speech Dispatcher isn't running.
+    #[allow(clippy::should_implement_trait)]
+    pub fn default() -> Result<Tts, Error> {
+        let mut tts = match env::var("TTS_BACKEND") {
+            Ok(name) => match Backends::from_name(&name) {
+                Some(backend) => Tts::new(backend),
+                None => {
+                    log::warn!("Unknown TTS_BACKEND {:?}, using the default backend", name);
+                    Tts::platform_default()
+                }
+            },
+            Err(_) => Tts::platform_default(),
+        }?;
+        tts.apply_environment();
+        Ok(tts)
+    }
+
+    /// Discards speech in place of `backend`, which isn't available, saying so in the log and `backend_info()`.
+    #[cfg(all(target_arch = "wasm32", feature = "web", feature = "web_noop_fallback"))]
+    fn fallback(backend: Backends, reason: &str) -> Tts {
+        log::warn!(
+            "{} backend unavailable ({}), so speech will be discarded",
+            backend.to_string().trim_end(),
+            reason
+        );
+        let noop = backends::Noop::fallback(backend);
+        Tts::from_backend(noop)
+    }
+
+    fn platform_default() -> Result<Tts, Error> {
+        #[cfg(all(target_os = "linux", feature = "speech_dispatcher"))]
+        let tts = Tts::new(Backends::SpeechDispatcher);
+        #[cfg(all(windows, feature = "tolk", feature = "winrt"))]
+        let tts = if let Ok(tts) = Tts::new(Backends::Tolk) {
+            Ok(tts)
+        } else {
+            Tts::new(Backends::WinRt)
+        };
+        #[cfg(all(windows, feature = "tolk", not(feature = "winrt")))]
+        let tts = Tts::new(Backends::Tolk);
+        #[cfg(all(windows, feature = "winrt", not(feature = "tolk")))]
+        let tts = Tts::new(Backends::WinRt);
+        #[cfg(all(
+            target_arch = "wasm32",
+            feature = "web",
+            not(feature = "web_noop_fallback")
+        ))]
+        let tts = Tts::new(Backends::Web);
+        #[cfg(all(target_arch = "wasm32", feature = "web", feature = "web_noop_fallback"))]
+        let tts = match Tts::new(Backends::Web) {
+            Err(Error::BackendUnavailable { backend, reason }) => {
+                Ok(Tts::fallback(backend, &reason))
+            }
+            tts => tts,
+        };
+        #[cfg(all(target_os = "macos", feature = "appkit", feature = "av_foundation"))]
+        let tts = unsafe {
+            // Needed because the Rust NSProcessInfo structs report bogus values, and I don't want to pull in a full bindgen stack.
+            let pi: id = msg_send![class!(NSProcessInfo), new];
+            let version: id = msg_send![pi, operatingSystemVersionString];
+            let str: *const c_char = msg_send![version, UTF8String];
+            let str = CStr::from_ptr(str);
+            let str = str.to_string_lossy();
+            let version: Vec<&str> = str.split(' ').collect();
+            let version = version[1];
+            let version_parts: Vec<&str> = version.split('.').collect();
+            let major_version: i8 = version_parts[0].parse().unwrap();
             let minor_version: i8 = version_parts[1].parse().unwrap();
             if major_version >= 11 || minor_version >= 14 {
                 Tts::new(Backends::AvFoundation)
@@ -351,35 +1601,832 @@ impl Tts {
                 Tts::new(Backends::AppKit)
             }
         };
-        #[cfg(target_os = "ios")]
-        let tts = Tts::new(Backends::AvFoundation);
-        #[cfg(target_os = "android")]
-        let tts = Tts::new(Backends::Android);
-        tts
+        #[cfg(all(
+            target_os = "macos",
+            feature = "appkit",
+            not(feature = "av_foundation")
+        ))]
+        let tts = Tts::new(Backends::AppKit);
+        #[cfg(all(
+            target_os = "macos",
+            feature = "av_foundation",
+            not(feature = "appkit")
+        ))]
+        let tts = Tts::new(Backends::AvFoundation);
+        #[cfg(all(target_os = "ios", feature = "av_foundation"))]
+        let tts = Tts::new(Backends::AvFoundation);
+        #[cfg(all(target_os = "android", feature = "android"))]
+        let tts = Tts::new(Backends::Android);
+        // Without a backend for this platform, speech is silently discarded.
+        #[cfg(not(any(
+            all(target_os = "linux", feature = "speech_dispatcher"),
+            all(windows, any(feature = "tolk", feature = "winrt")),
+            all(target_arch = "wasm32", feature = "web"),
+            all(
+                target_os = "macos",
+                any(feature = "appkit", feature = "av_foundation")
+            ),
+            all(target_os = "ios", feature = "av_foundation"),
+            all(target_os = "android", feature = "android"),
+        )))]
+        let tts = Tts::new(Backends::Noop);
+        tts
+    }
+
+    /// Applies `TTS_RATE` and `TTS_VOICE`.
+    fn apply_environment(&mut self) {
+        if let Ok(rate) = env::var("TTS_RATE") {
+            match rate.parse() {
+                Ok(rate) => {
+                    if let Err(e) = self.set_rate(rate) {
+                        log::warn!("Couldn't apply TTS_RATE {}: {}", rate, e);
+                    }
+                }
+                Err(_) => log::warn!("Invalid TTS_RATE {:?}", rate),
+            }
+        }
+        if let Ok(name) = env::var("TTS_VOICE") {
+            if let Err(e) = self.set_voice_by_id(&name) {
+                log::warn!("Couldn't apply TTS_VOICE {:?}: {}", name, e);
+            }
+        }
+    }
+
+    /// Returns which engine this speaks with, and its version where known.
+    ///
+    /// This may query the engine, so call it when logging rather than before each utterance.
+    pub fn backend_info(&self) -> BackendInfo {
+        self.0.read().backend_info()
+    }
+
+    /// Returns the features supported by this TTS engine
+    pub fn supported_features(&self) -> Features {
+        self.0.read().supported_features()
+    }
+
+    /// Speaks the specified text, optionally interrupting current speech.
+    ///
+    /// This and the other methods that speak return once the text is queued, without waiting for it to be
+    /// synthesized or played, so they can be called from input handlers. Speech Dispatcher and Tolk wait for the
+    /// server or screen reader to accept the text, which is quick unless it has hung. Errors after queueing, such as
+    /// failed synthesis, go to `on_error()`.
+    ///
+    /// Since 0.27, this and the other methods taking text accept `AsRef<str>` rather than `Into<String>`, so borrowed
+    /// text isn't copied. `String` and `&str` work as before, but other types, such as `char`, must be converted
+    /// first, and generic callers need an `AsRef<str>` bound.
+    pub fn speak<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let text = self.preprocess(text.as_ref(), None);
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tts.speak", len = text.len(), interrupt).entered();
+        let Some(interrupt) = self.announce(&text, interrupt) else {
+            return Ok(None);
+        };
+        let utterance = self.submit(&text, interrupt, None)?;
+        self.announced(&text, &utterance);
+        Ok(utterance)
+    }
+
+    /// Speaks `text` from the start of the sentence containing byte `offset`, optionally interrupting current speech,
+    /// to resume reading a long text where it left off, such as at the offset `progress()` reported before speech
+    /// stopped or the app exited.
+    ///
+    /// Offsets index into the text after filters and the lexicon, as word boundaries report them. Word boundaries and
+    /// `progress()` index into the whole text, not the part spoken, so their offsets can be resumed from in turn.
+    pub fn speak_from<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        offset: usize,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let text = self.preprocess(text.as_ref(), None);
+        let start = chunking::sentence_start(&text, offset);
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::debug_span!("tts.speak_from", len = text.len(), start, interrupt).entered();
+        let Some(interrupt) = self.announce(&text[start..], interrupt) else {
+            return Ok(None);
+        };
+        let utterance = self.submit_from(&text, start, interrupt, None)?;
+        self.announced(&text[start..], &utterance);
+        Ok(utterance)
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, calling `highlight` with the byte range of `text`
+    /// holding each word as it's spoken, as for karaoke-style highlighting.
+    ///
+    /// Ranges index into `text` as given, whichever offsets the backend reports and however filters or the lexicon
+    /// rewrote it. A rewritten word highlights the words it replaced, and words that filters added aren't
+    /// highlighted. Requires word boundary callbacks.
+    pub fn speak_with_highlights<S, F>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+        highlight: F,
+    ) -> Result<Option<UtteranceId>, Error>
+    where
+        S: AsRef<str>,
+        F: FnMut(Range<usize>) + 'static,
+    {
+        let id = self.0.read().id();
+        if !self.supported_features().word_callbacks || id.is_none() {
+            return Err(Error::UnsupportedFeature);
+        }
+        let original = text.as_ref();
+        let text = self.preprocess(original, None);
+        let Some((mut tts, request)) = self.intercept(&text, interrupt, None) else {
+            return Ok(None);
+        };
+        let (text, interrupt, options) = match request {
+            Some(request) => (
+                Cow::Owned(request.text),
+                request.interrupt,
+                Some(request.options),
+            ),
+            None => (text, interrupt, None),
+        };
+        tts.enable_callbacks()?;
+        let utterance = tts.deliver(&text, 0, interrupt, options.as_ref())?;
+        // A rerouted utterance is highlighted from the backend it was rerouted to.
+        let id = tts.0.read().id();
+        if let (Some(id), Some(utterance)) = (id, &utterance) {
+            highlight::add(id, utterance.as_u64(), original, &text, Box::new(highlight));
+        }
+        Ok(utterance)
+    }
+
+    /// Speaks the specified text as an ARIA live region of the given politeness would: assertive speech interrupts,
+    /// polite speech waits for current speech to finish, and speech that's off isn't spoken.
+    pub fn speak_with_politeness<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        politeness: Politeness,
+    ) -> Result<Option<UtteranceId>, Error> {
+        match politeness {
+            Politeness::Off => Ok(None),
+            Politeness::Polite => self.speak(text, false),
+            Politeness::Assertive => self.speak(text, true),
+        }
+    }
+
+    /// Speaks the specified text after current speech finishes, like a polite ARIA live region.
+    pub fn speak_polite<S: AsRef<str>>(&mut self, text: S) -> Result<Option<UtteranceId>, Error> {
+        self.speak_with_politeness(text, Politeness::Polite)
+    }
+
+    /// Speaks the specified text immediately, interrupting current speech, like an assertive ARIA live region.
+    pub fn speak_assertive<S: AsRef<str>>(
+        &mut self,
+        text: S,
+    ) -> Result<Option<UtteranceId>, Error> {
+        self.speak_with_politeness(text, Politeness::Assertive)
+    }
+
+    /// Speaks `text` as source code, naming its punctuation, reading identifiers as words and announcing indentation,
+    /// as the filter from `set_code_filter()`, or the default one, says.
+    ///
+    /// `language_hint` is the programming language, such as `python` or `rust`, which decides whether indentation is
+    /// announced by default. Text filters and the lexicon then apply as usual, and word boundaries index into the text
+    /// as spoken.
+    pub fn speak_code<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+        language_hint: Option<&str>,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let text = text.as_ref();
+        let text = self.2.lock().code_filter.verbalize(text, language_hint);
+        self.speak(text, interrupt)
+    }
+
+    /// Speaks what `reader` yields, a paragraph at a time, optionally interrupting current speech with the first, so a
+    /// file or standard input can be read aloud without loading it all into memory. Returns the last utterance once
+    /// everything is queued.
+    ///
+    /// Paragraphs end at blank lines. Longer ones are broken after sentences, and each piece is spoken like text
+    /// from `speak()`, so it's split further if `set_max_utterance_length()` says to. Reading waits while speech is
+    /// a few utterances ahead if the backend supports utterance callbacks, or while it's speaking if it only supports
+    /// `is_speaking()`. On macOS, the run loop is pumped while waiting.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn speak_reader<R: BufRead>(
+        &mut self,
+        reader: R,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let Features {
+            is_speaking,
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        let id = self.0.read().id();
+        let tracked = id.filter(|_| utterance_callbacks);
+        if tracked.is_some() {
+            self.enable_callbacks()?;
+        }
+        let mut queued: VecDeque<u64> = VecDeque::new();
+        let mut last = None;
+        let mut interrupt = interrupt;
+        let mut paragraph = String::new();
+        let mut lines = reader.lines();
+        loop {
+            let line = lines.next().transpose()?;
+            let end = match &line {
+                Some(line) if !line.trim().is_empty() => {
+                    if !paragraph.is_empty() {
+                        paragraph.push('\n');
+                    }
+                    paragraph.push_str(line.trim_end());
+                    if paragraph.len() < READ_LEN {
+                        continue;
+                    }
+                    // Hold back the unfinished sentence, unless it's all there is.
+                    match chunking::sentence_start(&paragraph, paragraph.len()) {
+                        0 => paragraph.len(),
+                        start => start,
+                    }
+                }
+                _ => paragraph.len(),
+            };
+            if !paragraph[..end].trim().is_empty() {
+                let mut wait = || match tracked {
+                    Some(id) => {
+                        queued.retain(|&utterance| speaking::is_pending(id, utterance));
+                        queued.len() < READ_AHEAD
+                    }
+                    None if is_speaking && !interrupt => !self.is_speaking().unwrap_or(false),
+                    None => true,
+                };
+                while !wait() {
+                    #[cfg(target_os = "macos")]
+                    Tts::pump_run_loop(Duration::from_millis(10));
+                    #[cfg(not(target_os = "macos"))]
+                    thread::sleep(Duration::from_millis(10));
+                }
+                if let Some(utterance) = self.speak(&paragraph[..end], interrupt)? {
+                    queued.push_back(utterance.as_u64());
+                    last = Some(utterance);
+                }
+                interrupt = false;
+            }
+            paragraph.replace_range(..end, "");
+            let trimmed = paragraph.len() - paragraph.trim_start().len();
+            paragraph.replace_range(..trimmed, "");
+            if line.is_none() {
+                return Ok(last);
+            }
+        }
+    }
+
+    /// Returns the channel named `name`, a queue of speech that can be interrupted and stopped without affecting other
+    /// channels.
+    ///
+    /// Requires utterance callbacks and stopping.
+    pub fn channel<S: Into<String>>(&self, name: S) -> Result<Channel, Error> {
+        let Features {
+            stop,
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        if stop && utterance_callbacks && self.0.read().id().is_some() {
+            self.enable_callbacks()?;
+            Ok(Channel::new(self.clone(), name.into()))
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Returns an empty dialogue, to add lines spoken by different voices to, then speak as one utterance.
+    ///
+    /// Requires voices, utterance callbacks and stopping.
+    pub fn dialogue(&self) -> Result<Dialogue, Error> {
+        let Features {
+            stop,
+            utterance_callbacks,
+            voice,
+            ..
+        } = self.supported_features();
+        if stop && utterance_callbacks && voice && self.0.read().id().is_some() {
+            self.enable_callbacks()?;
+            Ok(Dialogue::new(self.clone()))
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Speaks each line with its voice, presented as one utterance, then restores the voice if it's known.
+    pub(crate) fn speak_dialogue(
+        &mut self,
+        lines: &[(Voice, String)],
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        if self.discards() {
+            return Ok(None);
+        }
+        let previous = if self.supported_features().get_voice {
+            self.voice()?
+        } else {
+            None
+        };
+        let texts: Vec<Cow<str>> = lines
+            .iter()
+            .map(|(voice, text)| self.preprocess(text, Some(&voice.language)))
+            .collect();
+        // Offsets are into the lines joined by newlines.
+        let mut offset = 0;
+        let pieces: Vec<(&str, usize)> = texts
+            .iter()
+            .map(|text| {
+                let piece = (text.as_ref(), offset);
+                offset += text.len() + 1;
+                piece
+            })
+            .collect();
+        let mut voices = lines.iter().map(|(voice, _)| voice);
+        let spoken_at = Instant::now();
+        let utterance = self.speak_pieces(
+            &pieces,
+            interrupt,
+            false,
+            |backend, piece, interrupt, _, _| {
+                if let Some(voice) = voices.next() {
+                    backend.set_voice(voice)?;
+                }
+                backend.speak(piece, interrupt)
+            },
+        );
+        if let Some(previous) = previous {
+            self.set_voice(&previous)?;
+        }
+        let utterance = utterance?;
+        let len = texts.iter().map(|text| text.len()).sum();
+        self.spoken(&utterance, spoken_at, len);
+        Ok(utterance)
+    }
+
+    /// Speaks the specified text with options that apply only to this utterance.
+    ///
+    /// Options not supported by the current backend are ignored.
+    pub fn speak_with_options<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        interrupt: bool,
+        options: &UtteranceOptions,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let text = self.preprocess(text.as_ref(), options.language.as_ref());
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tts.speak", len = text.len(), interrupt).entered();
+        let Some(interrupt) = self.announce(&text, interrupt) else {
+            return Ok(None);
+        };
+        let utterance = self.submit(&text, interrupt, Some(options))?;
+        self.announced(&text, &utterance);
+        Ok(utterance)
+    }
+
+    /// Speaks the template `key`, with `args` interpolated, as resolved by the resolver from
+    /// `set_template_resolver()`.
+    ///
+    /// If the text is in a language the current voice doesn't speak, this switches to the best voice for it, which
+    /// remains the voice afterward.
+    pub fn speak_template(
+        &mut self,
+        key: &str,
+        args: &[(&str, &str)],
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let resolver = match self.0.read().id() {
+            Some(id) => template::resolver(id),
+            None => return Err(Error::UnsupportedFeature),
+        };
+        let Localized { text, language } = resolver
+            .and_then(|resolver| resolver.resolve(key, args))
+            .ok_or_else(|| Error::UnknownTemplate(key.into()))?;
+        if let Some(language) = &language {
+            self.switch_voice(language);
+        }
+        let options = UtteranceOptions {
+            language,
+            ..Default::default()
+        };
+        self.speak_with_options(text, interrupt, &options)
+    }
+
+    /// Switches to the best voice for `language`, unless the current one speaks it. Failures leave the voice as is.
+    fn switch_voice(&mut self, language: &LanguageTag<String>) {
+        let Features {
+            voice, get_voice, ..
+        } = self.supported_features();
+        if !voice {
+            return;
+        }
+        if get_voice {
+            if let Ok(Some(current)) = self.voice() {
+                if current.matches(language) >= MatchQuality::Script {
+                    return;
+                }
+            }
+        }
+        if let Ok(Some(best)) = self.best_voice(std::slice::from_ref(language)) {
+            if let Err(e) = self.set_voice(&best) {
+                log::warn!("Couldn't switch to a voice for {}: {}", language, e);
+            }
+        }
+    }
+
+    /// Speaks preprocessed `text`, with `options` if given.
+    pub(crate) fn submit(
+        &mut self,
+        text: &str,
+        interrupt: bool,
+        options: Option<&UtteranceOptions>,
+    ) -> Result<Option<UtteranceId>, Error> {
+        self.submit_from(text, 0, interrupt, options)
+    }
+
+    /// Speaks preprocessed `text` from byte `start`, with `options` if given, as the interceptor, if any, says.
+    fn submit_from(
+        &mut self,
+        text: &str,
+        start: usize,
+        interrupt: bool,
+        options: Option<&UtteranceOptions>,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let Some((mut tts, request)) = self.intercept(&text[start..], interrupt, options) else {
+            return Ok(None);
+        };
+        match request {
+            Some(request) if request.text != text[start..] => {
+                tts.deliver(&request.text, 0, request.interrupt, Some(&request.options))
+            }
+            Some(request) => tts.deliver(text, start, request.interrupt, Some(&request.options)),
+            None => tts.deliver(text, start, interrupt, options),
+        }
+    }
+
+    /// Runs the interceptor, if any, on `text`, returning the `Tts` to speak it with and the request as the
+    /// interceptor left it, or `None` to drop it. Without an interceptor, the request is `None`.
+    fn intercept(
+        &self,
+        text: &str,
+        interrupt: bool,
+        options: Option<&UtteranceOptions>,
+    ) -> Option<(Tts, Option<UtteranceRequest>)> {
+        if self.discards() {
+            log::debug!("Muted, so discarding utterance");
+            return None;
+        }
+        let id = self.0.read().id();
+        let Some((id, interceptor)) = id.and_then(|id| Some((id, intercept::interceptor(id)?)))
+        else {
+            return Some((self.clone(), None));
+        };
+        let mut request = UtteranceRequest {
+            text: text.into(),
+            interrupt,
+            options: options.cloned().unwrap_or_default(),
+        };
+        // A panicking interceptor lets the utterance through as it was.
+        let mut action = InterceptAction::Speak;
+        panics::guard(id, || {
+            let mut rewritten = request.clone();
+            action = interceptor(&mut rewritten);
+            request = rewritten;
+        });
+        match action {
+            InterceptAction::Speak => Some((self.clone(), Some(request))),
+            InterceptAction::Drop => {
+                log::debug!("Interceptor dropped utterance");
+                None
+            }
+            InterceptAction::Reroute(tts) => Some((tts, Some(request))),
+        }
     }
 
-    /// Returns the features supported by this TTS engine
-    pub fn supported_features(&self) -> Features {
-        self.0.read().unwrap().supported_features()
+    /// Gives preprocessed `text` from byte `start` to the backend, with `options` if given.
+    fn deliver(
+        &mut self,
+        text: &str,
+        start: usize,
+        interrupt: bool,
+        options: Option<&UtteranceOptions>,
+    ) -> Result<Option<UtteranceId>, Error> {
+        // Notice a stuck backend, and perhaps reinitialize it, before giving it more to speak.
+        let _ = self.check_watchdog();
+        #[cfg(feature = "tracing")]
+        self.trace_latency();
+        let spoken_at = Instant::now();
+        let utterance = self.speak_chunks(
+            text,
+            start,
+            interrupt,
+            |backend, chunk, interrupt, first, last| {
+                match options {
+                    Some(options) => {
+                        // Delays surround the whole text, not each chunk.
+                        let options = UtteranceOptions {
+                            pre_utterance_delay: options.pre_utterance_delay.filter(|_| first),
+                            post_utterance_delay: options.post_utterance_delay.filter(|_| last),
+                            ..options.clone()
+                        };
+                        backend.speak_with_options(chunk, interrupt, &options)
+                    }
+                    None => backend.speak(chunk, interrupt),
+                }
+            },
+        )?;
+        self.spoken(&utterance, spoken_at, text.len() - start);
+        let id = self
+            .0
+            .read()
+            .id()
+            .filter(|&id| callbacks::wants_visemes(id));
+        if let (Some(id), Some(utterance)) = (id, &utterance) {
+            let words = self.supported_features().word_callbacks;
+            viseme::spoken(id, utterance, text, start, words);
+        }
+        Ok(utterance)
     }
 
-    /// Speaks the specified text, optionally interrupting current speech.
-    pub fn speak<S: Into<String>>(
+    /// Returns the SSML the current backend accepts.
+    pub fn ssml_dialect(&self) -> SsmlDialect {
+        self.0.read().ssml_dialect()
+    }
+
+    /// Speaks `ssml`, rendered in the dialect the current backend accepts, optionally interrupting current speech.
+    ///
+    /// Backends without SSML speak its text. Text filters, the lexicon and `set_max_utterance_length()` apply only
+    /// then, since they would otherwise break the markup.
+    pub fn speak_ssml(
+        &mut self,
+        ssml: &SsmlBuilder,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let dialect = self.ssml_dialect();
+        if dialect == SsmlDialect::PlainText {
+            return self.speak(ssml.render(dialect), interrupt);
+        }
+        if self.discards() {
+            return Ok(None);
+        }
+        let language = self.voice().ok().flatten().map(|v| v.language);
+        let ssml = ssml.render_in(dialect, language.as_ref());
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tts.speak_ssml", len = ssml.len(), interrupt).entered();
+        let spoken_at = Instant::now();
+        let id = self.0.read().id();
+        let utterance = callbacks::deferred(id, || self.0.write().speak_ssml(&ssml, interrupt))?;
+        self.spoken(&utterance, spoken_at, ssml.len());
+        Ok(utterance)
+    }
+
+    /// Spells `text` a character at a time, as for passwords and confirmation codes, optionally interrupting current
+    /// speech.
+    ///
+    /// The characters are presented as one utterance, like long text split with `set_max_utterance_length()`. Text
+    /// filters and the lexicon don't apply.
+    pub fn spell<S: AsRef<str>>(
         &mut self,
         text: S,
         interrupt: bool,
+        style: &SpellStyle,
     ) -> Result<Option<UtteranceId>, Error> {
-        self.0
-            .write()
-            .unwrap()
-            .speak(text.into().as_str(), interrupt)
+        if self.discards() {
+            return Ok(None);
+        }
+        let text = text.as_ref();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tts.spell", len = text.len(), interrupt).entered();
+        let pieces = spell::pieces(text, style);
+        let pieces: Vec<(&str, usize)> = pieces.iter().map(|(p, i)| (p.as_str(), *i)).collect();
+        let spoken_at = Instant::now();
+        let utterance = self.speak_pieces(
+            &pieces,
+            interrupt,
+            false,
+            |backend, piece, interrupt, _, last| {
+                if last || style.pause.is_zero() {
+                    backend.speak(piece, interrupt)
+                } else {
+                    let options = UtteranceOptions {
+                        post_utterance_delay: Some(style.pause),
+                        ..Default::default()
+                    };
+                    backend.speak_with_options(piece, interrupt, &options)
+                }
+            },
+        )?;
+        let len = pieces.iter().map(|(piece, _)| piece.len()).sum();
+        self.spoken(&utterance, spoken_at, len);
+        Ok(utterance)
+    }
+
+    /// Plays `earcon` in the speech queue, after speech already queued and before speech queued later, optionally
+    /// interrupting current speech.
+    ///
+    /// Earcons get utterance IDs and callbacks where the backend reports them, like the speech around them.
+    pub fn play_earcon(
+        &mut self,
+        earcon: &Earcon,
+        interrupt: bool,
+    ) -> Result<Option<UtteranceId>, Error> {
+        let Features { earcons, .. } = self.supported_features();
+        if !earcons {
+            return Err(Error::UnsupportedFeature);
+        }
+        if self.discards() {
+            return Ok(None);
+        }
+        let spoken_at = Instant::now();
+        let id = self.0.read().id();
+        let utterance = callbacks::deferred(id, || self.0.write().play_earcon(earcon, interrupt))?;
+        self.spoken(&utterance, spoken_at, 0);
+        Ok(utterance)
     }
 
     /// Stops current speech.
     pub fn stop(&mut self) -> Result<&Self, Error> {
         let Features { stop, .. } = self.supported_features();
         if stop {
-            self.0.write().unwrap().stop()?;
+            // Stopping fires stop callbacks on some backends, which may call back into this `Tts`.
+            let id = self.0.read().id();
+            callbacks::deferred(id, || self.0.write().stop())?;
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Synthesizes the specified text to audio without speaking it, using the current voice, rate, pitch and volume.
+    ///
+    /// Blocks until synthesis completes.
+    pub fn synthesize<S: AsRef<str>>(&mut self, text: S) -> Result<AudioBuffer, Error> {
+        let Features { synthesize, .. } = self.supported_features();
+        if synthesize {
+            let text = self.preprocess(text.as_ref(), None);
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("tts.synthesize", len = text.len()).entered();
+            #[cfg(not(target_arch = "wasm32"))]
+            let cached = self.synthesis_cache_key(&text);
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(audio) = cached.as_ref().and_then(|(cache, key)| cache.get(key)) {
+                return Ok(audio);
+            }
+            let audio = self.0.write().synthesize(&text)?;
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some((cache, key)) = cached {
+                if let Err(e) = cache.insert(&key, &audio) {
+                    log::warn!("Failed to cache synthesized speech: {}", e);
+                }
+            }
+            Ok(audio)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Synthesizes the specified text like `synthesize()`, converted to `format`, as for telephony or VoIP.
+    ///
+    /// Engines synthesize at their own rate, so audio is resampled and remixed here when it doesn't match.
+    pub fn synthesize_as<S: AsRef<str>>(
+        &mut self,
+        text: S,
+        format: &AudioFormat,
+    ) -> Result<EncodedAudio, Error> {
+        Ok(format.encode(&self.synthesize(text)?))
+    }
+
+    /// Synthesizes the specified text like `synthesize()`, also returning when each word is spoken, as for subtitles
+    /// or lip sync.
+    ///
+    /// Supported on Android 8 and later, if the engine reports word ranges, and WinRT. Ranges index into the text after
+    /// filters and the lexicon, as word boundaries do. The synthesis cache doesn't apply.
+    pub fn synthesize_with_timings<S: AsRef<str>>(
+        &mut self,
+        text: S,
+    ) -> Result<(AudioBuffer, Vec<WordTiming>), Error> {
+        let Features { synthesize, .. } = self.supported_features();
+        if synthesize {
+            let text = self.preprocess(text.as_ref(), None);
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("tts.synthesize", len = text.len()).entered();
+            self.0.write().synthesize_with_timings(&text)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Synthesizes each of `items` like `synthesize()`, one at a time, returning their results in order.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn synthesize_batch(&mut self, items: &[BatchItem]) -> Vec<Result<AudioBuffer, Error>> {
+        self.synthesize_batch_with(items, BatchOptions::default())
+    }
+
+    /// Synthesizes each of `items` like `synthesize()`, as `options` says, returning their results in order.
+    ///
+    /// With more than one item at a time, extra instances of the backend synthesize on threads of their own, with this
+    /// one's voice, rate, pitch and volume, and the text is filtered here first. Items left when the batch is
+    /// cancelled return `Error::Cancelled`. Settings changed for items are restored afterward. The synthesis cache
+    /// doesn't apply.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn synthesize_batch_with(
+        &mut self,
+        items: &[BatchItem],
+        options: BatchOptions,
+    ) -> Vec<Result<AudioBuffer, Error>> {
+        let Features { synthesize, .. } = self.supported_features();
+        if !synthesize {
+            return items
+                .iter()
+                .map(|_| Err(Error::UnsupportedFeature))
+                .collect();
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("tts.synthesize_batch", len = items.len()).entered();
+        batch::run(self, items, options)
+    }
+
+    /// Caches synthesized speech on disk, so `synthesize()` returns repeated text without synthesizing it again.
+    /// `None` disables caching.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_synthesis_cache(&mut self, cache: Option<SynthesisCache>) -> Result<&Self, Error> {
+        let Features { synthesize, .. } = self.supported_features();
+        if synthesize {
+            self.2.lock().synthesis_cache = cache;
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Returns the cache, if any, and the key identifying `text` spoken with the current settings.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn synthesis_cache_key(&self, text: &str) -> Option<(SynthesisCache, String)> {
+        let id = self.0.read().id()?;
+        let cache = self.2.lock().synthesis_cache.clone()?;
+        let voice = self.voice().ok().flatten().map(|v| v.id);
+        let key = format!(
+            "{:?}\0{:?}\0{:?}\0{:?}\0{:?}\0{}",
+            id.backend(),
+            voice,
+            self.get_rate().ok(),
+            self.get_pitch().ok(),
+            self.get_volume().ok(),
+            text
+        );
+        Some((cache, key))
+    }
+
+    /// Synthesizes the specified text to a file at `path` without speaking it, as WAV unless the extension of `path`
+    /// names another format `FileFormat::from_path()` knows. Extensions of formats that can't be written, such as
+    /// `.mp3`, return `Error::UnsupportedFeature`.
+    ///
+    /// Blocks until synthesis completes.
+    pub fn synthesize_to_file<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        text: S,
+        path: P,
+    ) -> Result<&Self, Error> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let format = FileFormat::from_path(path.as_ref())?;
+            if format != FileFormat::Wav {
+                return self.synthesize_to_file_as(text, path, &format);
+            }
+        }
+        self.synthesize_to_wav(text.as_ref(), path.as_ref())
+    }
+
+    /// Synthesizes the specified text to a file at `path` in `format` without speaking it.
+    ///
+    /// Blocks until synthesis completes. Formats other than WAV are encoded from `synthesize()`, whose cache applies.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn synthesize_to_file_as<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        text: S,
+        path: P,
+        format: &FileFormat,
+    ) -> Result<&Self, Error> {
+        match format {
+            FileFormat::Wav => self.synthesize_to_wav(text.as_ref(), path.as_ref()),
+            #[cfg(feature = "opus")]
+            FileFormat::Opus(_) => {
+                let audio = self.synthesize(text)?;
+                std::fs::write(path, format.encode(&audio)?)?;
+                Ok(self)
+            }
+        }
+    }
+
+    fn synthesize_to_wav(&mut self, text: &str, path: &Path) -> Result<&Self, Error> {
+        let Features { synthesize, .. } = self.supported_features();
+        if synthesize {
+            let text = self.preprocess(text, None);
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("tts.synthesize", len = text.len()).entered();
+            self.0.write().synthesize_to_file(&text, path)?;
             Ok(self)
         } else {
             Err(Error::UnsupportedFeature)
@@ -388,24 +2435,24 @@ impl Tts {
 
     /// Returns the minimum rate for this speech synthesizer.
     pub fn min_rate(&self) -> f32 {
-        self.0.read().unwrap().min_rate()
+        self.0.read().min_rate()
     }
 
     /// Returns the maximum rate for this speech synthesizer.
     pub fn max_rate(&self) -> f32 {
-        self.0.read().unwrap().max_rate()
+        self.0.read().max_rate()
     }
 
     /// Returns the normal rate for this speech synthesizer.
     pub fn normal_rate(&self) -> f32 {
-        self.0.read().unwrap().normal_rate()
+        self.0.read().normal_rate()
     }
 
     /// Gets the current speech rate.
     pub fn get_rate(&self) -> Result<f32, Error> {
         let Features { rate, .. } = self.supported_features();
         if rate {
-            self.0.read().unwrap().get_rate()
+            self.0.read().get_rate()
         } else {
             Err(Error::UnsupportedFeature)
         }
@@ -417,7 +2464,7 @@ impl Tts {
             rate: rate_feature, ..
         } = self.supported_features();
         if rate_feature {
-            let mut backend = self.0.write().unwrap();
+            let mut backend = self.0.write();
             if rate < backend.min_rate() || rate > backend.max_rate() {
                 Err(Error::OutOfRange)
             } else {
@@ -431,24 +2478,24 @@ impl Tts {
 
     /// Returns the minimum pitch for this speech synthesizer.
     pub fn min_pitch(&self) -> f32 {
-        self.0.read().unwrap().min_pitch()
+        self.0.read().min_pitch()
     }
 
     /// Returns the maximum pitch for this speech synthesizer.
     pub fn max_pitch(&self) -> f32 {
-        self.0.read().unwrap().max_pitch()
+        self.0.read().max_pitch()
     }
 
     /// Returns the normal pitch for this speech synthesizer.
     pub fn normal_pitch(&self) -> f32 {
-        self.0.read().unwrap().normal_pitch()
+        self.0.read().normal_pitch()
     }
 
     /// Gets the current speech pitch.
     pub fn get_pitch(&self) -> Result<f32, Error> {
         let Features { pitch, .. } = self.supported_features();
         if pitch {
-            self.0.read().unwrap().get_pitch()
+            self.0.read().get_pitch()
         } else {
             Err(Error::UnsupportedFeature)
         }
@@ -461,7 +2508,7 @@ impl Tts {
             ..
         } = self.supported_features();
         if pitch_feature {
-            let mut backend = self.0.write().unwrap();
+            let mut backend = self.0.write();
             if pitch < backend.min_pitch() || pitch > backend.max_pitch() {
                 Err(Error::OutOfRange)
             } else {
@@ -473,153 +2520,798 @@ impl Tts {
         }
     }
 
-    /// Returns the minimum volume for this speech synthesizer.
-    pub fn min_volume(&self) -> f32 {
-        self.0.read().unwrap().min_volume()
+    /// Returns the minimum volume for this speech synthesizer.
+    pub fn min_volume(&self) -> f32 {
+        self.0.read().min_volume()
+    }
+
+    /// Returns the maximum volume for this speech synthesizer.
+    pub fn max_volume(&self) -> f32 {
+        self.0.read().max_volume()
+    }
+
+    /// Returns the normal volume for this speech synthesizer.
+    pub fn normal_volume(&self) -> f32 {
+        self.0.read().normal_volume()
+    }
+
+    /// Gets the current speech volume.
+    pub fn get_volume(&self) -> Result<f32, Error> {
+        let Features { volume, .. } = self.supported_features();
+        if volume {
+            let id = self.0.read().id();
+            match id.and_then(|id| intercept::mute(id).volume) {
+                Some(volume) => Ok(volume),
+                None => self.0.read().get_volume(),
+            }
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Sets the desired speech volume.
+    pub fn set_volume(&mut self, volume: f32) -> Result<&Self, Error> {
+        let Features {
+            volume: volume_feature,
+            ..
+        } = self.supported_features();
+        if volume_feature {
+            let mut backend = self.0.write();
+            let id = backend.id();
+            if volume < backend.min_volume() || volume > backend.max_volume() {
+                return Err(Error::OutOfRange);
+            }
+            // While muted by lowering the volume, the new volume takes effect when unmuted.
+            match id.map(|id| (id, intercept::mute(id))) {
+                Some((id, mut mute)) if mute.volume.is_some() => {
+                    mute.volume = Some(volume);
+                    intercept::set_mute(id, mute);
+                }
+                _ => backend.set_volume(volume)?,
+            }
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Changes the speech rate by `delta`, a fraction of the range from `min_rate()` to `max_rate()`, stopping at
+    /// either end, and returns the new rate.
+    ///
+    /// Since `delta` is relative to the range, the same steps suit every backend, such as 0.05 and -0.05 for hotkeys
+    /// that speed up and slow down speech.
+    pub fn adjust_rate(&mut self, delta: f32) -> Result<f32, Error> {
+        let (min, max) = (self.min_rate(), self.max_rate());
+        let rate = (self.get_rate()? + delta * (max - min)).clamp(min, max);
+        self.set_rate(rate)?;
+        Ok(rate)
+    }
+
+    /// Changes the speech pitch by `delta`, a fraction of its range, stopping at either end, and returns the new
+    /// pitch.
+    pub fn adjust_pitch(&mut self, delta: f32) -> Result<f32, Error> {
+        let (min, max) = (self.min_pitch(), self.max_pitch());
+        let pitch = (self.get_pitch()? + delta * (max - min)).clamp(min, max);
+        self.set_pitch(pitch)?;
+        Ok(pitch)
+    }
+
+    /// Changes the speech volume by `delta`, a fraction of its range, stopping at either end, and returns the new
+    /// volume.
+    pub fn adjust_volume(&mut self, delta: f32) -> Result<f32, Error> {
+        let (min, max) = (self.min_volume(), self.max_volume());
+        let volume = (self.get_volume()? + delta * (max - min)).clamp(min, max);
+        self.set_volume(volume)?;
+        Ok(volume)
+    }
+
+    /// Resets whichever of the rate, pitch and volume this backend supports to normal.
+    pub fn reset_to_normal(&mut self) -> Result<&Self, Error> {
+        let Features {
+            rate,
+            pitch,
+            volume,
+            ..
+        } = self.supported_features();
+        if rate {
+            self.set_rate(self.normal_rate())?;
+        }
+        if pitch {
+            self.set_pitch(self.normal_pitch())?;
+        }
+        if volume {
+            self.set_volume(self.normal_volume())?;
+        }
+        Ok(self)
+    }
+
+    /// Returns whether this speech synthesizer is speaking.
+    pub fn is_speaking(&self) -> Result<bool, Error> {
+        let Features { is_speaking, .. } = self.supported_features();
+        if is_speaking {
+            self.0.read().is_speaking()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Pauses speech, which can be continued with `resume()`.
+    pub fn pause(&mut self) -> Result<&Self, Error> {
+        let Features { pause, .. } = self.supported_features();
+        if pause {
+            let id = {
+                let mut backend = self.0.write();
+                backend.pause()?;
+                backend.id()
+            };
+            // State callbacks may call back into this `Tts`, so they run once it's unlocked.
+            if let Some(id) = id {
+                speaking::set_paused(id, true);
+                watchdog::set_paused(id, true);
+            }
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Resumes speech paused with `pause()`.
+    pub fn resume(&mut self) -> Result<&Self, Error> {
+        let Features { pause, .. } = self.supported_features();
+        if pause {
+            let id = {
+                let mut backend = self.0.write();
+                backend.resume()?;
+                backend.id()
+            };
+            if let Some(id) = id {
+                speaking::set_paused(id, false);
+                watchdog::set_paused(id, false);
+            }
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Stops the utterance being spoken and moves on to the next one queued, calling the utterance stop callback for
+    /// the one skipped. Supported on Speech Dispatcher and WinRT.
+    pub fn skip(&mut self) -> Result<&Self, Error> {
+        let id = self.0.read().id();
+        callbacks::deferred(id, || self.0.write().skip())?;
+        Ok(self)
+    }
+
+    /// Blocks until this speech synthesizer has finished speaking, including while it's paused. Requires
+    /// `is_speaking()`. On macOS, the run loop is pumped while waiting.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn wait(&self) -> Result<&Self, Error> {
+        while self.is_speaking()? {
+            #[cfg(target_os = "macos")]
+            Tts::pump_run_loop(Duration::from_millis(10));
+            #[cfg(not(target_os = "macos"))]
+            thread::sleep(Duration::from_millis(10));
+        }
+        Ok(self)
+    }
+
+    /// Returns `true` if speech is paused.
+    pub fn is_paused(&self) -> Result<bool, Error> {
+        let Features { pause, .. } = self.supported_features();
+        if pause {
+            self.0.read().is_paused()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Returns `true` if utterances are queued behind the one currently being spoken.
+    pub fn is_pending(&self) -> Result<bool, Error> {
+        let Features { queue, .. } = self.supported_features();
+        if queue {
+            self.0.read().is_pending()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Returns the number of utterances from this instance that are speaking or queued.
+    pub fn queue_len(&self) -> Result<usize, Error> {
+        let Features { queue, .. } = self.supported_features();
+        if queue {
+            self.0.read().queue_len()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Returns list of available voices.
+    ///
+    /// The list is cached until the platform reports that voices were installed or removed, where it does, or
+    /// `refresh_voices()` is called.
+    pub fn voices(&self) -> Result<Vec<Voice>, Error> {
+        let Features { voice, .. } = self.supported_features();
+        if !voice {
+            return Err(Error::UnsupportedFeature);
+        }
+        let id = self.0.read().id();
+        if let Some(voices) = id.and_then(voices::get) {
+            return Ok(voices);
+        }
+        let voices = self.0.read().voices()?;
+        if let Some(id) = id {
+            voices::set(id, &voices);
+        }
+        Ok(voices)
+    }
+
+    /// Lists available voices again, replacing those cached by `voices()`, as after the user installs one on a
+    /// platform that doesn't report it.
+    pub fn refresh_voices(&self) -> Result<Vec<Voice>, Error> {
+        if let Some(id) = self.0.read().id() {
+            voices::remove(id);
+        }
+        self.voices()
+    }
+
+    /// Returns the highest-quality voice for the given language, if any is available.
+    ///
+    /// If `language` includes a region, only voices for that region are considered.
+    pub fn highest_quality_voice(
+        &self,
+        language: &LanguageTag<String>,
+    ) -> Result<Option<Voice>, Error> {
+        let voices = self.voices()?;
+        let voice = voices
+            .into_iter()
+            .filter(|v| {
+                v.language
+                    .primary_language()
+                    .eq_ignore_ascii_case(language.primary_language())
+                    && language.region().is_none_or(|region| {
+                        v.language
+                            .region()
+                            .is_some_and(|r| r.eq_ignore_ascii_case(region))
+                    })
+            })
+            .max_by_key(|v| v.quality);
+        Ok(voice)
+    }
+
+    /// Returns the voice that best suits `preferences`, a list of languages in order of preference such as the user's
+    /// locale settings.
+    ///
+    /// The first language that a voice of the same script matches wins, with the closest match and then the highest
+    /// quality chosen among its voices. Only if none match does a voice in another script of a preferred language
+    /// qualify.
+    pub fn best_voice(&self, preferences: &[LanguageTag<String>]) -> Result<Option<Voice>, Error> {
+        let voices = self.voices()?;
+        for threshold in [MatchQuality::Script, MatchQuality::Language] {
+            for language in preferences {
+                let voice = voices
+                    .iter()
+                    .map(|voice| (voice.matches(language), voice))
+                    .filter(|(quality, _)| *quality >= threshold)
+                    .max_by_key(|(quality, voice)| (*quality, voice.quality));
+                if let Some((_, voice)) = voice {
+                    return Ok(Some(voice.clone()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Return the current speaking voice.
+    pub fn voice(&self) -> Result<Option<Voice>, Error> {
+        let Features { get_voice, .. } = self.supported_features();
+        if get_voice {
+            self.0.read().voice()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Set speaking voice.
+    pub fn set_voice(&mut self, voice: &Voice) -> Result<(), Error> {
+        let Features {
+            voice: voice_feature,
+            ..
+        } = self.supported_features();
+        if voice_feature {
+            self.0.write().set_voice(voice)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Sets the voice `id` identifies, and returns it.
+    ///
+    /// `id` is a token from `Voice::stable_id()`, or a voice ID or name. If no voice has the saved ID, this looks for
+    /// one with the same name, then one with a similar name or ID, then the best voice for the same language.
+    pub fn set_voice_by_id(&mut self, id: &str) -> Result<Voice, Error> {
+        let voices = self.voices()?;
+        let voice = voice_id::resolve(&voices, id).ok_or_else(|| Error::UnknownVoice(id.into()))?;
+        self.set_voice(voice)?;
+        Ok(voice.clone())
+    }
+
+    /// Sets whether other apps' audio is lowered while this speech synthesizer speaks.
+    ///
+    /// Supported on Android, through audio focus, and iOS, through the shared `AVAudioSession`. Elsewhere, use
+    /// `on_speaking_state_changed()` to duck your own audio.
+    pub fn set_ducking(&mut self, mode: DuckingMode) -> Result<&Self, Error> {
+        self.0.write().set_ducking(mode)?;
+        Ok(self)
+    }
+
+    /// Sets how speech ends when stopped or interrupted, such as fading out rather than cutting off with a click.
+    ///
+    /// Supported on WinRT. With the `audio-output` feature, speech fades out on the audio thread and stopping returns
+    /// at once; without it, stopping blocks until the fade has finished.
+    pub fn set_stop_style(&mut self, style: StopStyle) -> Result<&Self, Error> {
+        self.0.write().set_stop_style(style)?;
+        Ok(self)
+    }
+
+    /// Plays each utterance at `loudness`, in dBFS RMS, such as -20.0, so voices that synthesize at different levels
+    /// sound equally loud, as when a dialogue mixes them. Silence between words doesn't count toward loudness, and
+    /// quiet speech is only raised as far as it can be without clipping. `None` plays speech as synthesized.
+    ///
+    /// Supported on WinRT with the `audio-output` feature. Earcons play as recorded.
+    pub fn set_target_loudness(&mut self, loudness: Option<f32>) -> Result<&Self, Error> {
+        self.0.write().set_target_loudness(loudness)?;
+        Ok(self)
+    }
+
+    /// Sets how this speech synthesizer's audio mixes with that of others playing at the same time, as when a game
+    /// gives each character its own `Tts`. The default mixes them.
+    ///
+    /// Supported on WinRT with the `audio-output` feature, among whose instances it applies.
+    pub fn set_mix_policy(&mut self, policy: MixPolicy) -> Result<&Self, Error> {
+        self.0.write().set_mix_policy(policy)?;
+        Ok(self)
+    }
+
+    /// Returns the audio devices speech can be routed to.
+    ///
+    /// With the `audio-output` feature, WinRT lists the devices its audio output can open, whose IDs are their names.
+    pub fn output_devices(&self) -> Result<Vec<OutputDevice>, Error> {
+        let Features { output_device, .. } = self.supported_features();
+        if output_device {
+            self.0.read().output_devices()
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Routes speech to `device`, as returned by `output_devices()`, or back to the system default if `None`.
+    ///
+    /// On iOS, apps can only force output to the built-in speaker, so any other device restores the system's route.
+    pub fn set_output_device(&mut self, device: Option<&OutputDevice>) -> Result<&Self, Error> {
+        let Features { output_device, .. } = self.supported_features();
+        if output_device {
+            self.0.write().set_output_device(device)?;
+            Ok(self)
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Configures the shared `AVAudioSession`, and whether the synthesizer uses it.
+    ///
+    /// Use this to control whether speech interrupts, ducks or mixes with other audio, such as a game's music.
+    #[cfg(target_os = "ios")]
+    pub fn set_audio_session_options(
+        &mut self,
+        options: &AudioSessionOptions,
+    ) -> Result<&Self, Error> {
+        self.0.write().set_audio_session_options(options)?;
+        Ok(self)
+    }
+
+    /// Works around Chromium cutting off long speech, which otherwise stops after about 15 seconds with some voices.
+    ///
+    /// When enabled, long text is split into several browser utterances at sentence or word boundaries, and speech is periodically paused and resumed to keep it going.
+    /// Callbacks still report a single utterance.
+    #[cfg(target_arch = "wasm32")]
+    pub fn set_long_utterance_workaround(&mut self, enabled: bool) -> Result<&Self, Error> {
+        self.0.write().set_long_utterance_workaround(enabled)?;
+        Ok(self)
     }
 
-    /// Returns the maximum volume for this speech synthesizer.
-    pub fn max_volume(&self) -> f32 {
-        self.0.read().unwrap().max_volume()
+    /// Returns the TTS engines installed on this Android device.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn engines(&self) -> Result<Vec<android::Engine>, Error> {
+        self.0.read().engines()
     }
 
-    /// Returns the normal volume for this speech synthesizer.
-    pub fn normal_volume(&self) -> f32 {
-        self.0.read().unwrap().normal_volume()
+    /// Sets the `AudioAttributes` usage for subsequent speech.
+    ///
+    /// Use `AudioUsage::AssistanceAccessibility` so speech follows the accessibility volume and coexists with TalkBack.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn set_audio_usage(&mut self, usage: android::AudioUsage) -> Result<&Self, Error> {
+        self.0.write().set_audio_usage(usage)?;
+        Ok(self)
     }
 
-    /// Gets the current speech volume.
-    pub fn get_volume(&self) -> Result<f32, Error> {
-        let Features { volume, .. } = self.supported_features();
-        if volume {
-            self.0.read().unwrap().get_volume()
-        } else {
-            Err(Error::UnsupportedFeature)
-        }
+    /// Returns whether the Android engine can speak the given language, and in how much detail.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn language_availability(
+        &self,
+        language: &LanguageTag<String>,
+    ) -> Result<android::LanguageAvailability, Error> {
+        self.0.read().language_availability(language)
     }
 
-    /// Sets the desired speech volume.
-    pub fn set_volume(&mut self, volume: f32) -> Result<&Self, Error> {
+    /// Returns whether the Android engine can speak the given language now.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn is_language_available(&self, language: &LanguageTag<String>) -> Result<bool, Error> {
+        Ok(self.language_availability(language)?.is_available())
+    }
+
+    /// Returns whether the given language is supported but its voice data isn't installed.
+    ///
+    /// If so, the app can start an activity with the `tts::android::ACTION_INSTALL_TTS_DATA` intent action to offer installing it.
+    #[cfg(all(target_os = "android", feature = "android"))]
+    pub fn is_language_data_missing(&self, language: &LanguageTag<String>) -> Result<bool, Error> {
+        Ok(self.language_availability(language)? == android::LanguageAvailability::MissingData)
+    }
+
+    /// Returns whether this app may speak with the user's Personal Voice.
+    ///
+    /// Requires iOS 17 or MacOS 14 and above.
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+    pub fn personal_voice_authorization_status() -> PersonalVoiceAuthorizationStatus {
+        backends::AvFoundation::personal_voice_authorization_status()
+    }
+
+    /// Asks the user for permission to speak with their Personal Voice, calling `callback` with the outcome.
+    ///
+    /// The callback may run on any thread. Requires iOS 17 or MacOS 14 and above.
+    #[cfg(all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"))]
+    pub fn request_personal_voice_authorization(
+        callback: Box<dyn FnOnce(PersonalVoiceAuthorizationStatus) + Send>,
+    ) {
+        backends::AvFoundation::request_personal_voice_authorization(callback)
+    }
+
+    /// Sets this handle's callback for `event`: its own if it has them, otherwise the shared one.
+    fn set_utterance_callback(
+        &self,
+        event: UtteranceEvent,
+        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+    ) -> Result<(), Error> {
+        self.enable_callbacks()?;
+        let id = self.0.read().id().unwrap();
+        let subscriber = self.1.as_ref().map(|subscription| subscription.id());
+        let callback = match subscriber {
+            Some(_) => callback,
+            None => utterance_callback(id, event, callback),
+        };
+        callbacks::with_set(id, subscriber, |set| set.utterance(event).set(callback));
+        Ok(())
+    }
+
+    /// Returns a handle to this speech synthesizer with its own utterance and word boundary callbacks.
+    ///
+    /// Callbacks set through clones of a `Tts` replace each other, so a library and the app using it can't both
+    /// listen. Callbacks set through this handle, and its clones, are called alongside those and any other
+    /// handle's, and are removed once the last of them is dropped.
+    pub fn with_own_callbacks(&self) -> Result<Tts, Error> {
         let Features {
-            volume: volume_feature,
+            utterance_callbacks,
+            word_callbacks,
             ..
         } = self.supported_features();
-        if volume_feature {
-            let mut backend = self.0.write().unwrap();
-            if volume < backend.min_volume() || volume > backend.max_volume() {
-                Err(Error::OutOfRange)
-            } else {
-                backend.set_volume(volume)?;
-                Ok(self)
+        let id = self.0.read().id();
+        match id.filter(|_| utterance_callbacks || word_callbacks) {
+            Some(id) => {
+                self.enable_callbacks()?;
+                let subscription = Subscription::new(id).ok_or(Error::UnsupportedFeature)?;
+                Ok(Tts(
+                    self.0.clone(),
+                    Some(Rc::new(subscription)),
+                    self.2.clone(),
+                ))
             }
+            None => Err(Error::UnsupportedFeature),
+        }
+    }
+
+    /// Called when this speech synthesizer begins speaking an utterance.
+    pub fn on_utterance_begin(
+        &self,
+        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+    ) -> Result<(), Error> {
+        let Features {
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        if utterance_callbacks {
+            self.set_utterance_callback(UtteranceEvent::Begin, callback)
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Returns whether this speech synthesizer is speaking.
-    pub fn is_speaking(&self) -> Result<bool, Error> {
-        let Features { is_speaking, .. } = self.supported_features();
-        if is_speaking {
-            self.0.read().unwrap().is_speaking()
+    /// Called when this speech synthesizer finishes speaking an utterance.
+    pub fn on_utterance_end(
+        &self,
+        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+    ) -> Result<(), Error> {
+        let Features {
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        if utterance_callbacks {
+            self.set_utterance_callback(UtteranceEvent::End, callback)
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Returns list of available voices.
-    pub fn voices(&self) -> Result<Vec<Voice>, Error> {
-        let Features { voice, .. } = self.supported_features();
-        if voice {
-            self.0.read().unwrap().voices()
+    /// Called when this speech synthesizer is stopped and still has utterances in its queue.
+    pub fn on_utterance_stop(
+        &self,
+        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+    ) -> Result<(), Error> {
+        let Features {
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        if utterance_callbacks {
+            self.set_utterance_callback(UtteranceEvent::Stop, callback)
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Return the current speaking voice.
-    pub fn voice(&self) -> Result<Option<Voice>, Error> {
-        let Features { get_voice, .. } = self.supported_features();
-        if get_voice {
-            self.0.read().unwrap().voice()
+    /// Called when this speech synthesizer is about to speak a word.
+    ///
+    /// The range is given in bytes, and indexes into the text passed to `speak`.
+    pub fn on_word_boundary(&self, callback: Option<WordBoundaryCallback>) -> Result<(), Error> {
+        let Features { word_callbacks, .. } = self.supported_features();
+        if word_callbacks {
+            self.enable_callbacks()?;
+            let id = self.0.read().id().unwrap();
+            let subscriber = self.1.as_ref().map(|subscription| subscription.id());
+            let callback = match subscriber {
+                Some(_) => callback,
+                None => Some(word_callback(id, callback)),
+            };
+            callbacks::with_set(id, subscriber, |set| set.word_boundary.set(callback));
+            Ok(())
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Set speaking voice.
-    pub fn set_voice(&mut self, voice: &Voice) -> Result<(), Error> {
+    /// Called when this speech synthesizer begins speaking a sentence, with the byte range of the text holding it, as
+    /// for highlighting the current sentence.
+    ///
+    /// This works wherever utterance callbacks do, since text is then spoken a sentence at a time, which may add
+    /// short pauses between sentences on some backends. Ranges index into the text after filters and the lexicon, as
+    /// word boundaries do. Sentences longer than the maximum utterance length are reported a piece at a time, and
+    /// SSML isn't split, so it reports none.
+    pub fn on_sentence_begin(&self, callback: Option<SentenceCallback>) -> Result<(), Error> {
         let Features {
-            voice: voice_feature,
+            utterance_callbacks,
             ..
         } = self.supported_features();
-        if voice_feature {
-            self.0.write().unwrap().set_voice(voice)
+        if utterance_callbacks {
+            self.enable_callbacks()?;
+            let id = self.0.read().id().unwrap();
+            let subscriber = self.1.as_ref().map(|subscription| subscription.id());
+            let sentences = callback.is_some();
+            let callback = match subscriber {
+                Some(_) => callback,
+                None => Some(sentence_callback(id, callback)),
+            };
+            callbacks::with_set(id, subscriber, |set| {
+                set.sentence_begin.set(callback);
+                set.sentences = sentences;
+            });
+            Ok(())
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Called when this speech synthesizer begins speaking an utterance.
-    pub fn on_utterance_begin(
-        &self,
-        callback: Option<Box<dyn FnMut(UtteranceId)>>,
-    ) -> Result<(), Error> {
+    /// Called with each viseme, or mouth shape, of speech as it's estimated, with how far into the utterance it
+    /// begins, as for animating a talking character.
+    ///
+    /// No backend reports visemes, so they're estimated from the spelling of the text. They're timed from word
+    /// boundaries where the backend reports them, so each word's visemes arrive as it begins, and otherwise all arrive
+    /// as the utterance begins, spread over the time text of its length usually takes. Each utterance ends with
+    /// `Viseme::Silence`. Only text given to `speak()` and its variants is covered, not SSML or spelling.
+    pub fn on_viseme(&self, callback: Option<VisemeCallback>) -> Result<(), Error> {
         let Features {
             utterance_callbacks,
             ..
         } = self.supported_features();
         if utterance_callbacks {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let id = self.0.read().unwrap().id().unwrap();
-            let callbacks = callbacks.get_mut(&id).unwrap();
-            callbacks.utterance_begin = callback;
+            self.enable_callbacks()?;
+            let id = self.0.read().id().unwrap();
+            let subscriber = self.1.as_ref().map(|subscription| subscription.id());
+            let visemes = callback.is_some();
+            let callback = match subscriber {
+                Some(_) => callback,
+                None => Some(viseme_callback(id, callback)),
+            };
+            callbacks::with_set(id, subscriber, |set| {
+                set.viseme.set(callback);
+                set.visemes = visemes;
+            });
             Ok(())
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Called when this speech synthesizer finishes speaking an utterance.
-    pub fn on_utterance_end(
+    /// Returns the time from `speak()` to the start of audio for the most recently begun utterance.
+    ///
+    /// Measured with utterance callbacks, so `None` if the backend doesn't support them or nothing has been spoken
+    /// since they, or this, were first used.
+    pub fn last_latency(&self) -> Option<Duration> {
+        if !self.supported_features().utterance_callbacks {
+            return None;
+        }
+        self.enable_callbacks().ok()?;
+        let id = self.0.read().id()?;
+        speaking::last_latency(id)
+    }
+
+    /// Returns how far `utterance` has got, or `None` if it hasn't begun, has finished, or the backend doesn't
+    /// report when utterances begin. Only utterances spoken after callbacks, or this, were first used are tracked.
+    ///
+    /// The offset is only known on backends with word callbacks, such as the Web, WinRT and AVFoundation.
+    pub fn progress(&self, utterance: &UtteranceId) -> Option<UtteranceProgress> {
+        if !self.supported_features().utterance_callbacks {
+            return None;
+        }
+        self.enable_callbacks().ok()?;
+        let id = self.0.read().id()?;
+        speaking::progress(id, utterance)
+    }
+
+    /// Called with `true` when this speech synthesizer starts speaking after being silent, and `false` once it has
+    /// finished or stopped everything queued.
+    ///
+    /// Use this to duck your own audio, such as a game's mix, while speech plays.
+    pub fn on_speaking_state_changed(
         &self,
-        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+        callback: Option<Box<dyn FnMut(bool)>>,
     ) -> Result<(), Error> {
         let Features {
             utterance_callbacks,
             ..
         } = self.supported_features();
         if utterance_callbacks {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let id = self.0.read().unwrap().id().unwrap();
-            let callbacks = callbacks.get_mut(&id).unwrap();
-            callbacks.utterance_end = callback;
+            self.enable_callbacks()?;
+            let id = self.0.read().id().unwrap();
+            let callback = callback.map(|mut callback| -> Box<dyn FnMut(bool)> {
+                Box::new(move |speaking| panics::guard(id, || callback(speaking)))
+            });
+            speaking::set_callback(id, callback);
             Ok(())
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
-    /// Called when this speech synthesizer is stopped and still has utterances in its queue.
-    pub fn on_utterance_stop(
+    /// Called when this speech synthesizer goes idle, starts speaking, or is paused or resumed while speech is
+    /// queued, so play and pause controls can follow it.
+    pub fn on_state_changed(
         &self,
-        callback: Option<Box<dyn FnMut(UtteranceId)>>,
+        callback: Option<Box<dyn FnMut(SpeakingState)>>,
     ) -> Result<(), Error> {
         let Features {
             utterance_callbacks,
             ..
         } = self.supported_features();
         if utterance_callbacks {
-            let mut callbacks = CALLBACKS.lock().unwrap();
-            let id = self.0.read().unwrap().id().unwrap();
-            let callbacks = callbacks.get_mut(&id).unwrap();
-            callbacks.utterance_stop = callback;
+            self.enable_callbacks()?;
+            let id = self.0.read().id().unwrap();
+            let callback = callback.map(|mut callback| -> Box<dyn FnMut(SpeakingState)> {
+                Box::new(move |state| panics::guard(id, || callback(state)))
+            });
+            speaking::set_state_callback(id, callback);
+            Ok(())
+        } else {
+            Err(Error::UnsupportedFeature)
+        }
+    }
+
+    /// Called with errors that happen outside any method call, such as `Error::CallbackPanicked` when another callback
+    /// panics, synthesis failing after `speak()` returned, or `Error::BackendStalled` from the watchdog. The panic is
+    /// caught, so the engine keeps speaking.
+    pub fn on_error(&self, callback: Option<Box<dyn FnMut(Error)>>) -> Result<(), Error> {
+        let id = self.0.read().id();
+        if let Some(id) = id {
+            panics::set_callback(id, callback);
             Ok(())
         } else {
             Err(Error::UnsupportedFeature)
         }
     }
 
+    /// Watches for the backend getting stuck, with utterances that don't begin or end within `watchdog`'s limits, as
+    /// when an engine hangs or its service dies. `None` stops watching.
+    ///
+    /// Requires utterance callbacks.
+    pub fn set_watchdog(&mut self, watchdog: Option<Watchdog>) -> Result<&Self, Error> {
+        let Features {
+            utterance_callbacks,
+            ..
+        } = self.supported_features();
+        let id = self.0.read().id();
+        match id {
+            Some(id) if utterance_callbacks => {
+                self.enable_callbacks()?;
+                watchdog::set(id, watchdog);
+                Ok(self)
+            }
+            _ => Err(Error::UnsupportedFeature),
+        }
+    }
+
+    /// Checks whether the backend is stuck, returning whether it was. If it was, `Error::BackendStalled` goes to the
+    /// error callback, and if the watchdog says to, the backend is reinitialized and the utterances it was stuck on
+    /// are stopped.
+    ///
+    /// This is checked whenever text is spoken, and regularly on a `Worker`. Otherwise, apps that can go quiet with
+    /// speech queued should call this regularly, such as once a second.
+    pub fn check_watchdog(&mut self) -> Result<bool, Error> {
+        let id = self.0.read().id().ok_or(Error::UnsupportedFeature)?;
+        let Some((watchdog, stuck)) = watchdog::check(id) else {
+            return Ok(false);
+        };
+        log::warn!("Backend stuck with {} utterances unfinished", stuck.len());
+        panics::report(id, Error::BackendStalled);
+        if watchdog.reinitialize {
+            // Stop callbacks may call back into this `Tts`, so they run once it's unlocked.
+            let reinitialized = self.0.write().reinitialize();
+            match reinitialized {
+                Ok(()) => {
+                    for utterance in stuck {
+                        callbacks::fire_utterance(id, UtteranceEvent::Stop, utterance);
+                    }
+                }
+                Err(e) => log::warn!("Couldn't reinitialize stuck backend: {}", e),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Runs the current thread's run loop for up to `timeout`, delivering any pending callbacks.
+    ///
+    /// Apple backends deliver callbacks through the run loop of the thread that created them. GUI apps run it
+    /// already, but command-line tools and many game loops don't. Call this regularly from that thread, for
+    /// instance once per frame with a zero timeout, or with a longer timeout while waiting for speech to finish.
+    #[cfg(target_os = "macos")]
+    pub fn pump_run_loop(timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ran: BOOL = unsafe {
+                let run_loop: id = msg_send![class!(NSRunLoop), currentRunLoop];
+                let date: id = msg_send![
+                    class!(NSDate),
+                    dateWithTimeIntervalSinceNow: remaining.as_secs_f64()
+                ];
+                msg_send![run_loop, runMode: NSDefaultRunLoopMode beforeDate: date]
+            };
+            if ran == NO {
+                // Nothing is attached to the run loop, so there's nothing to deliver.
+                thread::sleep(remaining);
+                break;
+            }
+            if remaining.is_zero() {
+                break;
+            }
+        }
+    }
+
     /*
      * Returns `true` if a screen reader is available to provide speech.
      */
@@ -642,26 +3334,167 @@ impl Tts {
 impl Drop for Tts {
     fn drop(&mut self) {
         if Rc::strong_count(&self.0) <= 1 {
-            if let Some(id) = self.0.read().unwrap().id() {
-                let mut callbacks = CALLBACKS.lock().unwrap();
-                callbacks.remove(&id);
+            let id = self.0.read().id();
+            let behavior = self.2.lock().drop_behavior;
+            let features = self.supported_features();
+            match behavior {
+                DropBehavior::StopImmediately if features.stop => {
+                    let _ = self.0.write().stop();
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                DropBehavior::FinishQueue if features.is_speaking => {
+                    while let Ok(true) = self.0.read().is_speaking() {
+                        std::thread::sleep(Duration::from_millis(10));
+                    }
+                }
+                _ => {}
             }
+            if let Some(id) = id {
+                callbacks::remove(id);
+                template::remove(id);
+                intercept::remove(id);
+                policy::remove(id);
+                panics::remove(id);
+                channel::remove(id);
+                speaking::remove(id);
+                chunking::remove(id);
+                highlight::remove(id);
+                viseme::remove(id);
+                watchdog::remove(id);
+                voices::remove(id);
+            }
+        }
+    }
+}
+
+/// Converts a range of UTF-16 code units, as reported by most platform engines, into a byte range of `text`.
+#[cfg(any(
+    all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation"),
+    all(target_os = "android", feature = "android"),
+    all(target_arch = "wasm32", feature = "web"),
+    all(windows, feature = "winrt")
+))]
+pub(crate) fn utf16_range_to_byte_range(text: &str, start: usize, len: usize) -> Range<usize> {
+    let end = start + len;
+    let mut byte_start = None;
+    let mut byte_end = None;
+    let mut units = 0;
+    for (i, c) in text.char_indices() {
+        if byte_start.is_none() && units >= start {
+            byte_start = Some(i);
+        }
+        if units >= end {
+            byte_end = Some(i);
+            break;
+        }
+        units += c.len_utf16();
+    }
+    let byte_start = byte_start.unwrap_or(text.len());
+    let byte_end = byte_end.unwrap_or(text.len()).max(byte_start);
+    byte_start..byte_end
+}
+
+/// The `AVAudioSession` category used while speaking on iOS.
+#[cfg(target_os = "ios")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AudioSessionCategory {
+    /// Speech mixes with other audio, and is silenced by the ringer switch.
+    Ambient,
+    /// Speech silences other audio, and is silenced by the ringer switch.
+    SoloAmbient,
+    /// Speech plays regardless of the ringer switch.
+    #[default]
+    Playback,
+    /// Speech plays alongside audio recording.
+    PlayAndRecord,
+}
+
+/// How speech interacts with other audio on iOS.
+#[cfg(target_os = "ios")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AudioSessionOptions {
+    pub category: AudioSessionCategory,
+    /// Mix speech with audio from other apps rather than pausing it.
+    pub mix_with_others: bool,
+    /// Lower the volume of other audio while speaking.
+    pub duck_others: bool,
+    /// Pause spoken audio from other apps, such as podcasts, while mixing with everything else.
+    pub interrupt_spoken_audio: bool,
+    /// Whether the synthesizer uses the app's audio session, or a separate one managed by the system.
+    pub uses_application_audio_session: bool,
+}
+
+#[cfg(target_os = "ios")]
+impl Default for AudioSessionOptions {
+    fn default() -> Self {
+        Self {
+            category: AudioSessionCategory::default(),
+            mix_with_others: false,
+            duck_others: false,
+            interrupt_spoken_audio: false,
+            uses_application_audio_session: true,
         }
     }
 }
 
+/// Whether this app may speak with the user's Personal Voice.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PersonalVoiceAuthorizationStatus {
+    /// The user hasn't yet been asked.
+    NotDetermined,
+    /// The user declined.
+    Denied,
+    /// Personal Voice isn't available on this device or OS version.
+    Unsupported,
+    /// Personal Voices are available, and are included in `Tts::voices()`.
+    Authorized,
+}
+
+/// The quality tier of a voice, ordered from lowest to highest.
+///
+/// On Apple platforms, default voices are `Normal`, enhanced voices are `High`, and premium voices are `VeryHigh`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VoiceQuality {
+    VeryLow,
+    Low,
+    Normal,
+    High,
+    VeryHigh,
+}
+
+/// The gender of a voice, as its backend reports it.
+///
+/// The discriminants and serialized names are stable, so they can be stored and passed across the FFI.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+#[repr(C)]
 pub enum Gender {
-    Male,
-    Female,
+    Male = 0,
+    Female = 1,
+    /// Neither male nor female, as AppKit reports some novelty voices.
+    Neutral = 2,
+    /// The backend doesn't say, as with the Web, Speech Dispatcher and Android, and Apple's unspecified voices.
+    Unknown = 3,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Voice {
     pub(crate) id: String,
     pub(crate) name: String,
-    pub(crate) gender: Option<Gender>,
+    pub(crate) gender: Gender,
     pub(crate) language: LanguageTag<String>,
+    pub(crate) personal: bool,
+    pub(crate) quality: Option<VoiceQuality>,
+    pub(crate) network: bool,
 }
 
 impl Voice {
@@ -673,11 +3506,315 @@ impl Voice {
         self.name.clone()
     }
 
+    /// Returns this voice's gender, or `None` if the backend doesn't say.
     pub fn gender(&self) -> Option<Gender> {
-        self.gender
+        match self.gender {
+            Gender::Unknown => None,
+            gender => Some(gender),
+        }
     }
 
     pub fn language(&self) -> LanguageTag<String> {
         self.language.clone()
     }
+
+    /// Returns `true` if this is the user's own Personal Voice.
+    pub fn is_personal(&self) -> bool {
+        self.personal
+    }
+
+    /// Returns this voice's quality tier, if the backend reports one.
+    pub fn quality(&self) -> Option<VoiceQuality> {
+        self.quality
+    }
+
+    /// Returns `true` if this voice needs a network connection to speak.
+    pub fn requires_network(&self) -> bool {
+        self.network
+    }
+
+    /// Returns how well this voice's language matches `language`, falling back from region to script to language.
+    pub fn matches(&self, language: &LanguageTag<String>) -> MatchQuality {
+        language::match_quality(&self.language, language)
+    }
+
+    /// Returns a token identifying this voice, to save and restore with `Tts::set_voice_by_id()`.
+    ///
+    /// Unlike `id()`, the token still finds this voice if the engine renames or re-identifies it between sessions.
+    pub fn stable_id(&self) -> String {
+        voice_id::encode(self)
+    }
+}
+
+/// How urgently speech is announced, modeled on the `aria-live` attribute of ARIA live regions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Politeness {
+    /// Not announced.
+    Off,
+    /// Announced once current speech finishes.
+    #[default]
+    Polite,
+    /// Announced immediately, interrupting current speech.
+    Assertive,
+}
+
+/// How far an utterance has been spoken, from `Tts::progress()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UtteranceProgress {
+    /// Time spent speaking it, not counting pauses.
+    pub elapsed: Duration,
+    /// The byte offset of the word being spoken, as word boundary callbacks report it, if the backend reports words.
+    pub offset: Option<usize>,
+}
+
+/// Whether a speech synthesizer is speaking, as reported to `Tts::on_state_changed()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpeakingState {
+    /// Nothing is queued.
+    #[default]
+    Idle,
+    Speaking,
+    /// Speech is queued, but paused with `Tts::pause()`.
+    Paused,
+}
+
+/// What happens to speech when the last clone of a `Tts` is dropped. Set it with `Tts::set_drop_behavior()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DropBehavior {
+    /// Current and queued speech stops.
+    #[default]
+    StopImmediately,
+    /// Dropping blocks until queued speech finishes, as short-lived command-line tools want.
+    FinishQueue,
+    /// Speech is left to the engine, which keeps speaking if it outlives the `Tts`. Speech Dispatcher and Web Speech
+    /// do, but most platform engines stop once released.
+    Detach,
+}
+
+/// How speech affects other apps' audio.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DuckingMode {
+    /// Other audio plays as usual.
+    #[default]
+    Off,
+    /// Other audio is lowered while speech plays.
+    Duck,
+}
+
+/// How speech ends when stopped or interrupted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopStyle {
+    /// Speech is cut off at once.
+    #[default]
+    Immediate,
+    /// Speech fades out over this long, which avoids the click of cutting it off. On WinRT without the
+    /// `audio-output` feature, stopping blocks until the fade has finished, so keep it short there.
+    Fade(Duration),
+}
+
+/// How a speech synthesizer's audio mixes with that of others playing at the same time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixPolicy {
+    /// Plays alongside the others.
+    #[default]
+    Mix,
+    /// Waits for those already playing to finish, and those that start later wait for it.
+    Exclusive,
+    /// Plays alongside the others, lowering their volume to this fraction while it plays.
+    Duck(f32),
+}
+
+/// An audio device that speech can be routed to.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OutputDevice {
+    pub(crate) id: String,
+    pub(crate) name: String,
+}
+
+impl OutputDevice {
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// IDs of each kind this platform has, numbered `n`.
+    fn utterance_ids(n: u64) -> Vec<UtteranceId> {
+        vec![
+            #[cfg(target_os = "android")]
+            UtteranceId::Android(n),
+            #[cfg(target_os = "macos")]
+            UtteranceId::AppKit(n),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            UtteranceId::AvFoundation(n),
+            #[cfg(target_os = "linux")]
+            UtteranceId::SpeechDispatcher(n),
+            #[cfg(target_arch = "wasm32")]
+            UtteranceId::Web(n),
+            #[cfg(windows)]
+            UtteranceId::WinRt(n),
+        ]
+    }
+
+    #[test]
+    fn utterance_ids_round_trip() {
+        for n in [0, 42, u64::MAX] {
+            for id in utterance_ids(n) {
+                assert_eq!(id.to_string().parse::<UtteranceId>().unwrap(), id);
+                assert_eq!(id.with_u64(id.as_u64()), id);
+                assert_eq!(id.as_u64(), n);
+            }
+        }
+    }
+
+    /// Backend IDs of each kind this platform has, numbered `n`.
+    fn backend_ids(n: u64) -> Vec<BackendId> {
+        vec![
+            #[cfg(target_os = "android")]
+            BackendId::Android(n),
+            #[cfg(target_os = "macos")]
+            BackendId::AppKit(n),
+            #[cfg(any(target_os = "macos", target_os = "ios"))]
+            BackendId::AvFoundation(n),
+            #[cfg(target_os = "linux")]
+            BackendId::SpeechDispatcher(n as usize),
+            #[cfg(target_arch = "wasm32")]
+            BackendId::Web(n),
+            #[cfg(windows)]
+            BackendId::WinRt(n),
+            BackendId::Noop(n),
+        ]
+    }
+
+    #[test]
+    fn backend_ids_round_trip() {
+        for n in [0, 42, u64::MAX] {
+            for id in backend_ids(n) {
+                assert_eq!(id.to_string().parse::<BackendId>().unwrap(), id);
+            }
+        }
+        for s in ["", "noop", "noop:", "noop:-1", "nonsense:42"] {
+            assert!(matches!(s.parse::<BackendId>(), Err(Error::InvalidId(_))));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ids_round_trip_through_serde() {
+        for n in [0, 42, u64::MAX] {
+            for id in backend_ids(n) {
+                let json = serde_json::to_string(&id).unwrap();
+                assert_eq!(json, format!("\"{id}\""));
+                assert_eq!(serde_json::from_str::<BackendId>(&json).unwrap(), id);
+            }
+            for id in utterance_ids(n) {
+                let json = serde_json::to_string(&id).unwrap();
+                assert_eq!(json, format!("\"{id}\""));
+                assert_eq!(serde_json::from_str::<UtteranceId>(&json).unwrap(), id);
+            }
+        }
+        assert!(serde_json::from_str::<BackendId>("\"nonsense:42\"").is_err());
+    }
+
+    #[test]
+    fn adjustments_add_up() {
+        let mut tts = Tts::fake();
+        tts.adjust_volume(-0.25).unwrap();
+        assert_eq!(tts.adjust_volume(-0.25).unwrap(), 0.5);
+        assert_eq!(tts.get_volume().unwrap(), 0.5);
+        tts.adjust_pitch(0.25).unwrap();
+        assert_eq!(tts.adjust_pitch(0.25).unwrap(), 2.);
+        tts.adjust_rate(0.1).unwrap();
+        let rate = tts.adjust_rate(0.1).unwrap();
+        assert!((rate - 2.98).abs() < 1e-5);
+        assert_eq!(tts.get_rate().unwrap(), rate);
+    }
+
+    #[test]
+    fn unmuting_restores_the_volume() {
+        let mut tts = Tts::fake();
+        tts.set_volume(0.3).unwrap();
+        tts.set_muted(true).unwrap();
+        assert_eq!(tts.0.read().get_volume().unwrap(), 0.);
+        assert_eq!(tts.get_volume().unwrap(), 0.3);
+        tts.set_muted(false).unwrap();
+        assert_eq!(tts.get_volume().unwrap(), 0.3);
+        tts.set_muted(true).unwrap();
+        tts.set_volume(0.6).unwrap();
+        tts.set_muted(false).unwrap();
+        assert_eq!(tts.get_volume().unwrap(), 0.6);
+    }
+
+    #[test]
+    fn noop_takes_settings() {
+        let mut tts = Tts::new(Backends::Noop).unwrap();
+        tts.add_filter(Box::new(filter::HtmlFilter)).unwrap();
+        tts.set_lexicon(Some(Lexicon::new())).unwrap();
+        tts.set_muted(true).unwrap();
+        tts.set_max_utterance_length(Some(100)).unwrap();
+        tts.set_announcement_policy(AnnouncementPolicy {
+            replace_unfinished: true,
+            ..Default::default()
+        })
+        .unwrap();
+        tts.set_interceptor(Some(Box::new(|_| InterceptAction::Speak)))
+            .unwrap();
+        tts.on_error(Some(Box::new(|_| {}))).unwrap();
+        tts.on_utterance_begin(Some(Box::new(|_| {}))).unwrap();
+        tts.on_utterance_end(Some(Box::new(|_| {}))).unwrap();
+        tts.on_utterance_stop(Some(Box::new(|_| {}))).unwrap();
+        assert_eq!(tts.speak("Hello", false).unwrap(), None);
+    }
+
+    #[test]
+    fn settings_are_shared_by_clones_but_not_other_instances() {
+        let mut tts = Tts::new(Backends::Noop).unwrap();
+        let clone = tts.clone();
+        let other = Tts::new(Backends::Noop).unwrap();
+        tts.add_filter(Box::new(|text: &str, _: Option<&LanguageTag<String>>| {
+            text.to_uppercase()
+        }))
+        .unwrap();
+        let mut lexicon = Lexicon::new();
+        lexicon.add_word("HELLO", "HI");
+        tts.set_lexicon(Some(lexicon)).unwrap();
+        assert_eq!(clone.preprocess("hello there", None), "HI THERE");
+        assert_eq!(other.preprocess("hello there", None), "hello there");
+        tts.clear_filters().unwrap();
+        assert_eq!(clone.preprocess("hello there", None), "HI there");
+        tts.set_lexicon(None).unwrap();
+        assert_eq!(clone.preprocess("hello there", None), "hello there");
+    }
+
+    #[test]
+    fn callbacks_are_only_wired_up_once_used() {
+        let mut tts = Tts::new(Backends::Noop).unwrap();
+        let id = tts.0.read().id().unwrap();
+        tts.speak("Hello", false).unwrap();
+        assert!(!callbacks::contains(id));
+        tts.on_utterance_end(Some(Box::new(|_| {}))).unwrap();
+        assert!(callbacks::contains(id));
+    }
+
+    #[test]
+    fn invalid_utterance_ids_are_rejected() {
+        for s in ["", "42", "speechd", "speechd:", "speechd:-1", "nonsense:42"] {
+            assert!(matches!(s.parse::<UtteranceId>(), Err(Error::InvalidId(_))));
+        }
+    }
 }
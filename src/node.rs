@@ -0,0 +1,274 @@
+//! Node.js bindings, built with [napi-rs](https://napi.rs).
+//!
+//! Enabled with the `node` feature. The native `Tts` class takes an `emit(event, ...args)` function, which
+//! `node/index.js` wires to an `EventEmitter`. Events are `"begin"`, `"end"` and `"stop"` with the utterance ID, and
+//! `"word"` with the utterance ID and the UTF-16 offsets of the word in the spoken text.
+
+use std::{collections::HashMap, sync::Arc};
+
+use napi::{
+    bindgen_prelude::*,
+    threadsafe_function::{ErrorStrategy, ThreadsafeFunction, ThreadsafeFunctionCallMode},
+    JsFunction, JsUnknown,
+};
+use napi_derive::napi;
+use parking_lot::Mutex;
+
+use crate::{Error, Features, UtteranceId};
+
+fn to_napi(e: Error) -> napi::Error {
+    napi::Error::from_reason(e.to_string())
+}
+
+enum Event {
+    Begin(u64),
+    End(u64),
+    Stop(u64),
+    Word(u64, u32, u32),
+}
+
+/// A voice, as returned by `Tts.voices()`.
+#[napi(object, js_name = "Voice")]
+pub struct NodeVoice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+    pub requires_network: bool,
+}
+
+impl From<crate::Voice> for NodeVoice {
+    fn from(v: crate::Voice) -> Self {
+        NodeVoice {
+            id: v.id(),
+            name: v.name(),
+            language: v.language().to_string(),
+            requires_network: v.requires_network(),
+        }
+    }
+}
+
+/// The features supported by a backend, as returned by `Tts.supportedFeatures()`.
+#[napi(object, js_name = "Features")]
+pub struct NodeFeatures {
+    pub is_speaking: bool,
+    pub pitch: bool,
+    pub rate: bool,
+    pub stop: bool,
+    pub utterance_callbacks: bool,
+    pub voice: bool,
+    pub get_voice: bool,
+    pub volume: bool,
+    pub word_callbacks: bool,
+    pub synthesize: bool,
+    pub pause: bool,
+    pub queue: bool,
+    pub output_device: bool,
+    pub earcons: bool,
+    pub ssml: bool,
+    pub braille: bool,
+    pub priorities: bool,
+}
+
+impl From<Features> for NodeFeatures {
+    fn from(f: Features) -> Self {
+        NodeFeatures {
+            is_speaking: f.is_speaking,
+            pitch: f.pitch,
+            rate: f.rate,
+            stop: f.stop,
+            utterance_callbacks: f.utterance_callbacks,
+            voice: f.voice,
+            get_voice: f.get_voice,
+            volume: f.volume,
+            word_callbacks: f.word_callbacks,
+            synthesize: f.synthesize,
+            pause: f.pause,
+            queue: f.queue,
+            output_device: f.output_device,
+            earcons: f.earcons,
+            ssml: f.ssml,
+            braille: f.braille,
+            priorities: f.priorities,
+        }
+    }
+}
+
+/// The crate's `Tts` API, exported to JavaScript as `Tts`.
+#[napi(js_name = "Tts")]
+pub struct NodeTts {
+    tts: crate::Tts,
+    /// The text of each pending utterance, for converting word boundaries to UTF-16 offsets.
+    texts: Arc<Mutex<HashMap<u64, String>>>,
+}
+
+#[napi]
+impl NodeTts {
+    /// Creates a speech synthesizer with the default backend, delivering events through `emit`.
+    #[napi(constructor)]
+    pub fn new(env: Env, emit: JsFunction) -> Result<Self> {
+        let tts = crate::Tts::default().map_err(to_napi)?;
+        let texts: Arc<Mutex<HashMap<u64, String>>> = Default::default();
+        let mut emit: ThreadsafeFunction<Event, ErrorStrategy::Fatal> = emit
+            .create_threadsafe_function(0, |ctx| {
+                let env = ctx.env;
+                let (name, id, range) = match ctx.value {
+                    Event::Begin(id) => ("begin", id, None),
+                    Event::End(id) => ("end", id, None),
+                    Event::Stop(id) => ("stop", id, None),
+                    Event::Word(id, start, end) => ("word", id, Some((start, end))),
+                };
+                let mut args: Vec<JsUnknown> = vec![
+                    env.create_string(name)?.into_unknown(),
+                    env.create_double(id as f64)?.into_unknown(),
+                ];
+                if let Some((start, end)) = range {
+                    args.push(env.create_uint32(start)?.into_unknown());
+                    args.push(env.create_uint32(end)?.into_unknown());
+                }
+                Ok(args)
+            })?;
+        // Don't keep the process alive just to wait for events.
+        emit.unref(&env)?;
+        let Features {
+            utterance_callbacks,
+            word_callbacks,
+            ..
+        } = tts.supported_features();
+        let send = move |event| {
+            emit.call(event, ThreadsafeFunctionCallMode::NonBlocking);
+        };
+        if utterance_callbacks {
+            let s = send.clone();
+            tts.on_utterance_begin(Some(Box::new(move |id| s(Event::Begin(id.as_u64())))))
+                .map_err(to_napi)?;
+            let (s, t) = (send.clone(), texts.clone());
+            tts.on_utterance_end(Some(Box::new(move |id| {
+                let id = id.as_u64();
+                t.lock().remove(&id);
+                s(Event::End(id));
+            })))
+            .map_err(to_napi)?;
+            let (s, t) = (send.clone(), texts.clone());
+            tts.on_utterance_stop(Some(Box::new(move |id| {
+                let id = id.as_u64();
+                t.lock().remove(&id);
+                s(Event::Stop(id));
+            })))
+            .map_err(to_napi)?;
+        }
+        if word_callbacks {
+            let t = texts.clone();
+            tts.on_word_boundary(Some(Box::new(move |id: UtteranceId, range| {
+                let id = id.as_u64();
+                let offsets = t.lock().get(&id).map(|text| {
+                    let start = text[..range.start].encode_utf16().count() as u32;
+                    (start, start + text[range].encode_utf16().count() as u32)
+                });
+                if let Some((start, end)) = offsets {
+                    send(Event::Word(id, start, end));
+                }
+            })))
+            .map_err(to_napi)?;
+        }
+        Ok(NodeTts { tts, texts })
+    }
+
+    #[napi]
+    pub fn supported_features(&self) -> NodeFeatures {
+        self.tts.supported_features().into()
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, and returns the utterance ID if the backend reports one.
+    #[napi]
+    pub fn speak(&mut self, text: String, interrupt: Option<bool>) -> Result<Option<f64>> {
+        let id = self
+            .tts
+            .speak(text.as_str(), interrupt.unwrap_or(false))
+            .map_err(to_napi)?
+            .map(|id| id.as_u64());
+        if let (Some(id), true) = (id, self.tts.supported_features().word_callbacks) {
+            self.texts.lock().insert(id, text);
+        }
+        Ok(id.map(|id| id as f64))
+    }
+
+    #[napi]
+    pub fn stop(&mut self) -> Result<()> {
+        self.tts.stop().map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub fn pause(&mut self) -> Result<()> {
+        self.tts.pause().map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub fn resume(&mut self) -> Result<()> {
+        self.tts.resume().map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub fn is_speaking(&self) -> Result<bool> {
+        self.tts.is_speaking().map_err(to_napi)
+    }
+
+    #[napi(getter)]
+    pub fn rate(&self) -> Result<f64> {
+        self.tts.get_rate().map(f64::from).map_err(to_napi)
+    }
+
+    #[napi(setter)]
+    pub fn set_rate(&mut self, rate: f64) -> Result<()> {
+        self.tts.set_rate(rate as f32).map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi(getter)]
+    pub fn pitch(&self) -> Result<f64> {
+        self.tts.get_pitch().map(f64::from).map_err(to_napi)
+    }
+
+    #[napi(setter)]
+    pub fn set_pitch(&mut self, pitch: f64) -> Result<()> {
+        self.tts.set_pitch(pitch as f32).map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi(getter)]
+    pub fn volume(&self) -> Result<f64> {
+        self.tts.get_volume().map(f64::from).map_err(to_napi)
+    }
+
+    #[napi(setter)]
+    pub fn set_volume(&mut self, volume: f64) -> Result<()> {
+        self.tts.set_volume(volume as f32).map_err(to_napi)?;
+        Ok(())
+    }
+
+    #[napi]
+    pub fn voices(&self) -> Result<Vec<NodeVoice>> {
+        let voices = self.tts.voices().map_err(to_napi)?;
+        Ok(voices.into_iter().map(NodeVoice::from).collect())
+    }
+
+    #[napi]
+    pub fn voice(&self) -> Result<Option<NodeVoice>> {
+        let voice = self.tts.voice().map_err(to_napi)?;
+        Ok(voice.map(NodeVoice::from))
+    }
+
+    /// Selects the voice with the given ID, as returned by `Voice.id`.
+    #[napi]
+    pub fn set_voice(&mut self, id: String) -> Result<()> {
+        let voices = self.tts.voices().map_err(to_napi)?;
+        let voice = voices
+            .iter()
+            .find(|v| v.id() == id)
+            .ok_or_else(|| to_napi(Error::OperationFailed))?;
+        self.tts.set_voice(voice).map_err(to_napi)?;
+        Ok(())
+    }
+}
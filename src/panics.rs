@@ -0,0 +1,102 @@
+//! Catches panics in callbacks, which would otherwise unwind into engine threads and delegates, and reports them to the
+//! error callback instead. Global state is behind `parking_lot` locks, which aren't poisoned, so a panic that does
+//! escape while one is held doesn't leave every later call failing.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    panic::{self, AssertUnwindSafe},
+    sync::LazyLock,
+};
+
+use log::error;
+use parking_lot::Mutex;
+
+use crate::{callbacks::Slot, BackendId, Error};
+
+#[derive(Default)]
+struct ErrorCallback(Slot<Box<dyn FnMut(Error)>>);
+
+unsafe impl Send for ErrorCallback {}
+
+static ERROR_CALLBACKS: LazyLock<Mutex<HashMap<BackendId, ErrorCallback>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set_callback(backend: BackendId, callback: Option<Box<dyn FnMut(Error)>>) {
+    let mut callbacks = ERROR_CALLBACKS.lock();
+    callbacks.entry(backend).or_default().0.set(callback);
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    ERROR_CALLBACKS.lock().remove(&backend);
+}
+
+fn message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".into()
+    }
+}
+
+/// Runs the callback `f`, reporting a panic to `backend`'s error callback rather than unwinding.
+pub(crate) fn guard<F: FnOnce()>(backend: BackendId, f: F) {
+    let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) else {
+        return;
+    };
+    let message = message(payload.as_ref());
+    error!("Callback panicked: {}", message);
+    report(backend, Error::CallbackPanicked(message));
+}
+
+/// Reports `error`, which happened outside any method call, to `backend`'s error callback.
+pub(crate) fn report(backend: BackendId, error: Error) {
+    let callback = ERROR_CALLBACKS
+        .lock()
+        .get_mut(&backend)
+        .and_then(|callback| callback.0.take());
+    if let Some(mut callback) = callback {
+        // A panicking error callback has nowhere left to report to.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| (callback.0)(error)));
+        if let Some(slot) = ERROR_CALLBACKS.lock().get_mut(&backend) {
+            slot.0.restore(callback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{backends::fake, Error, Tts};
+
+    #[test]
+    fn panicking_callbacks_are_reported_and_speech_continues() {
+        let mut tts = Tts::fake();
+        let backend = tts.0.read().id().unwrap();
+        let errors = Rc::new(RefCell::new(vec![]));
+        let reported = errors.clone();
+        tts.on_error(Some(Box::new(move |e| reported.borrow_mut().push(e))))
+            .unwrap();
+        tts.on_utterance_begin(Some(Box::new(|_| panic!("begin"))))
+            .unwrap();
+        let ended = Rc::new(RefCell::new(vec![]));
+        let end = ended.clone();
+        tts.on_utterance_end(Some(Box::new(move |id| end.borrow_mut().push(id))))
+            .unwrap();
+        let first = tts.speak("one", false).unwrap().unwrap();
+        let second = tts.speak("two", false).unwrap().unwrap();
+        for _ in 0..3 {
+            fake::advance(backend);
+        }
+        assert_eq!(*ended.borrow(), [first, second]);
+        let errors = errors.borrow();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|e| matches!(e, Error::CallbackPanicked(message) if message == "begin")));
+        assert!(!tts.is_speaking().unwrap());
+    }
+}
@@ -0,0 +1,73 @@
+//! Drops and replaces announcements that come faster than they can be spoken.
+
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::BackendId;
+
+/// Rules for announcements that arrive too quickly, such as from progress bars and sliders, so speech doesn't lag
+/// far behind what's on screen.
+///
+/// The default speaks everything. Set one with `Tts::set_announcement_policy()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnnouncementPolicy {
+    /// Text identical to the previous announcement is dropped if it comes within this long.
+    pub duplicate_window: Option<Duration>,
+    /// Whether a new announcement interrupts the previous one if it's still queued or speaking, rather than queueing
+    /// behind it.
+    pub replace_unfinished: bool,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Announcement {
+    pub(crate) text: String,
+    pub(crate) at: Instant,
+    pub(crate) utterance: Option<u64>,
+}
+
+#[derive(Default)]
+struct State {
+    policy: AnnouncementPolicy,
+    last: Option<Announcement>,
+}
+
+static POLICIES: LazyLock<Mutex<HashMap<BackendId, State>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set(backend: BackendId, policy: AnnouncementPolicy) {
+    let mut policies = POLICIES.lock();
+    if policy == AnnouncementPolicy::default() {
+        policies.remove(&backend);
+    } else {
+        policies.entry(backend).or_default().policy = policy;
+    }
+}
+
+/// Returns the policy, if any, and the previous announcement made under it.
+pub(crate) fn get(backend: BackendId) -> Option<(AnnouncementPolicy, Option<Announcement>)> {
+    let policies = POLICIES.lock();
+    let state = policies.get(&backend)?;
+    Some((state.policy, state.last.clone()))
+}
+
+/// Records an announcement, if a policy is set.
+pub(crate) fn announced(backend: BackendId, text: &str, utterance: Option<u64>) {
+    let mut policies = POLICIES.lock();
+    if let Some(state) = policies.get_mut(&backend) {
+        state.last = Some(Announcement {
+            text: text.to_string(),
+            at: Instant::now(),
+            utterance,
+        });
+    }
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    POLICIES.lock().remove(&backend);
+}
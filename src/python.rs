@@ -0,0 +1,323 @@
+//! Python bindings, built with [PyO3](https://pyo3.rs).
+//!
+//! Enabled with the `python` feature, which builds the cdylib as a Python extension module named `tts`. Callbacks
+//! may fire on a backend thread, so each acquires the GIL before calling into Python. Word boundaries are passed as
+//! character offsets into the spoken string, so they can be used to slice it directly.
+
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+use pyo3::{
+    exceptions::{PyNotImplementedError, PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyTuple,
+};
+
+use crate::{Error, Features, Tts, UtteranceId, Voice};
+
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::UnsupportedFeature => PyNotImplementedError::new_err(e.to_string()),
+            Error::OutOfRange => PyValueError::new_err(e.to_string()),
+            e => PyRuntimeError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// Identifies an utterance returned by `Tts.speak()` and passed to callbacks.
+#[pyclass(name = "UtteranceId", module = "tts", frozen, eq, hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PyUtteranceId(u64);
+
+impl From<&UtteranceId> for PyUtteranceId {
+    fn from(id: &UtteranceId) -> Self {
+        PyUtteranceId(id.as_u64())
+    }
+}
+
+#[pymethods]
+impl PyUtteranceId {
+    fn __repr__(&self) -> String {
+        format!("UtteranceId({})", self.0)
+    }
+}
+
+/// A voice, as returned by `Tts.voices()`.
+#[pyclass(name = "Voice", module = "tts", frozen)]
+#[derive(Clone, Debug)]
+pub struct PyVoice(Voice);
+
+#[pymethods]
+impl PyVoice {
+    #[getter]
+    fn id(&self) -> String {
+        self.0.id()
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.0.name()
+    }
+
+    #[getter]
+    fn language(&self) -> String {
+        self.0.language().to_string()
+    }
+
+    #[getter]
+    fn requires_network(&self) -> bool {
+        self.0.requires_network()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Voice(id={:?}, name={:?})", self.0.id(), self.0.name())
+    }
+}
+
+/// The features supported by a backend, as returned by `Tts.supported_features()`.
+#[pyclass(name = "Features", module = "tts", frozen, get_all)]
+#[derive(Clone, Copy, Debug)]
+pub struct PyFeatures {
+    is_speaking: bool,
+    pitch: bool,
+    rate: bool,
+    stop: bool,
+    utterance_callbacks: bool,
+    voice: bool,
+    get_voice: bool,
+    volume: bool,
+    word_callbacks: bool,
+    synthesize: bool,
+    pause: bool,
+    queue: bool,
+    output_device: bool,
+    earcons: bool,
+    ssml: bool,
+    braille: bool,
+    priorities: bool,
+}
+
+impl From<Features> for PyFeatures {
+    fn from(f: Features) -> Self {
+        PyFeatures {
+            is_speaking: f.is_speaking,
+            pitch: f.pitch,
+            rate: f.rate,
+            stop: f.stop,
+            utterance_callbacks: f.utterance_callbacks,
+            voice: f.voice,
+            get_voice: f.get_voice,
+            volume: f.volume,
+            word_callbacks: f.word_callbacks,
+            synthesize: f.synthesize,
+            pause: f.pause,
+            queue: f.queue,
+            output_device: f.output_device,
+            earcons: f.earcons,
+            ssml: f.ssml,
+            braille: f.braille,
+            priorities: f.priorities,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Handlers {
+    begin: Option<PyObject>,
+    end: Option<PyObject>,
+    stop: Option<PyObject>,
+    word_boundary: Option<PyObject>,
+    /// The text of each pending utterance, for converting word boundaries to character offsets.
+    texts: HashMap<PyUtteranceId, String>,
+}
+
+/// Calls the handler chosen by `f`, if any, reporting exceptions through `sys.unraisablehook`.
+fn call<F, A>(handlers: &Mutex<Handlers>, f: F, args: A)
+where
+    F: FnOnce(&Handlers) -> Option<&PyObject>,
+    A: for<'py> IntoPyObject<'py, Target = PyTuple>,
+{
+    Python::with_gil(|py| {
+        // Clone the handler so the lock isn't held while Python runs.
+        let handler = f(&handlers.lock()).map(|h| h.clone_ref(py));
+        if let Some(handler) = handler {
+            if let Err(e) = handler.call1(py, args) {
+                e.write_unraisable(py, None);
+            }
+        }
+    });
+}
+
+/// The crate's `Tts` API, exported to Python as `tts.Tts`.
+#[pyclass(name = "Tts", module = "tts", unsendable)]
+pub struct PyTts {
+    tts: Tts,
+    handlers: Arc<Mutex<Handlers>>,
+}
+
+#[pymethods]
+impl PyTts {
+    #[new]
+    fn new() -> PyResult<Self> {
+        let tts = Tts::default()?;
+        let handlers: Arc<Mutex<Handlers>> = Default::default();
+        let Features {
+            utterance_callbacks,
+            word_callbacks,
+            ..
+        } = tts.supported_features();
+        if utterance_callbacks {
+            let h = handlers.clone();
+            tts.on_utterance_begin(Some(Box::new(move |id| {
+                call(&h, |h| h.begin.as_ref(), (PyUtteranceId::from(&id),));
+            })))?;
+            let h = handlers.clone();
+            tts.on_utterance_end(Some(Box::new(move |id| {
+                let id = PyUtteranceId::from(&id);
+                h.lock().texts.remove(&id);
+                call(&h, |h| h.end.as_ref(), (id,));
+            })))?;
+            let h = handlers.clone();
+            tts.on_utterance_stop(Some(Box::new(move |id| {
+                let id = PyUtteranceId::from(&id);
+                h.lock().texts.remove(&id);
+                call(&h, |h| h.stop.as_ref(), (id,));
+            })))?;
+        }
+        if word_callbacks {
+            let h = handlers.clone();
+            tts.on_word_boundary(Some(Box::new(move |id, range| {
+                let id = PyUtteranceId::from(&id);
+                let offsets = h.lock().texts.get(&id).map(|text| {
+                    let start = text[..range.start].chars().count();
+                    (start, start + text[range].chars().count())
+                });
+                if let Some((start, end)) = offsets {
+                    call(&h, |h| h.word_boundary.as_ref(), (id, start, end));
+                }
+            })))?;
+        }
+        Ok(PyTts { tts, handlers })
+    }
+
+    fn supported_features(&self) -> PyFeatures {
+        self.tts.supported_features().into()
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, and returns the utterance ID if the backend reports one.
+    #[pyo3(signature = (text, interrupt = false))]
+    fn speak(&mut self, text: String, interrupt: bool) -> PyResult<Option<PyUtteranceId>> {
+        let id = self
+            .tts
+            .speak(text.as_str(), interrupt)?
+            .map(|id| PyUtteranceId::from(&id));
+        if let (Some(id), true) = (id, self.tts.supported_features().word_callbacks) {
+            self.handlers.lock().texts.insert(id, text);
+        }
+        Ok(id)
+    }
+
+    fn stop(&mut self) -> PyResult<()> {
+        self.tts.stop()?;
+        Ok(())
+    }
+
+    fn pause(&mut self) -> PyResult<()> {
+        self.tts.pause()?;
+        Ok(())
+    }
+
+    fn resume(&mut self) -> PyResult<()> {
+        self.tts.resume()?;
+        Ok(())
+    }
+
+    fn is_speaking(&self) -> PyResult<bool> {
+        Ok(self.tts.is_speaking()?)
+    }
+
+    #[getter]
+    fn rate(&self) -> PyResult<f32> {
+        Ok(self.tts.get_rate()?)
+    }
+
+    #[setter]
+    fn set_rate(&mut self, rate: f32) -> PyResult<()> {
+        self.tts.set_rate(rate)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn pitch(&self) -> PyResult<f32> {
+        Ok(self.tts.get_pitch()?)
+    }
+
+    #[setter]
+    fn set_pitch(&mut self, pitch: f32) -> PyResult<()> {
+        self.tts.set_pitch(pitch)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn volume(&self) -> PyResult<f32> {
+        Ok(self.tts.get_volume()?)
+    }
+
+    #[setter]
+    fn set_volume(&mut self, volume: f32) -> PyResult<()> {
+        self.tts.set_volume(volume)?;
+        Ok(())
+    }
+
+    fn voices(&self) -> PyResult<Vec<PyVoice>> {
+        let voices = self.tts.voices()?;
+        Ok(voices.into_iter().map(PyVoice).collect())
+    }
+
+    #[getter]
+    fn voice(&self) -> PyResult<Option<PyVoice>> {
+        Ok(self.tts.voice()?.map(PyVoice))
+    }
+
+    #[setter]
+    fn set_voice(&mut self, voice: PyVoice) -> PyResult<()> {
+        self.tts.set_voice(&voice.0)?;
+        Ok(())
+    }
+
+    /// Calls `callback(utterance_id)` when an utterance begins, or removes the callback if `None`.
+    #[pyo3(signature = (callback))]
+    fn on_utterance_begin(&self, callback: Option<PyObject>) {
+        self.handlers.lock().begin = callback;
+    }
+
+    /// Calls `callback(utterance_id)` when an utterance finishes, or removes the callback if `None`.
+    #[pyo3(signature = (callback))]
+    fn on_utterance_end(&self, callback: Option<PyObject>) {
+        self.handlers.lock().end = callback;
+    }
+
+    /// Calls `callback(utterance_id)` when an utterance is stopped, or removes the callback if `None`.
+    #[pyo3(signature = (callback))]
+    fn on_utterance_stop(&self, callback: Option<PyObject>) {
+        self.handlers.lock().stop = callback;
+    }
+
+    /// Calls `callback(utterance_id, start, end)` when a word is about to be spoken, or removes the callback if
+    /// `None`. `start` and `end` are character offsets into the text passed to `speak()`.
+    #[pyo3(signature = (callback))]
+    fn on_word_boundary(&self, callback: Option<PyObject>) {
+        self.handlers.lock().word_boundary = callback;
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "tts")]
+fn tts_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTts>()?;
+    m.add_class::<PyVoice>()?;
+    m.add_class::<PyFeatures>()?;
+    m.add_class::<PyUtteranceId>()?;
+    Ok(())
+}
@@ -0,0 +1,235 @@
+//! Tracks whether each backend is speaking or paused, and how long its utterances take to start, from its utterance
+//! callbacks. This lets apps be told when speech starts, stops or pauses without polling `is_speaking()`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use log::trace;
+use parking_lot::Mutex;
+
+use crate::{
+    callbacks::Slot, BackendId, SpeakingState, UtteranceEvent, UtteranceId, UtteranceProgress,
+};
+
+/// How far an utterance that has begun has got.
+struct Progress {
+    began: Instant,
+    /// Time spent paused since it began.
+    paused: Duration,
+    offset: Option<usize>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Utterances queued but not yet finished or stopped.
+    pending: HashSet<u64>,
+    /// When each utterance that hasn't yet begun was queued.
+    spoken_at: HashMap<u64, Instant>,
+    /// The time to first audio of the most recently begun utterance.
+    latency: Option<(u64, Duration)>,
+    /// Utterances that finished before `speak()` returned their IDs.
+    finished: HashSet<u64>,
+    speaking: bool,
+    /// Whether `Tts::pause()` was called more recently than `Tts::resume()`.
+    paused: bool,
+    paused_at: Option<Instant>,
+    /// Utterances that have begun but not yet finished or stopped.
+    progress: HashMap<u64, Progress>,
+    callback: Slot<Box<dyn FnMut(bool)>>,
+    state_callback: Slot<Box<dyn FnMut(SpeakingState)>>,
+}
+
+impl State {
+    fn speaking_state(&self) -> SpeakingState {
+        if self.paused && (self.speaking || !self.pending.is_empty()) {
+            SpeakingState::Paused
+        } else if self.speaking {
+            SpeakingState::Speaking
+        } else {
+            SpeakingState::Idle
+        }
+    }
+}
+
+unsafe impl Send for State {}
+
+static STATES: LazyLock<Mutex<HashMap<BackendId, State>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Records that an utterance was queued by a `speak()` call made at `at`, returning whether it's still pending rather
+/// than already finished.
+pub(crate) fn spoken(backend: BackendId, utterance: &UtteranceId, at: Instant) -> bool {
+    let utterance = utterance.as_u64();
+    let mut pending = false;
+    update(backend, |state| {
+        if !state.finished.remove(&utterance) {
+            state.pending.insert(utterance);
+            state.spoken_at.insert(utterance, at);
+            pending = true;
+        }
+    });
+    pending
+}
+
+/// Whether `utterance` is queued or speaking.
+pub(crate) fn is_pending(backend: BackendId, utterance: u64) -> bool {
+    let states = STATES.lock();
+    states
+        .get(&backend)
+        .is_some_and(|state| state.pending.contains(&utterance))
+}
+
+/// Returns the time from `speak()` to audio of the most recently begun utterance.
+pub(crate) fn last_latency(backend: BackendId) -> Option<Duration> {
+    let states = STATES.lock();
+    states.get(&backend)?.latency.map(|(_, latency)| latency)
+}
+
+/// Returns the time from `speak()` to audio of `utterance`, if it was the most recent to begin.
+#[cfg(feature = "tracing")]
+pub(crate) fn latency(backend: BackendId, utterance: u64) -> Option<Duration> {
+    let states = STATES.lock();
+    match states.get(&backend)?.latency {
+        Some((u, latency)) if u == utterance => Some(latency),
+        _ => None,
+    }
+}
+
+pub(crate) fn set_callback(backend: BackendId, callback: Option<Box<dyn FnMut(bool)>>) {
+    let mut states = STATES.lock();
+    states.entry(backend).or_default().callback.set(callback);
+}
+
+pub(crate) fn set_state_callback(
+    backend: BackendId,
+    callback: Option<Box<dyn FnMut(SpeakingState)>>,
+) {
+    let mut states = STATES.lock();
+    states
+        .entry(backend)
+        .or_default()
+        .state_callback
+        .set(callback);
+}
+
+/// Records that speech was paused or resumed.
+pub(crate) fn set_paused(backend: BackendId, paused: bool) {
+    update(backend, |state| {
+        if paused {
+            state.paused_at.get_or_insert_with(Instant::now);
+        } else if let Some(paused_at) = state.paused_at.take() {
+            for progress in state.progress.values_mut() {
+                progress.paused += paused_at.max(progress.began).elapsed();
+            }
+        }
+        state.paused = paused;
+    });
+}
+
+/// Records that speech reached byte `offset` of `utterance`.
+pub(crate) fn word(backend: BackendId, utterance: &UtteranceId, offset: usize) {
+    let mut states = STATES.lock();
+    if let Some(progress) = states
+        .get_mut(&backend)
+        .and_then(|state| state.progress.get_mut(&utterance.as_u64()))
+    {
+        progress.offset = Some(offset);
+    }
+}
+
+/// Returns how far `utterance` has got, if it's speaking.
+pub(crate) fn progress(backend: BackendId, utterance: &UtteranceId) -> Option<UtteranceProgress> {
+    let states = STATES.lock();
+    let state = states.get(&backend)?;
+    let progress = state.progress.get(&utterance.as_u64())?;
+    let now = state.paused_at.unwrap_or_else(Instant::now);
+    Some(UtteranceProgress {
+        elapsed: now
+            .saturating_duration_since(progress.began)
+            .saturating_sub(progress.paused),
+        offset: progress.offset,
+    })
+}
+
+/// Applies `f` to `backend`'s state, then calls the callbacks for whatever changed, without the lock held.
+fn update<F: FnOnce(&mut State)>(backend: BackendId, f: F) {
+    let (speaking, state) = {
+        let mut states = STATES.lock();
+        let state = states.entry(backend).or_default();
+        let was_speaking = state.speaking;
+        let was = state.speaking_state();
+        f(state);
+        let speaking = (state.speaking != was_speaking)
+            .then(|| state.callback.take())
+            .flatten()
+            .map(|callback| (callback, state.speaking));
+        let now = state.speaking_state();
+        let speaking_state = (now != was)
+            .then(|| state.state_callback.take())
+            .flatten()
+            .map(|callback| (callback, now));
+        if now != was {
+            trace!("{:?} state: {:?}", backend, now);
+        }
+        (speaking, speaking_state)
+    };
+    if let Some((mut callback, speaking)) = speaking {
+        (callback.0)(speaking);
+        if let Some(state) = STATES.lock().get_mut(&backend) {
+            state.callback.restore(callback);
+        }
+    }
+    if let Some((mut callback, now)) = state {
+        (callback.0)(now);
+        if let Some(state) = STATES.lock().get_mut(&backend) {
+            state.state_callback.restore(callback);
+        }
+    }
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    STATES.lock().remove(&backend);
+}
+
+/// Wraps an utterance callback, if any, to update the speaking state before it runs.
+pub(crate) fn utterance_callback(
+    backend: BackendId,
+    event: UtteranceEvent,
+    mut callback: Option<Box<dyn FnMut(UtteranceId)>>,
+) -> Box<dyn FnMut(UtteranceId)> {
+    Box::new(move |id: UtteranceId| {
+        let utterance = id.as_u64();
+        update(backend, |state| {
+            let spoken_at = state.spoken_at.remove(&utterance);
+            state.speaking = match event {
+                UtteranceEvent::Begin => {
+                    if let Some(spoken_at) = spoken_at {
+                        state.latency = Some((utterance, spoken_at.elapsed()));
+                    }
+                    state.progress.insert(
+                        utterance,
+                        Progress {
+                            began: Instant::now(),
+                            paused: Duration::ZERO,
+                            offset: None,
+                        },
+                    );
+                    true
+                }
+                UtteranceEvent::End | UtteranceEvent::Stop => {
+                    state.progress.remove(&utterance);
+                    if !state.pending.remove(&utterance) {
+                        state.finished.insert(utterance);
+                    }
+                    !state.pending.is_empty()
+                }
+            };
+        });
+        if let Some(callback) = callback.as_mut() {
+            callback(id);
+        }
+    })
+}
@@ -0,0 +1,102 @@
+//! Spells text a character at a time, for passwords and confirmation codes.
+
+use std::time::Duration;
+
+const NATO: [&str; 26] = [
+    "Alfa", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliett",
+    "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+    "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
+/// Names of characters engines tend to skip or pronounce inconsistently when spoken alone.
+const SYMBOLS: &[(char, &str)] = &[
+    (' ', "space"),
+    ('!', "exclamation mark"),
+    ('"', "quote"),
+    ('#', "hash"),
+    ('$', "dollar"),
+    ('%', "percent"),
+    ('&', "ampersand"),
+    ('\'', "apostrophe"),
+    ('(', "left paren"),
+    (')', "right paren"),
+    ('*', "asterisk"),
+    ('+', "plus"),
+    (',', "comma"),
+    ('-', "dash"),
+    ('.', "dot"),
+    ('/', "slash"),
+    (':', "colon"),
+    (';', "semicolon"),
+    ('<', "less than"),
+    ('=', "equals"),
+    ('>', "greater than"),
+    ('?', "question mark"),
+    ('@', "at"),
+    ('[', "left bracket"),
+    ('\\', "backslash"),
+    (']', "right bracket"),
+    ('^', "caret"),
+    ('_', "underscore"),
+    ('`', "backtick"),
+    ('{', "left brace"),
+    ('|', "bar"),
+    ('}', "right brace"),
+    ('~', "tilde"),
+];
+
+/// How letters are spelled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpellAlphabet {
+    /// Each letter by name.
+    #[default]
+    Letters,
+    /// The NATO phonetic alphabet, as in "Alfa", "Bravo", "Charlie".
+    Nato,
+}
+
+/// Settings for `Tts::spell()`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpellStyle {
+    pub alphabet: SpellAlphabet,
+    /// Whether to say "capital" before uppercase letters.
+    pub indicate_capitals: bool,
+    /// Silence between characters. Supported on AVFoundation. Elsewhere, each character is spoken as its own
+    /// utterance, with the engine's usual pause between them.
+    pub pause: Duration,
+}
+
+impl Default for SpellStyle {
+    fn default() -> Self {
+        Self {
+            alphabet: Default::default(),
+            indicate_capitals: true,
+            pause: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Returns what to say for each character of `text`, with its byte offset.
+pub(crate) fn pieces(text: &str, style: &SpellStyle) -> Vec<(String, usize)> {
+    text.char_indices()
+        .map(|(i, c)| {
+            let name = if c.is_ascii_alphabetic() && style.alphabet == SpellAlphabet::Nato {
+                NATO[(c.to_ascii_lowercase() as u8 - b'a') as usize].to_string()
+            } else if let Some(&(_, name)) = SYMBOLS.iter().find(|(s, _)| *s == c) {
+                name.to_string()
+            } else if c.is_whitespace() {
+                "space".to_string()
+            } else {
+                // Engines read lone lowercase letters as words, such as "a".
+                c.to_uppercase().collect()
+            };
+            if style.indicate_capitals && c.is_uppercase() {
+                (format!("capital {name}"), i)
+            } else {
+                (name, i)
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,218 @@
+//! Typed SSML, rendered to the dialect each backend accepts.
+
+use std::{fmt::Write, time::Duration};
+
+use crate::LanguageTag;
+
+/// The SSML a backend accepts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SsmlDialect {
+    /// SSML 1.1, as accepted by WinRT.
+    Full,
+    /// The subset Speech Dispatcher passes to its output modules, without phonemes.
+    SpeechDispatcher,
+    /// Plain text, with markup stripped and phonemes replaced by the text they pronounce.
+    #[default]
+    PlainText,
+}
+
+/// Changes to prosody, as multiples of the current rate, pitch and volume.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Prosody {
+    pub rate: Option<f32>,
+    pub pitch: Option<f32>,
+    pub volume: Option<f32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Break(Duration),
+    SayAs {
+        interpret_as: String,
+        text: String,
+    },
+    Phoneme {
+        alphabet: String,
+        ph: String,
+        text: String,
+    },
+    Mark(String),
+    Prosody(Prosody, Vec<Node>),
+}
+
+/// Builds speech with pauses, prosody and pronunciations, authored once and rendered to whatever SSML the backend
+/// accepts. Speak it with `Tts::speak_ssml()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SsmlBuilder {
+    language: Option<LanguageTag<String>>,
+    nodes: Vec<Node>,
+}
+
+impl SsmlBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the language of the speech. If unset, `Tts::speak_ssml()` uses that of the current voice.
+    pub fn language(mut self, language: LanguageTag<String>) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    pub fn text<S: Into<String>>(mut self, text: S) -> Self {
+        self.nodes.push(Node::Text(text.into()));
+        self
+    }
+
+    /// Adds a pause.
+    pub fn pause(mut self, duration: Duration) -> Self {
+        self.nodes.push(Node::Break(duration));
+        self
+    }
+
+    /// Adds text to be interpreted as, for instance, "characters", "date" or "telephone".
+    pub fn say_as<I: Into<String>, S: Into<String>>(mut self, interpret_as: I, text: S) -> Self {
+        self.nodes.push(Node::SayAs {
+            interpret_as: interpret_as.into(),
+            text: text.into(),
+        });
+        self
+    }
+
+    /// Adds `text` pronounced as `ph`, a pronunciation in `alphabet`, such as "ipa" or "x-sampa". Backends that don't
+    /// support phonemes speak the text.
+    pub fn phoneme<A: Into<String>, P: Into<String>, S: Into<String>>(
+        mut self,
+        alphabet: A,
+        ph: P,
+        text: S,
+    ) -> Self {
+        self.nodes.push(Node::Phoneme {
+            alphabet: alphabet.into(),
+            ph: ph.into(),
+            text: text.into(),
+        });
+        self
+    }
+
+    /// Adds a named mark.
+    pub fn mark<S: Into<String>>(mut self, name: S) -> Self {
+        self.nodes.push(Node::Mark(name.into()));
+        self
+    }
+
+    /// Adds the speech built by `build` with different prosody.
+    pub fn prosody<F: FnOnce(SsmlBuilder) -> SsmlBuilder>(
+        mut self,
+        prosody: Prosody,
+        build: F,
+    ) -> Self {
+        let nodes = build(SsmlBuilder::new()).nodes;
+        self.nodes.push(Node::Prosody(prosody, nodes));
+        self
+    }
+
+    /// Renders the speech in `dialect`.
+    pub fn render(&self, dialect: SsmlDialect) -> String {
+        self.render_in(dialect, self.language.as_ref())
+    }
+
+    /// Renders the speech in `dialect`, in `language` unless the builder sets one.
+    pub(crate) fn render_in(
+        &self,
+        dialect: SsmlDialect,
+        language: Option<&LanguageTag<String>>,
+    ) -> String {
+        let mut rendered = String::new();
+        if dialect == SsmlDialect::PlainText {
+            render_text(&self.nodes, &mut rendered);
+            return rendered.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        rendered.push_str(r#"<speak version="1.1" xmlns="http://www.w3.org/2001/10/synthesis""#);
+        if let Some(language) = self.language.as_ref().or(language) {
+            write!(rendered, r#" xml:lang="{}""#, escape(language.as_str())).unwrap();
+        }
+        rendered.push('>');
+        render_ssml(&self.nodes, dialect, &mut rendered);
+        rendered.push_str("</speak>");
+        rendered
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_ssml(nodes: &[Node], dialect: SsmlDialect, rendered: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => rendered.push_str(&escape(text)),
+            Node::Break(duration) => {
+                write!(rendered, r#"<break time="{}ms"/>"#, duration.as_millis()).unwrap();
+            }
+            Node::SayAs { interpret_as, text } => write!(
+                rendered,
+                r#"<say-as interpret-as="{}">{}</say-as>"#,
+                escape(interpret_as),
+                escape(text)
+            )
+            .unwrap(),
+            Node::Phoneme { alphabet, ph, text } if dialect == SsmlDialect::Full => write!(
+                rendered,
+                r#"<phoneme alphabet="{}" ph="{}">{}</phoneme>"#,
+                escape(alphabet),
+                escape(ph),
+                escape(text)
+            )
+            .unwrap(),
+            Node::Phoneme { text, .. } => rendered.push_str(&escape(text)),
+            Node::Mark(name) => write!(rendered, r#"<mark name="{}"/>"#, escape(name)).unwrap(),
+            Node::Prosody(prosody, nodes) => {
+                rendered.push_str("<prosody");
+                if let Some(rate) = prosody.rate {
+                    write!(rendered, r#" rate="{:.0}%""#, rate.max(0.) * 100.).unwrap();
+                }
+                if let Some(pitch) = prosody.pitch {
+                    write!(rendered, r#" pitch="{:+.0}%""#, (pitch.max(0.) - 1.) * 100.).unwrap();
+                }
+                if let Some(volume) = prosody.volume {
+                    // Silence is negative infinity decibels.
+                    if volume > 0. {
+                        write!(rendered, r#" volume="{:+.1}dB""#, 20. * volume.log10()).unwrap();
+                    } else {
+                        rendered.push_str(r#" volume="silent""#);
+                    }
+                }
+                rendered.push('>');
+                render_ssml(nodes, dialect, rendered);
+                rendered.push_str("</prosody>");
+            }
+        }
+    }
+}
+
+fn render_text(nodes: &[Node], rendered: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) | Node::SayAs { text, .. } | Node::Phoneme { text, .. } => {
+                rendered.push_str(text);
+            }
+            Node::Break(_) | Node::Mark(_) => rendered.push(' '),
+            Node::Prosody(_, nodes) => render_text(nodes, rendered),
+        }
+    }
+}
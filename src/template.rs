@@ -0,0 +1,113 @@
+//! Looks up localized announcements by key, so every string an app speaks goes through its translation system.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock},
+};
+
+use parking_lot::Mutex;
+
+use crate::{BackendId, LanguageTag};
+
+/// Text resolved from a template, and the language it's in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Localized {
+    pub text: String,
+    /// The language of `text`, to switch to a suitable voice if the current one doesn't speak it.
+    pub language: Option<LanguageTag<String>>,
+}
+
+/// Turns a template key and its arguments into text to speak, usually by way of Fluent, gettext or similar.
+///
+/// Set a resolver with `Tts::set_template_resolver()`, then speak with `Tts::speak_template()`. Closures taking the
+/// key and arguments implement this trait.
+pub trait TemplateResolver: Send + Sync {
+    /// Returns the text for `key`, with `args` interpolated, or `None` if there's no such template.
+    fn resolve(&self, key: &str, args: &[(&str, &str)]) -> Option<Localized>;
+}
+
+impl<F> TemplateResolver for F
+where
+    F: Fn(&str, &[(&str, &str)]) -> Option<Localized> + Send + Sync,
+{
+    fn resolve(&self, key: &str, args: &[(&str, &str)]) -> Option<Localized> {
+        self(key, args)
+    }
+}
+
+/// A minimal resolver for apps without a translation system, mapping keys to templates in a single language.
+///
+/// Templates name their arguments in braces, as in `"{count} new messages"`. Unknown arguments are left as written.
+#[derive(Clone, Debug, Default)]
+pub struct Templates {
+    language: Option<LanguageTag<String>>,
+    templates: HashMap<String, String>,
+}
+
+impl Templates {
+    /// Creates an empty set of templates in `language`, if known.
+    pub fn new(language: Option<LanguageTag<String>>) -> Self {
+        Self {
+            language,
+            templates: HashMap::new(),
+        }
+    }
+
+    /// Adds `template` under `key`, replacing any already there.
+    pub fn insert<K: Into<String>, T: Into<String>>(&mut self, key: K, template: T) -> &mut Self {
+        self.templates.insert(key.into(), template.into());
+        self
+    }
+}
+
+impl TemplateResolver for Templates {
+    fn resolve(&self, key: &str, args: &[(&str, &str)]) -> Option<Localized> {
+        let template = self.templates.get(key)?;
+        let mut text = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let value = rest.find('}').and_then(|end| {
+                let name = &rest[1..end];
+                let (_, value) = args.iter().find(|(arg, _)| *arg == name)?;
+                Some((end, value))
+            });
+            match value {
+                Some((end, value)) => {
+                    text.push_str(value);
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    text.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text.push_str(rest);
+        Some(Localized {
+            text,
+            language: self.language.clone(),
+        })
+    }
+}
+
+static RESOLVERS: LazyLock<Mutex<HashMap<BackendId, Arc<dyn TemplateResolver>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set(backend: BackendId, resolver: Option<Box<dyn TemplateResolver>>) {
+    let mut resolvers = RESOLVERS.lock();
+    match resolver {
+        Some(resolver) => resolvers.insert(backend, resolver.into()),
+        None => resolvers.remove(&backend),
+    };
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    RESOLVERS.lock().remove(&backend);
+}
+
+/// Returns `backend`'s resolver, to be called without the lock held.
+pub(crate) fn resolver(backend: BackendId) -> Option<Arc<dyn TemplateResolver>> {
+    RESOLVERS.lock().get(&backend).cloned()
+}
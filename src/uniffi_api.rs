@@ -0,0 +1,240 @@
+//! Bindings for Swift and Kotlin, generated with [UniFFI](https://mozilla.github.io/uniffi-rs/).
+//!
+//! Enabled with the `uniffi` feature. Build the library, then run `uniffi-bindgen generate --library` against it to
+//! produce the foreign bindings. Utterance IDs are exposed as numbers, and word boundaries as UTF-16 offsets into the
+//! spoken text, matching Swift's `NSString` and Kotlin's `String` indexing.
+
+use std::{collections::HashMap, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::{Error, Features};
+
+/// An error returned by the bindings.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum TtsError {
+    #[error("Unsupported feature")]
+    UnsupportedFeature,
+    #[error("Out of range")]
+    OutOfRange,
+    #[error("{0}")]
+    Backend(String),
+}
+
+impl From<Error> for TtsError {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::UnsupportedFeature => TtsError::UnsupportedFeature,
+            Error::OutOfRange => TtsError::OutOfRange,
+            e => TtsError::Backend(e.to_string()),
+        }
+    }
+}
+
+/// A voice, as returned by `Tts.voices()`.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+    pub requires_network: bool,
+}
+
+impl From<crate::Voice> for Voice {
+    fn from(v: crate::Voice) -> Self {
+        Voice {
+            id: v.id(),
+            name: v.name(),
+            language: v.language().to_string(),
+            requires_network: v.requires_network(),
+        }
+    }
+}
+
+/// Receives utterance events. Called on a backend thread.
+#[uniffi::export(callback_interface)]
+pub trait UtteranceListener: Send + Sync {
+    fn on_utterance_begin(&self, utterance_id: u64);
+    fn on_utterance_end(&self, utterance_id: u64);
+    fn on_utterance_stop(&self, utterance_id: u64);
+    /// Called when a word is about to be spoken, with UTF-16 offsets into the text passed to `speak()`.
+    fn on_word_boundary(&self, utterance_id: u64, start: u32, end: u32);
+}
+
+#[derive(Default)]
+struct Listener {
+    listener: Option<Arc<dyn UtteranceListener>>,
+    /// The text of each pending utterance, for converting word boundaries to UTF-16 offsets.
+    texts: HashMap<u64, String>,
+}
+
+impl Listener {
+    /// Clones the listener so it isn't called with the lock held.
+    fn get(listener: &Mutex<Listener>) -> Option<Arc<dyn UtteranceListener>> {
+        listener.lock().listener.clone()
+    }
+}
+
+/// The crate's `Tts` API, exported as `Tts`.
+#[derive(uniffi::Object)]
+pub struct Tts {
+    tts: Mutex<crate::Tts>,
+    listener: Arc<Mutex<Listener>>,
+}
+
+// The foreign side may call into this object from any thread. All access to the inner `Tts` goes through the mutex,
+// and its `Rc` is never cloned, so this is sound.
+unsafe impl Send for Tts {}
+
+unsafe impl Sync for Tts {}
+
+impl Tts {
+    fn with<T, F>(&self, f: F) -> Result<T, TtsError>
+    where
+        F: FnOnce(&mut crate::Tts) -> Result<T, Error>,
+    {
+        let mut tts = self.tts.lock();
+        Ok(f(&mut tts)?)
+    }
+}
+
+#[uniffi::export]
+impl Tts {
+    /// Creates a speech synthesizer with the default backend for this platform.
+    #[uniffi::constructor]
+    pub fn new() -> Result<Arc<Self>, TtsError> {
+        let tts = crate::Tts::default()?;
+        let listener: Arc<Mutex<Listener>> = Default::default();
+        let Features {
+            utterance_callbacks,
+            word_callbacks,
+            ..
+        } = tts.supported_features();
+        if utterance_callbacks {
+            let l = listener.clone();
+            tts.on_utterance_begin(Some(Box::new(move |id| {
+                if let Some(listener) = Listener::get(&l) {
+                    listener.on_utterance_begin(id.as_u64());
+                }
+            })))?;
+            let l = listener.clone();
+            tts.on_utterance_end(Some(Box::new(move |id| {
+                let id = id.as_u64();
+                l.lock().texts.remove(&id);
+                if let Some(listener) = Listener::get(&l) {
+                    listener.on_utterance_end(id);
+                }
+            })))?;
+            let l = listener.clone();
+            tts.on_utterance_stop(Some(Box::new(move |id| {
+                let id = id.as_u64();
+                l.lock().texts.remove(&id);
+                if let Some(listener) = Listener::get(&l) {
+                    listener.on_utterance_stop(id);
+                }
+            })))?;
+        }
+        if word_callbacks {
+            let l = listener.clone();
+            tts.on_word_boundary(Some(Box::new(move |id, range| {
+                let id = id.as_u64();
+                let offsets = l.lock().texts.get(&id).map(|text| {
+                    let start = text[..range.start].encode_utf16().count() as u32;
+                    (start, start + text[range].encode_utf16().count() as u32)
+                });
+                if let (Some((start, end)), Some(listener)) = (offsets, Listener::get(&l)) {
+                    listener.on_word_boundary(id, start, end);
+                }
+            })))?;
+        }
+        Ok(Arc::new(Tts {
+            tts: Mutex::new(tts),
+            listener,
+        }))
+    }
+
+    pub fn supported_features(&self) -> Features {
+        self.tts.lock().supported_features()
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, and returns the utterance ID if the backend reports one.
+    pub fn speak(&self, text: String, interrupt: bool) -> Result<Option<u64>, TtsError> {
+        let (id, word_callbacks) = self.with(|tts| {
+            let id = tts.speak(text.as_str(), interrupt)?;
+            Ok((id, tts.supported_features().word_callbacks))
+        })?;
+        let id = id.map(|id| id.as_u64());
+        if let (Some(id), true) = (id, word_callbacks) {
+            self.listener.lock().texts.insert(id, text);
+        }
+        Ok(id)
+    }
+
+    pub fn stop(&self) -> Result<(), TtsError> {
+        self.with(|tts| tts.stop().map(|_| ()))
+    }
+
+    pub fn pause(&self) -> Result<(), TtsError> {
+        self.with(|tts| tts.pause().map(|_| ()))
+    }
+
+    pub fn resume(&self) -> Result<(), TtsError> {
+        self.with(|tts| tts.resume().map(|_| ()))
+    }
+
+    pub fn is_speaking(&self) -> Result<bool, TtsError> {
+        self.with(|tts| tts.is_speaking())
+    }
+
+    pub fn rate(&self) -> Result<f32, TtsError> {
+        self.with(|tts| tts.get_rate())
+    }
+
+    pub fn set_rate(&self, rate: f32) -> Result<(), TtsError> {
+        self.with(|tts| tts.set_rate(rate).map(|_| ()))
+    }
+
+    pub fn pitch(&self) -> Result<f32, TtsError> {
+        self.with(|tts| tts.get_pitch())
+    }
+
+    pub fn set_pitch(&self, pitch: f32) -> Result<(), TtsError> {
+        self.with(|tts| tts.set_pitch(pitch).map(|_| ()))
+    }
+
+    pub fn volume(&self) -> Result<f32, TtsError> {
+        self.with(|tts| tts.get_volume())
+    }
+
+    pub fn set_volume(&self, volume: f32) -> Result<(), TtsError> {
+        self.with(|tts| tts.set_volume(volume).map(|_| ()))
+    }
+
+    pub fn voices(&self) -> Result<Vec<Voice>, TtsError> {
+        self.with(|tts| tts.voices())
+            .map(|voices| voices.into_iter().map(Voice::from).collect())
+    }
+
+    pub fn voice(&self) -> Result<Option<Voice>, TtsError> {
+        self.with(|tts| tts.voice())
+            .map(|voice| voice.map(Voice::from))
+    }
+
+    /// Selects the voice with the given ID, as returned by `Voice.id`.
+    pub fn set_voice(&self, id: String) -> Result<(), TtsError> {
+        self.with(|tts| {
+            let voice = tts
+                .voices()?
+                .into_iter()
+                .find(|v| v.id() == id)
+                .ok_or(Error::OperationFailed)?;
+            tts.set_voice(&voice)
+        })
+    }
+
+    /// Sets the listener for utterance events, replacing any previous one, or removes it if `None`.
+    pub fn set_listener(&self, listener: Option<Box<dyn UtteranceListener>>) {
+        self.listener.lock().listener = listener.map(Arc::from);
+    }
+}
@@ -0,0 +1,195 @@
+//! Estimates visemes, the mouth shapes of speech, from the text being spoken, for animating talking characters.
+//!
+//! No backend reports visemes, so each word's letters are mapped to them as its boundary is reported, or the whole
+//! utterance's as it begins on backends without word boundaries. They're spread over the time speech of that length
+//! usually takes, and each utterance closes with `Viseme::Silence`.
+
+use std::{collections::HashMap, ops::Range, sync::LazyLock, time::Duration};
+
+use parking_lot::Mutex;
+
+use crate::{callbacks, speaking, watchdog, BackendId, UtteranceEvent, UtteranceId};
+
+/// A mouth shape, from the set of 15 that lip sync tools for games commonly animate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Viseme {
+    /// The mouth at rest, between words and after speech.
+    Silence,
+    /// Closed lips, as in `p`, `b` and `m`.
+    Pp,
+    /// Lower lip to upper teeth, as in `f` and `v`.
+    Ff,
+    /// Tongue between the teeth, as in `th`.
+    Th,
+    /// Tongue behind the teeth, as in `t` and `d`.
+    Dd,
+    /// The back of the tongue raised, as in `k` and `g`.
+    Kk,
+    /// Rounded, protruding lips, as in `ch`, `sh` and `j`.
+    Ch,
+    /// Teeth together, as in `s` and `z`.
+    Ss,
+    /// As in `n` and `l`.
+    Nn,
+    /// As in `r`.
+    Rr,
+    /// Open, as in "car".
+    Aa,
+    /// As in "bed".
+    E,
+    /// As in "sit".
+    Ih,
+    /// As in "tone".
+    Oh,
+    /// Rounded, as in "you" and "wet".
+    Ou,
+}
+
+struct Utterance {
+    text: String,
+    /// Whether the backend reports words, whose visemes are estimated as each begins.
+    words: bool,
+    /// When the last viseme estimated so far ends.
+    end: Duration,
+}
+
+static UTTERANCES: LazyLock<Mutex<HashMap<BackendId, HashMap<u64, Utterance>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the visemes of `text`, as its letters suggest, merging repeats.
+fn visemes(text: &str) -> Vec<Viseme> {
+    let text = text.to_lowercase();
+    let mut chars = text.chars().peekable();
+    let mut visemes = vec![];
+    while let Some(c) = chars.next() {
+        let next = chars.peek().copied();
+        let (viseme, digraph) = match (c, next) {
+            ('t', Some('h')) => (Some(Viseme::Th), true),
+            ('c' | 's', Some('h')) => (Some(Viseme::Ch), true),
+            ('p', Some('h')) => (Some(Viseme::Ff), true),
+            ('n', Some('g')) => (Some(Viseme::Nn), true),
+            ('p' | 'b' | 'm', _) => (Some(Viseme::Pp), false),
+            ('f' | 'v', _) => (Some(Viseme::Ff), false),
+            ('t' | 'd', _) => (Some(Viseme::Dd), false),
+            ('k' | 'g' | 'c' | 'q' | 'x', _) => (Some(Viseme::Kk), false),
+            ('j', _) => (Some(Viseme::Ch), false),
+            ('s' | 'z', _) => (Some(Viseme::Ss), false),
+            ('n' | 'l', _) => (Some(Viseme::Nn), false),
+            ('r', _) => (Some(Viseme::Rr), false),
+            ('a', _) => (Some(Viseme::Aa), false),
+            ('e', _) => (Some(Viseme::E), false),
+            ('i' | 'y', _) => (Some(Viseme::Ih), false),
+            ('o', _) => (Some(Viseme::Oh), false),
+            ('u' | 'w', _) => (Some(Viseme::Ou), false),
+            (c, _) if c.is_whitespace() || c.is_ascii_punctuation() => {
+                (Some(Viseme::Silence), false)
+            }
+            // `h` and letters outside English keep the mouth as it was.
+            _ => (None, false),
+        };
+        if digraph {
+            chars.next();
+        }
+        if let Some(viseme) = viseme {
+            if visemes.last() != Some(&viseme) {
+                visemes.push(viseme);
+            }
+        }
+    }
+    visemes
+}
+
+/// Spreads the visemes of `text` over how long it usually takes to speak from `at`, returning them with when they
+/// end.
+fn estimate(text: &str, at: Duration) -> (Vec<(Viseme, Duration)>, Duration) {
+    let visemes = visemes(text);
+    let len = text.chars().filter(|c| !c.is_whitespace()).count();
+    let duration = Duration::from_secs_f32(len as f32 / watchdog::BYTES_PER_SECOND);
+    let step = duration
+        .checked_div(visemes.len() as u32)
+        .unwrap_or_default();
+    let visemes = visemes
+        .into_iter()
+        .enumerate()
+        .map(|(i, viseme)| (viseme, at + step * i as u32))
+        .collect();
+    (visemes, at + duration)
+}
+
+/// Fires `visemes`, once the lock is released, so callbacks can speak.
+fn fire(backend: BackendId, utterance: &UtteranceId, visemes: Vec<(Viseme, Duration)>) {
+    for (viseme, at) in visemes {
+        callbacks::fire_viseme(backend, *utterance, viseme, at);
+    }
+}
+
+/// Records the text of `utterance`, starting at byte `start` of the text its word boundaries index into.
+pub(crate) fn spoken(
+    backend: BackendId,
+    utterance: &UtteranceId,
+    text: &str,
+    start: usize,
+    words: bool,
+) {
+    let mut utterances = UTTERANCES.lock();
+    utterances.entry(backend).or_default().insert(
+        utterance.as_u64(),
+        Utterance {
+            text: if words { text } else { &text[start..] }.into(),
+            words,
+            end: Duration::ZERO,
+        },
+    );
+}
+
+pub(crate) fn utterance(backend: BackendId, event: UtteranceEvent, utterance: &UtteranceId) {
+    let visemes = {
+        let mut utterances = UTTERANCES.lock();
+        let Some(utterances) = utterances.get_mut(&backend) else {
+            return;
+        };
+        match event {
+            UtteranceEvent::Begin => {
+                let Some(u) = utterances.get_mut(&utterance.as_u64()).filter(|u| !u.words) else {
+                    return;
+                };
+                let (visemes, end) = estimate(&u.text, Duration::ZERO);
+                u.end = end;
+                visemes
+            }
+            UtteranceEvent::End | UtteranceEvent::Stop => {
+                let Some(u) = utterances.remove(&utterance.as_u64()) else {
+                    return;
+                };
+                vec![(Viseme::Silence, u.end)]
+            }
+        }
+    };
+    fire(backend, utterance, visemes);
+}
+
+/// Fires the visemes of the word at `range`, which is just beginning.
+pub(crate) fn word(backend: BackendId, utterance: &UtteranceId, range: Range<usize>) {
+    let at = speaking::progress(backend, utterance).map(|progress| progress.elapsed);
+    let visemes = {
+        let mut utterances = UTTERANCES.lock();
+        let Some(u) = utterances
+            .get_mut(&backend)
+            .and_then(|utterances| utterances.get_mut(&utterance.as_u64()))
+        else {
+            return;
+        };
+        let Some(word) = u.text.get(range) else {
+            return;
+        };
+        let (visemes, end) = estimate(word, at.unwrap_or(u.end));
+        u.end = end;
+        visemes
+    };
+    fire(backend, utterance, visemes);
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    UTTERANCES.lock().remove(&backend);
+}
@@ -0,0 +1,114 @@
+//! Encodes voices as tokens that can be saved and resolved again in a later session, even if the engine has since
+//! renamed or re-identified them.
+//!
+//! A token records a voice's ID, name, language and gender. Resolving prefers the same ID, then the same name, then
+//! a voice whose name or ID differs only in decoration, such as Apple's quality tiers in `Samantha (Enhanced)` or
+//! `com.apple.voice.premium.en-US.Samantha`, and last of all a voice for the same language.
+
+use oxilangtag::LanguageTag;
+
+use crate::{Gender, MatchQuality, Voice};
+
+const SEPARATOR: &str = "|";
+
+fn escape(field: &str) -> String {
+    field.replace('%', "%25").replace(SEPARATOR, "%7C")
+}
+
+fn unescape(field: &str) -> String {
+    field.replace("%7C", SEPARATOR).replace("%25", "%")
+}
+
+/// What a token says about the voice it was made from.
+struct Saved {
+    id: String,
+    name: String,
+    language: Option<LanguageTag<String>>,
+    gender: Gender,
+}
+
+impl Saved {
+    /// Parses `token`, or treats it as a bare voice ID or name if it isn't one.
+    fn parse(token: &str) -> Self {
+        let fields: Vec<&str> = token.split(SEPARATOR).collect();
+        let [id, name, language, gender] = fields[..] else {
+            return Self {
+                id: token.into(),
+                name: token.into(),
+                language: None,
+                gender: Gender::Unknown,
+            };
+        };
+        Self {
+            id: unescape(id),
+            name: unescape(name),
+            language: LanguageTag::parse(unescape(language)).ok(),
+            gender: match gender {
+                "male" => Gender::Male,
+                "female" => Gender::Female,
+                "neutral" => Gender::Neutral,
+                _ => Gender::Unknown,
+            },
+        }
+    }
+}
+
+pub(crate) fn encode(voice: &Voice) -> String {
+    // Unknown is left empty, as it was before voices could be neutral.
+    let gender = match voice.gender {
+        Gender::Male => "male",
+        Gender::Female => "female",
+        Gender::Neutral => "neutral",
+        Gender::Unknown => "",
+    };
+    [
+        escape(&voice.id),
+        escape(&voice.name),
+        escape(voice.language.as_str()),
+        gender.into(),
+    ]
+    .join(SEPARATOR)
+}
+
+/// The parts of a voice's ID and name that stay the same across quality tiers and naming schemes, lowercased.
+fn stems(id: &str, name: &str) -> [String; 2] {
+    let id = id.rsplit('.').next().unwrap_or(id);
+    let name = match name.find(" (") {
+        Some(i) if name.ends_with(')') => &name[..i],
+        _ => name,
+    };
+    [id.trim().to_lowercase(), name.trim().to_lowercase()]
+}
+
+/// Returns the voice in `voices` that best matches `token`, if any does.
+pub(crate) fn resolve<'a>(voices: &'a [Voice], token: &str) -> Option<&'a Voice> {
+    let saved = Saved::parse(token);
+    let saved_stems = stems(&saved.id, &saved.name);
+    voices
+        .iter()
+        .filter_map(|voice| {
+            let identity = if voice.id == saved.id {
+                3
+            } else if voice.name.eq_ignore_ascii_case(&saved.name) {
+                2
+            } else if stems(&voice.id, &voice.name)
+                .iter()
+                .any(|stem| !stem.is_empty() && saved_stems.contains(stem))
+            {
+                1
+            } else {
+                0
+            };
+            let language = saved
+                .language
+                .as_ref()
+                .map_or(MatchQuality::None, |language| voice.matches(language));
+            if identity == 0 && language < MatchQuality::Script {
+                return None;
+            }
+            let gender = saved.gender != Gender::Unknown && voice.gender == saved.gender;
+            Some(((identity, language, gender, voice.quality), voice))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, voice)| voice)
+}
@@ -0,0 +1,34 @@
+//! Caches each backend's voices, which some platforms are slow to enumerate, until they change or are refreshed.
+
+use std::{collections::HashMap, sync::LazyLock};
+
+use parking_lot::Mutex;
+
+use crate::{BackendId, Voice};
+
+static VOICES: LazyLock<Mutex<HashMap<BackendId, Vec<Voice>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn get(backend: BackendId) -> Option<Vec<Voice>> {
+    VOICES.lock().get(&backend).cloned()
+}
+
+/// Caches `voices` for `backend`, unless there are none, as browsers report until they've loaded theirs.
+pub(crate) fn set(backend: BackendId, voices: &[Voice]) {
+    if !voices.is_empty() {
+        VOICES.lock().insert(backend, voices.to_vec());
+    }
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    VOICES.lock().remove(&backend);
+}
+
+/// Forgets every backend's voices, when the platform says voices were installed or removed.
+#[cfg(any(
+    all(target_arch = "wasm32", feature = "web"),
+    all(any(target_os = "macos", target_os = "ios"), feature = "av_foundation")
+))]
+pub(crate) fn invalidate() {
+    VOICES.lock().clear();
+}
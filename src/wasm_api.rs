@@ -0,0 +1,248 @@
+//! A JavaScript-facing API, for consuming this crate directly from a compiled wasm module.
+//!
+//! Enabled with the `wasm-api` feature. Utterance IDs are exposed as numbers, and word boundaries as UTF-16 offsets
+//! into the spoken text, so they can be used directly with JavaScript strings.
+
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use js_sys::Function;
+use wasm_bindgen::prelude::*;
+
+use crate::{Error, Features, Tts, UtteranceId, Voice};
+
+fn to_js(e: Error) -> JsValue {
+    match e {
+        Error::JavaScriptError(e) => e,
+        e => JsError::new(&e.to_string()).into(),
+    }
+}
+
+fn web_utterance_id(id: UtteranceId) -> u64 {
+    let UtteranceId::Web(id) = id;
+    id
+}
+
+#[derive(Default)]
+struct Handlers {
+    begin: Option<Function>,
+    end: Option<Function>,
+    stop: Option<Function>,
+    word_boundary: Option<Function>,
+    /// The text of each pending utterance, for converting word boundaries to UTF-16 offsets.
+    texts: HashMap<u64, String>,
+}
+
+fn call(f: Option<Function>, args: &[JsValue]) {
+    if let Some(f) = f {
+        let args: js_sys::Array = args.iter().collect();
+        if let Err(e) = f.apply(&JsValue::NULL, &args) {
+            log::error!("Callback threw: {:?}", e);
+        }
+    }
+}
+
+/// A voice, as returned by `Tts.voices()`.
+#[wasm_bindgen(js_name = Voice)]
+pub struct JsVoice(Voice);
+
+#[wasm_bindgen(js_class = Voice)]
+impl JsVoice {
+    #[wasm_bindgen(getter)]
+    pub fn id(&self) -> String {
+        self.0.id()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.0.name()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> String {
+        self.0.language().to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = requiresNetwork)]
+    pub fn requires_network(&self) -> bool {
+        self.0.requires_network()
+    }
+}
+
+/// The crate's `Tts` API, exported to JavaScript as `Tts`.
+#[wasm_bindgen(js_name = Tts)]
+pub struct JsTts {
+    tts: Tts,
+    handlers: Rc<RefCell<Handlers>>,
+}
+
+#[wasm_bindgen(js_class = Tts)]
+impl JsTts {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<JsTts, JsValue> {
+        let tts = Tts::default().map_err(to_js)?;
+        let handlers: Rc<RefCell<Handlers>> = Default::default();
+        let Features {
+            utterance_callbacks,
+            word_callbacks,
+            ..
+        } = tts.supported_features();
+        // The Noop fallback doesn't support callbacks.
+        if utterance_callbacks {
+            let h = handlers.clone();
+            tts.on_utterance_begin(Some(Box::new(move |id| {
+                let id = web_utterance_id(id);
+                let f = h.borrow().begin.clone();
+                call(f, &[(id as f64).into()]);
+            })))
+            .map_err(to_js)?;
+            let h = handlers.clone();
+            tts.on_utterance_end(Some(Box::new(move |id| {
+                let id = web_utterance_id(id);
+                h.borrow_mut().texts.remove(&id);
+                let f = h.borrow().end.clone();
+                call(f, &[(id as f64).into()]);
+            })))
+            .map_err(to_js)?;
+            let h = handlers.clone();
+            tts.on_utterance_stop(Some(Box::new(move |id| {
+                let id = web_utterance_id(id);
+                h.borrow_mut().texts.remove(&id);
+                let f = h.borrow().stop.clone();
+                call(f, &[(id as f64).into()]);
+            })))
+            .map_err(to_js)?;
+        }
+        if word_callbacks {
+            let h = handlers.clone();
+            tts.on_word_boundary(Some(Box::new(move |id, range| {
+                let id = web_utterance_id(id);
+                let (f, offsets) = {
+                    let h = h.borrow();
+                    let offsets = h.texts.get(&id).map(|text| {
+                        let start = text[..range.start].encode_utf16().count() as u32;
+                        let end = start + text[range].encode_utf16().count() as u32;
+                        (start, end)
+                    });
+                    (h.word_boundary.clone(), offsets)
+                };
+                if let Some((start, end)) = offsets {
+                    call(f, &[(id as f64).into(), start.into(), end.into()]);
+                }
+            })))
+            .map_err(to_js)?;
+        }
+        Ok(JsTts { tts, handlers })
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, and returns the utterance ID.
+    pub fn speak(&mut self, text: String, interrupt: bool) -> Result<Option<f64>, JsValue> {
+        let id = self
+            .tts
+            .speak(text.as_str(), interrupt)
+            .map_err(to_js)?
+            .map(web_utterance_id);
+        if let Some(id) = id {
+            self.handlers.borrow_mut().texts.insert(id, text);
+        }
+        Ok(id.map(|id| id as f64))
+    }
+
+    pub fn stop(&mut self) -> Result<(), JsValue> {
+        self.tts.stop().map_err(to_js)?;
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<(), JsValue> {
+        self.tts.pause().map_err(to_js)?;
+        Ok(())
+    }
+
+    pub fn resume(&mut self) -> Result<(), JsValue> {
+        self.tts.resume().map_err(to_js)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = isSpeaking)]
+    pub fn is_speaking(&self) -> Result<bool, JsValue> {
+        self.tts.is_speaking().map_err(to_js)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rate(&self) -> Result<f32, JsValue> {
+        self.tts.get_rate().map_err(to_js)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_rate(&mut self, rate: f32) -> Result<(), JsValue> {
+        self.tts.set_rate(rate).map_err(to_js)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pitch(&self) -> Result<f32, JsValue> {
+        self.tts.get_pitch().map_err(to_js)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_pitch(&mut self, pitch: f32) -> Result<(), JsValue> {
+        self.tts.set_pitch(pitch).map_err(to_js)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn volume(&self) -> Result<f32, JsValue> {
+        self.tts.get_volume().map_err(to_js)
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_volume(&mut self, volume: f32) -> Result<(), JsValue> {
+        self.tts.set_volume(volume).map_err(to_js)?;
+        Ok(())
+    }
+
+    pub fn voices(&self) -> Result<Vec<JsVoice>, JsValue> {
+        let voices = self.tts.voices().map_err(to_js)?;
+        Ok(voices.into_iter().map(JsVoice).collect())
+    }
+
+    pub fn voice(&self) -> Result<Option<JsVoice>, JsValue> {
+        let voice = self.tts.voice().map_err(to_js)?;
+        Ok(voice.map(JsVoice))
+    }
+
+    /// Selects the voice with the given ID, as returned by `Voice.id`.
+    #[wasm_bindgen(js_name = setVoice)]
+    pub fn set_voice(&mut self, id: &str) -> Result<(), JsValue> {
+        let voices = self.tts.voices().map_err(to_js)?;
+        let voice = voices
+            .iter()
+            .find(|v| v.id() == id)
+            .ok_or_else(|| to_js(Error::OperationFailed))?;
+        self.tts.set_voice(voice).map_err(to_js)?;
+        Ok(())
+    }
+
+    /// Calls `callback(utteranceId)` when an utterance begins.
+    #[wasm_bindgen(js_name = onUtteranceBegin)]
+    pub fn on_utterance_begin(&self, callback: Option<Function>) {
+        self.handlers.borrow_mut().begin = callback;
+    }
+
+    /// Calls `callback(utteranceId)` when an utterance finishes.
+    #[wasm_bindgen(js_name = onUtteranceEnd)]
+    pub fn on_utterance_end(&self, callback: Option<Function>) {
+        self.handlers.borrow_mut().end = callback;
+    }
+
+    /// Calls `callback(utteranceId)` when an utterance is stopped.
+    #[wasm_bindgen(js_name = onUtteranceStop)]
+    pub fn on_utterance_stop(&self, callback: Option<Function>) {
+        self.handlers.borrow_mut().stop = callback;
+    }
+
+    /// Calls `callback(utteranceId, start, end)` when a word is about to be spoken, with UTF-16 offsets into the text.
+    #[wasm_bindgen(js_name = onWordBoundary)]
+    pub fn on_word_boundary(&self, callback: Option<Function>) {
+        self.handlers.borrow_mut().word_boundary = callback;
+    }
+}
@@ -0,0 +1,185 @@
+//! Notices backends that have stopped speaking what they're given, as when an engine hangs or its service dies, so
+//! speech can recover without restarting the app.
+
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::{BackendId, UtteranceEvent, UtteranceId};
+
+/// Roughly how much text speech covers a second at a typical rate, for estimating how long utterances take.
+pub(crate) const BYTES_PER_SECOND: f32 = 15.;
+
+/// How long utterances may take before their backend is considered stuck. Set one with `Tts::set_watchdog()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Watchdog {
+    /// How long the next utterance may take to begin, once it's spoken or the one before it ends.
+    pub begin_timeout: Duration,
+    /// How many times as long as expected an utterance may take to end once it begins. How long it's expected to
+    /// take is estimated from the length of its text at a typical rate, and it's always allowed `begin_timeout`.
+    pub duration_factor: f32,
+    /// Whether to reinitialize the backend when it's stuck, if it can be.
+    pub reinitialize: bool,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self {
+            begin_timeout: Duration::from_secs(5),
+            duration_factor: 3.,
+            reinitialize: false,
+        }
+    }
+}
+
+struct Tracked {
+    id: UtteranceId,
+    expected: Duration,
+    began: Option<Instant>,
+}
+
+struct Watch {
+    watchdog: Watchdog,
+    /// Utterances queued but not yet finished or stopped.
+    utterances: HashMap<u64, Tracked>,
+    /// When the backend was last given something to speak while idle, or began or finished an utterance.
+    progress: Instant,
+    paused_at: Option<Instant>,
+}
+
+impl Watch {
+    fn is_stuck(&self, now: Instant) -> bool {
+        let Watchdog {
+            begin_timeout,
+            duration_factor,
+            ..
+        } = self.watchdog;
+        let mut begun = false;
+        for tracked in self.utterances.values() {
+            if let Some(began) = tracked.began {
+                begun = true;
+                let allowed = tracked.expected.mul_f32(duration_factor).max(begin_timeout);
+                if now.saturating_duration_since(began) > allowed {
+                    return true;
+                }
+            }
+        }
+        !begun
+            && !self.utterances.is_empty()
+            && now.saturating_duration_since(self.progress) > begin_timeout
+    }
+}
+
+static WATCHES: LazyLock<Mutex<HashMap<BackendId, Watch>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub(crate) fn set(backend: BackendId, watchdog: Option<Watchdog>) {
+    let mut watches = WATCHES.lock();
+    match watchdog {
+        Some(watchdog) => {
+            watches
+                .entry(backend)
+                .or_insert_with(|| Watch {
+                    watchdog,
+                    utterances: HashMap::new(),
+                    progress: Instant::now(),
+                    paused_at: None,
+                })
+                .watchdog = watchdog;
+        }
+        None => {
+            watches.remove(&backend);
+        }
+    }
+}
+
+pub(crate) fn remove(backend: BackendId) {
+    WATCHES.lock().remove(&backend);
+}
+
+/// Starts watching `utterance`, queued with `len` bytes of text.
+pub(crate) fn spoken(backend: BackendId, utterance: &UtteranceId, len: usize) {
+    let mut watches = WATCHES.lock();
+    let Some(watch) = watches.get_mut(&backend) else {
+        return;
+    };
+    if watch.utterances.is_empty() {
+        watch.progress = Instant::now();
+    }
+    // It may have begun before `speak()` returned its ID.
+    watch
+        .utterances
+        .entry(utterance.as_u64())
+        .or_insert_with(|| Tracked {
+            id: *utterance,
+            expected: Duration::ZERO,
+            began: None,
+        })
+        .expected = Duration::from_secs_f32(len as f32 / BYTES_PER_SECOND);
+}
+
+pub(crate) fn utterance(backend: BackendId, event: UtteranceEvent, utterance: &UtteranceId) {
+    let mut watches = WATCHES.lock();
+    let Some(watch) = watches.get_mut(&backend) else {
+        return;
+    };
+    let now = Instant::now();
+    watch.progress = now;
+    match event {
+        UtteranceEvent::Begin => {
+            watch
+                .utterances
+                .entry(utterance.as_u64())
+                .or_insert_with(|| Tracked {
+                    id: *utterance,
+                    expected: Duration::ZERO,
+                    began: None,
+                })
+                .began = Some(now);
+        }
+        UtteranceEvent::End | UtteranceEvent::Stop => {
+            watch.utterances.remove(&utterance.as_u64());
+        }
+    }
+}
+
+/// Records that speech was paused or resumed. Time spent paused doesn't count toward any limit.
+pub(crate) fn set_paused(backend: BackendId, paused: bool) {
+    let mut watches = WATCHES.lock();
+    let Some(watch) = watches.get_mut(&backend) else {
+        return;
+    };
+    if paused {
+        watch.paused_at.get_or_insert_with(Instant::now);
+    } else if let Some(paused_at) = watch.paused_at.take() {
+        let paused = paused_at.elapsed();
+        watch.progress += paused;
+        for tracked in watch.utterances.values_mut() {
+            if let Some(began) = &mut tracked.began {
+                *began += paused;
+            }
+        }
+    }
+}
+
+/// If `backend` is stuck, stops watching the utterances it's stuck on and returns them, with its watchdog.
+pub(crate) fn check(backend: BackendId) -> Option<(Watchdog, Vec<UtteranceId>)> {
+    let mut watches = WATCHES.lock();
+    let watch = watches.get_mut(&backend)?;
+    let now = Instant::now();
+    if watch.paused_at.is_some() || !watch.is_stuck(now) {
+        return None;
+    }
+    watch.progress = now;
+    let stuck = watch
+        .utterances
+        .drain()
+        .map(|(_, tracked)| tracked.id)
+        .collect();
+    Some((watch.watchdog, stuck))
+}
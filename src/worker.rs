@@ -0,0 +1,143 @@
+//! Runs a `Tts` on a thread of its own, which every command is sent to, so it can be driven from any thread and
+//! nothing the backend does holds up the caller.
+//!
+//! This is opt-in. `Tts` itself still calls the backend on the caller's thread, and is still marked `Send` and
+//! `Sync` regardless of its backend, so code that shares it across threads should move to a `Worker` instead.
+
+use std::{
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::{panics, Backends, Error, Tts};
+
+type Command = Box<dyn FnOnce(&mut Tts) + Send>;
+
+/// How often the thread checks whether the backend is stuck, if a watchdog is set.
+const WATCHDOG_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A `Tts` owned by a dedicated thread, which runs commands sent to it one at a time, in order.
+///
+/// Methods only send commands, so they return without waiting on the backend, unless they return a value from it.
+/// Callbacks are set with `run()`, and fire as they would on the `Tts`. On macOS, the thread pumps its run loop
+/// between commands, so callbacks arrive without the app doing so. The watchdog, if set with `Tts::set_watchdog()`,
+/// is checked regularly, whether or not commands arrive. Backends that must be created on the main thread, such as
+/// AppKit, can't run on a worker.
+///
+/// The thread exits, dropping the `Tts`, when the last clone of the `Worker` is dropped.
+#[derive(Clone)]
+pub struct Worker {
+    commands: Sender<Command>,
+}
+
+impl Worker {
+    /// Starts a worker running a `Tts` with the specified backend.
+    pub fn new(backend: Backends) -> Result<Self, Error> {
+        Self::spawn(move || Tts::new(backend))
+    }
+
+    /// Starts a worker running a `Tts` with the usual backend for this platform, as `Tts::default()` does.
+    #[allow(clippy::should_implement_trait)]
+    pub fn default() -> Result<Self, Error> {
+        Self::spawn(Tts::default)
+    }
+
+    /// Starts a thread that creates a `Tts` with `create`, then runs commands until every handle is dropped.
+    fn spawn<F>(create: F) -> Result<Self, Error>
+    where
+        F: FnOnce() -> Result<Tts, Error> + Send + 'static,
+    {
+        let (commands, received) = mpsc::channel::<Command>();
+        let (created, creation) = mpsc::sync_channel(1);
+        thread::Builder::new()
+            .name("tts-worker".into())
+            .spawn(move || {
+                let mut tts = match create() {
+                    Ok(tts) => {
+                        let _ = created.send(Ok(()));
+                        tts
+                    }
+                    Err(e) => {
+                        let _ = created.send(Err(e));
+                        return;
+                    }
+                };
+                let mut checked = Instant::now();
+                loop {
+                    #[cfg(not(target_os = "macos"))]
+                    let received = received.recv_timeout(WATCHDOG_INTERVAL);
+                    #[cfg(target_os = "macos")]
+                    let received = received.recv_timeout(Duration::ZERO);
+                    match received {
+                        Ok(command) => command(&mut tts),
+                        Err(RecvTimeoutError::Timeout) => {
+                            #[cfg(target_os = "macos")]
+                            Tts::pump_run_loop(Duration::from_millis(5));
+                        }
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                    if checked.elapsed() >= WATCHDOG_INTERVAL {
+                        checked = Instant::now();
+                        let _ = tts.check_watchdog();
+                    }
+                }
+            })?;
+        creation.recv().map_err(|_| Error::OperationFailed)??;
+        Ok(Self { commands })
+    }
+
+    /// Runs `f` on the worker's thread after the commands already sent, without waiting for it.
+    ///
+    /// Returns `Error::OperationFailed` if the thread has exited, which only happens if a command panicked.
+    pub fn post<F>(&self, f: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Tts) + Send + 'static,
+    {
+        self.commands
+            .send(Box::new(f))
+            .map_err(|_| Error::OperationFailed)
+    }
+
+    /// Runs `f` on the worker's thread after the commands already sent, and returns its result.
+    pub fn run<F, R>(&self, f: F) -> Result<R, Error>
+    where
+        F: FnOnce(&mut Tts) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let (result, received) = mpsc::sync_channel(1);
+        self.post(move |tts| {
+            let _ = result.send(f(tts));
+        })?;
+        received.recv().map_err(|_| Error::OperationFailed)
+    }
+
+    /// Speaks `text`, optionally interrupting current speech, without waiting for the backend. Failures go to the
+    /// `Tts`'s error callback.
+    pub fn speak<S: Into<String>>(&self, text: S, interrupt: bool) -> Result<(), Error> {
+        let text = text.into();
+        self.post(move |tts| {
+            if let Err(e) = tts.speak(text, interrupt) {
+                report(tts, e);
+            }
+        })
+    }
+
+    /// Stops current speech without waiting for the backend. Failures go to the `Tts`'s error callback.
+    pub fn stop(&self) -> Result<(), Error> {
+        self.post(|tts| {
+            if let Err(e) = tts.stop() {
+                report(tts, e);
+            }
+        })
+    }
+}
+
+fn report(tts: &Tts, error: Error) {
+    match tts.0.read().id() {
+        Some(id) => panics::report(id, error),
+        None => warn!("Worker command failed: {}", error),
+    }
+}
@@ -0,0 +1,13 @@
+const COMMANDS: &[&str] = &[
+    "speak",
+    "stop",
+    "is_speaking",
+    "voices",
+    "set_voice",
+    "get_config",
+    "set_config",
+];
+
+fn main() {
+    tauri_plugin::Builder::new(COMMANDS).build();
+}
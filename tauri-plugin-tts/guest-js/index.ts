@@ -0,0 +1,62 @@
+import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
+
+export interface Voice {
+  id: string
+  name: string
+  language: string
+}
+
+export interface Config {
+  rate?: number | null
+  pitch?: number | null
+  volume?: number | null
+}
+
+export interface UtteranceEvent {
+  utteranceId: number
+}
+
+/** Speaks `text`, optionally interrupting current speech, and returns the utterance ID if the backend reports one. */
+export async function speak(text: string, interrupt = false): Promise<number | null> {
+  return await invoke('plugin:tts|speak', { text, interrupt })
+}
+
+export async function stop(): Promise<void> {
+  await invoke('plugin:tts|stop')
+}
+
+export async function isSpeaking(): Promise<boolean> {
+  return await invoke('plugin:tts|is_speaking')
+}
+
+export async function voices(): Promise<Voice[]> {
+  return await invoke('plugin:tts|voices')
+}
+
+/** Selects the voice with the given ID, as returned by `voices()`. */
+export async function setVoice(id: string): Promise<void> {
+  await invoke('plugin:tts|set_voice', { id })
+}
+
+/** Returns the current rate, pitch and volume, or `null` for those the backend doesn't support. */
+export async function getConfig(): Promise<Config> {
+  return await invoke('plugin:tts|get_config')
+}
+
+/** Sets any of the rate, pitch and volume, ignoring those the backend doesn't support. */
+export async function setConfig(config: Config): Promise<void> {
+  await invoke('plugin:tts|set_config', { config })
+}
+
+export async function onUtteranceBegin(handler: (event: UtteranceEvent) => void): Promise<UnlistenFn> {
+  return await listen<UtteranceEvent>('tts://begin', (e) => handler(e.payload))
+}
+
+export async function onUtteranceEnd(handler: (event: UtteranceEvent) => void): Promise<UnlistenFn> {
+  return await listen<UtteranceEvent>('tts://end', (e) => handler(e.payload))
+}
+
+export async function onUtteranceStop(handler: (event: UtteranceEvent) => void): Promise<UnlistenFn> {
+  return await listen<UtteranceEvent>('tts://stop', (e) => handler(e.payload))
+}
@@ -0,0 +1,201 @@
+//! Native text-to-speech for [Tauri](https://tauri.app) apps.
+//!
+//! Register the plugin with `tauri::Builder::default().plugin(tauri_plugin_tts::init())`, grant `tts:default` in your
+//! capabilities, and use `guest-js/index.ts` from the webview. Utterance events are emitted as `tts://begin`,
+//! `tts://end` and `tts://stop`, each with an [`UtteranceEvent`] payload.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize, Serializer};
+use tauri::{
+    command,
+    plugin::{Builder, TauriPlugin},
+    AppHandle, Emitter, Manager, Runtime, State,
+};
+use tts::{Features, Tts};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Tts(#[from] tts::Error),
+    #[error("No voice with ID {0}")]
+    NoSuchVoice(String),
+}
+
+impl Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.to_string().as_ref())
+    }
+}
+
+type Result<T> = std::result::Result<T, Error>;
+
+/// The synthesizer, as managed state.
+///
+/// Commands run on the main thread, which is also where the synthesizer is created, so Apple backends behave.
+pub struct TtsState(Mutex<Tts>);
+
+// All access to the inner `Tts` goes through the mutex, and its `Rc` is never cloned, so this is sound.
+unsafe impl Send for TtsState {}
+
+unsafe impl Sync for TtsState {}
+
+impl TtsState {
+    /// Locks the synthesizer, for using it from Rust.
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, Tts> {
+        self.0.lock().unwrap()
+    }
+}
+
+/// The payload of utterance events.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtteranceEvent {
+    pub utterance_id: u64,
+}
+
+/// A voice, as returned by the `voices` command.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Voice {
+    pub id: String,
+    pub name: String,
+    pub language: String,
+}
+
+/// Speech parameters. Those the backend doesn't support are `None` when read, and ignored when set.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub rate: Option<f32>,
+    pub pitch: Option<f32>,
+    pub volume: Option<f32>,
+}
+
+#[command]
+fn speak(state: State<'_, TtsState>, text: String, interrupt: Option<bool>) -> Result<Option<u64>> {
+    let id = state.lock().speak(text, interrupt.unwrap_or(false))?;
+    Ok(id.map(|id| id.as_u64()))
+}
+
+#[command]
+fn stop(state: State<'_, TtsState>) -> Result<()> {
+    state.lock().stop()?;
+    Ok(())
+}
+
+#[command]
+fn is_speaking(state: State<'_, TtsState>) -> Result<bool> {
+    Ok(state.lock().is_speaking()?)
+}
+
+#[command]
+fn voices(state: State<'_, TtsState>) -> Result<Vec<Voice>> {
+    let voices = state.lock().voices()?;
+    Ok(voices
+        .into_iter()
+        .map(|v| Voice {
+            id: v.id(),
+            name: v.name(),
+            language: v.language().to_string(),
+        })
+        .collect())
+}
+
+#[command]
+fn set_voice(state: State<'_, TtsState>, id: String) -> Result<()> {
+    let mut tts = state.lock();
+    let voice = tts
+        .voices()?
+        .into_iter()
+        .find(|v| v.id() == id)
+        .ok_or(Error::NoSuchVoice(id))?;
+    tts.set_voice(&voice)?;
+    Ok(())
+}
+
+#[command]
+fn get_config(state: State<'_, TtsState>) -> Result<Config> {
+    let tts = state.lock();
+    let Features {
+        rate,
+        pitch,
+        volume,
+        ..
+    } = tts.supported_features();
+    Ok(Config {
+        rate: if rate { Some(tts.get_rate()?) } else { None },
+        pitch: if pitch { Some(tts.get_pitch()?) } else { None },
+        volume: if volume {
+            Some(tts.get_volume()?)
+        } else {
+            None
+        },
+    })
+}
+
+#[command]
+fn set_config(state: State<'_, TtsState>, config: Config) -> Result<()> {
+    let mut tts = state.lock();
+    let Features {
+        rate,
+        pitch,
+        volume,
+        ..
+    } = tts.supported_features();
+    if let (Some(value), true) = (config.rate, rate) {
+        tts.set_rate(value)?;
+    }
+    if let (Some(value), true) = (config.pitch, pitch) {
+        tts.set_pitch(value)?;
+    }
+    if let (Some(value), true) = (config.volume, volume) {
+        tts.set_volume(value)?;
+    }
+    Ok(())
+}
+
+fn emit<R: Runtime>(app: &AppHandle<R>, event: &str, id: tts::UtteranceId) {
+    let payload = UtteranceEvent {
+        utterance_id: id.as_u64(),
+    };
+    if let Err(e) = app.emit(event, payload) {
+        log::error!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn register_callbacks<R: Runtime>(app: &AppHandle<R>, tts: &Tts) -> Result<()> {
+    let a = app.clone();
+    tts.on_utterance_begin(Some(Box::new(move |id| emit(&a, "tts://begin", id))))?;
+    let a = app.clone();
+    tts.on_utterance_end(Some(Box::new(move |id| emit(&a, "tts://end", id))))?;
+    let a = app.clone();
+    tts.on_utterance_stop(Some(Box::new(move |id| emit(&a, "tts://stop", id))))?;
+    Ok(())
+}
+
+/// Initializes the plugin with the default backend for this platform.
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::new("tts")
+        .invoke_handler(tauri::generate_handler![
+            speak,
+            stop,
+            is_speaking,
+            voices,
+            set_voice,
+            get_config,
+            set_config
+        ])
+        .setup(|app, _api| {
+            let tts = Tts::default()?;
+            if tts.supported_features().utterance_callbacks {
+                register_callbacks(app, &tts)?;
+            }
+            app.manage(TtsState(Mutex::new(tts)));
+            Ok(())
+        })
+        .build()
+}